@@ -2,7 +2,7 @@
 //! starting [`Row`] and yields a sequence of permuted [`Row`]s.
 
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     fmt::{Debug, Display, Formatter},
     iter::repeat_with,
     ops::RangeBounds,
@@ -211,6 +211,25 @@ impl<A> Block<A> {
         Truth::True // If no rows repeated, composition is true
     }
 
+    /// Returns the largest number of times that any [`Row`] is rung within this [`Block`] (not
+    /// including the [leftover row](Self::leftover_row)).  This is `1` for any non-empty true
+    /// `Block`, and `0` if the `Block` is empty.
+    pub fn max_row_repeats(&self) -> usize {
+        let mut row_counts = HashMap::<&Row, usize>::with_capacity(self.len());
+        for row in self.rows() {
+            *row_counts.entry(row).or_insert(0) += 1;
+        }
+        row_counts.into_values().max().unwrap_or(0)
+    }
+
+    /// Returns the number of [`Row`]s in this [`Block`] which repeat a [`Row`] rung earlier in
+    /// the `Block` (not including the [leftover row](Self::leftover_row)).  This is `0` if and
+    /// only if the `Block` is true.
+    pub fn num_repeated_rows(&self) -> usize {
+        let unique_rows = self.rows().collect::<HashSet<&Row>>().len();
+        self.len() - unique_rows
+    }
+
     //////////////////////////////
     // ITERATORS / PATH GETTERS //
     //////////////////////////////
@@ -224,6 +224,34 @@ impl Class {
         })
     }
 
+    /// Parses the name of a `Class` (as returned by [`Class::name`]), ignoring case.
+    ///
+    /// # Example
+    /// ```
+    /// use bellframe::method::class::Class;
+    ///
+    /// assert_eq!(Class::from_name("Surprise"), Some(Class::Surprise));
+    /// assert_eq!(Class::from_name("treble bob"), Some(Class::TrebleBob));
+    /// assert_eq!(Class::from_name("Surprize"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_lowercase().as_str() {
+            "principle" => Class::Principle,
+
+            "place" => Class::Place,
+            "bob" => Class::Bob,
+
+            "treble bob" => Class::TrebleBob,
+            "delight" => Class::Delight,
+            "surprise" => Class::Surprise,
+
+            "treble place" => Class::TreblePlace,
+            "alliance" => Class::Alliance,
+            "hybrid" => Class::Hybrid,
+            _ => return None,
+        })
+    }
+
     /// Returns the human-friendly name of this `Class`, including names for `Principle` and
     /// `Hybrid`.
     pub fn name(self) -> &'static str {
@@ -259,6 +287,14 @@ impl Display for Class {
 fn classify<A>(first_lead: &Block<A>) -> FullClass {
     let stage = first_lead.stage();
 
+    // The method is a jump method if any pair of consecutive rows (including the lead end) aren't
+    // related by a normal change
+    let is_jump = first_lead
+        .rows()
+        .chain([first_lead.leftover_row()])
+        .tuple_windows()
+        .any(|(r1, r2)| PlaceNot::pn_between(r1, r2).is_none());
+
     // Generate hunt & working bell cycles, and their paths
     let cycles = Cycle::cycles_from_lead(first_lead);
     let (hunt_cycles, working_cycles) = Cycle::partition_cycles(cycles);
@@ -273,7 +309,7 @@ fn classify<A>(first_lead: &Block<A>) -> FullClass {
     // If there are no hunt bells, then the method is a Principle
     if hunt_cycles.is_empty() {
         return FullClass {
-            is_jump: false,
+            is_jump,
             is_little: false, // little principles are impossible
             is_differential,
             class: Class::Principle,
@@ -282,10 +318,10 @@ fn classify<A>(first_lead: &Block<A>) -> FullClass {
 
     // Classify all the hunt bell paths, and use the dominant class for the method
     let (mut best_is_little, mut best_hunt_bell_class) =
-        classify_hunt_cycle(&hunt_cycles[0], stage);
+        classify_hunt_cycle(&hunt_cycles[0], stage, is_jump);
     let mut hunt_cycles_in_best_class = vec![&hunt_cycles[0]];
     for cycle in &hunt_cycles[1..] {
-        let (is_little, class) = classify_hunt_cycle(cycle, stage);
+        let (is_little, class) = classify_hunt_cycle(cycle, stage, is_jump);
 
         match class.cmp(&best_hunt_bell_class) {
             // If this class is strictly better than the current best, then it becomes the new best
@@ -336,7 +372,7 @@ fn classify<A>(first_lead: &Block<A>) -> FullClass {
 
     // Build a `FullClass` for this method
     FullClass {
-        is_jump: false,
+        is_jump,
         is_little: best_is_little,
         is_differential,
         class,
@@ -344,7 +380,7 @@ fn classify<A>(first_lead: &Block<A>) -> FullClass {
 }
 
 /// Classify a hunt bell path (and whether or not the method is Little)
-fn classify_hunt_cycle(cycle: &Cycle, stage: Stage) -> (bool, HuntBellClass) {
+fn classify_hunt_cycle(cycle: &Cycle, stage: Stage, is_jump: bool) -> (bool, HuntBellClass) {
     // Count the number of rows spent in each place (we ignore the first element of the path
     // because the `path` includes the leftover row and therefore counts the first place twice).
     let mut num_rows_in_each_place = vec![0usize; stage.num_bells()];
@@ -362,8 +398,8 @@ fn classify_hunt_cycle(cycle: &Cycle, stage: Stage) -> (bool, HuntBellClass) {
     // If a hunt bell path is stationary, then it classifies as Treble Place
     // (from Framework):
     // a) The Hunt Bell is a Stationary Bell
-    // b) The Method does not use Jump Changes.
-    if is_stationary {
+    // b) The Method does not use Jump Changes [`is_jump` check]
+    if is_stationary && !is_jump {
         return (is_little, HuntBellClass::TreblePlace);
     }
 
@@ -371,8 +407,8 @@ fn classify_hunt_cycle(cycle: &Cycle, stage: Stage) -> (bool, HuntBellClass) {
     // a) The Hunt Bell rings exactly twice in each Place of the Path during a Plain Lead
     //    [condition of the if statement]
     // b) The Hunt Bell is not a Stationary Bell [early return]
-    // c) The Method does not use Jump Changes [pre-check for jump methods]
-    if are_places_visited_exactly_twice {
+    // c) The Method does not use Jump Changes [`is_jump` check]
+    if are_places_visited_exactly_twice && !is_jump {
         return (is_little, HuntBellClass::Plain);
     }
 
@@ -439,7 +475,9 @@ fn sub_classify_treble_dodging<A>(first_lead: &Block<A>, hunt_cycles: &[&Cycle])
     for i in cross_indices {
         let r1 = first_lead.get_row(i).unwrap();
         let r2 = first_lead.get_row(i + 1).unwrap();
-        let has_internal_places = PlaceNot::pn_between(r1, r2).unwrap().has_internal_places();
+        // Jump changes never count as making internal places
+        let has_internal_places =
+            PlaceNot::pn_between(r1, r2).is_some_and(|pn| pn.has_internal_places());
         if has_internal_places {
             all_no_internal_places = false;
         } else {
@@ -577,7 +615,7 @@ impl Cycle {
 }
 #[cfg(test)]
 mod tests {
-    use crate::{method::FullClass, Block, MethodLib};
+    use crate::{method::FullClass, Block, MethodLib, PnBlock, Stage};
 
     #[test]
     fn classification() {
@@ -599,4 +637,19 @@ mod tests {
             panic!();
         }
     }
+
+    #[test]
+    fn jump_methods() {
+        #[track_caller]
+        fn check(pn: &str, stage: Stage, exp_is_jump: bool) {
+            let plain_lead: Block<()> = PnBlock::parse(pn, stage).unwrap().to_block_from_rounds();
+            assert_eq!(FullClass::classify(&plain_lead).is_jump(), exp_is_jump);
+        }
+
+        check("x16x16x16,12", Stage::MINOR, false);
+        check("x16x16x16,(13)6", Stage::MINOR, true);
+        check("(13)6.x.(13)6.x", Stage::MINOR, true);
+        // Jumps of one place are just normal swaps
+        check("x16x16x16,(12)", Stage::MINOR, false);
+    }
 }
@@ -1,6 +1,6 @@
 use itertools::Itertools;
 
-use crate::{place_not::PnBlockParseError, Block, PnBlock, Row, RowBuf, Stage};
+use crate::{place_not::PnBlockParseError, Block, PlaceNot, PnBlock, Row, RowBuf, Stage};
 
 use self::class::FullClass;
 
@@ -137,6 +137,14 @@ impl Method {
         self.class
     }
 
+    /// Gets the standard lead head code of this `Method` (e.g. `"b"` for Cambridge Surprise Major
+    /// or `"m"` for Bristol Surprise Major), or `None` if this `Method`'s lead head isn't one of
+    /// Plain Bob's.  See [`lead_head_code`] for how the codes are assigned.
+    pub fn lead_head_code(&self) -> Option<String> {
+        let lead_end_change = PlaceNot::pn_between(self.lead_end(), self.lead_head())?;
+        lead_head_code(self.lead_head(), lead_end_change.contains(1))
+    }
+
     /// Gets the **title** of this `Method` - i.e. including the classification or [`Stage`].
     /// Take Bristol Major as an example: its name is `"Bristol"` but its title is `"Bristol
     /// Surprise Major"`.
@@ -283,6 +291,95 @@ pub fn generate_title(name: &str, class: FullClass, omit_class: bool, stage: Sta
     s
 }
 
+/// Returns the lead head of Plain Bob on a given [`Stage`] (e.g. `13527486` for Major), or `None`
+/// if the [`Stage`] is too small for Plain Bob.
+///
+/// # Example
+/// ```
+/// use bellframe::{method::plain_bob_lead_head, RowBuf, Stage};
+///
+/// assert_eq!(plain_bob_lead_head(Stage::MAJOR), Some(RowBuf::parse("13527486")?));
+/// assert_eq!(plain_bob_lead_head(Stage::TRIPLES), Some(RowBuf::parse("1352746")?));
+/// assert_eq!(plain_bob_lead_head(Stage::TWO), None);
+/// # Ok::<(), bellframe::InvalidRowError>(())
+/// ```
+pub fn plain_bob_lead_head(stage: Stage) -> Option<RowBuf> {
+    let n = stage.num_bells_u8();
+    if n < 3 {
+        return None;
+    }
+    // Plain Bob alternates between hunting changes (`x` or `n`) and internal changes (`1n` or `1`),
+    // except that the last internal change becomes `12` (or `12n`)
+    let hunt_change = match PlaceNot::cross(stage) {
+        Some(cross) => cross,
+        None => PlaceNot::from_slice(&mut [n - 1], stage).ok()?,
+    };
+    let internal_change = PlaceNot::from_slice(&mut [0], stage).ok()?;
+    let lead_end_change = PlaceNot::from_slice(&mut [0, 1], stage).ok()?;
+    let mut row = RowBuf::rounds(stage);
+    for i in 0..n {
+        hunt_change.permute(&mut row);
+        if i == n - 1 {
+            lead_end_change.permute(&mut row);
+        } else {
+            internal_change.permute(&mut row);
+        }
+    }
+    Some(row)
+}
+
+/// Returns the standard code for a lead head, or `None` if `lead_head` isn't one of
+/// [Plain Bob's](plain_bob_lead_head).  Methods with 2nds place lead ends use the codes `a` to `f`,
+/// and methods with nths place lead ends use `g` to `m` (skipping `i`).  `a` (or `g`) is Plain
+/// Bob's lead head, `b` (or `h`) is its square, and so on, whereas `f` (or `m`) is the inverse of
+/// Plain Bob's lead head, `e` (or `l`) is the inverse of its square, etc.  Above Major there are
+/// more lead heads than letters, so the extra lead heads in the middle are numbered after `c` and
+/// `d` (or `j` and `k`), e.g. `c1` or `d2`.
+///
+/// # Example
+/// ```
+/// use bellframe::{method::lead_head_code, RowBuf};
+///
+/// let code = |lh: &str, is_seconds_place: bool| {
+///     lead_head_code(&RowBuf::parse(lh).unwrap(), is_seconds_place)
+/// };
+/// assert_eq!(code("13527486", true), Some("a".to_owned())); // Plain Bob Major
+/// assert_eq!(code("15738264", true), Some("b".to_owned())); // Cambridge Surprise Major
+/// assert_eq!(code("14263857", false), Some("m".to_owned())); // Bristol Surprise Major
+/// assert_eq!(code("1907856342", true), Some("c1".to_owned())); // Royal has two extra lead heads
+/// assert_eq!(code("1089674523", false), Some("k1".to_owned()));
+/// assert_eq!(code("12345678", true), None); // Rounds isn't a lead head code
+/// assert_eq!(code("12537486", true), None); // Neither are non-Plain Bob lead heads
+/// ```
+pub fn lead_head_code(lead_head: &Row, is_seconds_place: bool) -> Option<String> {
+    let plain_bob_lead_heads = plain_bob_lead_head(lead_head.stage())?.closure();
+    // `closure` ends with rounds, which doesn't get a code
+    let num_lead_heads = plain_bob_lead_heads.len() - 1;
+    let power = plain_bob_lead_heads[..num_lead_heads]
+        .iter()
+        .position(|lh| &**lh == lead_head)?
+        + 1;
+    let inverse_power = num_lead_heads + 1 - power;
+
+    let letters = match is_seconds_place {
+        true => ['a', 'b', 'c', 'd', 'e', 'f'],
+        false => ['g', 'h', 'j', 'k', 'l', 'm'],
+    };
+    // Assign letters from both ends of the group towards the middle
+    let code = if power <= inverse_power {
+        match power {
+            1..=3 => letters[power - 1].to_string(),
+            _ => format!("{}{}", letters[2], power - 3),
+        }
+    } else {
+        match inverse_power {
+            1..=3 => letters[6 - inverse_power].to_string(),
+            _ => format!("{}{}", letters[3], inverse_power - 3),
+        }
+    };
+    Some(code)
+}
+
 /// The source of a [`Row`] within a [`Method`]
 #[derive(Debug, Clone)]
 pub struct RowAnnot<'meth> {
@@ -298,3 +395,25 @@ impl<'meth> RowAnnot<'meth> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::MethodLib;
+
+    #[test]
+    fn lead_head_codes() {
+        let lib = MethodLib::cc_lib().unwrap();
+        for (title, exp_code) in [
+            ("Plain Bob Major", Some("a")),
+            ("Cambridge Surprise Major", Some("b")),
+            ("Bristol Surprise Major", Some("m")),
+            ("Cambridge Surprise Minor", Some("b")),
+            ("Plain Bob Triples", Some("a")),
+            ("Bristol Surprise Royal", Some("g")),
+            ("Stedman Triples", None),
+        ] {
+            let method = lib.get_by_title(title).unwrap();
+            assert_eq!(method.lead_head_code().as_deref(), exp_code, "{}", title);
+        }
+    }
+}
@@ -5,7 +5,10 @@ use itertools::Itertools;
 use shortlist::Shortlist;
 
 use crate::{
-    method::{class::FullClass, generate_title},
+    method::{
+        class::{Class, FullClass},
+        generate_title,
+    },
     place_not::PnBlockParseError,
     Method, PnBlock, Stage,
 };
@@ -13,6 +16,9 @@ use crate::{
 mod lib_serde;
 pub(crate) mod parse_cc_lib;
 
+#[cfg(feature = "method_lib_xml")]
+pub use parse_cc_lib::XmlError;
+
 /// Convenient type alias for the nested [`HashMap`] type used to store methods in the library
 type LibraryMap = HashMap<Stage, HashMap<String, CompactMethod>>;
 
@@ -142,6 +148,51 @@ impl MethodLib {
             .collect_vec()
     }
 
+    /// Returns every [`Method`] in this `MethodLib` which matches a [`MethodFilter`], sorted by
+    /// title.  Methods whose place notation can't be parsed are skipped.
+    pub fn search(&self, filter: &MethodFilter) -> Vec<Method> {
+        let mut methods = Vec::new();
+        for (stage, methods_of_stage) in &self.method_map {
+            if filter.stage.is_some_and(|s| s != *stage) {
+                continue;
+            }
+            for compact_method in methods_of_stage.values() {
+                // Check the class before parsing the method, since parsing is much slower
+                if filter
+                    .class
+                    .is_some_and(|c| c != compact_method.full_class.class())
+                {
+                    continue;
+                }
+                let Ok(method) = compact_method.to_method() else {
+                    continue;
+                };
+                if let Some(code) = &filter.lead_head_code {
+                    if method.lead_head_code().as_ref() != Some(code) {
+                        continue;
+                    }
+                }
+                methods.push(method);
+            }
+        }
+        methods.sort_by_cached_key(Method::title);
+        methods
+    }
+
+    /// Returns every [`Method`] in this `MethodLib`, sorted by title.  If any method's place
+    /// notation can't be parsed, then that method's title and the parse error are returned.
+    pub fn all_methods(&self) -> Result<Vec<Method>, (String, PnBlockParseError)> {
+        let mut methods = Vec::new();
+        for compact_method in self.method_map.values().flat_map(HashMap::values) {
+            let method = compact_method
+                .to_method()
+                .map_err(|(_pn, e)| (compact_method.title(), e))?;
+            methods.push(method);
+        }
+        methods.sort_by_cached_key(Method::title);
+        Ok(methods)
+    }
+
     // This method is only used by the method classification test suite
     #[cfg(test)]
     pub(crate) fn all_pns_and_classes(&self) -> Vec<(String, PnBlock, FullClass)> {
@@ -224,6 +275,18 @@ impl MethodLib {
     }
 }
 
+/// A set of conditions for [`MethodLib::search`], e.g. 'all Surprise Major methods with lead head
+/// code `b`'.  Any condition which is `None` matches every method.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MethodFilter {
+    pub stage: Option<Stage>,
+    /// The [`Class`] of the methods.  This also matches Little, Differential and Jump methods
+    /// (e.g. `Class::Surprise` matches Little Surprise methods).
+    pub class: Option<Class>,
+    /// The [lead head code](Method::lead_head_code) of the methods
+    pub lead_head_code: Option<String>,
+}
+
 /// A light-weight version of [`Method`] that can be easily stored in a method library.  This is
 /// not intended to be used outside of [`MethodLib`]
 #[derive(Debug, Clone)]
@@ -280,3 +343,33 @@ impl<T> SearchError<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{method::class::Class, Stage};
+
+    use super::{MethodFilter, MethodLib};
+
+    #[test]
+    fn search() {
+        let lib = MethodLib::cc_lib().unwrap();
+        let filter = MethodFilter {
+            stage: Some(Stage::MAJOR),
+            class: Some(Class::Surprise),
+            lead_head_code: Some("b".to_owned()),
+        };
+        let methods = lib.search(&filter);
+        for m in &methods {
+            assert_eq!(m.stage(), Stage::MAJOR);
+            assert_eq!(m.class().class(), Class::Surprise);
+            assert_eq!(m.lead_head_code().as_deref(), Some("b"));
+        }
+        let titles = methods.iter().map(|m| m.title()).collect::<Vec<_>>();
+        assert!(titles.iter().any(|t| t == "Cambridge Surprise Major"));
+        assert!(titles.iter().any(|t| t == "Yorkshire Surprise Major"));
+        assert!(!titles.iter().any(|t| t == "Bristol Surprise Major"));
+        assert!(!titles.iter().any(|t| t == "Plain Bob Major"));
+        // Results are sorted by title
+        assert!(titles.windows(2).all(|w| w[0] <= w[1]));
+    }
+}
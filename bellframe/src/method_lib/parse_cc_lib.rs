@@ -1,29 +1,70 @@
-//! Module to read the Central Council's XML format into a [`MethodLib`].  This is very unlikely to
-//! be used outside of the CI workflow which keeps a copy of the CC library up-to-date.
+//! Module to read the Central Council's XML format into a [`MethodLib`].  This is used by the CI
+//! workflow which keeps a copy of the CC library up-to-date, as well as to load user-supplied
+//! libraries of methods (e.g. ones which haven't been rung yet).
 
-#![cfg(feature = "cc_lib_gen")]
+#![cfg(feature = "method_lib_xml")]
 
-use crate::method::Class;
+use std::fmt::{Display, Formatter};
+
+use crate::method::class::Class;
 
 use super::*;
 use minidom::Element;
 
-const NAMESPACE: &'static str = "http://www.cccbr.org.uk/methods/schemas/2007/05/methods";
-
 /// Parse the CCCBR's XML format into a [`MethodLib`] (removing a large amount of unnecessary
-/// information in the process
+/// information in the process).
+///
+/// # Panics
+///
+/// Panics if `xml` isn't a valid CCCBR method library.  Use [`MethodLib::from_cccbr_xml`] to
+/// handle these errors.
+#[cfg(feature = "cc_lib_gen")]
 pub fn parse_cc_lib(xml: &str) -> MethodLib {
-    let root: Element = xml.parse().unwrap();
+    MethodLib::from_cccbr_xml(xml).expect("Invalid CCCBR method library")
+}
 
-    // Get the file's date
-    let date = root.attr("date").expect("Attr 'date' not found in root.");
-    println!("File last modified on {}", date);
+impl MethodLib {
+    /// Reads a `MethodLib` from the Central Council's XML format.  This can be the whole CC
+    /// library, or any other file of methods written in the same format.
+    pub fn from_cccbr_xml(xml: &str) -> Result<Self, XmlError> {
+        let root: Element = xml.parse().map_err(XmlError::Xml)?;
+        Ok(MethodLib {
+            method_map: read_methods(&root)?,
+        })
+    }
+}
 
-    MethodLib {
-        method_map: read_methods(&root),
+/// The ways that reading a [`MethodLib`] from XML can fail
+#[derive(Debug)]
+pub enum XmlError {
+    /// The string wasn't valid XML
+    Xml(minidom::Error),
+    /// An element didn't contain a child element which it should always have
+    MissingElement {
+        parent: &'static str,
+        child: &'static str,
+    },
+    /// A `<stage>` element didn't contain a valid number of bells
+    InvalidStage(String),
+    /// A `<classification>` element didn't contain a known method class
+    UnknownClass(String),
+}
+
+impl Display for XmlError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            XmlError::Xml(e) => write!(f, "invalid XML: {}", e),
+            XmlError::MissingElement { parent, child } => {
+                write!(f, "`<{}>` element has no `<{}>` element", parent, child)
+            }
+            XmlError::InvalidStage(s) => write!(f, "{:?} is not a valid stage", s),
+            XmlError::UnknownClass(s) => write!(f, "unknown method class {:?}", s),
+        }
     }
 }
 
+impl std::error::Error for XmlError {}
+
 /// The bits of the XML file that we care about are:
 /// ```xml
 /// <collection date="{{ date }}">
@@ -32,6 +73,7 @@ pub fn parse_cc_lib(xml: &str) -> MethodLib {
 ///         <properties>
 ///             <stage>{{ stage }}</stage>
 ///             <classification
+///                 jump="{{ is_jump }}"
 ///                 little="{{ is_little }}"
 ///                 differential="{{ is_differential }}"
 ///                 >{{ classification }}</classification>
@@ -48,85 +90,143 @@ pub fn parse_cc_lib(xml: &str) -> MethodLib {
 ///     {{ endfor }}
 /// </collection>
 /// ```
-fn read_methods(root: &Element) -> LibraryMap {
+fn read_methods(root: &Element) -> Result<LibraryMap, XmlError> {
     let mut methods: LibraryMap = HashMap::new();
 
     // Iterate over all the `methodSet` elements
     for method_set in root.children().filter(|e| e.name() == "methodSet") {
         // Read the `properties` element
-        let properties = method_set
-            .get_child("properties", NAMESPACE)
-            .expect("Couldn't find `properties` element");
-        let (stage, full_class) = read_properties(properties);
+        let properties = get_child(method_set, "methodSet", "properties")?;
+        let (stage, full_class) = read_properties(properties)?;
 
         // The map from titles to `CompactMethod`s (which all share the same stage)
-        let method_map = methods.entry(stage).or_insert_with(HashMap::new);
+        let method_map = methods.entry(stage).or_default();
 
         // Read the methods
         for method in method_set.children().filter(|e| e.name() == "method") {
             // Read the XML for the method
-            let title = method
-                .get_child("title", NAMESPACE)
-                .expect("Couldn't find `title` element")
-                .text();
-            let name = method
-                .get_child("name", NAMESPACE)
-                .expect("Couldn't find `name` element")
-                .text();
-            let place_notation = method
-                .get_child("notation", NAMESPACE)
-                .expect("Couldn't find `notation` element")
-                .text();
+            let title = get_child(method, "method", "title")?.text();
+            let name = get_child(method, "method", "name")?.text();
+            let place_notation = get_child(method, "method", "notation")?.text();
 
+            // Some methods (e.g. Grandsire or Stedman) don't have their class in their title
+            let omit_class = title != generate_title(&name, full_class, false, stage);
             // Push the newly parsed method onto the map corresponding to the correct stage
             method_map.insert(
-                title,
+                title.to_lowercase(),
                 CompactMethod {
                     name,
+                    omit_class,
                     full_class,
                     place_notation,
+                    stage,
                 },
             );
         }
     }
 
-    methods
+    Ok(methods)
 }
 
 /// Read the `<properties>` element
-fn read_properties(properties: &Element) -> (Stage, FullClass) {
-    let stage: Stage = properties
-        .get_child("stage", NAMESPACE)
-        .expect("Couldn't find `stage` element")
-        .text()
-        .parse::<usize>()
-        .expect("Stage wasn't a valid number")
-        .into();
-
-    let classification_elem = properties
-        .get_child("classification", NAMESPACE)
-        .expect("Couldn't find `classification` element");
+fn read_properties(properties: &Element) -> Result<(Stage, FullClass), XmlError> {
+    let stage_str = get_child(properties, "properties", "stage")?.text();
+    let stage = stage_str
+        .trim()
+        .parse::<u8>()
+        .ok()
+        .and_then(|n| Stage::try_from(n).ok())
+        .ok_or(XmlError::InvalidStage(stage_str))?;
+
+    let classification_elem = get_child(properties, "properties", "classification")?;
+    let is_jump = classification_elem.attr("jump") == Some("true");
     let is_little = classification_elem.attr("little") == Some("true");
     let is_differential = classification_elem.attr("differential") == Some("true");
-    let class = match classification_elem.text().as_str() {
+    let class_name = classification_elem.text();
+    let class = match class_name.trim() {
         "" => Class::Principle,
+        name => Class::from_name(name).ok_or(XmlError::UnknownClass(class_name))?,
+    };
 
-        "Place" => Class::Place,
-        "Bob" => Class::Bob,
-
-        "Treble Bob" => Class::TrebleBob,
-        "Delight" => Class::Delight,
-        "Surprise" => Class::Surprise,
-
-        "Treble Place" => Class::TreblePlace,
-        "Alliance" => Class::Alliance,
-        "Hybrid" => Class::Hybrid,
+    let full_class = FullClass::new(is_jump, is_little, is_differential, class);
+    Ok((stage, full_class))
+}
 
-        x => panic!("Unknown classification {:?}", x),
-    };
+/// Gets the first child of `elem` with a given name, ignoring namespaces
+fn get_child<'e>(
+    elem: &'e Element,
+    parent: &'static str,
+    child: &'static str,
+) -> Result<&'e Element, XmlError> {
+    elem.children()
+        .find(|e| e.name() == child)
+        .ok_or(XmlError::MissingElement { parent, child })
+}
 
-    // `is_jump` is always false, because the CCCBR doesn't contain jump methods yet
-    let full_class = FullClass::new(false, is_little, is_differential, class);
+#[cfg(test)]
+mod tests {
+    use crate::{method::class::Class, MethodLib};
+
+    use super::XmlError;
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<collection xmlns="http://www.cccbr.org.uk/methods/schemas/2007/05/methods">
+  <methodSet>
+    <properties>
+      <stage>8</stage>
+      <classification>Surprise</classification>
+    </properties>
+    <method>
+      <title>Experimental Surprise Major</title>
+      <name>Experimental</name>
+      <notation>&amp;-38-14-1258-36-14-58-16-78,12</notation>
+    </method>
+  </methodSet>
+  <methodSet>
+    <properties>
+      <stage>7</stage>
+      <classification/>
+    </properties>
+    <method>
+      <title>Grandsire Triples</title>
+      <name>Grandsire</name>
+      <notation>3,1.7.1.7.1.7.1</notation>
+    </method>
+  </methodSet>
+</collection>"#;
+
+    #[test]
+    fn from_cccbr_xml() {
+        let lib = MethodLib::from_cccbr_xml(XML).unwrap();
+
+        let exp = lib.get_by_title("Experimental Surprise Major").unwrap();
+        assert_eq!(exp.name, "Experimental");
+        assert_eq!(exp.class().class(), Class::Surprise);
+        assert_eq!(exp.lead_head_code().as_deref(), Some("b"));
+
+        let grandsire = lib.get_by_title("Grandsire Triples").unwrap();
+        assert_eq!(grandsire.title(), "Grandsire Triples");
+        assert_eq!(grandsire.class().class(), Class::Principle);
+    }
 
-    (stage, full_class)
+    #[test]
+    fn from_cccbr_xml_err() {
+        assert!(matches!(
+            MethodLib::from_cccbr_xml("<collection"),
+            Err(XmlError::Xml(_))
+        ));
+        let no_stage = XML.replace("<stage>8</stage>", "");
+        assert!(matches!(
+            MethodLib::from_cccbr_xml(&no_stage),
+            Err(XmlError::MissingElement {
+                parent: "properties",
+                child: "stage"
+            })
+        ));
+        let bad_class = XML.replace("Surprise</classification>", "Surprize</classification>");
+        assert!(matches!(
+            MethodLib::from_cccbr_xml(&bad_class),
+            Err(XmlError::UnknownClass(c)) if c == "Surprize"
+        ));
+    }
 }
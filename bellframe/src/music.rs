@@ -52,6 +52,10 @@ impl MusicType {
         self
     }
 
+    pub fn patterns(&self) -> &[Pattern] {
+        &self.patterns
+    }
+
     pub fn strokes(&self) -> StrokeSet {
         self.strokes
     }
@@ -133,6 +137,31 @@ impl MusicType {
         Self::new(patterns)
     }
 
+    /// Creates a `MusicType` which matches any row where `bell` is in one of the given `places`
+    /// (indexed from 0).  Each pattern covers a whole row, so matches within a row are always
+    /// counted at the front (and matches which span two rows are counted as wraps).
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the `places` are outside the [`Stage`].
+    pub fn bell_in_places(
+        bell: Bell,
+        places: impl IntoIterator<Item = usize>,
+        stage: Stage,
+    ) -> Self {
+        let patterns = places
+            .into_iter()
+            .map(|place| {
+                assert!(place < stage.num_bells());
+                let mut bells = vec![None; stage.num_bells()];
+                bells[place] = Some(bell);
+                // SAFETY: The pattern only contains one bell, so can't contain duplicates
+                unsafe { Pattern::from_vec_unchecked(bells) }
+            })
+            .collect_vec();
+        Self::new(patterns)
+    }
+
     pub fn reversed_tenors_at_back(stage: Stage) -> Self {
         assert!(stage.is_even());
 
@@ -732,6 +761,27 @@ mod tests {
         check("Let's Ring! Delight Minor", "123456", 4);
     }
 
+    #[test]
+    fn bell_in_places() {
+        // Plain hunt on 6 (leftover row not counted)
+        let block = crate::Block::<()>::parse(
+            "123456\n214365\n241635\n426153\n462513\n645231\n654321\n563412\n536142\n351624\n315264\n132546\n123456",
+        )
+        .unwrap();
+        let count_5_in = |places: &[usize]| {
+            let mt = MusicType::bell_in_places(
+                Bell::from_number(5).unwrap(),
+                places.to_vec(),
+                Stage::MINOR,
+            );
+            mt.count_block(&block, crate::Stroke::Back).front
+        };
+
+        assert_eq!(count_5_in(&[4]), 2);
+        assert_eq!(count_5_in(&[4, 5]), 4);
+        assert_eq!(count_5_in(&[0, 1, 2, 3, 4, 5]), 12);
+    }
+
     #[test]
     fn full_length_rows() {
         let patterns = [
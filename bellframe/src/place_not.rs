@@ -9,11 +9,22 @@ use std::{
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub enum ParseError {
-    PlaceOutOfStage { place: u8, stage: Stage },
-    AmbiguousPlacesBetween { p: u8, q: u8 },
+    PlaceOutOfStage {
+        place: u8,
+        stage: Stage,
+    },
+    AmbiguousPlacesBetween {
+        p: u8,
+        q: u8,
+    },
     DuplicatePlace(u8),
     OddStageCross(Stage),
     NoPlacesGiven,
+    /// A jump wasn't of the form `(ab)`, with two distinct places inside a matching pair of
+    /// brackets
+    InvalidJump,
+    /// A place is covered by more than one jump, or is both made and jumped over
+    JumpOverlap(u8),
 }
 
 impl Display for ParseError {
@@ -47,10 +58,23 @@ impl Display for ParseError {
             ParseError::DuplicatePlace(p) => {
                 write!(f, "Place '{}' is duplicated", Bell::from_index(*p))
             }
+            ParseError::InvalidJump => write!(
+                f,
+                "Jumps must be written as '(ab)', where the bell in place 'a' jumps to place 'b'"
+            ),
+            ParseError::JumpOverlap(p) => {
+                write!(
+                    f,
+                    "Place '{}' is covered by more than one jump or place",
+                    Bell::from_index(*p)
+                )
+            }
         }
     }
 }
 
+impl std::error::Error for ParseError {}
+
 /// A single piece of place notation on any [`Stage`].
 #[derive(Clone, Eq, PartialEq, Hash)]
 pub struct PlaceNot {
@@ -70,6 +94,12 @@ pub struct PlaceNot {
     /// Enforcing these invariants improves the speed and simplicity of permutation and equality
     /// tests at the cost of (slightly) slower parsing.
     places: Vec<u8>,
+    /// A **0-indexed** list of the jumps made during this `PlaceNot`, as `(from, to)` pairs.  In
+    /// each jump, the bell in place `from` moves to place `to` and every bell in between moves
+    /// one place towards `from`.  Jumps are stored in ascending order of the lowest place they
+    /// cover, never overlap each other or `places`, and always move a bell by at least two places
+    /// (a 'jump' of one place is just a normal swap).
+    jumps: Vec<(u8, u8)>,
     /// The [`Stage`] that this `PlaceNot` is intended to be used for.
     stage: Stage,
 }
@@ -82,6 +112,9 @@ impl PlaceNot {
     /// will fail if there is any kind of ambiguity, returning a [`ParseError`] describing the
     /// problem.
     ///
+    /// Jump changes are written as `(ab)`, meaning that the bell in place `a` jumps to place `b`
+    /// (see [`PlaceNot::with_jumps`]).
+    ///
     /// # Example
     /// ```
     /// use bellframe::{Stage, PlaceNot, place_not::ParseError};
@@ -109,14 +142,13 @@ impl PlaceNot {
         if s.len() == 1 && s.chars().next().map(CharMeaning::from) == Some(CharMeaning::Cross) {
             return Self::cross(stage).ok_or(ParseError::OddStageCross(stage));
         }
-        // Parse the string into bell indices, ignoring any invalid characters
-        let mut parsed_places: Vec<u8> = s
-            .chars()
-            .filter_map(Bell::from_name)
-            .map(Bell::index_u8)
-            .collect();
+        // Parse the string into places and jumps, ignoring any invalid characters
+        let mut builder = PlaceNotBuilder::default();
+        for c in s.chars() {
+            builder.push(CharMeaning::from(c))?;
+        }
         // Create a new `PlaceNot` with these places (or error)
-        Self::from_slice(&mut parsed_places, stage)
+        builder.finish(stage)
     }
 
     /// Creates a new `PlaceNot` from a sorted slice of places, performing bounds checks and
@@ -124,8 +156,46 @@ impl PlaceNot {
     pub fn from_slice(input_places: &mut [u8], stage: Stage) -> Result<Self, ParseError> {
         // Sort the places into ascending order (unstable sort doesn't matter for integers)
         input_places.sort_unstable();
-        let lowest_place = *input_places.first().ok_or(ParseError::NoPlacesGiven)?;
-        let highest_place = *input_places.last().unwrap();
+        Self::with_jumps(input_places, &[], stage)
+    }
+
+    /// Creates a new `PlaceNot` from a slice of places and a slice of **0-indexed** `(from, to)`
+    /// jumps, where the bell in place `from` jumps to place `to` and the bells in between all
+    /// move one place towards `from`.  Implicit places are expanded exactly like
+    /// [`PlaceNot::from_slice`], treating each jump like a block of places which no other bells
+    /// can swap into.
+    ///
+    /// # Example
+    /// ```
+    /// use bellframe::{PlaceNot, RowBuf, Stage};
+    ///
+    /// // The treble jumps from lead to 3rds, with an implicit place made at the back
+    /// let pn = PlaceNot::with_jumps(&[], &[(0, 2)], Stage::MINOR)?;
+    /// assert_eq!(pn.to_string(), "(13)6");
+    /// assert_eq!(pn.transposition(), RowBuf::parse("231546")?);
+    /// // Jumps can go down as well as up
+    /// let pn = PlaceNot::with_jumps(&[0], &[(4, 1)], Stage::MINOR)?;
+    /// assert_eq!(pn.transposition(), RowBuf::parse("152346")?);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn with_jumps(
+        input_places: &[u8],
+        jumps: &[(u8, u8)],
+        stage: Stage,
+    ) -> Result<Self, ParseError> {
+        // Every place or jump covers a range of places which no other bells can swap into.  Places
+        // cover a single place, whereas jumps always cover more than one place.
+        let mut ranges = Vec::with_capacity(input_places.len() + jumps.len());
+        ranges.extend(input_places.iter().map(|&p| (p, p)));
+        for &(from, to) in jumps {
+            if from == to {
+                return Err(ParseError::InvalidJump);
+            }
+            ranges.push((from.min(to), from.max(to)));
+        }
+        ranges.sort_unstable();
+        let (lowest_place, _) = *ranges.first().ok_or(ParseError::NoPlacesGiven)?;
+        let highest_place = ranges.iter().map(|&(_, hi)| hi).max().unwrap();
 
         // Check if any of the bells are out of range
         if highest_place >= stage.num_bells_u8() {
@@ -141,35 +211,58 @@ impl PlaceNot {
         if lowest_place % 2 == 1 {
             places.push(0);
         }
-        // Copy the contents of `parsed_places`, inserting implicit places where necessary
-        for (p, q) in input_places.iter().copied().tuple_windows() {
-            // Add `p` to `places`
-            places.push(p);
+        // Copy the places from `ranges`, inserting implicit places where necessary
+        for ((lo1, hi1), (lo2, hi2)) in ranges.iter().copied().tuple_windows() {
+            // Add the first range to `places` if it's a place
+            if lo1 == hi1 {
+                places.push(lo1);
+            }
             // Check if there is an implicit place made between these, or if the place notation is
             // ambiguous
-            let num_intermediate_places = (q - p)
-                .checked_sub(1) // If p == q, then `p - q == 0` and this subtraction will underflow ...
-                .ok_or(ParseError::DuplicatePlace(p))?; // ... so report the duplicate places
+            let num_intermediate_places = lo2.checked_sub(hi1 + 1).ok_or({
+                // If the ranges overlap, then this subtraction will underflow.  We report this as
+                // a duplicate place if both ranges are places, otherwise as an overlapping jump
+                if lo1 == hi1 && lo2 == hi2 {
+                    ParseError::DuplicatePlace(lo1)
+                } else {
+                    ParseError::JumpOverlap(lo2)
+                }
+            })?;
             if num_intermediate_places == 1 {
-                places.push(p + 1);
+                places.push(hi1 + 1);
             } else if num_intermediate_places % 2 == 1 {
                 // Any other even number causes an error
-                return Err(ParseError::AmbiguousPlacesBetween { p, q });
+                return Err(ParseError::AmbiguousPlacesBetween { p: hi1, q: lo2 });
             }
-            // `q` will be pushed in the next loop iteration
+            // The second range will be pushed in the next loop iteration
+        }
+        // Copy the last range if it's a place.  This is a special case, because `tuple_windows`
+        // won't return the last element as the first element of a tuple window (because there's
+        // nothing to pair it with)
+        let (last_lo, last_hi) = *ranges.last().unwrap();
+        if last_lo == last_hi {
+            places.push(last_lo);
         }
-        // Copy the last element from `places` (aka the highest place).  This is a special case,
-        // because `tuple_windows` won't return the last element as the first element of a tuple
-        // window (because there's nothing to pair it with)
-        places.push(highest_place);
         // Add implicit place at the back if necessary
         if (stage.num_bells_u8() - highest_place) % 2 == 0 {
             places.push(stage.num_bells_u8() - 1);
         }
 
+        // Jumps of only one place are just swaps, which `permute` will do anyway
+        let mut jumps = jumps
+            .iter()
+            .copied()
+            .filter(|&(from, to)| from.abs_diff(to) > 1)
+            .collect_vec();
+        jumps.sort_unstable_by_key(|&(from, to)| from.min(to));
+
         // Create struct and return.  We don't need to sort `places`, because we only pushed to it
         // in ascending order.
-        Ok(PlaceNot { places, stage })
+        Ok(PlaceNot {
+            places,
+            jumps,
+            stage,
+        })
     }
 
     /// Returns a new `PlaceNot` representing the 'cross' notation on a given stage.  This will
@@ -190,6 +283,7 @@ impl PlaceNot {
         if stage.num_bells() % 2 == 0 {
             Some(PlaceNot {
                 places: Vec::new(),
+                jumps: Vec::new(),
                 stage,
             })
         } else {
@@ -213,7 +307,24 @@ impl PlaceNot {
     /// ```
     #[inline(always)]
     pub fn is_cross(&self) -> bool {
-        self.places.is_empty()
+        self.places.is_empty() && self.jumps.is_empty()
+    }
+
+    /// Checks whether any bells jump (i.e. move by more than one place) in this `PlaceNot`.
+    ///
+    /// # Example
+    /// ```
+    /// use bellframe::{PlaceNot, Stage};
+    ///
+    /// assert!(PlaceNot::parse("(14)", Stage::MINOR)?.is_jump());
+    /// assert!(!PlaceNot::parse("14", Stage::MINOR)?.is_jump());
+    /// // A 'jump' of one place is just a swap
+    /// assert!(!PlaceNot::parse("(12)", Stage::MINOR)?.is_jump());
+    /// # Ok::<(), bellframe::place_not::ParseError>(())
+    /// ```
+    #[inline(always)]
+    pub fn is_jump(&self) -> bool {
+        !self.jumps.is_empty()
     }
 
     /// Checks whether a given `place` is made in this `PlaceNot`.
@@ -238,7 +349,8 @@ impl PlaceNot {
     }
 
     /// Returns the [`PlaceNot`] that goes between two [`Row`]s, or `None` if the [`Row`]s are not
-    /// adjacent.
+    /// adjacent.  Jump changes are not considered, so this also returns `None` if the [`Row`]s are
+    /// only related by a jump change.
     pub fn pn_between(r1: &Row, r2: &Row) -> Option<PlaceNot> {
         if r1.stage() != r2.stage() {
             return None;
@@ -263,10 +375,34 @@ impl PlaceNot {
 
         Some(Self {
             places,
+            jumps: Vec::new(),
             stage: r1.stage(),
         })
     }
 
+    /// Returns the `PlaceNot` which undoes the effect of this one.  Without jumps, every `PlaceNot`
+    /// is its own inverse; otherwise, every jump is reversed.
+    ///
+    /// # Example
+    /// ```
+    /// use bellframe::{PlaceNot, RowBuf, Stage};
+    ///
+    /// let pn = PlaceNot::parse("(14)", Stage::MINOR)?;
+    /// assert_eq!(pn.inverse(), PlaceNot::parse("(41)", Stage::MINOR)?);
+    /// assert_eq!(
+    ///     pn.permute_new(&pn.inverse().transposition()),
+    ///     RowBuf::rounds(Stage::MINOR)
+    /// );
+    /// # Ok::<(), bellframe::place_not::ParseError>(())
+    /// ```
+    pub fn inverse(&self) -> PlaceNot {
+        PlaceNot {
+            places: self.places.clone(),
+            jumps: self.jumps.iter().map(|&(from, to)| (to, from)).collect(),
+            stage: self.stage,
+        }
+    }
+
     /// Returns a [`RowBuf`] representing the same transposition as this `PlaceNot`.
     pub fn transposition(&self) -> RowBuf {
         let mut row = RowBuf::rounds(self.stage());
@@ -292,6 +428,7 @@ impl PlaceNot {
     /// This is safe if `self.stage() == row.stage()`.
     pub unsafe fn permute_unchecked(&self, row: &mut Row) {
         let mut places = self.places.iter().copied().peekable();
+        let mut jumps = self.jumps.iter().copied().peekable();
         let mut i = 0;
         while i < self.stage.num_bells_u8() {
             if places.peek() == Some(&i) {
@@ -299,9 +436,23 @@ impl PlaceNot {
                 // where it is, so no change is required.
                 places.next();
                 i += 1;
+            } else if let Some((from, to)) = jumps.next_if(|&(from, to)| from.min(to) == i) {
+                // If a jump starts at this index, then move the bell in `from` to `to` by swapping
+                // it past every bell in between
+                if from < to {
+                    for j in from..to {
+                        row.swap(j as usize, j as usize + 1);
+                    }
+                } else {
+                    for j in (to..from).rev() {
+                        row.swap(j as usize, j as usize + 1);
+                    }
+                }
+                i = from.max(to) + 1;
             } else {
-                // If this isn't a place, then we know by invariant that i + 1 is also not a place
-                // (or out of range), so we perform a swap and move on by two bells
+                // If this isn't a place or a jump, then we know by invariant that i + 1 is also
+                // not a place or jump (or out of range), so we perform a swap and move on by two
+                // bells
                 row.swap(i as usize, i as usize + 1);
                 i += 2;
             }
@@ -352,9 +503,20 @@ impl Display for PlaceNot {
             // Always display cross notation as '-' to avoid confusion with bell names
             write!(f, "-")
         } else {
-            // Otherwise concatenate all the bell names together
-            for p in &self.places {
-                write!(f, "{}", Bell::from_index(*p))?;
+            // Otherwise concatenate all the bell names together, writing jumps as `(ab)` in
+            // between the places
+            let mut jumps = self.jumps.iter().copied().peekable();
+            let write_jump = |f: &mut Formatter<'_>, (from, to): (u8, u8)| {
+                write!(f, "({}{})", Bell::from_index(from), Bell::from_index(to))
+            };
+            for &p in &self.places {
+                while let Some(jump) = jumps.next_if(|&(from, to)| from.min(to) < p) {
+                    write_jump(f, jump)?;
+                }
+                write!(f, "{}", Bell::from_index(p))?;
+            }
+            for jump in jumps {
+                write_jump(f, jump)?;
             }
             Ok(())
         }
@@ -452,8 +614,9 @@ impl PnBlock {
             } else {
                 // Clone sym_block_buf into `pns` in order
                 pns.extend_from_slice(&sym_block_buf);
-                // **Move** pns except the last one from sym_block_buf in reverse order
-                pns.extend(sym_block_buf.drain(..).rev().skip(1));
+                // Add the inverses of all pns except the last one from sym_block_buf in reverse
+                // order (so that any jumps are reflected)
+                pns.extend(sym_block_buf.drain(..).rev().skip(1).map(|pn| pn.inverse()));
             }
         }
         // Return an error if pns is empty, otherwise construct the block
@@ -506,32 +669,33 @@ impl PnBlock {
             tok_indices.next();
         }
 
-        // A buffer used to accumulate the block of places that are currently being parsed
-        let mut places: Vec<u8> = Vec::new();
+        // A buffer used to accumulate the places and jumps that are currently being parsed
+        let mut builder = PlaceNotBuilder::default();
         // Tracks the index of the first byte in the chunk of PN currently being read.  This is
         // used so that we can return a byte range in the case of an error
         let mut current_pn_start_index = 0;
         for (i, m) in tok_indices {
             match m {
-                // If the char is a bell name, then add it to the places
-                CharMeaning::Bell(b) => {
-                    if places.is_empty() {
-                        // If this was the first place of the pn chunk, then we store its index as
+                // If the char is a bell name or part of a jump, then add it to the builder
+                CharMeaning::Bell(_) | CharMeaning::JumpStart | CharMeaning::JumpEnd => {
+                    if builder.is_empty() {
+                        // If this was the first char of the pn chunk, then we store its index as
                         // the start of this pn block
                         current_pn_start_index = i;
                     }
-                    places.push(b.index_u8());
+                    builder.push(m).map_err(|e| {
+                        PnBlockParseError::PnError(current_pn_start_index..i + 1, e)
+                    })?;
                 }
                 // If it's a cross notation or a delimiter, then we create a new PlaceNot out of
                 // the places we've collected so far and push it to `buf`
                 CharMeaning::Cross | CharMeaning::Delimiter => {
-                    if !places.is_empty() {
+                    if !builder.is_empty() {
                         // Push the new place notation to the buffer
-                        let new_pn = PlaceNot::from_slice(&mut places, stage).map_err(|e| {
+                        let new_pn = builder.finish(stage).map_err(|e| {
                             PnBlockParseError::PnError(current_pn_start_index..i, e)
                         })?;
                         buf.push(new_pn);
-                        places.clear();
                     }
                 }
                 // A '+' (for asymmetric block) not at the start of a block is an error
@@ -661,12 +825,61 @@ impl PnBlock {
     }
 }
 
+/// Accumulates the places and jumps of a single [`PlaceNot`] while it's being parsed
+#[derive(Debug, Default)]
+struct PlaceNotBuilder {
+    places: Vec<u8>,
+    jumps: Vec<(u8, u8)>,
+    /// The places read since the last `(`, if we're currently inside a jump
+    open_jump: Option<Vec<u8>>,
+}
+
+impl PlaceNotBuilder {
+    fn is_empty(&self) -> bool {
+        self.places.is_empty() && self.jumps.is_empty() && self.open_jump.is_none()
+    }
+
+    /// Adds the meaning of one char to this builder.  Chars which don't contribute to a single
+    /// [`PlaceNot`] are ignored.
+    fn push(&mut self, m: CharMeaning) -> Result<(), ParseError> {
+        match m {
+            CharMeaning::Bell(b) => match &mut self.open_jump {
+                Some(jump_places) => jump_places.push(b.index_u8()),
+                None => self.places.push(b.index_u8()),
+            },
+            CharMeaning::JumpStart => {
+                if self.open_jump.is_some() {
+                    return Err(ParseError::InvalidJump); // Jumps can't be nested
+                }
+                self.open_jump = Some(Vec::new());
+            }
+            CharMeaning::JumpEnd => match self.open_jump.take().as_deref() {
+                Some(&[from, to]) if from != to => self.jumps.push((from, to)),
+                _ => return Err(ParseError::InvalidJump),
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Creates a [`PlaceNot`] out of everything pushed so far, leaving this builder empty
+    fn finish(&mut self, stage: Stage) -> Result<PlaceNot, ParseError> {
+        let builder = std::mem::take(self);
+        if builder.open_jump.is_some() {
+            return Err(ParseError::InvalidJump); // Unclosed `(`
+        }
+        PlaceNot::with_jumps(&builder.places, &builder.jumps, stage)
+    }
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 enum CharMeaning {
     Bell(Bell),
     Delimiter,
     Cross,
     Asym,
+    JumpStart,
+    JumpEnd,
     Unknown,
 }
 
@@ -679,6 +892,8 @@ impl From<char> for CharMeaning {
                 '+' => CharMeaning::Asym,
                 ' ' | '.' => CharMeaning::Delimiter,
                 'x' | 'X' | '-' => CharMeaning::Cross,
+                '(' => CharMeaning::JumpStart,
+                ')' => CharMeaning::JumpEnd,
                 _ => CharMeaning::Unknown,
             }
         }
@@ -792,6 +1007,79 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_jumps_ok() {
+        #[track_caller]
+        fn check(
+            inp_string: &str,
+            stage: Stage,
+            exp_places: Vec<u8>,
+            exp_jumps: Vec<(u8, u8)>,
+            exp_transposition: &str,
+        ) {
+            let pn = PlaceNot::parse(inp_string, stage).unwrap();
+            assert_eq!(pn.places, exp_places);
+            assert_eq!(pn.jumps, exp_jumps);
+            assert_eq!(
+                pn.transposition(),
+                RowBuf::parse(exp_transposition).unwrap()
+            );
+            // Jumps should survive being displayed and re-parsed
+            assert_eq!(PlaceNot::parse(&pn.to_string(), stage).unwrap(), pn);
+        }
+
+        // Single jumps
+        check("(13)", Stage::MINIMUS, vec![3], vec![(0, 2)], "2314");
+        check("(31)", Stage::MINIMUS, vec![3], vec![(2, 0)], "3124");
+        check("(14)", Stage::MINOR, vec![], vec![(0, 3)], "234165");
+        check("1(25)", Stage::MINOR, vec![0, 5], vec![(1, 4)], "134526");
+        check("(52)6", Stage::MINOR, vec![0, 5], vec![(4, 1)], "152346");
+        // Implicit places around jumps
+        check("(24)", Stage::DOUBLES, vec![0, 4], vec![(1, 3)], "13425");
+        check("(35)", Stage::MAJOR, vec![7], vec![(2, 4)], "21453768");
+        // Several jumps in one change
+        check(
+            "(13)(64)",
+            Stage::MINOR,
+            vec![],
+            vec![(0, 2), (5, 3)],
+            "231645",
+        );
+        // Jumps of one place are just swaps
+        check("(12)", Stage::MINIMUS, vec![], vec![], "2143");
+        check("(21)3", Stage::TRIPLES, vec![2], vec![], "2135476");
+    }
+
+    #[test]
+    fn parse_jumps_err() {
+        #[track_caller]
+        fn check(inp_string: &str, stage: Stage, exp_err: ParseError) {
+            assert_eq!(PlaceNot::parse(inp_string, stage), Err(exp_err));
+        }
+
+        check("(1)", Stage::MINOR, ParseError::InvalidJump);
+        check("(123)", Stage::MINOR, ParseError::InvalidJump);
+        check("(33)", Stage::MINOR, ParseError::InvalidJump);
+        check("(13", Stage::MINOR, ParseError::InvalidJump);
+        check("13)", Stage::MINOR, ParseError::InvalidJump);
+        check("((13))", Stage::MINOR, ParseError::InvalidJump);
+        check("2(13)", Stage::MINOR, ParseError::JumpOverlap(1));
+        check("(14)(36)", Stage::MINOR, ParseError::JumpOverlap(2));
+        check(
+            "(17)",
+            Stage::MINOR,
+            ParseError::PlaceOutOfStage {
+                place: 6,
+                stage: Stage::MINOR,
+            },
+        );
+        check(
+            "(13)7",
+            Stage::MAJOR,
+            ParseError::AmbiguousPlacesBetween { p: 2, q: 6 },
+        );
+    }
+
     #[test]
     fn parse_block_ok() {
         #[track_caller]
@@ -812,6 +1100,8 @@ mod tests {
         check(Stage::MAXIMUS, "x4x1,", "x14x1Tx14x", 7);
         check(Stage::MAXIMUS, "xxx1", "---1T", 4);
         check(Stage::MAXIMUS, "x   -\tx1", "---1T", 4);
+        check(Stage::MINOR, "(13)6.x(64)", "(13)-(64)", 3);
+        check(Stage::MINOR, "x(13)x1,2", "-(13)6-16-(31)6-12", 8);
     }
 
     #[test]
@@ -849,6 +1139,16 @@ mod tests {
             Stage::MAJOR,
             PE::PnError(9..16, ParseError::DuplicatePlace(0)),
         );
+        check(
+            "x1(253)x.12",
+            Stage::MINOR,
+            PE::PnError(1..7, ParseError::InvalidJump),
+        );
+        check(
+            "x1(25x.12",
+            Stage::MINOR,
+            PE::PnError(1..5, ParseError::InvalidJump),
+        );
     }
 
     #[test]
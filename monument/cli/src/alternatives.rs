@@ -0,0 +1,67 @@
+//! Code for running specification files with `method_alternatives`, where one method 'slot' can
+//! be filled by any of several candidate methods.  A separate search is run for each candidate,
+//! and the results are merged into one report (like the `merge` subcommand).
+
+use std::{path::Path, sync::atomic::Ordering};
+
+use monument::{Config, Session};
+
+use crate::{args::Options, toml_file::TomlFile, Environment};
+
+/// Returns `true` if the specification file at `toml_path` has any `method_alternatives` (and
+/// therefore should be run with [`run`]).
+pub fn has_method_alternatives(toml_path: &Path) -> anyhow::Result<bool> {
+    Ok(TomlFile::new(toml_path)?.num_method_alternatives() > 0)
+}
+
+/// Run one search for each of the `method_alternatives` in the specification file at `toml_path`,
+/// and print the merged results.  Returns `Ok(false)` if no searches were run (e.g. because a
+/// `debug_option` was set).
+pub fn run(toml_path: &Path, options: &Options, env: Environment) -> anyhow::Result<bool> {
+    let toml_buf = crate::utils::read_file_to_string(toml_path)?;
+    let num_alternatives = TomlFile::parse(&toml_buf, toml_path)?.num_method_alternatives();
+
+    // Run the searches, displaying just the update line for each one
+    let search_options = Options {
+        only_display_update_line: true,
+        ..options.clone()
+    };
+    let abort_flag = crate::abort_flag(&env);
+    // Share falseness tables and the method library between the searches
+    let session = Session::new(Config::default());
+    let mut results = Vec::new();
+    for idx in 0..num_alternatives {
+        let mut toml_file = TomlFile::parse(&toml_buf, toml_path)?;
+        toml_file.choose_method_alternative(idx);
+        let name = format!("alternative #{}", idx + 1);
+        let session = Some((&session, name.as_str()));
+        match crate::run_toml_file(
+            toml_file,
+            toml_path,
+            &search_options,
+            env,
+            &abort_flag,
+            session,
+        )? {
+            Some(result) => results.push(result),
+            None => return Ok(false),
+        }
+        eprintln!();
+        if abort_flag.load(Ordering::SeqCst) {
+            break; // Don't start any more searches if the user pressed ctrl-C
+        }
+    }
+
+    // Label each result with the method that filled the slot, which is always the last method
+    let source_names = results
+        .iter()
+        .map(|r| r.search.parameters().methods.last().unwrap().title())
+        .collect::<Vec<_>>();
+    let searches = results
+        .iter()
+        .map(|r| (toml_path.to_owned(), r.search.seed()))
+        .collect();
+    let weights = results[0].search.parameters().clone();
+    crate::merge::print_report(&results, &source_names, &weights, options, searches);
+    Ok(true)
+}
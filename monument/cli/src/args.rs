@@ -1,18 +1,27 @@
 use std::path::PathBuf;
 
+use bellframe::Bell;
+use itertools::Itertools;
 use log::LevelFilter;
+use monument::{
+    composition::{DifficultyMetric, ScoreNormalisation},
+    TruncationPolicy,
+};
 use structopt::StructOpt;
 
-use crate::DebugOption;
+use crate::{DebugOption, ProgressFormat, SortKey};
 
 /// A struct storing the CLI args taken by Monument.  `StructOpt` will generate the argument
 /// parsing/help code for us.
 #[derive(Debug, Clone, StructOpt)]
 #[structopt(name = "Monument", about = "Fast and flexible composition generator")]
 pub struct CliArgs {
-    /// The name of the specification file for Monument (`*.toml`)
+    /// The name of the specification file for Monument (`*.toml`).  Required unless running a
+    /// subcommand.
     #[structopt(parse(from_os_str))]
-    pub input_file: PathBuf,
+    pub input_file: Option<PathBuf>,
+    #[structopt(subcommand)]
+    pub sub_command: Option<SubCommand>,
 
     #[structopt(flatten)]
     pub options: Options,
@@ -25,14 +34,54 @@ pub struct CliArgs {
     pub quietness: usize,
 }
 
+#[derive(Debug, Clone, StructOpt)]
+pub enum SubCommand {
+    /// Run several specification files and merge their results into one report, removing
+    /// duplicate compositions and re-ranking them under a single set of weights.
+    Merge {
+        /// The specification files to run (`*.toml`)
+        #[structopt(parse(from_os_str), required = true, min_values = 1)]
+        input_files: Vec<PathBuf>,
+        /// Specification file who's music and weights are used to re-score every composition.
+        /// Defaults to the first of the `input_files`.
+        #[structopt(long, parse(from_os_str))]
+        weights: Option<PathBuf>,
+        #[structopt(flatten)]
+        options: Options,
+    },
+    /// Build the graph for a specification file and save it, so that it can be searched many
+    /// times with `--graph`.  The specification can change between searches, but only in ways
+    /// which don't change the graph (e.g. `num_comps`, score thresholds or calling filters).
+    BuildGraph {
+        /// The specification file to build the graph for (`*.toml`)
+        #[structopt(parse(from_os_str))]
+        input_file: PathBuf,
+        /// Where to save the graph
+        #[structopt(short, long, parse(from_os_str))]
+        output: PathBuf,
+        #[structopt(flatten)]
+        options: Options,
+    },
+    /// Check a specification file for errors without searching it.  This builds the graph of
+    /// every search in the file and prints a summary of each (e.g. its size and which lengths are
+    /// possible).  Exits with a non-zero code if the specification has any errors.
+    Check {
+        /// The specification file to check (`*.toml`)
+        #[structopt(parse(from_os_str))]
+        input_file: PathBuf,
+        #[structopt(flatten)]
+        options: Options,
+    },
+}
+
 // Parameters passed directly into `monument_cli::run`, used to generated the [`monument::Config`]
 // for the search.  This isn't a doc-comment because doc comments override
 // `#[structopt(about = "...")]`.
 #[derive(Default, Debug, Clone, StructOpt)]
 pub struct Options {
-    /// The maximum number of threads that Monument will use.
-    // TODO: Uncomment this once multi-threading is possible
-    // #[structopt(short = "T", long)]
+    /// The maximum number of threads that Monument will use.  Defaults to the number of physical
+    /// CPU cores.
+    #[structopt(short = "T", long)]
     pub num_threads: Option<usize>,
     /// The maximum number of chunks in the chunk graph.  Exceeding this during generation will
     /// cause an error.  Defaults to 100K.
@@ -42,11 +91,143 @@ pub struct Options {
     /// Defaults to 80% of what's available.  E.g. `-M 10G` will limit memory to 10 gigabytes.
     #[structopt(short = "M", long, parse(try_from_str = parse_big_int))]
     pub mem_limit: Option<usize>,
+    /// The maximum number of partial compositions that Monument's search queue can hold.  E.g.
+    /// `--queue-limit 10M` will limit the queue to 10 million entries.  Defaults to no limit (other
+    /// than memory).
+    #[structopt(long, parse(try_from_str = parse_big_int))]
+    pub queue_limit: Option<usize>,
+    /// Adapt the queue limit so that the search queue fits into this many bytes, measuring how
+    /// much memory each partial composition really uses.  Unlike `--queue-limit`, the queue is only
+    /// trimmed slightly when it fills up.  E.g. `--max-memory-bytes 4G`.
+    #[structopt(long, parse(try_from_str = parse_big_int))]
+    pub max_memory_bytes: Option<usize>,
+    /// Which partial compositions to keep when the search queue is truncated: `best` (default)
+    /// keeps only the best ones, whereas `stratified` keeps the best few of every length and part
+    /// head.  `stratified` stops the search from getting stuck exploring very similar
+    /// compositions.
+    #[structopt(long, default_value = "best", parse(try_from_str = parse_truncation_policy))]
+    pub truncation_policy: TruncationPolicy,
+    /// Choose the thread count, memory limit and queue limit automatically based on this machine's
+    /// CPUs and memory, and on the size of the search.  Any limits given explicitly are kept.
+    #[structopt(long)]
+    pub auto_tune: bool,
+    /// If set, every start chunk gets its own search queue, and Monument takes turns to spend at
+    /// most this many seconds on each one.  This stops one start chunk from using up the whole
+    /// search.
+    #[structopt(long)]
+    pub start_chunk_time_budget: Option<f32>,
+    /// Order partial compositions by the best score they could possibly reach, rather than by
+    /// their score so far.  This is less likely to miss the best compositions, but can take much
+    /// longer to find any.
+    #[structopt(long)]
+    pub a_star: bool,
+    /// Seed used to break ties between equally good compositions.  Running the same search with
+    /// the same seed will produce the same compositions.  Defaults to a random seed, which is
+    /// printed once the search finishes.
+    #[structopt(long)]
+    pub seed: Option<u64>,
+    /// If set, Monument picks partial compositions to explore at random (better ones being more
+    /// likely), instead of always exploring the best one.  Higher temperatures are more random.
+    /// Combined with different seeds, this makes repeated runs explore different compositions.
+    #[structopt(long)]
+    pub temperature: Option<f32>,
+    /// Once the search is complete, print a report of where it spent its effort: which chunks
+    /// were expanded most, why partial compositions were discarded and how often the queue was
+    /// truncated.  This shows which constraints are limiting the search.
+    #[structopt(long)]
+    pub stats: bool,
+    /// Search a graph saved by `monument build-graph`, rather than building the graph from
+    /// scratch.  This fails if the specification has changed in a way which would change the
+    /// graph.
+    #[structopt(long, parse(from_os_str))]
+    pub graph: Option<PathBuf>,
+    /// If set, Monument won't save or load falseness tables from its cache directory, and will
+    /// always compute them from scratch.
+    #[structopt(long)]
+    pub no_falseness_cache: bool,
+    /// Once the search is complete, show the differences between the two compositions with these
+    /// numbers.  E.g. `--diff 3 7` will compare compositions #3 and #7.
+    #[structopt(long, number_of_values = 2, value_names = &["A", "B"])]
+    pub diff: Option<Vec<usize>>,
+    /// Once the search is complete, show how the music in the composition with this number is
+    /// split between the courses it rings.
+    #[structopt(long)]
+    pub course_music: Option<usize>,
+    /// Once the search is complete, show the composition with this number as a traditional table
+    /// of course ends against calling positions.
+    #[structopt(long)]
+    pub course_table: Option<usize>,
+    /// Once the search is complete, print the composition with this number in the leadwise text
+    /// format accepted by CompLib, ready to be submitted.
+    #[structopt(long)]
+    pub complib: Option<usize>,
+    /// Once the search is complete, print the composition with this number as a MicroSIRIL
+    /// script, so that it can be proved by MicroSIRIL, BYROC or similar programs.
+    #[structopt(long)]
+    pub microsiril: Option<usize>,
+    /// Once the search is complete, print every row of the composition with this number, with
+    /// lines between the leads.
+    #[structopt(long)]
+    pub print_rows: Option<usize>,
+    /// Once the search is complete, draw the lines of the treble and a working bell through the
+    /// composition with this number as an SVG image, saved to `blue-line-<number>.svg`.
+    #[structopt(long)]
+    pub blue_line: Option<usize>,
+    /// The working bell whose line is drawn by `--blue-line`.  Defaults to the heaviest working
+    /// bell.
+    #[structopt(long, parse(try_from_str = parse_bell))]
+    pub blue_line_bell: Option<Bell>,
+    /// How the compositions' scores are normalised when sorting the final output: `total`,
+    /// `per-row` or `z-score`.  If unset, compositions are sorted by music score.  `z-score`
+    /// compares each composition to the others from the same search, so it's mostly useful with
+    /// `monument merge`.
+    #[structopt(long = "normalise")]
+    pub score_normalisation: Option<ScoreNormalisation>,
+    /// Sort the final output by how easy the compositions are to call, with the easiest last:
+    /// `calls`, `calling-positions`, `max-gap` or `conductability`.  Ties are broken by score.
+    #[structopt(long)]
+    pub sort_by_difficulty: Option<DifficultyMetric>,
+    /// Sort the final output by this key, with the largest values last: `score`, `music`,
+    /// `length`, `calls` or the name of a music type (e.g. `--sort-by 5678s`).  Ties are broken
+    /// by score.
+    #[structopt(long)]
+    pub sort_by: Option<SortKey>,
+    /// Hide every composition which can be turned into one listed before it by adding, removing
+    /// or replacing fewer than this many calls (or leads of spliced), so that the final output
+    /// only shows genuinely different compositions.  If two compositions are too similar, the one which ranks higher is
+    /// kept.
+    #[structopt(long)]
+    pub min_call_distance: Option<usize>,
+    /// Reverse the order of the final output, so that the best compositions are printed first.
+    #[structopt(long)]
+    pub reverse: bool,
+    /// If the specification file has several `queries`, run their searches at the same time rather
+    /// than one after another.
+    #[structopt(long)]
+    pub parallel_queries: bool,
+    /// Name of the composer, printed alongside the results so they can be traced back to you.
+    #[structopt(long)]
+    pub composer: Option<String>,
+    /// Free-form notes, printed alongside the results (e.g. what the search was for).
+    #[structopt(long)]
+    pub notes: Option<String>,
 
     /// Debug options.  `toml`, `params`, `search` and `graph` print the corresponding data
-    /// structures.  `no-search` will run as normal but stop just before starting the full search.
+    /// structures.  `fchs` prints the false course head groups of each method, and `splice`
+    /// prints whether each pair of methods can be lead-spliced and where they're false against each
+    /// other.  `no-search` will run as normal but stop just before starting the full search.
     #[structopt(short = "D", long)]
     pub debug_option: Option<DebugOption>,
+    /// How the search's progress is shown: `human` (default) or `json`.  `json` writes one JSON
+    /// object per line to stderr for every update, for use by other programs.  Other messages on
+    /// stderr (e.g. warnings) aren't JSON, so any line not starting with `{` should be skipped.
+    #[structopt(long, default_value = "human")]
+    pub progress: ProgressFormat,
+    /// Show a full-screen display of the search's progress and the best compositions so far,
+    /// which also lets you abort the search or change `num_comps` and the queue limit while it
+    /// runs.
+    #[structopt(long)]
+    pub tui: bool,
     /// If set, Monument will only display the update line, outputting no compositions until
     /// the search is complete.
     // note: this is used by the benchmark harness
@@ -56,6 +237,10 @@ pub struct Options {
     // note: this is used by the test harness
     #[structopt(long = "no-comp-numbers")]
     pub dont_display_comp_numbers: bool,
+    /// If set, doesn't print which version of Monument generated the results, or when.
+    // note: this is used by the test harness
+    #[structopt(long = "no-provenance")]
+    pub dont_display_provenance: bool,
 }
 
 impl CliArgs {
@@ -73,6 +258,27 @@ impl CliArgs {
     }
 }
 
+/// Parse a bell name like '8' or 'T'
+fn parse_bell(s: &str) -> anyhow::Result<Bell> {
+    s.chars()
+        .exactly_one()
+        .ok()
+        .and_then(Bell::from_name)
+        .ok_or_else(|| anyhow::Error::msg(format!("{s:?} isn't a bell name")))
+}
+
+fn parse_truncation_policy(s: &str) -> anyhow::Result<TruncationPolicy> {
+    Ok(match s.to_lowercase().as_str() {
+        "best" => TruncationPolicy::KeepBest,
+        "stratified" => TruncationPolicy::Stratified,
+        _ => {
+            return Err(anyhow::Error::msg(format!(
+                "Unknown value {s:?}. Expected `best` or `stratified`."
+            )))
+        }
+    })
+}
+
 /// Parse a big integer like '100' or '140M'
 fn parse_big_int(s: &str) -> anyhow::Result<usize> {
     let (last_char_idx, last_char) = s.char_indices().last().unwrap();
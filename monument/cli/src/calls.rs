@@ -1,7 +1,7 @@
-use bellframe::{method::LABEL_LEAD_END, PlaceNot, Stage};
+use bellframe::{method::LABEL_LEAD_END, Bell, PlaceNot, PnBlock, RowBuf, Stage};
 use itertools::Itertools;
 use monument::parameters::{
-    default_calling_positions, BaseCallType, CallId, DEFAULT_MISC_CALL_WEIGHT,
+    BaseCallType, CallId, CallTransposition, Method, MethodVec, DEFAULT_MISC_CALL_WEIGHT,
 };
 use serde::Deserialize;
 
@@ -34,16 +34,27 @@ impl Default for BaseCalls {
 #[derive(Debug, Clone, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct CustomCall {
-    place_notation: String,
+    /// Either a single change (e.g. `14`), or a block of changes (e.g. `3.123`) which replaces the
+    /// change at the call's label
+    place_notation: Option<String>,
+    /// Row by which the call transposes the row before it, for calls which can't be expressed as
+    /// a single piece of place notation
+    transposition: Option<String>,
     #[serde(default = "default_misc_call_score")]
     weight: f32,
     symbol: char,
     debug_symbol: Option<String>, // Deprecated in v0.13.0
     calling_positions: Option<String>,
-    #[serde(default = "lead_end")]
-    label: CallLabel,
+    /// Defaults to the lead end, unless `treble` is set
+    label: Option<CallLabel>,
+    /// Place the call at every change where the treble moves between two places (e.g. `"1-2"`),
+    /// instead of at a `label`
+    treble: Option<String>,
     /// Deprecated alias for `label`
     lead_location: Option<CallLabel>,
+    /// Titles or shorthands of the only methods in which this call can be made.  Defaults to
+    /// every method
+    methods: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -61,13 +72,45 @@ impl CustomCall {
         &self,
         id: CallId,
         stage: Stage,
+        methods: &MethodVec<Method>,
     ) -> anyhow::Result<monument::parameters::Call> {
-        let place_notation = PlaceNot::parse(&self.place_notation, stage).map_err(|e| {
-            anyhow::Error::msg(format!(
-                "Can't parse place notation {:?} for call {:?}: {}",
-                self.place_notation, &self.symbol, e
-            ))
-        })?;
+        let transposition = match (&self.place_notation, &self.transposition) {
+            (Some(pn), None) => match PnBlock::parse(pn, stage) {
+                // `PlaceNot::parse` would happily merge several changes into one, so blocks of
+                // changes have to be detected first
+                Ok(block) if block.len() > 1 => CallTransposition::Block(block),
+                _ => {
+                    let place_notation = PlaceNot::parse(pn, stage).map_err(|e| {
+                        anyhow::Error::msg(format!(
+                            "Can't parse place notation {:?} for call {:?}: {}",
+                            pn, &self.symbol, e
+                        ))
+                    })?;
+                    CallTransposition::PlaceNot(place_notation)
+                }
+            },
+            (None, Some(row)) => {
+                let row = RowBuf::parse_with_stage(row, stage).map_err(|e| {
+                    anyhow::Error::msg(format!(
+                        "Can't parse transposition {:?} for call {:?}: {}",
+                        row, &self.symbol, e
+                    ))
+                })?;
+                CallTransposition::Row(row)
+            }
+            (Some(_), Some(_)) => {
+                return Err(anyhow::Error::msg(format!(
+                    "Call {:?} can't have both `place_notation` and `transposition`",
+                    &self.symbol
+                )));
+            }
+            (None, None) => {
+                return Err(anyhow::Error::msg(format!(
+                    "Call {:?} needs either `place_notation` or `transposition`",
+                    &self.symbol
+                )));
+            }
+        };
         if self.lead_location.is_some() {
             return Err(anyhow::Error::msg(
                 "`calls.lead_location` has been renamed to `label`",
@@ -78,13 +121,10 @@ impl CustomCall {
                 "`debug_symbol` is now calculated automatically.  Use `symbol = \"-\" for bobs.`",
             ));
         }
-        let (label_from, label_to) = match self.label.clone() {
-            CallLabel::Same(loc) => (loc.clone(), loc),
-            CallLabel::Different { from, to } => (from, to),
-        };
+        let (label_from, label_to) = self.labels(stage)?;
         let calling_positions = match &self.calling_positions {
             Some(c) => c.chars().collect_vec(),
-            None => default_calling_positions(&place_notation),
+            None => transposition.default_calling_positions(),
         };
 
         Ok(monument::parameters::Call {
@@ -93,14 +133,144 @@ impl CustomCall {
             calling_positions,
             label_from,
             label_to,
-            place_notation,
+            transposition,
             weight: self.weight,
+            allowed_methods: match &self.methods {
+                Some(names) => Some(
+                    names
+                        .iter()
+                        .map(|name| {
+                            crate::toml_file::find_method_id(name, "calls.methods", methods)
+                        })
+                        .collect::<anyhow::Result<_>>()?,
+                ),
+                None => None,
+            },
+        })
+    }
+
+    /// The labels which this call goes from and to
+    pub(super) fn labels(&self, stage: Stage) -> anyhow::Result<(String, String)> {
+        Ok(match (self.label.clone(), self.treble_position(stage)?) {
+            (Some(_), Some(_)) => {
+                return Err(anyhow::Error::msg(format!(
+                    "Call {:?} can't have both `label` and `treble`",
+                    &self.symbol
+                )));
+            }
+            (None, Some(position)) => (position.label(), position.label()),
+            (Some(CallLabel::Same(loc)), None) => (loc.clone(), loc),
+            (Some(CallLabel::Different { from, to }), None) => (from, to),
+            (None, None) => (LABEL_LEAD_END.to_owned(), LABEL_LEAD_END.to_owned()),
         })
     }
+
+    /// Parses this call's `treble` position, if it has one
+    fn treble_position(&self, stage: Stage) -> anyhow::Result<Option<TreblePosition>> {
+        let Some(position_str) = &self.treble else {
+            return Ok(None);
+        };
+        let position = TreblePosition::parse(position_str, stage).ok_or_else(|| {
+            anyhow::Error::msg(format!(
+                "Can't parse treble position {:?} for call {:?}.  Expected two places, like \"1-2\"",
+                position_str, &self.symbol
+            ))
+        })?;
+        Ok(Some(position))
+    }
 }
 
-fn lead_end() -> CallLabel {
-    CallLabel::Same(LABEL_LEAD_END.to_owned())
+/// Label for the half-lead of treble-hunting methods, i.e. where the treble lies at the back
+pub const LABEL_HALF_LEAD: &str = "HL";
+/// Label for the row half-way through each lead of any method
+pub const LABEL_MIDDLE: &str = "middle";
+
+/// Works out where a `label` goes in a `method` which doesn't define it, returning `None` if this
+/// isn't a label which Monument knows how to infer.  Every inferred label except
+/// [`LABEL_MIDDLE`] comes from the treble's path, so requires the treble to be a hunt bell.
+pub fn infer_label_indices(
+    label: &str,
+    method: &bellframe::Method,
+) -> anyhow::Result<Option<Vec<usize>>> {
+    let stage = method.stage();
+    let position = match label {
+        LABEL_MIDDLE => return Ok(Some(vec![method.lead_len() / 2])),
+        LABEL_HALF_LEAD => {
+            // The treble lies at the back at the half-lead
+            let back = stage.num_bells_u8() - 1;
+            TreblePosition {
+                from: back,
+                to: back,
+            }
+        }
+        _ => match label
+            .strip_prefix("treble ")
+            .and_then(|position_str| TreblePosition::parse(position_str, stage))
+        {
+            Some(position) => position,
+            None => return Ok(None),
+        },
+    };
+    if method.lead_head().place_of(Bell::TREBLE) != 0 {
+        return Err(anyhow::Error::msg(format!(
+            "Can't work out where label {:?} goes in {:?}, because the treble isn't a hunt bell.  Try setting the method's `labels`",
+            label,
+            method.title()
+        )));
+    }
+    Ok(Some(position.label_indices(method)))
+}
+
+/// A change where the treble moves from one place to another (e.g. `1-2` for the treble moving
+/// from lead to 2nds place).  Calls can be placed at these positions instead of at labels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TreblePosition {
+    /// 0-indexed place of the treble before the change
+    from: u8,
+    /// 0-indexed place of the treble after the change
+    to: u8,
+}
+
+impl TreblePosition {
+    /// Parses a position like `"1-2"`, returning `None` if it's invalid or the places aren't
+    /// within `stage`
+    fn parse(s: &str, stage: Stage) -> Option<Self> {
+        let parse_place = |place_str: &str| -> Option<u8> {
+            let mut chars = place_str.trim().chars();
+            let bell = Bell::from_name(chars.next()?)?;
+            (chars.next().is_none() && bell.number() <= stage.num_bells_u8())
+                .then_some(bell.index() as u8)
+        };
+        let (from, to) = s.split_once('-')?;
+        Some(Self {
+            from: parse_place(from)?,
+            to: parse_place(to)?,
+        })
+    }
+
+    /// The label which is generated for this position
+    fn label(self) -> String {
+        format!(
+            "treble {}-{}",
+            Bell::from_index(self.from).name(),
+            Bell::from_index(self.to).name()
+        )
+    }
+
+    /// The indices in the first lead of `method` which should be given this position's label.
+    /// Each is the index of the row *after* a change where the treble moves between these places,
+    /// so that the call replaces that change.
+    fn label_indices(self, method: &bellframe::Method) -> Vec<usize> {
+        method
+            .first_lead()
+            .all_rows()
+            .tuple_windows()
+            .positions(|(r1, r2)| {
+                r1.place_of(Bell::TREBLE) == self.from && r2.place_of(Bell::TREBLE) == self.to
+            })
+            .map(|change_idx| (change_idx + 1) % method.lead_len())
+            .collect_vec()
+    }
 }
 
 fn default_misc_call_score() -> f32 {
@@ -0,0 +1,87 @@
+//! Code for the `check` subcommand, which verifies a specification file (and builds its graph)
+//! without running any searches.  This is useful when editing specifications, or for checking that
+//! a collection of specification files are all still valid.
+
+use std::path::Path;
+
+use itertools::Itertools;
+use monument::{utils::TotalLength, BuiltGraph, Parameters};
+
+use crate::{args::Options, diagnostic, toml_file::TomlFile};
+
+/// Check the specification file at `toml_path` by building the graph of every search it
+/// specifies (i.e. one for each query or method alternative), then print a summary of each graph.
+/// Any problem with the specification is returned as an error.
+pub fn run(toml_path: &Path, options: &Options) -> anyhow::Result<()> {
+    let toml_buf = crate::utils::read_file_to_string(toml_path)?;
+    let toml_file = TomlFile::parse(&toml_buf, toml_path)?;
+
+    // Work out which searches the specification would run
+    let searches = if toml_file.has_queries() {
+        crate::queries::split_queries(&toml_buf)?.0
+    } else if toml_file.num_method_alternatives() > 0 {
+        let mut searches = Vec::new();
+        for idx in 0..toml_file.num_method_alternatives() {
+            let mut toml_file = TomlFile::parse(&toml_buf, toml_path)?;
+            toml_file.choose_method_alternative(idx);
+            searches.push((format!("alternative #{}", idx + 1), toml_file));
+        }
+        searches
+    } else {
+        vec![(String::new(), toml_file)]
+    };
+
+    for (name, toml_file) in &searches {
+        let params = toml_file
+            .to_params(toml_path)
+            .map_err(|e| diagnostic::locate(e, toml_file, toml_path, None))?;
+        let locate_error =
+            |e: monument::Error| diagnostic::locate(e.into(), toml_file, toml_path, Some(&params));
+        let config = crate::search_config(toml_file, options, false)
+            .check()
+            .map_err(locate_error)?;
+        let graph = BuiltGraph::with_build_progress(
+            &params,
+            &config,
+            crate::build_progress_fn(options.progress),
+        )
+        .map_err(locate_error)?;
+
+        if searches.len() > 1 {
+            println!("{name}:");
+        }
+        println!("{}", summary(&graph, &params));
+    }
+    Ok(())
+}
+
+/// A summary of a [`BuiltGraph`], like:
+/// ```text
+/// graph: 1234 chunks, 5678 links (branching factor 2.31)
+/// length: 5000-5088
+/// method counts: B 1056-1280, Y 1120-1344
+/// ```
+fn summary(graph: &BuiltGraph, params: &Parameters) -> String {
+    let stats = graph.stats();
+    let method_counts = params
+        .methods
+        .iter()
+        .zip_eq(graph.method_count_ranges())
+        .map(|(method, range)| format!("{} {}", method.shorthand(), range_string(range)))
+        .join(", ");
+    format!(
+        "graph: {} chunks, {} links (branching factor {:.2})\nlength: {}\nmethod counts: {}",
+        stats.num_chunks,
+        stats.num_links,
+        stats.branching_factor,
+        range_string(&graph.length_range()),
+        method_counts,
+    )
+}
+
+fn range_string(range: &std::ops::RangeInclusive<TotalLength>) -> String {
+    match range.start() == range.end() {
+        true => range.start().to_string(),
+        false => format!("{}-{}", range.start(), range.end()),
+    }
+}
@@ -0,0 +1,352 @@
+//! Errors which point to the part of a TOML specification which caused them, displayed like
+//! `rustc`'s errors:
+//!
+//! ```text
+//! error[E0405]: Music type "5678s" requires a count of at least 30, but ...
+//!   --> spec.toml:8:11
+//!    |
+//!  8 | music = [{ pattern = "5678", count = { min = 30 } }]
+//!    |          ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ music[0]
+//! ```
+
+use std::{
+    fmt::{Display, Formatter},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use colored::{ColoredString, Colorize};
+use itertools::Itertools;
+use monument::{ErrorCode, Parameters};
+use toml_edit::{ImDocument, Item, Value};
+
+use crate::toml_file::TomlFile;
+
+/// A path to a value in a TOML specification: either a top-level key (e.g. `method`) or an entry
+/// in a top-level array (e.g. `music[2]`).  Errors caused by a specific value can be given one of
+/// these as [context](anyhow::Context), which [`locate`] turns into a [`SpecError`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TomlPath {
+    key: &'static str,
+    index: Option<usize>,
+}
+
+impl TomlPath {
+    pub fn key(key: &'static str) -> Self {
+        Self { key, index: None }
+    }
+
+    pub fn index(key: &'static str, index: usize) -> Self {
+        Self {
+            key,
+            index: Some(index),
+        }
+    }
+
+    /// The path in TOML syntax (e.g. `music[2]`)
+    fn name(&self) -> String {
+        match self.index {
+            Some(idx) => format!("{}[{idx}]", self.key),
+            None => self.key.to_owned(),
+        }
+    }
+
+    /// Find the span of bytes in `source` which contains this value
+    fn span(&self, source: &str) -> Option<Range<usize>> {
+        let doc = ImDocument::parse(source).ok()?;
+        let item = doc.get(self.key)?;
+        match self.index {
+            None => item.span(),
+            Some(idx) => match item {
+                Item::Value(Value::Array(array)) => array.get(idx)?.span(),
+                Item::ArrayOfTables(tables) => tables.get(idx)?.span(),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl Display for TomlPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "in `{}`", self.name())
+    }
+}
+
+/// An error in a TOML specification, along with its [`ErrorCode`] (if it came from Monument's
+/// library) and the snippet of TOML which caused it (if it could be found).
+#[derive(Debug)]
+pub struct SpecError {
+    code: Option<ErrorCode>,
+    message: String,
+    snippet: Option<Snippet>,
+}
+
+/// One line of a TOML file, with part of it underlined
+#[derive(Debug)]
+struct Snippet {
+    path: PathBuf,
+    line_idx: usize,
+    col_idx: usize,
+    line: String,
+    underline_len: usize,
+    label: String,
+}
+
+impl SpecError {
+    /// Create a `SpecError` for a TOML file which couldn't be deserialized
+    pub fn toml_parse(error: toml::de::Error, source: &str, path: &Path) -> Self {
+        Self {
+            code: None,
+            message: crate::utils::with_suggestion(
+                format!("Error parsing composition file: {}", error.message().trim()),
+                error.message(),
+            ),
+            snippet: error
+                .span()
+                .map(|span| Snippet::new(source, span, path, String::new())),
+        }
+    }
+
+    /// Write this error like `rustc` would, optionally using colours.
+    pub fn write(&self, f: &mut impl std::fmt::Write, use_colours: bool) -> std::fmt::Result {
+        let paint = |s: &str, colour: fn(&str) -> ColoredString| match use_colours {
+            true => colour(s),
+            false => s.normal(),
+        };
+        let red = |s: &str| s.bright_red().bold();
+        let blue = |s: &str| s.bright_blue().bold();
+
+        let header = match self.code {
+            Some(code) => format!("error[{code}]"),
+            None => "error".to_owned(),
+        };
+        write!(f, "{}: {}", paint(&header, red), self.message)?;
+        let Some(snippet) = &self.snippet else {
+            return Ok(());
+        };
+        let line_num = (snippet.line_idx + 1).to_string();
+        let gutter = " ".repeat(line_num.len());
+        let underline = "^".repeat(snippet.underline_len.max(1));
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{gutter}{} {}:{}:{}",
+            paint("-->", blue),
+            snippet.path.display(),
+            line_num,
+            snippet.col_idx + 1
+        )?;
+        writeln!(f, "{gutter} {}", paint("|", blue))?;
+        writeln!(
+            f,
+            "{} {}",
+            paint(&format!("{line_num} |"), blue),
+            snippet.line
+        )?;
+        write!(
+            f,
+            "{gutter} {} {}{}",
+            paint("|", blue),
+            " ".repeat(snippet.col_idx),
+            paint(format!("{underline} {}", snippet.label).trim_end(), red),
+        )
+    }
+
+    /// This error as a coloured string, for printing to a terminal
+    pub fn to_coloured_string(&self) -> String {
+        let mut s = String::new();
+        self.write(&mut s, true).unwrap();
+        s
+    }
+}
+
+impl Snippet {
+    fn new(source: &str, span: Range<usize>, path: &Path, label: String) -> Self {
+        let line_start = source[..span.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[span.start..]
+            .find('\n')
+            .map_or(source.len(), |i| span.start + i);
+        let line = source[line_start..line_end].trim_end_matches('\r');
+        // Only underline the first line of multi-line spans
+        let underlined = &source[span.start..span.end.min(line_end)];
+        Self {
+            path: path.to_owned(),
+            line_idx: source[..line_start].matches('\n').count(),
+            col_idx: source[line_start..span.start].chars().count(),
+            line: line.to_owned(),
+            underline_len: underlined.trim_end().chars().count(),
+            label,
+        }
+    }
+}
+
+impl Display for SpecError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.write(f, false)
+    }
+}
+
+impl std::error::Error for SpecError {}
+
+/// Convert an error from building a search into a [`SpecError`] which points to the part of
+/// `toml_file` which caused it.  This handles Monument's [`Error`](monument::Error)s (using
+/// `params` to find which method or music type caused the error) and errors which have a
+/// [`TomlPath`] as context.  All other errors are returned unchanged.
+pub fn locate(
+    error: anyhow::Error,
+    toml_file: &TomlFile,
+    toml_path: &Path,
+    params: Option<&Parameters>,
+) -> anyhow::Error {
+    let (code, message, location) = if let Some(e) = error.downcast_ref::<monument::Error>() {
+        let location = e
+            .location()
+            .zip(params)
+            .and_then(|(location, params)| toml_file.toml_path_of(location, params));
+        (Some(e.code()), e.to_string(), location)
+    } else if let Some(location) = error.downcast_ref::<TomlPath>() {
+        // The context is the outermost error, so the message comes from the errors it wraps
+        let message = error.chain().skip(1).join(": ");
+        (None, message, Some(location.clone()))
+    } else {
+        return error;
+    };
+    let snippet = location.and_then(|location| {
+        let source = toml_file.source()?;
+        let span = location.span(source)?;
+        Some(Snippet::new(source, span, toml_path, location.name()))
+    });
+    anyhow::Error::new(SpecError {
+        code,
+        message,
+        snippet,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{Snippet, TomlPath};
+
+    /// Returns `(line_idx, col_idx, line, underline_len)` of the snippet for `span` in `source`
+    fn snippet(source: &str, span: std::ops::Range<usize>) -> (usize, usize, String, usize) {
+        let s = Snippet::new(source, span, Path::new("spec.toml"), String::new());
+        (s.line_idx, s.col_idx, s.line, s.underline_len)
+    }
+
+    /// The text covered by `path` in `source`
+    fn span_text<'s>(path: &TomlPath, source: &'s str) -> Option<&'s str> {
+        path.span(source).map(|span| &source[span])
+    }
+
+    #[test]
+    fn snippet_single_line() {
+        let source = "length = 5040\nmethod = \"Bristol Surprise Major\"\n";
+        let start = source.find("\"Bristol").unwrap();
+        assert_eq!(
+            snippet(source, start..start + 23),
+            (1, 9, "method = \"Bristol Surprise Major\"".to_owned(), 23)
+        );
+    }
+
+    #[test]
+    fn snippet_multi_line() {
+        // Only the first line of the span is shown, and its trailing whitespace isn't underlined
+        let source = "length = 5040\nmethods = [  \n  \"Bristol\",\n]\n";
+        let start = source.find('[').unwrap();
+        let end = source.rfind(']').unwrap() + 1;
+        assert_eq!(
+            snippet(source, start..end),
+            (1, 10, "methods = [  ".to_owned(), 1)
+        );
+    }
+
+    #[test]
+    fn snippet_crlf() {
+        let source = "length = 5040\r\nmethod = \"Bristol\"\r\nnum_comps = 10\r\n";
+        let start = source.find("\"Bristol").unwrap();
+        assert_eq!(
+            snippet(source, start..start + 9),
+            (1, 9, "method = \"Bristol\"".to_owned(), 9)
+        );
+        // A multi-line span shouldn't underline the `\r`
+        let end = source.find("10").unwrap();
+        assert_eq!(snippet(source, start..end).3, 9);
+    }
+
+    #[test]
+    fn snippet_end_of_file() {
+        // E.g. a parse error because the file ended too soon
+        let source = "length = 5040\nmethod = ";
+        assert_eq!(
+            snippet(source, source.len()..source.len()),
+            (1, 9, "method = ".to_owned(), 0)
+        );
+        // Same, but with a trailing newline
+        let source = "length = 5040\n";
+        assert_eq!(
+            snippet(source, source.len()..source.len()),
+            (1, 0, String::new(), 0)
+        );
+    }
+
+    #[test]
+    fn snippet_non_ascii() {
+        // Columns and underlines are measured in chars, not bytes
+        let source = "method = \"Bristol Surprise Major\" # ✓\nlength = \"★\"";
+        let start = source.find("\"★").unwrap();
+        assert_eq!(
+            snippet(source, start..start + "\"★\"".len()),
+            (1, 9, "length = \"★\"".to_owned(), 3)
+        );
+    }
+
+    #[test]
+    fn toml_path_span() {
+        let source = r#"
+length = { min = 5000, max = 5200 }
+methods = [
+    "Bristol Surprise Major",
+    { title = "Yorkshire Surprise Major", shorthand = "Y" },
+]
+
+[[music]]
+run_lengths = [4, 5]
+
+[[music]]
+pattern = "5678"
+count = { min = 10 }
+
+[ch_weights]
+"1*78" = 1.0
+"#;
+        let span_of = |path: TomlPath| span_text(&path, source);
+        assert_eq!(
+            span_of(TomlPath::key("length")),
+            Some("{ min = 5000, max = 5200 }")
+        );
+        // Entries in inline arrays
+        assert_eq!(
+            span_of(TomlPath::index("methods", 0)),
+            Some("\"Bristol Surprise Major\"")
+        );
+        assert_eq!(
+            span_of(TomlPath::index("methods", 1)),
+            Some("{ title = \"Yorkshire Surprise Major\", shorthand = \"Y\" }")
+        );
+        assert_eq!(span_of(TomlPath::index("methods", 2)), None);
+        // Entries in arrays of tables
+        let music_1 = span_of(TomlPath::index("music", 1)).unwrap();
+        assert!(music_1.starts_with("[[music]]\npattern = \"5678\""));
+        assert!(!music_1.contains("run_lengths"));
+        assert_eq!(span_of(TomlPath::index("music", 2)), None);
+        // Tables
+        assert!(span_of(TomlPath::key("ch_weights")).is_some_and(|s| s.starts_with("[ch_weights]")));
+        // Values which aren't arrays can't be indexed
+        assert_eq!(span_of(TomlPath::index("length", 0)), None);
+        // Missing keys and invalid TOML have no span
+        assert_eq!(span_of(TomlPath::key("calls")), None);
+        assert_eq!(TomlPath::key("length").span("length = "), None);
+    }
+}
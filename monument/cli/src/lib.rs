@@ -2,18 +2,30 @@
 //! around `monument_toml`, parsing CLI args and immediately calling into this.  This crate is also
 //! shared between the various integration test runners, making sure that the integration tests run
 //! in exactly the same way as Monument itself.
+//!
+//! Other programs (e.g. GUIs) can get exactly the CLI's behaviour, including its defaults and
+//! output format, by calling [`run_spec`].
 
 #![deny(rustdoc::broken_intra_doc_links, rustdoc::private_intra_doc_links)]
 
+pub mod alternatives;
 pub mod args;
 pub mod calls;
+pub mod check;
+pub mod diagnostic;
 pub mod logging;
+pub mod merge;
 pub mod music;
+pub mod queries;
 pub mod toml_file;
+pub mod tui;
 pub mod utils;
 
 use std::{
-    path::Path,
+    cmp::Reverse,
+    fmt::{Display, Formatter},
+    io::Write,
+    path::{Path, PathBuf},
     str::FromStr,
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -22,14 +34,25 @@ use std::{
     time::{Duration, Instant},
 };
 
+use bellframe::{Bell, Parity};
+use colored::Colorize;
+use itertools::Itertools;
 use log::LevelFilter;
-use monument::{composition::ParamsData, Composition, Search};
+use monument::{
+    composition::{CompositionValues, ParamsData},
+    display::{self, CompositionPrinter},
+    parameters::{Method, MusicTypeIdx, Parameters},
+    BuildProgress, BuiltGraph, Composition, Config, Query, Search, Session, Update,
+};
 use ordered_float::OrderedFloat;
 use ringing_utils::PrettyDuration;
 use simple_logger::SimpleLogger;
 use toml_file::TomlFile;
 
-use crate::logging::{CompositionPrinter, SingleLineProgressLogger};
+use crate::{
+    logging::{JsonProgressLogger, SingleLineProgressLogger},
+    tui::Tui,
+};
 
 pub fn init_logging(filter: LevelFilter) {
     SimpleLogger::new()
@@ -44,6 +67,146 @@ pub fn run(
     toml_path: &Path,
     options: &args::Options,
     env: Environment,
+) -> anyhow::Result<Option<SearchResult>> {
+    let abort_flag = abort_flag(&env);
+    run_with_abort_flag(toml_path, options, env, &abort_flag)
+}
+
+/// Where the specification of a search comes from
+#[derive(Debug, Clone, Copy)]
+pub enum SpecSource<'a> {
+    /// A TOML file on disk
+    Path(&'a Path),
+    /// A TOML file which has already been loaded.  `path` is the location that the TOML would have
+    /// been loaded from; it's used to find other files (e.g. music files) and in the
+    /// [`Provenance`] of the results, but is never read itself.
+    Str { toml: &'a str, path: &'a Path },
+}
+
+impl SpecSource<'_> {
+    fn path(&self) -> &Path {
+        match self {
+            Self::Path(path) | Self::Str { path, .. } => path,
+        }
+    }
+}
+
+/// Run a search end-to-end exactly as the CLI would (i.e. parsing the specification, building and
+/// running the search, then sorting the results), writing everything that the CLI would print to
+/// stdout into `sink`.  This includes any extra output requested by `options`, such as `--diff` or
+/// `--course-table`.  Returns `Ok(None)` if the search was stopped early by a `debug_option`.
+pub fn run_spec(
+    spec: SpecSource,
+    options: &args::Options,
+    sink: &mut impl Write,
+) -> anyhow::Result<Option<SearchResult>> {
+    let env = Environment::Library;
+    let Some(mut result) = run_spec_with_abort_flag(spec, options, env, &abort_flag(&env))? else {
+        return Ok(None);
+    };
+    if !options.only_display_update_line {
+        result.write_comps(sink)?;
+    }
+    result.write_requested_output(options, sink)?;
+    Ok(Some(result))
+}
+
+/// Build the graph for the search specified by `toml_path`, and save it to `output_path`.  The
+/// graph can then be searched many times by passing `--graph <output_path>`, as long as the
+/// specification only changes in ways which don't affect the graph (see [`BuiltGraph`]).
+pub fn build_graph(
+    toml_path: &Path,
+    output_path: &Path,
+    options: &args::Options,
+) -> anyhow::Result<()> {
+    let toml_file = TomlFile::new(toml_path)?;
+    let params = toml_file
+        .to_params(toml_path)
+        .map_err(|e| diagnostic::locate(e, &toml_file, toml_path, None))?;
+    let config = search_config(&toml_file, options, false);
+    let start_time = Instant::now();
+    let graph =
+        BuiltGraph::with_build_progress(&params, &config, build_progress_fn(options.progress))
+            .map_err(|e| diagnostic::locate(e.into(), &toml_file, toml_path, Some(&params)))?;
+    let stats = graph.stats();
+    graph
+        .save(output_path)
+        .map_err(|e| anyhow::Error::msg(format!("Can't write graph to {output_path:?}: {e}")))?;
+    log::info!(
+        "Saved graph with {} chunks and {} links to {output_path:?} (built in {})",
+        stats.num_chunks,
+        stats.num_links,
+        PrettyDuration(start_time.elapsed()),
+    );
+    Ok(())
+}
+
+/// Create the [`Config`] for the search specified by `toml_file`
+fn search_config(
+    toml_file: &TomlFile,
+    options: &args::Options,
+    leak_search_memory: bool,
+) -> Config {
+    let mut config = toml_file.config(options, leak_search_memory);
+    if !options.no_falseness_cache {
+        config.falseness_cache_dir = dirs::cache_dir().map(|dir| dir.join("monument"));
+    }
+    config
+}
+
+/// Returns a function which displays [`BuildProgress`] updates in the given format
+fn build_progress_fn(format: ProgressFormat) -> impl FnMut(BuildProgress) {
+    move |progress| match format {
+        ProgressFormat::Human => logging::log_build_progress(progress),
+        ProgressFormat::Json => logging::log_json_build_progress(progress),
+    }
+}
+
+/// Create a flag which can be used to abort a search.  In CLI mode, this gets attached to `ctrl-C`.
+pub fn abort_flag(env: &Environment) -> Arc<AtomicBool> {
+    let abort_flag = Arc::new(AtomicBool::new(false));
+    if *env == Environment::Cli {
+        let abort_flag = Arc::clone(&abort_flag);
+        if let Err(e) = ctrlc::set_handler(move || abort_flag.store(true, Ordering::SeqCst)) {
+            log::warn!("Error setting ctrl-C handler: {}", e);
+        }
+    }
+    abort_flag
+}
+
+/// Same as [`run`], but using an existing `abort_flag` (see [`abort_flag`]).
+pub fn run_with_abort_flag(
+    toml_path: &Path,
+    options: &args::Options,
+    env: Environment,
+    abort_flag: &Arc<AtomicBool>,
+) -> anyhow::Result<Option<SearchResult>> {
+    run_spec_with_abort_flag(SpecSource::Path(toml_path), options, env, abort_flag)
+}
+
+fn run_spec_with_abort_flag(
+    spec: SpecSource,
+    options: &args::Options,
+    env: Environment,
+    abort_flag: &Arc<AtomicBool>,
+) -> anyhow::Result<Option<SearchResult>> {
+    let toml_file = match spec {
+        SpecSource::Path(path) => TomlFile::new(path)?,
+        SpecSource::Str { toml, path } => TomlFile::parse(toml, path)?,
+    };
+    run_toml_file(toml_file, spec.path(), options, env, abort_flag, None)
+}
+
+/// Run the search specified by an already-parsed [`TomlFile`], which was loaded from `toml_path`.
+/// If this is one of several searches, `session` contains the [`Session`] which they share and the
+/// name of this search.
+fn run_toml_file(
+    toml_file: TomlFile,
+    toml_path: &Path,
+    options: &args::Options,
+    env: Environment,
+    abort_flag: &Arc<AtomicBool>,
+    session: Option<(&Session, &str)>,
 ) -> anyhow::Result<Option<SearchResult>> {
     /// If the user specifies a [`DebugPrint`] flag with e.g. `-D layout`, then debug print the
     /// corresponding value and exit.
@@ -58,29 +221,80 @@ pub fn run(
 
     let start_time = Instant::now();
 
-    // Generate & debug print the TOML file specifying the search
-    let toml_file = TomlFile::new(toml_path)?;
+    // Debug print the TOML file specifying the search
     debug_print!(Toml, toml_file);
     // If running in CLI mode, don't `drop` any of the search data structures, since Monument will
     // exit shortly after the search terminates.  With the `Arc`-based data structures, this is
     // seriously beneficial - it shaves many seconds off Monument's total running time.
     let leak_search_memory = env == Environment::Cli;
     // Convert the `TomlFile` into a `Layout` and other data required for running a search
-    let params = toml_file.to_params(toml_path)?;
+    let params = match session.and_then(|(session, _)| session.method_lib()) {
+        Some(cc_lib) => toml_file.to_params_with_method_lib(toml_path, cc_lib),
+        None => toml_file.to_params(toml_path),
+    }
+    .map_err(|e| diagnostic::locate(e, &toml_file, toml_path, None))?;
     debug_print!(Params, params);
-    // Build the search
-    let search = Arc::new(Search::new(
-        params.clone(),
-        toml_file.config(options, leak_search_memory),
-    )?);
+    match options.debug_option {
+        Some(DebugOption::FalseCourseHeads) => {
+            print_false_course_head_groups(&params);
+            return Ok(None);
+        }
+        Some(DebugOption::SpliceCompatibility) => {
+            print_splice_compatibility(&params);
+            return Ok(None);
+        }
+        _ => {}
+    }
+    // Check the sort key before searching, so that a typo doesn't waste a whole search
+    if let Some(sort_key) = &options.sort_by {
+        sort_key.check(&params)?;
+    }
+    // Build the search, loading its graph from a file if one was given
+    let config = search_config(&toml_file, options, leak_search_memory);
+    let search = match (&options.graph, session) {
+        (Some(graph_path), _) => BuiltGraph::load(graph_path, &params)
+            .and_then(|graph| Search::from_graph(params.clone(), config, &graph)),
+        (None, Some((session, name))) => {
+            let query = Query {
+                name: name.to_owned(),
+                params: params.clone(),
+                config: Some(config),
+            };
+            session.search(query, build_progress_fn(options.progress))
+        }
+        (None, None) => {
+            Search::with_build_progress(params.clone(), config, build_progress_fn(options.progress))
+        }
+    }
+    .map_err(|e| diagnostic::locate(e.into(), &toml_file, toml_path, Some(&params)))?;
+    let search = Arc::new(search);
     debug_print!(Search, search);
+    let stats = search.graph_stats();
+    log::debug!(
+        "Graph has {} chunks, {} links ({:.2} per chunk, branching factor {:.2}), {} starts, {} ends and {} falseness edges",
+        stats.num_chunks,
+        stats.num_links,
+        stats.avg_successors,
+        stats.branching_factor,
+        stats.num_starts,
+        stats.num_ends,
+        stats.num_falseness_edges,
+    );
+    if stats.max_chunk_score < 0.0 {
+        log::warn!(
+            "Every chunk of ringing has a negative score, so Monument will favour the shortest \
+compositions it can find.  Consider adding music, reducing call weights, or comparing \
+compositions of different lengths with `--normalise per-row`."
+        );
+    }
 
     // Build all the data structures for the search
     let comp_printer = CompositionPrinter::new(
         &search,
         toml_file.should_print_atw(),
         !options.dont_display_comp_numbers,
-    );
+    )
+    .with_atw_style(|atw| atw.bright_green().to_string());
     let mut update_logger = SingleLineProgressLogger::new(match options.only_display_update_line {
         true => None,
         false => Some(comp_printer.clone()),
@@ -90,26 +304,48 @@ pub fn run(
         return Ok(None);
     }
 
-    // In CLI mode, attach `ctrl-C` to the abort flag
-    let abort_flag = Arc::new(AtomicBool::new(false));
-    if env == Environment::Cli {
-        let abort_flag = Arc::clone(&abort_flag);
-        if let Err(e) = ctrlc::set_handler(move || abort_flag.store(true, Ordering::SeqCst)) {
-            log::warn!("Error setting ctrl-C handler: {}", e);
-        }
-    }
-
     // Run the search, collecting the compositions as the search runs
+    let mut tui = match options.tui && env == Environment::Cli {
+        true => Tui::new(search.clone(), comp_printer.clone(), abort_flag.clone()),
+        false => None,
+    };
+    let json_logger = (tui.is_none() && options.progress == ProgressFormat::Json)
+        .then(|| JsonProgressLogger::new(search.clone()));
     let mut comps = Vec::<(Composition, usize)>::new();
-    search.run(
-        |update| {
-            let next_comp_number = comps.len();
-            if let Some(comp) = update_logger.log(update, next_comp_number) {
-                comps.push((comp, next_comp_number));
-            }
-        },
-        &abort_flag,
-    );
+    let mut no_comps_reason = None;
+    let mut search_stats = None;
+    let update_fn = |update: Update| {
+        match &update {
+            Update::NoComps(reason) => no_comps_reason = Some(reason.clone()),
+            Update::Stats(stats) => search_stats = Some(stats.clone()),
+            _ => {}
+        }
+        let next_comp_number = comps.len();
+        let comp = if let Some(tui) = &mut tui {
+            tui.log(update, next_comp_number)
+        } else if let Some(json_logger) = &json_logger {
+            json_logger.log(update, next_comp_number)
+        } else {
+            update_logger.log(update, next_comp_number)
+        };
+        if let Some(comp) = comp {
+            comps.push((comp, next_comp_number));
+        }
+    };
+    if toml_file.exhaustive() {
+        search.run_exhaustive(update_fn, abort_flag);
+    } else if toml_file.monte_carlo() {
+        search.run_monte_carlo(update_fn, abort_flag);
+    } else {
+        search.run(update_fn, abort_flag);
+    }
+    drop(tui); // Restore the terminal before printing the results
+    if let Some(reason) = no_comps_reason {
+        log::warn!("No compositions found: {reason}");
+    }
+    if let Some(stats) = search_stats {
+        eprintln!("{stats}");
+    }
 
     // Once the search has completed, sort the compositions and return
     fn rounded_float(f: f32) -> OrderedFloat<f32> {
@@ -118,17 +354,98 @@ pub fn run(
         OrderedFloat(rounded)
     }
     let params_data = ParamsData::new(&params);
-    comps.sort_by_cached_key(|(comp, _generation_index)| {
-        let getter = comp.values(&params_data).unwrap();
-        (
-            rounded_float(getter.music_score),
-            rounded_float(getter.score_per_row()),
-            getter.call_string.clone(),
-        )
-    });
+    // If we kept a shortlist, some compositions will have been pushed off it by better ones.  The
+    // sort is stable, so ties are resolved in favour of the compositions generated first (like the
+    // search does).  Exhaustive and Monte-Carlo searches don't keep a shortlist.
+    let keeps_shortlist = !toml_file.exhaustive() && !toml_file.monte_carlo();
+    if let Some(metric) = params.shortlist.filter(|_| keeps_shortlist) {
+        comps.sort_by_cached_key(|(comp, _generation_index)| {
+            let values = comp.values(&params_data).unwrap();
+            Reverse(rounded_float(metric.value(&values, &params)))
+        });
+        comps.truncate(search.num_comps()); // `num_comps` can be changed during the search
+    }
+    if let Some(min_distance) = options.min_call_distance.or(toml_file.min_call_distance()) {
+        let num_comps = comps.len();
+        // Compare the best compositions first, so that the same compositions are hidden no matter
+        // which order the search found them in
+        comps.sort_by_cached_key(|(comp, _generation_index)| {
+            let values = comp.values(&params_data).unwrap();
+            (
+                Reverse(rounded_float(params.ranking.value(&values))),
+                values.call_string.clone(),
+            )
+        });
+        comps = remove_similar_comps(comps, min_distance);
+        let num_removed = num_comps - comps.len();
+        if num_removed > 0 {
+            log::info!(
+                "Hid {num_removed} composition{} which are fewer than {min_distance} calls away from \
+another",
+                if num_removed == 1 { "" } else { "s" },
+            );
+        }
+    }
+    match options.score_normalisation {
+        // If the user chose a normalisation scheme, rank the comps by their normalised scores
+        Some(normalisation) => {
+            let sort_keys = {
+                let values = comps
+                    .iter()
+                    .map(|(comp, _)| comp.values(&params_data).unwrap())
+                    .collect_vec();
+                let scores = normalisation.normalised_scores(&values);
+                values
+                    .iter()
+                    .zip_eq(scores)
+                    .map(|(v, score)| (rounded_float(score), v.call_string.clone()))
+                    .collect_vec()
+            };
+            let mut keyed_comps = sort_keys.into_iter().zip_eq(comps).collect_vec();
+            keyed_comps.sort_by(|(key1, _), (key2, _)| key1.cmp(key2));
+            comps = keyed_comps.into_iter().map(|(_, c)| c).collect_vec();
+        }
+        // If the search was given a ranking, sort by that
+        None if toml_file.has_ranking() => comps.sort_by_cached_key(|(comp, _generation_index)| {
+            let values = comp.values(&params_data).unwrap();
+            (
+                rounded_float(params.ranking.value(&values)),
+                values.call_string.clone(),
+            )
+        }),
+        // Otherwise, sort by music score
+        None => comps.sort_by_cached_key(|(comp, _generation_index)| {
+            let getter = comp.values(&params_data).unwrap();
+            (
+                rounded_float(getter.music_score),
+                rounded_float(getter.score_per_row()),
+                getter.call_string.clone(),
+            )
+        }),
+    }
+    // Sorting is stable, so this keeps compositions of equal difficulty sorted by score
+    if let Some(metric) = options.sort_by_difficulty {
+        comps.sort_by_cached_key(|(comp, _generation_index)| {
+            rounded_float(metric.ease(&comp.difficulty(&params_data)))
+        });
+    }
+    // An explicit sort key overrides the score-based order.  Again, the sort is stable so ties are
+    // still sorted by score
+    if let Some(sort_key) = &options.sort_by {
+        comps.sort_by_cached_key(|(comp, _generation_index)| {
+            let values = comp.values(&params_data).unwrap();
+            rounded_float(sort_key.value(&values, &params, &params_data))
+        });
+    }
+    if options.reverse {
+        comps.reverse();
+    }
+    let provenance = (!options.dont_display_provenance)
+        .then(|| Provenance::new(options, vec![(toml_path.to_owned(), search.seed())]));
     Ok(Some(SearchResult {
         comps,
         comp_printer,
+        provenance,
         duration: start_time.elapsed(),
         aborted: abort_flag.load(Ordering::SeqCst),
 
@@ -136,13 +453,34 @@ pub fn run(
     }))
 }
 
+/// Remove every composition which is fewer than `min_distance` changes away from a composition
+/// earlier in `comps` (see [`Composition::call_distance`]).  Therefore, `comps` should be sorted
+/// best first.
+fn remove_similar_comps(
+    comps: Vec<(Composition, usize)>,
+    min_distance: usize,
+) -> Vec<(Composition, usize)> {
+    let mut kept = Vec::<(Composition, usize)>::new();
+    for (comp, generation_index) in comps {
+        let is_similar = kept
+            .iter()
+            .any(|(other, _)| comp.call_distance(other) < min_distance);
+        if !is_similar {
+            kept.push((comp, generation_index));
+        }
+    }
+    kept
+}
+
 /// How this instance of Monument is being run
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Environment {
     /// Being run by the test harness as a test case
     TestHarness,
     /// Being run by the CLI
     Cli,
+    /// Being run by another program, through [`run_spec`]
+    Library,
 }
 
 #[derive(Debug, Clone)]
@@ -151,34 +489,412 @@ pub struct SearchResult {
     pub search: Arc<Search>,
     pub duration: Duration,
     pub aborted: bool,
+    /// Where these results came from, or `None` if it shouldn't be printed
+    pub provenance: Option<Provenance>,
 
-    comp_printer: self::logging::CompositionPrinter,
+    comp_printer: CompositionPrinter,
 }
 
 impl SearchResult {
     pub fn print(&mut self) {
         eprintln!("\n\n\n\nSEARCH COMPLETE!\n\n\n");
-        for (c, generation_index) in &self.comps {
-            println!(
-                "{}",
-                self.comp_printer
-                    .comp_string_with_possible_headers(c, *generation_index)
-            );
-        }
-        println!("{}", self.comp_printer.footer_lines());
+        self.write_comps(&mut std::io::stdout())
+            .expect("Failed to write to stdout");
         eprintln!(
-            "{} composition{} generated{} {}",
+            "{} composition{} generated{} {} (seed {})",
             self.comps.len(),
             if self.comps.len() == 1 { "" } else { "s" }, // Handle "1 composition"
             match self.aborted {
                 true => ", aborted after",
                 false => " in",
             },
-            PrettyDuration(self.duration)
+            PrettyDuration(self.duration),
+            self.search.seed(),
         );
     }
 }
 
+impl SearchResult {
+    /// Write the table of compositions (and their [`Provenance`]) to `out`, exactly as printed by
+    /// the CLI
+    pub fn write_comps(&mut self, out: &mut impl Write) -> std::io::Result<()> {
+        for (c, generation_index) in &self.comps {
+            writeln!(
+                out,
+                "{}",
+                self.comp_printer
+                    .comp_string_with_possible_headers(c, *generation_index)
+            )?;
+        }
+        writeln!(out, "{}", self.comp_printer.footer_lines())?;
+        if let Some(provenance) = &self.provenance {
+            writeln!(out, "\n{provenance}")?;
+        }
+        Ok(())
+    }
+
+    /// Write every extra output requested by `options` (e.g. by `--diff` or `--course-table`) to
+    /// `out`, stopping at the first one which can't be generated
+    pub fn write_requested_output(
+        &self,
+        options: &args::Options,
+        out: &mut impl Write,
+    ) -> anyhow::Result<()> {
+        if let Some([a, b]) = options.diff.as_deref() {
+            writeln!(out, "\n{}", self.diff_string(*a, *b)?)?;
+        }
+        if let Some(idx) = options.course_music {
+            writeln!(out, "\n{}", self.course_music_string(idx)?)?;
+        }
+        if let Some(idx) = options.course_table {
+            writeln!(out, "\n{}", self.course_table_string(idx)?)?;
+        }
+        if let Some(idx) = options.complib {
+            writeln!(out, "\n{}", self.complib_string(idx)?)?;
+        }
+        if let Some(idx) = options.microsiril {
+            writeln!(out, "\n{}", self.microsiril_string(idx)?)?;
+        }
+        if let Some(idx) = options.print_rows {
+            writeln!(out, "\n{}", self.rows_string(idx)?)?;
+        }
+        if let Some(idx) = options.blue_line {
+            let path = PathBuf::from(format!("blue-line-{idx}.svg"));
+            std::fs::write(&path, self.blue_line_svg(idx, options.blue_line_bell)?)?;
+            writeln!(out, "\nBlue line of #{idx} saved to {}", path.display())?;
+        }
+        Ok(())
+    }
+}
+
+/// Information about who generated a set of results and how, printed alongside them so that
+/// results passed around a band can be traced back to where they came from.
+#[derive(Debug, Clone)]
+pub struct Provenance {
+    pub composer: Option<String>,
+    pub notes: Option<String>,
+    /// The specification files which were run, along with the seed used for each search
+    pub searches: Vec<(PathBuf, u64)>,
+    /// The (UTC) date on which the results were generated
+    pub date: time::Date,
+}
+
+impl Provenance {
+    pub fn new(options: &args::Options, searches: Vec<(PathBuf, u64)>) -> Self {
+        Self {
+            composer: options.composer.clone(),
+            notes: options.notes.clone(),
+            searches,
+            date: time::OffsetDateTime::now_utc().date(),
+        }
+    }
+}
+
+impl Display for Provenance {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let searches = self
+            .searches
+            .iter()
+            .map(|(path, seed)| format!("{} (seed {seed})", path.display()))
+            .join(", ");
+        write!(
+            f,
+            "Generated by Monument v{} on {} from {searches}",
+            env!("CARGO_PKG_VERSION"),
+            self.date,
+        )?;
+        if let Some(composer) = &self.composer {
+            write!(f, "\nComposer: {composer}")?;
+        }
+        if let Some(notes) = &self.notes {
+            write!(f, "\nNotes: {notes}")?;
+        }
+        Ok(())
+    }
+}
+
+impl SearchResult {
+    /// Show the differences between the compositions numbered `a` and `b` (as they are numbered
+    /// in the output, i.e. starting from 1)
+    fn diff_string(&self, a: usize, b: usize) -> anyhow::Result<String> {
+        let (comp_a, comp_b) = (self.comp_numbered(a)?, self.comp_numbered(b)?);
+        let diff = comp_a
+            .diff(comp_b, &ParamsData::new(self.search.parameters()))
+            .expect("Comps generated by the search should be valid");
+        Ok(logging::diff_string(&diff, a, b))
+    }
+
+    /// Show how the music of the composition numbered `idx` is split between its courses
+    fn course_music_string(&self, idx: usize) -> anyhow::Result<String> {
+        let params = self.search.parameters();
+        let courses = self
+            .comp_numbered(idx)?
+            .music_by_course(&ParamsData::new(params))
+            .expect("Comps generated by the search should be valid");
+        Ok(display::course_music_string(&courses, params))
+    }
+
+    /// Show the composition numbered `idx` as a table of course ends against calling positions
+    fn course_table_string(&self, idx: usize) -> anyhow::Result<String> {
+        let params = self.search.parameters();
+        let table = self
+            .comp_numbered(idx)?
+            .course_table(&ParamsData::new(params))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Composition #{idx} can't be shown as a course table, because its calls aren't \
+described by calling positions"
+                )
+            })?;
+        Ok(display::course_table_string(&table, params))
+    }
+
+    /// Show the composition numbered `idx` in the text format accepted by CompLib
+    fn complib_string(&self, idx: usize) -> anyhow::Result<String> {
+        let text = display::complib_string(
+            self.comp_numbered(idx)?,
+            &ParamsData::new(self.search.parameters()),
+        )
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Composition #{idx} can't be exported to CompLib, because it has calls, splices, \
+starts or finishes away from the lead heads"
+            )
+        })?;
+        Ok(text)
+    }
+
+    /// Show the composition numbered `idx` as a MicroSIRIL script
+    fn microsiril_string(&self, idx: usize) -> anyhow::Result<String> {
+        let text = display::microsiril_string(
+            self.comp_numbered(idx)?,
+            &ParamsData::new(self.search.parameters()),
+        )
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Composition #{idx} can't be exported to MicroSIRIL, because it doesn't start from \
+rounds, uses calls which aren't place notation, or has calls, splices, starts or finishes away \
+from the lead heads"
+            )
+        })?;
+        Ok(text)
+    }
+
+    /// Show every row of the composition numbered `idx`
+    fn rows_string(&self, idx: usize) -> anyhow::Result<String> {
+        let params = ParamsData::new(self.search.parameters());
+        let rows = self
+            .comp_numbered(idx)?
+            .rows(&params)
+            .expect("Comps generated by the search should be valid");
+        Ok(display::rows_string(&rows, &params))
+    }
+
+    /// Draw the lines of the treble and `working_bell` (or the heaviest working bell) through the
+    /// composition numbered `idx` as an SVG image
+    fn blue_line_svg(&self, idx: usize, working_bell: Option<Bell>) -> anyhow::Result<String> {
+        let params = ParamsData::new(self.search.parameters());
+        let rows = self
+            .comp_numbered(idx)?
+            .rows(&params)
+            .expect("Comps generated by the search should be valid");
+        let working_bell = match working_bell {
+            Some(bell) if bell.number() as usize > params.stage.num_bells() => {
+                return Err(anyhow::anyhow!(
+                    "Bell {bell} doesn't exist on {}",
+                    params.stage
+                ))
+            }
+            Some(bell) => bell,
+            None => *params.working_bells().last().unwrap_or(&Bell::TREBLE),
+        };
+        let mut lines = vec![(Bell::TREBLE, "red")];
+        if working_bell != Bell::TREBLE {
+            lines.push((working_bell, "blue"));
+        }
+        Ok(display::blue_line_svg(&rows, &lines, &params))
+    }
+
+    /// Find the composition with a given number in the output (i.e. starting from 1)
+    fn comp_numbered(&self, idx: usize) -> anyhow::Result<&Composition> {
+        self.comps
+            .iter()
+            .find(|(_, generation_index)| generation_index + 1 == idx)
+            .map(|(comp, _)| comp)
+            .ok_or_else(|| anyhow::anyhow!("No composition #{idx} was generated"))
+    }
+}
+
+/// A key by which the final compositions can be sorted, set with `--sort-by`.  Compositions are
+/// sorted so that the largest values are printed last.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SortKey {
+    /// Average score per row (i.e. the `score` column)
+    Score,
+    /// Total music score
+    Music,
+    Length,
+    NumCalls,
+    /// The total count of the music type with this name
+    MusicType(String),
+}
+
+impl SortKey {
+    /// Check that this `SortKey` makes sense for the given [`Parameters`]
+    fn check(&self, params: &Parameters) -> anyhow::Result<()> {
+        if let Self::MusicType(name) = self {
+            if self.music_type_idx(params).is_none() {
+                anyhow::bail!(
+                    "Can't sort by {name:?}: expected `score`, `music`, `length`, `calls` or the name of a music type ({})",
+                    params
+                        .music_types
+                        .iter()
+                        .filter(|ty| !ty.name.is_empty())
+                        .map(|ty| format!("{:?}", ty.name))
+                        .join(", ")
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// The value of a composition according to this key
+    fn value(&self, values: &CompositionValues, params: &Parameters, data: &ParamsData) -> f32 {
+        match self {
+            Self::Score => values.score_per_row(),
+            Self::Music => values.music_score,
+            Self::Length => values.length() as f32,
+            Self::NumCalls => values.difficulty(data).num_calls as f32,
+            Self::MusicType(_) => {
+                let idx = self.music_type_idx(params).unwrap();
+                params.music_types[idx].masked_total(values.music_counts[idx]) as f32
+            }
+        }
+    }
+
+    fn music_type_idx(&self, params: &Parameters) -> Option<MusicTypeIdx> {
+        match self {
+            Self::MusicType(name) => params.music_types.position(|ty| &ty.name == name),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(v: &str) -> Result<Self, String> {
+        // Anything which isn't a known key is assumed to be the name of a music type, which is
+        // checked once the music types are known
+        Ok(match v.to_lowercase().as_str() {
+            "score" => Self::Score,
+            "music" => Self::Music,
+            "length" => Self::Length,
+            "calls" => Self::NumCalls,
+            _ => Self::MusicType(v.to_owned()),
+        })
+    }
+}
+
+/// How the progress of a search is displayed, set with `--progress`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProgressFormat {
+    /// A single line which updates as the search runs, with compositions printed above it
+    #[default]
+    Human,
+    /// Newline-delimited JSON, written to `stderr`
+    Json,
+}
+
+impl FromStr for ProgressFormat {
+    type Err = String;
+
+    fn from_str(v: &str) -> Result<Self, String> {
+        Ok(match v.to_lowercase().as_str() {
+            "human" => Self::Human,
+            "json" => Self::Json,
+            _ => return Err(format!("Unknown value {v:?}. Expected `human` or `json`.")),
+        })
+    }
+}
+
+/// Print the false course head groups of every method in `params`, one group per line (for
+/// `-D fchs`).
+fn print_false_course_head_groups(params: &Parameters) {
+    for (idx, method) in params.methods.iter_enumerated() {
+        let fixed_bells = params.course_head_fixed_bells(idx);
+        println!(
+            "{} (fixed bells {}):",
+            method_summary(method),
+            fixed_bells.iter().join(", ")
+        );
+        let groups = params.false_course_head_groups(idx, &fixed_bells);
+        if groups.is_empty() {
+            println!("  clean proof");
+        }
+        for group in groups {
+            let course = match group[0].clone().parity() {
+                Parity::Even => "in course",
+                Parity::Odd => "out of course",
+            };
+            println!("  {} ({course})", group.iter().join(" "));
+        }
+    }
+}
+
+/// Print whether every pair of methods in `params` can be lead-spliced, and the course heads in
+/// which they're false against each other (for `-D splice`).  Pairs which are false in the plain
+/// course can't be spliced at all without falseness.
+fn print_splice_compatibility(params: &Parameters) {
+    for (idx1, idx2) in params.methods.indices().tuple_combinations() {
+        let (method1, method2) = (&params.methods[idx1], &params.methods[idx2]);
+        let fixed_bells = params.course_head_fixed_bells(idx1);
+        let fixed_bells = (params.course_head_fixed_bells(idx2).into_iter())
+            .filter(|b| fixed_bells.contains(b))
+            .collect_vec();
+        let splice = if !method1.can_lead_splice_with(method2) {
+            "can't lead splice (different hunt bells)"
+        } else if method1.lead_head() == method2.lead_head() {
+            "can lead splice (same lead heads)"
+        } else {
+            "can lead splice (different lead heads)"
+        };
+        let fchs = params.mutual_false_course_heads(idx1, idx2, &fixed_bells);
+        let falseness = if fchs.is_empty() {
+            "clean proof".to_owned()
+        } else {
+            let plain_course = match fchs.iter().any(|ch| ch.is_rounds()) {
+                true => ", including the plain course",
+                false => "",
+            };
+            format!(
+                "{} false course heads{plain_course}: {}",
+                fchs.len(),
+                fchs.iter().join(" ")
+            )
+        };
+        println!(
+            "{} & {} (fixed bells {}): {splice}, {falseness}",
+            method_summary(method1),
+            method_summary(method2),
+            fixed_bells.iter().join(", ")
+        );
+    }
+}
+
+/// A method's title, followed by its lead head code and the length of its plain course (e.g.
+/// `Bristol Surprise Major [m, 7 leads]`)
+fn method_summary(method: &Method) -> String {
+    let code = method
+        .lead_head_code()
+        .unwrap_or_else(|| "no lead head code".to_owned());
+    format!(
+        "{} [{code}, {} leads]",
+        method.title(),
+        method.leads_per_course()
+    )
+}
+
 /// What item should be debug printed
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DebugOption {
@@ -186,6 +902,11 @@ pub enum DebugOption {
     Params,
     Search,
     Graph,
+    /// Print the false course head groups of every method, then stop
+    FalseCourseHeads,
+    /// Print the lead-splice compatibility and mutual falseness of every pair of methods, then
+    /// stop
+    SpliceCompatibility,
     /// Stop just before the search starts, to let the user see what's been printed out without
     /// scrolling
     StopBeforeSearch,
@@ -200,10 +921,12 @@ impl FromStr for DebugOption {
             "params" => Self::Params,
             "search" => Self::Search,
             "graph" => Self::Graph,
+            "fchs" => Self::FalseCourseHeads,
+            "splice" => Self::SpliceCompatibility,
             "no-search" => Self::StopBeforeSearch,
             #[rustfmt::skip] // See https://github.com/rust-lang/rustfmt/issues/5204
             _ => return Err(format!(
-                "Unknown value {:?}. Expected `toml`, `params`, `search`, `graph` or `no-search`.",
+                "Unknown value {:?}. Expected `toml`, `params`, `search`, `graph`, `fchs`, `splice` or `no-search`.",
                 v
             )),
         })
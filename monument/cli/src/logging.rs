@@ -1,12 +1,15 @@
 //! Code for handling the logging of compositions or updates provided by Monument
 
-use std::{fmt::Write, io::Write as IoWrite};
+use std::{fmt::Write, io::Write as IoWrite, sync::Arc};
 
-use bellframe::row::ShortRow;
 use colored::Colorize;
 use itertools::Itertools;
 use log::log_enabled;
-use monument::{composition::ParamsData, Composition, Parameters, Progress, Search, Update};
+use monument::{
+    composition::{CompositionDiff, CourseDiff, ParamsData},
+    display::CompositionPrinter,
+    BuildProgress, Composition, Progress, Search, Update,
+};
 use ringing_utils::BigNumInt;
 
 /// Struct which handles logging updates, keeping the updates to a single line which updates as the
@@ -71,7 +74,9 @@ impl SingleLineProgressLogger {
         match update {
             Update::Comp(comp) => return Some(comp),
             Update::Progress(progress) => self.last_progress = progress,
-            Update::Complete => {} // Nothing to do, we're just about to return anyway
+            // Nothing to do, we're just about to return anyway (and the reason for finding no
+            // comps and the stats are printed once the search has finished)
+            Update::NoComps(_) | Update::Stats(_) | Update::Complete => {}
         }
         None
     }
@@ -81,12 +86,13 @@ impl SingleLineProgressLogger {
         let p = self.last_progress;
         write!(
             buf,
-            "    {} iters, {} comps :: {} items in queue, avg/max len {:.0}/{}",
+            "    {} iters, {} comps :: {} items in queue, avg/max len {:.0}/{} :: mem {}",
             BigNumInt(p.iter_count),
             BigNumInt(p.num_comps),
             BigNumInt(p.queue_len),
             p.avg_length,
-            p.max_length
+            p.max_length,
+            memory_string(&p),
         )
         .unwrap();
         buf.push_str(match (p.aborting, p.truncating_queue) {
@@ -122,229 +128,189 @@ impl SingleLineProgressLogger {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct CompositionPrinter {
-    params: Parameters, // TODO: Make this `ParamsData`
-    /// Counter which records how many compositions have been printed so far
-    comps_printed: usize,
-
-    /* COLUMN WIDTH INFORMATION */
-    /// The maximum width of a composition's number (i.e. the width of the total number of
-    /// compositions).  If `None`, no composition numbers are printed.
-    comp_count_width: Option<usize>,
-    /// The maximum width of a composition's (total) length
-    length_width: usize,
-    /// For each method in the composition:
-    /// ```text
-    /// (
-    ///     maximum width of row count,
-    ///     shorthand
-    /// )
-    /// ```
-    method_count_widths: Vec<(usize, String)>,
-    /// `true` if the user gave some weight to atw
-    print_atw: bool,
-    /// If a part head should be displayed, then what's its width
-    part_head_width: Option<usize>,
+/// A summary of the memory used by a search, like `1.23GB/8.00GB` (or just `1.23GB` if the search
+/// has no memory limit).  The limit only applies to the search's queue, so is shown as the limit on
+/// the total memory which the search will use.
+pub fn memory_string(p: &Progress) -> String {
+    let mut s = format!("{}B", BigNumInt(p.total_bytes()));
+    if let Some(limit) = p.mem_limit {
+        write!(s, "/{}B", BigNumInt(limit + p.graph_bytes)).unwrap();
+    }
+    s
 }
 
-impl CompositionPrinter {
-    pub fn new(search: &Search, print_atw: bool, print_comp_widths: bool) -> Self {
-        let params = search.parameters().clone();
-        Self {
-            comp_count_width: print_comp_widths.then_some(params.num_comps.to_string().len()),
-            length_width: params.max_length().to_string().len().max(3),
-            method_count_widths: params
-                .methods
-                .iter()
-                .map(|method| {
-                    let shorthand = method.shorthand();
-                    let max_count_width =
-                        search.method_count_range(method.id).end().to_string().len();
-                    let max_width = max_count_width.max(shorthand.len());
-                    (max_width, shorthand)
-                })
-                .collect_vec(),
-            comps_printed: 0,
-
-            print_atw,
-            part_head_width: (params.num_parts() > 2)
-                .then(|| params.part_head_group.effective_stage().num_bells()),
-
-            params,
-        }
+/// Display a [`BuildProgress`] update on a single line of `stderr`.  The line is cleared once the
+/// graph build is complete, so that it doesn't interfere with the search's update line.
+pub fn log_build_progress(progress: BuildProgress) {
+    if !log_enabled!(log::Level::Info) {
+        return;
     }
 
-    /// Create some lines which summarise the given [`Composition`].  This may include additional
-    /// lines for headers or ruleoffs, depending on how many compositions have been printed so far.
-    pub fn comp_string_with_possible_headers(
-        &mut self,
-        comp: &Composition,
-        generation_index: usize,
-    ) -> String {
-        let mut update_string = String::new();
-
-        // Add a header every 50 lines
-        if self.comps_printed % 50 == 0 {
-            if self.comps_printed > 0 {
-                update_string.push_str(&self.ruleoff());
-                update_string.push('\n');
-            }
-            update_string.push_str(&self.header());
-            update_string.push('\n');
-            update_string.push_str(&self.ruleoff());
-            update_string.push('\n');
-        }
-        // Add the composition
-        update_string.push_str(&self.comp_string(comp, generation_index));
-        self.comps_printed += 1;
-
-        update_string
+    let BuildProgress::Falseness { done, total } = progress;
+    // Only print when the percentage changes, to avoid flooding `stderr` on huge graphs
+    let percent = |n: usize| n * 100 / total.max(1);
+    if done > 0 && done < total && percent(done) == percent(done - 1) {
+        return;
     }
 
-    /// Return some lines which end a composition list.  This includes one line for a ruleoff and
-    /// one for the column headers, like:
-    /// ```text
-    /// --------------|---------|-----------|-----------
-    /// len    Y   C  |  music  | avg score | calling
-    /// ```
-    pub fn footer_lines(&self) -> String {
-        let mut s = String::new();
-        s.push_str(&self.ruleoff());
-        s.push('\n');
-        s.push_str(&self.header());
-        s
+    let line = format!("    Computing falseness: {done}/{total} chunk ranges");
+    let std_err = std::io::stderr();
+    let mut std_err = std_err.lock();
+    write!(std_err, "\r{line}").unwrap();
+    if done == total {
+        // Overwrite the line with spaces, leaving the cursor at the start
+        write!(std_err, "\r{}\r", " ".repeat(line.len())).unwrap();
     }
+    std_err.flush().unwrap();
+}
 
-    fn ruleoff(&self) -> String {
-        // Ruleoff is the same as header, but with every non-'|' char replaced with '-'
-        let mut ruleoff = self
-            .header()
-            .chars()
-            .map(|c| if c == '|' { '|' } else { '-' })
-            .collect::<String>();
-        ruleoff.push_str("---"); // Add a couple of extra `-`s to make the ruleoff a bit longer
-        ruleoff
-    }
+/// Writes updates from a search to `stderr` as newline-delimited JSON (for `--progress json`), so
+/// that other programs can show the search's progress without parsing human-readable output.
+/// Each line is one object, whose `type` is `"build"`, `"progress"`, `"comp"`, `"no_comps"` or
+/// `"complete"`.
+pub struct JsonProgressLogger {
+    search: Arc<Search>,
+}
 
-    fn header(&self) -> String {
-        let mut s = String::new();
-        // Comp index
-        if let Some(c) = self.comp_count_width {
-            write_centered_text(&mut s, "#", c);
-            s.push_str(" | ");
-        }
-        // Length
-        write_centered_text(&mut s, "len", self.length_width);
-        s.push(' ');
-        // Method shorthands (for counts)
-        if self.method_count_widths.len() > 1 {
-            s.push_str("  ");
-            for (width, shorthand) in &self.method_count_widths {
-                write_centered_text(&mut s, shorthand, *width);
-                s.push(' ');
-            }
-        }
-        s.push('|');
-        // Atw
-        if self.print_atw {
-            s.push_str(" atw |");
-        }
-        // Part head
-        if let Some(w) = self.part_head_width {
-            // Add 2 to the width to get one char of extra padding on either side
-            write_centered_text(&mut s, "PH", w + 2);
-            s.push('|');
-        }
-        // Music
-        let music_types_to_display = self.params.music_types_to_show();
-        s.push_str("  music  ");
-        if !music_types_to_display.is_empty() {
-            s.push(' ');
-        }
-        for (_idx, music_type) in music_types_to_display {
-            s.push_str("  ");
-            write_centered_text(
-                &mut s,
-                &music_type.name,
-                music_type.col_width(self.params.stage),
-            );
-            s.push(' ');
-        }
-        // Everything else
-        s.push_str("| avg score | calling");
-        s
+impl JsonProgressLogger {
+    pub fn new(search: Arc<Search>) -> Self {
+        Self { search }
     }
 
-    fn comp_string(&self, comp: &Composition, generation_index: usize) -> String {
-        let comp = comp.values(&ParamsData::new(&self.params)).unwrap();
-
-        let mut s = String::new();
-        // Comp index
-        if let Some(c) = self.comp_count_width {
-            write!(s, "{:>width$} | ", generation_index + 1, width = c).unwrap();
-        }
-        // Length
-        write!(s, "{:>width$} ", comp.length(), width = self.length_width).unwrap();
-        // Method counts (for spliced)
-        if self.method_count_widths.len() > 1 {
-            s.push_str(": ");
-            for ((width, _), count) in self.method_count_widths.iter().zip_eq(&comp.method_counts) {
-                write!(s, "{:>width$} ", count, width = *width).unwrap();
-            }
-        }
-        s.push('|');
-        // Atw
-        if self.print_atw {
-            if comp.atw_factor > 0.999999 {
-                s.push_str(&format!(" {} |", "atw".color(colored::Color::BrightGreen)));
-            } else {
-                write!(s, " {:>2}% |", (comp.atw_factor * 100.0).floor() as usize).unwrap();
+    pub fn log(&self, update: Update, comps_generated_so_far: usize) -> Option<Composition> {
+        let (json, comp) = match update {
+            Update::Comp(comp) => {
+                let values = comp.values(&ParamsData::new(self.search.parameters()));
+                let json = match values {
+                    Some(v) => serde_json::json!({
+                        "type": "comp",
+                        "index": comps_generated_so_far,
+                        "length": v.length(),
+                        "music_score": v.music_score,
+                        "score_per_row": v.score_per_row(),
+                        "repeated_rows": v.num_repeated_rows(),
+                        "call_string": v.call_string,
+                    }),
+                    None => serde_json::json!({ "type": "comp", "index": comps_generated_so_far }),
+                };
+                (json, Some(comp))
             }
-        }
-        // Part head (if >2 parts; up to 2-parts must always have the same part head)
-        if self.part_head_width.is_some() {
-            write!(s, " {} |", ShortRow(comp.part_head())).unwrap();
-        }
-        // Music
-        let music_types_to_show = self.params.music_types_to_show();
-        write!(s, " {:>7.2} ", comp.music_score).unwrap();
-        if !music_types_to_show.is_empty() {
-            s.push(':');
-        }
-        for (idx, music_type) in music_types_to_show {
-            s.push_str("  ");
-            write_left_centered_text(
-                &mut s,
-                &music_type.display_counts(comp.music_counts[idx], self.params.stage),
-                music_type.col_width(self.params.stage),
-            );
-            s.push(' ');
-        }
-        // avg score, call string
-        write!(s, "| {:>9.6} | {}", comp.score_per_row(), comp.call_string).unwrap();
-
-        s
+            Update::Progress(p) => (
+                serde_json::json!({
+                    "type": "progress",
+                    "iter_count": p.iter_count,
+                    "num_comps": p.num_comps,
+                    "target_num_comps": self.search.num_comps(),
+                    "queue_len": p.queue_len,
+                    "avg_length": p.avg_length,
+                    "max_length": p.max_length,
+                    "queue_bytes": p.queue_bytes,
+                    "graph_bytes": p.graph_bytes,
+                    "mem_limit": p.mem_limit,
+                    "truncating_queue": p.truncating_queue,
+                    "aborting": p.aborting,
+                }),
+                None,
+            ),
+            Update::NoComps(reason) => (
+                serde_json::json!({ "type": "no_comps", "reason": reason.to_string() }),
+                None,
+            ),
+            Update::Stats(stats) => (
+                serde_json::json!({
+                    "type": "stats",
+                    "num_expansions": stats.num_expansions,
+                    "most_expanded_chunks": stats.most_expanded_chunks,
+                    "prunes": stats
+                        .prunes
+                        .iter()
+                        .map(|(prune, count)| (format!("{prune:?}"), count))
+                        .collect::<Vec<_>>(),
+                    "rejections": stats
+                        .rejections
+                        .iter()
+                        .map(|(rejection, count)| (format!("{rejection:?}"), count))
+                        .collect::<Vec<_>>(),
+                    "num_truncations": stats.num_truncations,
+                    "num_truncated_prefixes": stats.num_truncated_prefixes,
+                }),
+                None,
+            ),
+            Update::Complete => (serde_json::json!({ "type": "complete" }), None),
+        };
+        write_json_line(&json);
+        comp
     }
 }
 
-/// Write some `string` to `out`, centering it among `width` spaces (rounding to the right).
-fn write_centered_text(out: &mut String, text: &str, width: usize) {
-    let w = width.saturating_sub(text.len());
-    push_multiple(' ', w - (w / 2), out);
-    out.push_str(text);
-    push_multiple(' ', w / 2, out);
+/// Write a [`BuildProgress`] update as a line of JSON (see [`JsonProgressLogger`])
+pub fn log_json_build_progress(progress: BuildProgress) {
+    let BuildProgress::Falseness { done, total } = progress;
+    write_json_line(&serde_json::json!({
+        "type": "build",
+        "stage": "falseness",
+        "done": done,
+        "total": total,
+    }));
 }
 
-/// Write some `string` to `out`, centering it among `width` spaces (rounding to the left).
-fn write_left_centered_text(out: &mut String, text: &str, width: usize) {
-    let w = width.saturating_sub(text.len());
-    push_multiple(' ', w / 2, out);
-    out.push_str(text);
-    push_multiple(' ', w - (w / 2), out);
+fn write_json_line(json: &serde_json::Value) {
+    let mut std_err = std::io::stderr().lock();
+    writeln!(std_err, "{json}").unwrap();
+    std_err.flush().unwrap();
 }
 
-/// Push `n` copies of `c` to the end of `out`
-fn push_multiple(c: char, n: usize, out: &mut String) {
-    out.extend(std::iter::repeat(c).take(n));
+/// Render a [`CompositionDiff`] between compositions `#a` and `#b` as a table, with one line per
+/// course.  Courses only in `#a` are marked with `-`, those only in `#b` with `+` and those with
+/// different callings in each with `~`.
+pub fn diff_string(diff: &CompositionDiff, a: usize, b: usize) -> String {
+    let mut s = String::new();
+    if diff.is_identical() {
+        write!(s, "Compositions #{a} and #{b} are identical").unwrap();
+        return s;
+    }
+
+    let rows = diff
+        .courses
+        .iter()
+        .map(|course| {
+            let (marker, calling_a, calling_b) = match course {
+                CourseDiff::Same { calling, .. } => (" ", calling.as_str(), calling.as_str()),
+                CourseDiff::Changed {
+                    old_calling,
+                    new_calling,
+                    ..
+                } => ("~", old_calling.as_str(), new_calling.as_str()),
+                CourseDiff::Removed { calling, .. } => ("-", calling.as_str(), ""),
+                CourseDiff::Added { calling, .. } => ("+", "", calling.as_str()),
+            };
+            (
+                marker,
+                course.course_head().to_string(),
+                calling_a,
+                calling_b,
+            )
+        })
+        .collect_vec();
+    let head_a = format!("#{a}");
+    let ch_width = rows.iter().map(|(_, ch, _, _)| ch.len()).max().unwrap_or(0);
+    let width_a = (rows.iter().map(|(_, _, c, _)| c.len()))
+        .chain([head_a.len()])
+        .max()
+        .unwrap();
+
+    writeln!(s, "  {:ch_width$} | {head_a:width_a$} | #{b}", "CH").unwrap();
+    for (marker, course_head, calling_a, calling_b) in rows {
+        let line = format!("{marker} {course_head:ch_width$} | {calling_a:width_a$} | {calling_b}");
+        let line = match marker {
+            "-" => line.red().to_string(),
+            "+" => line.green().to_string(),
+            "~" => line.yellow().to_string(),
+            _ => line,
+        };
+        s.push_str(line.trim_end());
+        s.push('\n');
+    }
+    s.pop(); // Remove the trailing newline
+    s
 }
@@ -1,14 +1,94 @@
 #![deny(rustdoc::broken_intra_doc_links)]
 
 use colored::Colorize;
-use monument_cli::{args::CliArgs, Environment};
+use monument_cli::{
+    args::{CliArgs, SubCommand},
+    diagnostic::SpecError,
+    Environment,
+};
 use structopt::StructOpt;
 
 fn main() {
     // Run Monument with the CLI args
     let args = CliArgs::from_args();
     monument_cli::init_logging(args.log_level());
-    let result = monument_cli::run(&args.input_file, &args.options, Environment::Cli);
+    if let Some(SubCommand::Merge {
+        input_files,
+        weights,
+        options,
+    }) = &args.sub_command
+    {
+        let result =
+            monument_cli::merge::run(input_files, weights.as_deref(), options, Environment::Cli);
+        match result {
+            Ok(searches_were_run) => assert!(searches_were_run || options.debug_option.is_some()),
+            Err(e) => exit_with_error(e),
+        }
+        return;
+    }
+
+    if let Some(SubCommand::BuildGraph {
+        input_file,
+        output,
+        options,
+    }) = &args.sub_command
+    {
+        if let Err(e) = monument_cli::build_graph(input_file, output, options) {
+            exit_with_error(e);
+        }
+        return;
+    }
+
+    if let Some(SubCommand::Check {
+        input_file,
+        options,
+    }) = &args.sub_command
+    {
+        if let Err(e) = monument_cli::check::run(input_file, options) {
+            exit_with_error(e);
+        }
+        return;
+    }
+
+    let Some(input_file) = &args.input_file else {
+        structopt::clap::Error::with_description(
+            "The following required arguments were not provided:\n    <input-file>",
+            structopt::clap::ErrorKind::MissingRequiredArgument,
+        )
+        .exit();
+    };
+    // Specifications with queries or method alternatives run several searches, so produce their
+    // own reports
+    match monument_cli::queries::has_queries(input_file) {
+        Ok(true) => {
+            let result = monument_cli::queries::run(input_file, &args.options, Environment::Cli);
+            match result {
+                Ok(searches_were_run) => {
+                    assert!(searches_were_run || args.options.debug_option.is_some())
+                }
+                Err(e) => exit_with_error(e),
+            }
+            return;
+        }
+        Ok(false) => {}
+        Err(e) => exit_with_error(e),
+    }
+    match monument_cli::alternatives::has_method_alternatives(input_file) {
+        Ok(true) => {
+            let result =
+                monument_cli::alternatives::run(input_file, &args.options, Environment::Cli);
+            match result {
+                Ok(searches_were_run) => {
+                    assert!(searches_were_run || args.options.debug_option.is_some())
+                }
+                Err(e) => exit_with_error(e),
+            }
+            return;
+        }
+        Ok(false) => {}
+        Err(e) => exit_with_error(e),
+    }
+    let result = monument_cli::run(input_file, &args.options, Environment::Cli);
 
     // Handle the results, either by doing a debug print or printing an error message
     match result {
@@ -16,14 +96,22 @@ fn main() {
             if !args.options.only_display_update_line {
                 search.print();
             }
+            if let Err(e) = search.write_requested_output(&args.options, &mut std::io::stdout()) {
+                exit_with_error(e);
+            }
         }
         Ok(None) => assert!(args.options.debug_option.is_some()),
-        Err(e) => {
-            // In the case of an error, print the error message nicely then terminate the program
-            // with code -1 without causing a panic message.
-            println!("{} {:?}", "Error:".bright_red().bold(), e);
-            drop(args);
-            std::process::exit(-1);
-        }
+        Err(e) => exit_with_error(e),
+    }
+}
+
+fn exit_with_error(e: anyhow::Error) -> ! {
+    // In the case of an error, print the error message nicely then terminate the program with
+    // code -1 without causing a panic message.  Errors in the specification are printed like
+    // `rustc`'s errors, pointing to the part of the file which caused them.
+    match e.downcast_ref::<SpecError>() {
+        Some(spec_error) => println!("{}", spec_error.to_coloured_string()),
+        None => println!("{} {:?}", "Error:".bright_red().bold(), e),
     }
+    std::process::exit(-1);
 }
@@ -0,0 +1,193 @@
+//! Code for the `merge` subcommand, which runs several specification files and merges their
+//! results into one consolidated report.
+
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+    sync::atomic::Ordering,
+};
+
+use monument::{
+    composition::{merge_results, MergedComposition},
+    Parameters,
+};
+use ringing_utils::PrettyDuration;
+
+use crate::{args::Options, toml_file::TomlFile, Environment, Provenance, SearchResult};
+
+/// Run every search in `toml_paths` and print the merged results.  Returns `Ok(false)` if no
+/// searches were run (e.g. because a `debug_option` was set).
+pub fn run(
+    toml_paths: &[PathBuf],
+    weights_path: Option<&Path>,
+    options: &Options,
+    env: Environment,
+) -> anyhow::Result<bool> {
+    // Run the searches, displaying just the update line for each one
+    let search_options = Options {
+        only_display_update_line: true,
+        ..options.clone()
+    };
+    let abort_flag = crate::abort_flag(&env);
+    let mut results = Vec::new();
+    for path in toml_paths {
+        log::info!("Running {:?}", path);
+        match crate::run_with_abort_flag(path, &search_options, env, &abort_flag)? {
+            Some(result) => results.push(result),
+            None => return Ok(false),
+        }
+        eprintln!();
+        if abort_flag.load(Ordering::SeqCst) {
+            break; // Don't start any more searches if the user pressed ctrl-C
+        }
+    }
+
+    // Determine which weights to use
+    let weights = match weights_path {
+        Some(path) => TomlFile::new(path)?.to_params(path)?,
+        None => results[0].search.parameters().clone(),
+    };
+    let source_names = toml_paths
+        .iter()
+        .map(|p| {
+            p.file_name()
+                .unwrap_or(p.as_os_str())
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect::<Vec<_>>();
+    let searches = toml_paths
+        .iter()
+        .zip(&results)
+        .map(|(path, r)| (path.clone(), r.search.seed()))
+        .collect();
+    print_report(&results, &source_names, &weights, options, searches);
+    Ok(true)
+}
+
+/// Merge the compositions from several [`SearchResult`]s, re-scored using `weights`, and print a
+/// single ranked report.  Each result is labelled with the corresponding name from
+/// `source_names`, and `searches` are shown in the [`Provenance`].
+pub(crate) fn print_report(
+    results: &[SearchResult],
+    source_names: &[String],
+    weights: &Parameters,
+    options: &Options,
+    searches: Vec<(PathBuf, u64)>,
+) {
+    // Merge the results
+    let result_comps = results
+        .iter()
+        .map(|r| r.comps.iter().map(|(c, _)| c.clone()).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let result_sets = results
+        .iter()
+        .zip(&result_comps)
+        .map(|(r, comps)| (r.search.parameters(), comps.as_slice()))
+        .collect::<Vec<_>>();
+    let merged = merge_results(
+        &result_sets,
+        weights,
+        options.score_normalisation.unwrap_or_default(),
+    );
+
+    // Print the report, with the best compositions at the bottom (i.e. closest to the prompt)
+    let source_width = source_names.iter().map(|n| n.len()).max().unwrap_or(0);
+    let music_summaries = merged
+        .iter()
+        .map(|comp| music_summary(comp, weights))
+        .collect::<Vec<_>>();
+    let music_width = music_summaries.iter().map(|s| s.len()).max().unwrap_or(0);
+    eprintln!("\n\n\n\nMERGE COMPLETE!\n\n\n");
+    let header = merged_header(source_width, music_width);
+    println!("{header}");
+    println!("{}", ruleoff(&header));
+    for (comp, music) in merged.iter().zip(&music_summaries).rev() {
+        println!(
+            "{}",
+            merged_comp_string(comp, source_names, source_width, music, music_width)
+        );
+    }
+    println!("{}", ruleoff(&header));
+    println!("{header}");
+    println!("\n{}", source_summary(&merged, source_names, source_width));
+    if !options.dont_display_provenance {
+        println!("\n{}", Provenance::new(options, searches));
+    }
+    let total_duration = results.iter().map(|r| r.duration).sum();
+    eprintln!(
+        "{} unique composition{} merged from {} searches in {}",
+        merged.len(),
+        if merged.len() == 1 { "" } else { "s" }, // Handle "1 composition"
+        results.len(),
+        PrettyDuration(total_duration),
+    );
+}
+
+/// Summarise how many compositions came from each source, along with the score of its best one
+fn source_summary(merged: &[MergedComposition], source_names: &[String], width: usize) -> String {
+    let mut s = format!("{:^width$} | comps |   best", "source");
+    for (source_idx, name) in source_names.iter().enumerate() {
+        let comps = merged.iter().filter(|c| c.source_idx == source_idx);
+        let num_comps = comps.clone().count();
+        // `merged` is sorted best-first, so the first composition is the best
+        let best_score = comps.map(|c| format!("{:>9.6}", c.normalised_score)).next();
+        write!(
+            s,
+            "\n{name:<width$} | {num_comps:>5} | {}",
+            best_score.as_deref().unwrap_or("        -")
+        )
+        .unwrap();
+    }
+    s
+}
+
+fn merged_header(source_width: usize, music_width: usize) -> String {
+    format!(
+        "  len |  music  |   score   | {:^source_width$} | {:^music_width$} | calling",
+        "source", "counts"
+    )
+}
+
+fn ruleoff(header: &str) -> String {
+    // Ruleoff is the same as header, but with every non-'|' char replaced with '-'
+    header
+        .chars()
+        .map(|c| if c == '|' { '|' } else { '-' })
+        .collect()
+}
+
+fn merged_comp_string(
+    comp: &MergedComposition,
+    source_names: &[String],
+    source_width: usize,
+    music_summary: &str,
+    music_width: usize,
+) -> String {
+    let mut s = String::new();
+    write!(
+        s,
+        "{:>5} | {:>7.2} | {:>9.6} | {:<source_width$} | {:<music_width$} | {}",
+        comp.values.length(),
+        comp.values.music_score,
+        comp.normalised_score,
+        source_names[comp.source_idx],
+        music_summary,
+        comp.values.call_string
+    )
+    .unwrap();
+    s
+}
+
+/// Summarise the music in a composition like "4-bell runs: 32 (12f 20b), 5678s: 12"
+fn music_summary(comp: &MergedComposition, weights: &Parameters) -> String {
+    weights
+        .music_types_to_show()
+        .into_iter()
+        .map(|(idx, music_type)| {
+            let counts = music_type.display_counts_unpadded(comp.values.music_counts[idx]);
+            format!("{}: {counts}", music_type.name)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -1,11 +1,13 @@
+use anyhow::Context;
 use bellframe::{
     music::{AtRowPositions, Pattern, RowPosition},
-    Stage,
+    Bell, Stage,
 };
+use itertools::Itertools;
 use monument::parameters::{MusicType, MusicTypeVec};
 use serde::Deserialize;
 
-use crate::utils::OptRangeInclusive;
+use crate::{diagnostic::TomlPath, utils::OptRangeInclusive};
 
 #[derive(Debug, Clone, Copy, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -31,12 +33,18 @@ pub enum TomlMusic {
     RunLength {
         #[serde(rename = "run_length")]
         length: u8,
+        /// If `true`, runs in the middle of rows are also counted
+        #[serde(default)]
+        internal: bool,
         #[serde(flatten)]
         common: MusicCommon,
     },
     RunLengths {
         #[serde(rename = "run_lengths")]
         lengths: Vec<u8>,
+        /// If `true`, runs in the middle of rows are also counted
+        #[serde(default)]
+        internal: bool,
         #[serde(flatten)]
         common: MusicCommon,
     },
@@ -51,7 +59,18 @@ pub enum TomlMusic {
         common: MusicCommon,
     },
     Preset {
-        preset: MusicPreset,
+        /// The name of a [`MusicPreset`].  This is parsed after deserialization (rather than
+        /// deserializing a `MusicPreset` directly) so that typos get a helpful error message instead
+        /// of `serde`'s generic error for untagged enums.
+        preset: String,
+        #[serde(flatten)]
+        common: MusicCommon,
+    },
+    BellPlaces {
+        /// The bell whose position is being scored
+        bell: u8,
+        /// The places (counting from 1) where `bell` scores
+        places: Vec<u8>,
         #[serde(flatten)]
         common: MusicCommon,
     },
@@ -68,6 +87,14 @@ pub struct MusicCommon {
     /// Which strokes this music can apply to
     #[serde(rename = "stroke", default)]
     strokes: StrokeSet,
+    /// If `true`, any chunk containing this music is not a duffer (see `no_duffers`)
+    #[serde(default)]
+    non_duffer: bool,
+    /// The [`Stage`] which this music's bells are written for.  If set, every bell named by this
+    /// music is renumbered to keep the same distance from the tenor on the stage actually being
+    /// rung (so `5678*` on Major becomes `7890*` on Royal).  Runs and presets always adapt to the
+    /// stage, so don't need this.
+    pattern_stage: Option<Stage>,
 
     /// If `true`, the count of this will be displayed in the composition summary line.  Defaults
     /// to `true`.
@@ -84,16 +111,32 @@ impl MusicCommon {
     }
 }
 
-#[derive(Debug, Clone, Copy, Deserialize)]
+#[derive(Debug, Clone, Copy)]
 pub enum MusicPreset {
-    #[serde(rename = "5678 combinations")]
     Combinations5678s,
-    #[serde(rename = "near misses")]
     NearMisses,
-    #[serde(rename = "crus")]
     Crus,
 }
 
+impl MusicPreset {
+    const ALL: [(Self, &'static str); 3] = [
+        (Self::Combinations5678s, "5678 combinations"),
+        (Self::NearMisses, "near misses"),
+        (Self::Crus, "crus"),
+    ];
+
+    fn from_name(name: &str) -> anyhow::Result<Self> {
+        let preset = Self::ALL.iter().find(|(_, n)| *n == name);
+        preset.map(|(preset, _)| *preset).ok_or_else(|| {
+            let names = Self::ALL.iter().map(|(_, n)| *n);
+            anyhow::anyhow!(
+                "Unknown music preset {name:?}.  {}",
+                crate::utils::suggest_or_list(name, names)
+            )
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy, Deserialize)]
 pub struct MusicWeight {
     front: Option<f32>,
@@ -157,8 +200,12 @@ pub fn generate_music(
     if let Some(music_file_toml) = music_file_str {
         music_builder.add_toml(music_file_toml, stage)?;
     }
-    // Explicit music types
-    music_builder.add_musics(toml_musics, stage)?;
+    // Explicit music types, labelled so that errors can point to the right entry
+    for (idx, toml_music) in toml_musics.iter().enumerate() {
+        music_builder
+            .add_musics([toml_music], stage)
+            .context(TomlPath::index("music", idx))?;
+    }
 
     // Generate `MusicDisplay`s necessary to display all the `MusicTypes` we've generated
     Ok(music_builder.finish())
@@ -267,6 +314,8 @@ impl Default for MusicCommon {
             specified_weight: MusicWeight::default(),
             count_range: OptRangeInclusive::default(),
             strokes: StrokeSet::Both,
+            non_duffer: false,
+            pattern_stage: None,
 
             show: true,
             name: None,
@@ -300,32 +349,58 @@ impl From<StrokeSet> for bellframe::StrokeSet {
 
 impl TomlMusic {
     /// Generates a [`MusicType`] representing `self`.
-    fn to_music_types(&self, stage: Stage) -> anyhow::Result<Vec<MusicType>> {
+    pub(crate) fn to_music_types(&self, stage: Stage) -> anyhow::Result<Vec<MusicType>> {
         // This function just delegates the work to one of `music_type_runs`,
         // `music_type_patterns` or `music_type_preset`.
 
         use std::slice::from_ref;
         match self {
-            Self::RunLength { length, common } => {
-                Ok(music_type_runs(from_ref(length), common, stage))
-            }
-            Self::RunLengths { lengths, common } => Ok(music_type_runs(lengths, common, stage)),
+            Self::RunLength {
+                length,
+                internal,
+                common,
+            } => Ok(music_type_runs(from_ref(length), *internal, common, stage)),
+            Self::RunLengths {
+                lengths,
+                internal,
+                common,
+            } => Ok(music_type_runs(lengths, *internal, common, stage)),
             Self::Pattern { pattern, common } => {
                 music_type_patterns(from_ref(pattern), common, stage)
             }
             Self::Patterns { patterns, common } => music_type_patterns(patterns, common, stage),
-            Self::Preset { preset, common } => music_type_preset(*preset, common, stage),
+            Self::Preset { preset, common } => {
+                music_type_preset(MusicPreset::from_name(preset)?, common, stage)
+            }
+            Self::BellPlaces {
+                bell,
+                places,
+                common,
+            } => music_type_bell_places(*bell, places, common, stage),
         }
     }
 }
 
-fn music_type_runs(lengths: &[u8], common: &MusicCommon, stage: Stage) -> Vec<MusicType> {
+fn music_type_runs(
+    lengths: &[u8],
+    internal: bool,
+    common: &MusicCommon,
+    stage: Stage,
+) -> Vec<MusicType> {
+    // Internal runs get counted (and shown separately) by giving them a weight.  Unless one is
+    // given explicitly, they're weighted the same as runs off the front.
+    let mut common = common.clone();
+    if internal {
+        let weights = &mut common.specified_weight;
+        weights.internal = weights.internal.or(weights.front).or(Some(0.0));
+    }
+
     let mut music_types = Vec::new();
     for &len in lengths {
         music_types.push(new_music_type(
             format!("{len}-bell runs"),
             bellframe::MusicType::runs(len, stage),
-            common,
+            &common,
             true,
         ));
     }
@@ -341,7 +416,11 @@ fn music_type_patterns(
     // Parse patterns
     let mut patterns = Vec::new();
     for pattern_string in pattern_strings {
-        patterns.push(Pattern::parse_with_stage(pattern_string, stage)?);
+        let pattern_string = match common.pattern_stage {
+            Some(pattern_stage) => adapt_pattern(pattern_string, pattern_stage, stage)?,
+            None => pattern_string.clone(),
+        };
+        patterns.push(Pattern::parse_with_stage(&pattern_string, stage)?);
     }
     // Create music types
     let mut music_types = Vec::new();
@@ -438,6 +517,93 @@ fn music_type_preset(
     )])
 }
 
+fn music_type_bell_places(
+    bell_number: u8,
+    places: &[u8],
+    common: &MusicCommon,
+    stage: Stage,
+) -> anyhow::Result<Vec<MusicType>> {
+    // Renumber the bell and places so that they're the same distance from the tenor
+    let (bell_number, places) = match common.pattern_stage {
+        Some(pattern_stage) => {
+            let shift = |n: u8| -> anyhow::Result<u8> {
+                let shifted =
+                    n as isize + stage.num_bells() as isize - pattern_stage.num_bells() as isize;
+                u8::try_from(shifted)
+                    .ok()
+                    .filter(|&n| n >= 1)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("Bell {n} on {pattern_stage} has no equivalent on {stage}")
+                    })
+            };
+            let places = places
+                .iter()
+                .map(|&p| shift(p))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            (shift(bell_number)?, places)
+        }
+        None => (bell_number, places.to_vec()),
+    };
+    let places = places.as_slice();
+    let bell = Bell::from_number(bell_number)
+        .filter(|b| stage.contains(*b))
+        .ok_or_else(|| anyhow::anyhow!("Bell {bell_number} isn't in {stage}"))?;
+    if places.is_empty() {
+        anyhow::bail!("Music for the {bell} needs at least one place");
+    }
+    let place_indices = places
+        .iter()
+        .unique()
+        .map(|&place| match place as usize {
+            p @ 1.. if p <= stage.num_bells() => Ok(p - 1),
+            _ => Err(anyhow::anyhow!("Place {place} isn't in {stage}")),
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    // Whole-row patterns always match at the front of the row, so that's the only position which
+    // can be weighted
+    let mut common = common.clone();
+    for pos in [RowPosition::Internal, RowPosition::Back, RowPosition::Wrap] {
+        *common.specified_weight.get_mut(pos) = None;
+    }
+    let default_name = format!("{bell} in {}", places.iter().unique().join("/"));
+    Ok(vec![new_music_type(
+        default_name,
+        bellframe::MusicType::bell_in_places(bell, place_indices, stage),
+        &common,
+        false,
+    )])
+}
+
+/// Renumber every bell in a pattern written for `pattern_stage` so that it's the same distance
+/// from the tenor on `stage`.  For example, adapting `*6578` from Major to Royal gives `*8790`.
+fn adapt_pattern(pattern: &str, pattern_stage: Stage, stage: Stage) -> anyhow::Result<String> {
+    // Check that the pattern actually makes sense on the stage it was written for
+    Pattern::parse_with_stage(pattern, pattern_stage)?;
+
+    let shift = stage.num_bells() as isize - pattern_stage.num_bells() as isize;
+    pattern
+        .chars()
+        .map(|c| match Bell::from_name(c) {
+            Some(bell) => {
+                let new_index = bell.index() as isize + shift;
+                u8::try_from(new_index)
+                    .ok()
+                    .map(Bell::from_index)
+                    .filter(|b| stage.contains(*b))
+                    .and_then(Bell::to_char)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Can't adapt pattern {pattern:?} from {pattern_stage} to {stage}: \
+the {bell} has no equivalent"
+                        )
+                    })
+            }
+            None => Ok(c), // `x`s and `*`s aren't bells, so are kept the same
+        })
+        .collect()
+}
+
 fn new_music_type(
     default_name: String,
     music_type: bellframe::MusicType,
@@ -453,6 +619,7 @@ fn new_music_type(
         show_positions: optional_weights.map(|x| x.is_some() && common.should_show()),
         show_total: (show_total || num_specified_weights == 1) && common.should_show(),
         count_range: common.count_range.into(),
+        non_duffer: common.non_duffer,
         name: match &common.name {
             Some(name) => name.clone(),
             None => default_name.to_owned(),
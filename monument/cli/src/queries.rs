@@ -0,0 +1,268 @@
+//! Code for running specification files with several named `queries`.  Each query is a table of
+//! values which override those at the top level of the file (e.g. to try different music weights
+//! or part heads).  A file can also `sweep` some values over lists of possibilities, which creates
+//! a query for every combination.  A separate search is run for each query, and each query's
+//! results are printed separately, labelled with its name.
+
+use std::{fmt::Write, path::Path, sync::atomic::Ordering};
+
+use colored::Colorize;
+use itertools::Itertools;
+use monument::{composition::ParamsData, Config, Session};
+
+use crate::{args::Options, toml_file::TomlFile, Environment, SearchResult};
+
+/// Returns `true` if the specification file at `toml_path` has any `queries` or a `sweep` (and
+/// therefore should be run with [`run`]).
+pub fn has_queries(toml_path: &Path) -> anyhow::Result<bool> {
+    Ok(TomlFile::new(toml_path)?.has_queries())
+}
+
+/// Run one search for each of the queries in the specification file at `toml_path`, and print
+/// the results of each.  Returns `Ok(false)` if no searches were run (e.g. because a
+/// `debug_option` was set).
+pub fn run(toml_path: &Path, options: &Options, env: Environment) -> anyhow::Result<bool> {
+    let toml_buf = crate::utils::read_file_to_string(toml_path)?;
+    let (queries, is_sweep) = split_queries(&toml_buf)?;
+
+    // Run the searches, displaying just the update line for each one
+    let search_options = Options {
+        only_display_update_line: true,
+        ..options.clone()
+    };
+    let abort_flag = crate::abort_flag(&env);
+    // Share graphs, falseness tables and the method library between the queries
+    let session = Session::new(Config::default());
+    let run_query = |name: &str, toml_file: TomlFile| {
+        let session = Some((&session, name));
+        crate::run_toml_file(
+            toml_file,
+            toml_path,
+            &search_options,
+            env,
+            &abort_flag,
+            session,
+        )
+    };
+    let mut results = Vec::<(String, SearchResult)>::new();
+    if options.parallel_queries {
+        let query_results = std::thread::scope(|scope| {
+            let handles = queries
+                .into_iter()
+                .map(|(name, toml_file)| {
+                    let query_name = name.clone();
+                    let handle = scope.spawn(move || run_query(&query_name, toml_file));
+                    (name, handle)
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|(name, handle)| (name, handle.join().expect("Search thread panicked")))
+                .collect::<Vec<_>>()
+        });
+        eprintln!();
+        for (name, result) in query_results {
+            match result? {
+                Some(result) => results.push((name, result)),
+                None => return Ok(false),
+            }
+        }
+    } else {
+        for (name, toml_file) in queries {
+            match run_query(&name, toml_file)? {
+                Some(result) => results.push((name, result)),
+                None => return Ok(false),
+            }
+            eprintln!();
+            if abort_flag.load(Ordering::SeqCst) {
+                break; // Don't start any more searches if the user pressed ctrl-C
+            }
+        }
+    }
+
+    // Print each query's results, labelled with its name
+    for (name, result) in &mut results {
+        println!("\n{}", format!("Query {name:?}:").bold());
+        result
+            .write_comps(&mut std::io::stdout())
+            .expect("Failed to write to stdout");
+    }
+    // For sweeps, finish by comparing the best composition from each combination of values
+    if is_sweep {
+        println!("\n{}", sweep_summary(&results));
+    }
+    Ok(true)
+}
+
+/// Split the contents of a specification file into one [`TomlFile`] per query, labelled with the
+/// query's name.  Each query's values are merged into the top-level values of the file, then every
+/// combination of `sweep` values is applied to each query.  Also returns whether the file has a
+/// `sweep`.
+pub(crate) fn split_queries(toml_buf: &str) -> anyhow::Result<(Vec<(String, TomlFile)>, bool)> {
+    let mut base = crate::utils::parse_toml::<toml::Table>(toml_buf)?;
+    let queries = match base.remove("queries") {
+        Some(toml::Value::Array(queries)) => queries,
+        Some(_) => anyhow::bail!("`queries` should be an array of tables"),
+        // No queries, so just run the top-level search
+        None => vec![toml::Value::Table(toml::Table::new())],
+    };
+    let sweep = match base.remove("sweep") {
+        Some(toml::Value::Table(sweep)) => Some(sweep),
+        Some(_) => anyhow::bail!("`sweep` should be a table"),
+        None => None,
+    };
+
+    let mut query_values = Vec::new();
+    for (idx, query) in queries.into_iter().enumerate() {
+        let toml::Value::Table(mut query) = query else {
+            anyhow::bail!("Query #{} should be a table", idx + 1);
+        };
+        let name = match query.remove("name") {
+            Some(toml::Value::String(name)) => name,
+            Some(_) => anyhow::bail!("Query #{}'s `name` should be a string", idx + 1),
+            None => format!("#{}", idx + 1),
+        };
+        let mut values = base.clone();
+        merge_tables(&mut values, query);
+        query_values.push((name, values));
+    }
+    if let Some(sweep) = &sweep {
+        query_values = apply_sweep(query_values, sweep)?;
+    }
+
+    let mut toml_files = Vec::new();
+    for (name, values) in query_values {
+        let toml_file = toml::Value::Table(values)
+            .try_into::<TomlFile>()
+            .map_err(|e| {
+                let message = format!("Error parsing query {name:?}: {e}");
+                anyhow::Error::msg(crate::utils::with_suggestion(message, e.message()))
+            })?;
+        toml_files.push((name, toml_file));
+    }
+    Ok((toml_files, sweep.is_some()))
+}
+
+/// Expand every query into one query per combination of the values in `sweep`.  The expanded
+/// queries are named after the values they use (e.g. `bob_weight = -2, music.0.weight = 3`).
+fn apply_sweep(
+    queries: Vec<(String, toml::Table)>,
+    sweep: &toml::Table,
+) -> anyhow::Result<Vec<(String, toml::Table)>> {
+    let mut paths = Vec::new();
+    let mut value_lists = Vec::new();
+    for (path, values) in sweep {
+        match values {
+            toml::Value::Array(values) if !values.is_empty() => {
+                paths.push(path.as_str());
+                value_lists.push(values.clone());
+            }
+            _ => anyhow::bail!("`sweep.{path}` should be a non-empty array of values to try"),
+        }
+    }
+
+    let single_query = queries.len() == 1;
+    let mut swept_queries = Vec::new();
+    for (query_name, query) in queries {
+        for combination in value_lists.iter().multi_cartesian_product() {
+            let mut values = query.clone();
+            let mut names = Vec::new();
+            if !single_query {
+                names.push(query_name.clone());
+            }
+            for (path, value) in paths.iter().zip_eq(combination) {
+                set_value_at_path(&mut values, path, value.clone())?;
+                names.push(format!("{path} = {value}"));
+            }
+            swept_queries.push((names.join(", "), values));
+        }
+    }
+    Ok(swept_queries)
+}
+
+/// Set the value at a `.`-separated `path` (e.g. `music.0.weight`), where numbers index into
+/// arrays.  Missing table entries are created, but arrays must already be long enough.
+fn set_value_at_path(
+    table: &mut toml::Table,
+    path: &str,
+    value: toml::Value,
+) -> anyhow::Result<()> {
+    let err = |key: &str| anyhow::anyhow!("Can't sweep `{path}`: there's no value at `{key}`");
+
+    let mut keys = path.split('.').collect_vec();
+    let last_key = keys.pop().unwrap();
+    let mut root = toml::Value::Table(std::mem::take(table));
+    let mut current = &mut root;
+    for key in keys {
+        current = child_value(current, key).ok_or_else(|| err(key))?;
+    }
+    *child_value(current, last_key).ok_or_else(|| err(last_key))? = value;
+    let toml::Value::Table(root) = root else {
+        unreachable!()
+    };
+    *table = root;
+    Ok(())
+}
+
+/// Get a mutable reference to the value with a given `key` inside `value` (which must be a table
+/// or an array), creating missing table entries
+fn child_value<'v>(value: &'v mut toml::Value, key: &str) -> Option<&'v mut toml::Value> {
+    match value {
+        toml::Value::Table(t) => Some(
+            t.entry(key)
+                .or_insert_with(|| toml::Value::Table(toml::Table::new())),
+        ),
+        toml::Value::Array(values) => values.get_mut(key.parse::<usize>().ok()?),
+        _ => None,
+    }
+}
+
+/// A table of the best composition generated by each query
+fn sweep_summary(results: &[(String, SearchResult)]) -> String {
+    let width = results
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    let mut s = format!(
+        "{:^width$} | comps | len |  music  |   score   | calling",
+        "query"
+    );
+    for (name, result) in results {
+        let params_data = ParamsData::new(result.search.parameters());
+        // Compositions are sorted best-last
+        let best = result
+            .comps
+            .last()
+            .and_then(|(c, _)| c.values(&params_data));
+        write!(s, "\n{name:<width$} | {:>5} | ", result.comps.len()).unwrap();
+        match best {
+            Some(c) => write!(
+                s,
+                "{:>3} | {:>7.2} | {:>9.6} | {}",
+                c.length(),
+                c.music_score,
+                c.score_per_row(),
+                c.call_string
+            )
+            .unwrap(),
+            None => s.push_str("  - |       - |         - |"),
+        }
+    }
+    s
+}
+
+/// Merge the values from `overrides` into `base`.  Tables are merged recursively, but any other
+/// value (including arrays) is replaced.
+fn merge_tables(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(override_table)) => {
+                merge_tables(base_table, override_table)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
@@ -1,32 +1,38 @@
 use std::{
-    collections::HashMap,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
     fmt::Write,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use bellframe::{
-    method::LABEL_LEAD_END, method_lib::SearchError, place_not::PnBlockParseError, Bell, Mask,
-    MethodLib, RowBuf, Stage, Stroke,
+    method::{class::Class, LABEL_LEAD_END},
+    method_lib::SearchError,
+    place_not::PnBlockParseError,
+    Bell, Mask, MethodLib, RowBuf, Stage, Stroke,
 };
 use colored::Colorize;
 use index_vec::index_vec;
 use itertools::Itertools;
 use monument::{
     parameters::{
-        BaseCallType, CallDisplayStyle, CallId, CallVec, MethodId, MethodVec, MusicType,
-        MusicTypeVec, OptionalRangeInclusive, Parameters, DEFAULT_BOB_WEIGHT,
-        DEFAULT_SINGLE_WEIGHT,
+        default_shorthand, BaseCallType, CallDisplayStyle, CallId, CallVec, CallingFilter,
+        MethodId, MethodVec, MusicType, MusicTypeVec, Parameters, Ranking, ShortlistMetric,
+        TargetLength, DEFAULT_BOB_WEIGHT, DEFAULT_SINGLE_WEIGHT,
     },
-    utils::IdGenerator,
-    Config, PartHeadGroup,
+    utils::{Boundary, IdGenerator, TotalLength},
+    Config, ErrorLocation, PartHeadGroup,
 };
+use regex::Regex;
 use serde::Deserialize;
 
 use crate::{
     calls::{BaseCalls, CustomCall},
+    diagnostic::{SpecError, TomlPath},
     music::{BaseMusic, TomlMusic},
-    utils::OptRangeInclusive,
+    utils::MethodCountRange,
 };
 
 use self::length::Length;
@@ -54,13 +60,63 @@ pub struct TomlFile {
     /// Allow Monument to ignore falseness and generate false compositions.  Compositions still
     /// won't have internal rounds.
     allow_false: Option<bool>, // Anti-alias of `require_truth`, deprecated in v0.13.0
+    /// The maximum number of times any row can be rung.  Defaults to `1` (i.e. true compositions)
+    #[serde(default = "default_max_row_repeats")]
+    max_row_repeats: usize,
     /// A [`Row`] which generates the part heads of this composition
     #[serde(default)]
     part_head: String,
+    /// If `true`, chunks which are always beaten by an interchangeable chunk will be removed
+    #[serde(default)]
+    prune_dominated_chunks: bool,
+    /// If set, Monument won't output compositions with a lower total score
+    min_total_score: Option<f32>,
+    /// If set, Monument won't output compositions with a lower music score
+    min_music_score: Option<f32>,
+    /// If set, Monument won't output compositions with more calls
+    max_calls: Option<usize>,
+    /// If set, Monument won't output compositions which are harder to call
+    min_conductability: Option<f32>,
+    /// If set, Monument will keep searching after finding `num_comps` compositions, keeping only
+    /// the best `num_comps` by this metric
+    shortlist: Option<Shortlist>,
+    /// The key used to rank compositions, both during the search and in the final output
+    ranking: Option<TomlRanking>,
+    /// Score applied to every row of a composition
+    #[serde(default)]
+    row_weight: f32,
+    /// If set, compositions get a bonus for being close to a given length
+    target_length: Option<TomlTargetLength>,
+    /// If `true`, Monument will generate every composition which satisfies the other parameters,
+    /// ignoring `num_comps` and `shortlist`
+    #[serde(default)]
+    exhaustive: bool,
+    /// If `true`, Monument will use a Monte-Carlo tree search rather than a best-first search
+    #[serde(default)]
+    monte_carlo: bool,
+
+    /// Named variations of this search, each of which overrides some of the values in this file.
+    /// A separate search is run for each query (see [`crate::queries`])
+    #[serde(default)]
+    queries: Vec<toml::Table>,
+    /// Maps paths of values in this file (e.g. `bob_weight` or `music.0.weight`) to lists of
+    /// values to try.  A separate search is run for every combination (see [`crate::queries`])
+    sweep: Option<toml::Table>,
 
     /* CONFIG OPTIONS */
     /// If set, overrides `--graph-size-limit` CLI argument
     graph_size_limit: Option<usize>,
+    /// If `true`, the search uses `--a-star` even if the CLI argument isn't given
+    #[serde(default)]
+    a_star: bool,
+    /// Used if the `--seed` CLI argument isn't given
+    seed: Option<u64>,
+    /// Used if the `--temperature` CLI argument isn't given
+    temperature: Option<f32>,
+    /// Used if the `--start-chunk-time-budget` CLI argument isn't given
+    start_chunk_time_budget: Option<f32>,
+    /// Used if the `--min-call-distance` CLI argument isn't given
+    min_call_distance: Option<usize>,
 
     /* METHODS */
     /// The method who's compositions we are after
@@ -68,18 +124,41 @@ pub struct TomlFile {
     #[serde(default)]
     /// A list of methods to be spliced together
     methods: Vec<TomlMethod>,
+    /// Custom methods which can be referred to by name or title in `method`/`methods`
+    #[serde(default)]
+    method_library: Vec<LibraryMethod>,
+    /// Path to a file of extra methods for `method_library`, relative to **this** TOML file.  This
+    /// is either in the CCCBR's XML format, or a JSON list of methods written like
+    /// `method_library`
+    method_library_file: Option<PathBuf>,
+    /// Adds every method from the CC library which matches this filter, alongside
+    /// `method`/`methods`
+    method_filter: Option<MethodFilter>,
+    /// Candidate methods for one extra method 'slot'.  A separate search is run for each of these,
+    /// using it alongside `method`/`methods`
+    #[serde(default)]
+    method_alternatives: Vec<TomlMethod>,
     /// At which locations method splices are allowed
     #[serde(default)]
     splice_style: SpliceStyle,
     /// Score which is applied for every change of method.  Defaults to `0.0`
     #[serde(default)]
     splice_weight: f32,
+    /// Scores which override `splice_weight` for splices from one method to another.  A weight of
+    /// `-inf` bans that splice
+    #[serde(default)]
+    splice_weights: Vec<SpliceWeight>,
     /// Bounds on how many rows of each method is allowed
     #[serde(default)]
-    method_count: OptRangeInclusive,
+    method_count: MethodCountRange,
+    /// The units in which method counts are given and reported
+    #[serde(default)]
+    method_count_unit: MethodCountUnit,
     /// Set to `true` to allow comps to not start at the lead head.
     #[serde(default)]
     snap_start: bool,
+    /// If set, compositions can only end at rows of this stroke
+    end_stroke: Option<Stroke>,
     /// Which indices within a lead should the composition be allowed to start.  If unspecified,
     /// then all locations are allowed.  All indices are taken modulo each method's lead length (so
     /// 2, -30 and 34 are all equivalent for Treble Dodging Major).
@@ -120,8 +199,19 @@ pub struct TomlFile {
     #[serde(default)]
     calls: Vec<CustomCall>,
     /// Sets the bell who's position will be used to determine calling positions.  Defaults to the
-    /// heaviest bell
+    /// heaviest bell which isn't moved by the part head
     calling_bell: Option<u8>,
+    /// Renames applied to every call's calling positions (e.g. `{ B = "O" }`)
+    #[serde(default)]
+    calling_position_names: HashMap<char, char>,
+    /// If set, calls can only be made at these calling positions (e.g. `"WMH"`)
+    allowed_calling_positions: Option<String>,
+    /// How the calling of multi-part compositions should be displayed
+    #[serde(default)]
+    multipart_call_display: MultipartCallDisplay,
+    /// If `true`, check that every call does something sensible at every row it can be placed
+    #[serde(default)]
+    strict_labels: bool,
 
     /* MUSIC */
     /// Adds preset music patterns to the scoring.  If you truly want no music (e.g. to search for
@@ -133,6 +223,17 @@ pub struct TomlFile {
     /// Specification of which classes of music Monument should consider
     #[serde(default)]
     music: Vec<TomlMusic>,
+    /// If `true`, Monument will remove every chunk which doesn't contain any `non_duffer` music
+    #[serde(default)]
+    no_duffers: bool,
+    /// The maximum number of consecutive rows in chunks without any `non_duffer` music
+    max_duffer_rows: Option<usize>,
+    /// Which music stops a chunk from being a duffer
+    #[serde(default)]
+    non_duffer_music: NonDufferMusic,
+    /// Rows which must appear somewhere in every composition (e.g. Queens)
+    #[serde(default)]
+    required_rows: Vec<String>,
     /// The [`Stroke`] of the first row of the composition
     #[serde(default = "crate::utils::handstroke")]
     start_stroke: Stroke,
@@ -152,6 +253,12 @@ pub struct TomlFile {
     /// Weight given to every row in a course, for every handbell pair that's coursing
     #[serde(default)]
     handbell_coursing_weight: f32,
+    /// Score applied to every row in every course where some bells are in a given coursing order
+    #[serde(default)]
+    coursing_order_weights: Vec<CoursingOrderWeightPattern>,
+    /// If `true`, compositions can't leave a course and then come back to it later
+    #[serde(default)]
+    no_repeated_courses: bool,
     /// If set, Monument will only output compositions which have this call sequence.  For example,
     /// "HHsWsHsW" will output only compositions with the classic 1282 Yorkshire/Cambridge Royal
     /// calling.
@@ -161,6 +268,12 @@ pub struct TomlFile {
     /// "WWWHHH" would generate "", "WWW", "HHH" and "WWWHHH").
     #[serde(default)]
     omit_round_blocks: bool,
+    /// If set, Monument will only output compositions who's call string matches this regex (e.g.
+    /// `"sH$"` to require compositions to end with `sH`).
+    calling_matches: Option<String>,
+    /// If set, Monument will only output compositions who's call string **doesn't** match this
+    /// regex (e.g. `"[sT]"` to forbid singles and calls at Before).
+    calling_excludes: Option<String>,
     /// The [`Row`] which starts the composition.  When computing falseness and music, this **is**
     /// considered included in the composition.
     #[serde(default)] // The default/empty string parses to rounds on any stage
@@ -170,19 +283,85 @@ pub struct TomlFile {
     /// reached.
     #[serde(default)] // The default/empty string parses to rounds on any stage
     end_row: String,
+
+    /// The TOML source which this was parsed from, if known.  Used to point to the parts of the
+    /// file which cause errors (see [`crate::diagnostic`])
+    #[serde(skip)]
+    source: Option<String>,
 }
 
 impl TomlFile {
     /// Load and parse a `TomlFile` structure from a TOML file
     pub fn new(toml_path: &Path) -> anyhow::Result<Self> {
         let toml_buf = crate::utils::read_file_to_string(toml_path)?;
-        crate::utils::parse_toml(&toml_buf)
+        Self::parse(&toml_buf, toml_path)
+    }
+
+    /// Parse a `TomlFile` from a string of TOML which was loaded from `toml_path`.  Unlike
+    /// [`crate::utils::parse_toml`], errors in this `TomlFile` will point to the part of the TOML
+    /// which caused them.
+    pub fn parse(toml: &str, toml_path: &Path) -> anyhow::Result<Self> {
+        let mut toml_file =
+            toml::from_str::<Self>(toml).map_err(|e| SpecError::toml_parse(e, toml, toml_path))?;
+        toml_file.source = Some(toml.to_owned());
+        Ok(toml_file)
+    }
+
+    /// The TOML source which this was parsed from, if known
+    pub(crate) fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// Returns `true` if this file specifies several searches, through `queries` or a `sweep`
+    pub fn has_queries(&self) -> bool {
+        !self.queries.is_empty() || self.sweep.is_some()
+    }
+
+    /// The number of methods in `method_alternatives`
+    pub fn num_method_alternatives(&self) -> usize {
+        self.method_alternatives.len()
+    }
+
+    /// Fill the method 'slot' with the `idx`th of the `method_alternatives`, so that this
+    /// `TomlFile` specifies just one search.
+    pub fn choose_method_alternative(&mut self, idx: usize) {
+        let method = self.method_alternatives.swap_remove(idx);
+        self.method_alternatives.clear();
+        self.methods.push(method);
     }
 
     /// Build a set of [`Parameters`] from this `TomlFile`
     pub fn to_params(&self, toml_path: &Path) -> anyhow::Result<Parameters> {
+        let cc_lib =
+            bellframe::MethodLib::cc_lib().expect("Couldn't load Central Council method library");
+        self.to_params_with_method_lib(toml_path, &cc_lib)
+    }
+
+    /// Same as [`TomlFile::to_params`], but looking up methods in an already-loaded copy of the
+    /// Central Council method library
+    pub fn to_params_with_method_lib(
+        &self,
+        toml_path: &Path,
+        cc_lib: &MethodLib,
+    ) -> anyhow::Result<Parameters> {
         log::debug!("Generating params");
 
+        if !self.queries.is_empty() {
+            anyhow::bail!(
+                "`queries` run several searches, so can only be used from the command line"
+            );
+        }
+        if self.sweep.is_some() {
+            anyhow::bail!(
+                "`sweep` runs several searches, so can only be used from the command line"
+            );
+        }
+        if !self.method_alternatives.is_empty() {
+            anyhow::bail!(
+                "`method_alternatives` runs several searches, so can only be used from the command line"
+            );
+        }
+
         // Error on deprecated paramaters
         if self.allow_false.is_some() {
             anyhow::bail!("`allow_false` has been replaced with `require_truth`");
@@ -190,15 +369,35 @@ impl TomlFile {
         if self.course_heads.is_some() {
             anyhow::bail!("`course_heads` has been renamed to `courses`");
         }
+        if self.max_row_repeats == 0 {
+            anyhow::bail!("`max_row_repeats` must be at least 1");
+        }
+        if self.exhaustive && self.monte_carlo {
+            anyhow::bail!("`exhaustive` and `monte_carlo` can't both be set");
+        }
 
-        let cc_lib =
-            bellframe::MethodLib::cc_lib().expect("Couldn't load Central Council method library");
         // Build the methods first so that we can compute the overall `Stage` *before* parsing
         // everything else.
-        let all_methods = self.methods.iter().chain(self.method.as_ref());
+        let mut method_library = self
+            .method_library
+            .iter()
+            .map(|m| method_from_place_notation(&m.name, &m.place_notation, m.stage))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if let Some(relative_path) = &self.method_library_file {
+            method_library.extend(load_method_library_file(toml_path, relative_path)?);
+        }
+        let all_methods = (self.methods.iter().enumerate())
+            .map(|(idx, m)| (m, TomlPath::index("methods", idx)))
+            .chain(self.method.as_ref().map(|m| (m, TomlPath::key("method"))));
         let mut parsed_methods = Vec::new();
-        for m in all_methods {
-            parsed_methods.push((m.as_bellframe_method(&cc_lib)?, m.common()));
+        for (m, toml_path) in all_methods {
+            let method = m
+                .as_bellframe_method(cc_lib, &method_library)
+                .context(toml_path)?;
+            parsed_methods.push((method, m.common()));
+        }
+        if let Some(filter) = &self.method_filter {
+            filter.add_methods(cc_lib, &mut parsed_methods)?;
         }
         // Compute the stage so we can use it to help with parsing the rest of the file
         let stage = parsed_methods
@@ -212,48 +411,206 @@ impl TomlFile {
         let part_head = parse_row("part head", &self.part_head, stage)?;
 
         let calling_bell = match self.calling_bell {
-            Some(v) => Bell::from_number(v).ok_or_else(|| {
-                anyhow::Error::msg("Invalid calling bell: bell number 0 doesn't exist.")
-            })?,
-            None => stage.tenor(),
+            Some(v) => {
+                let bell = Bell::from_number(v).ok_or_else(|| {
+                    anyhow::Error::msg("Invalid calling bell: bell number 0 doesn't exist.")
+                })?;
+                if !part_head.is_fixed(bell) {
+                    log::warn!(
+                        "The part head moves calling bell {bell}, so calls will be displayed positionally"
+                    );
+                }
+                Some(bell)
+            }
+            // If the part head moves the tenor, then observe the heaviest bell which the part head
+            // keeps fixed.  If there isn't one (e.g. in a cyclic), then calls can't be described
+            // by calling positions
+            None => stage
+                .bells()
+                .rev()
+                .find(|&b| b != Bell::TREBLE && part_head.is_fixed(b)),
         };
         // TODO: Make this configurable
         // TODO: Move this into `lib/`
+        let calling_bell = calling_bell.unwrap_or(stage.tenor());
         let call_display_style = if part_head.is_fixed(calling_bell) {
             CallDisplayStyle::CallingPositions
         } else {
             CallDisplayStyle::Positional
         };
 
+        let music_types = self.music(toml_path, stage)?;
+        let methods = self.build_methods(parsed_methods, &part_head, stage)?;
+        let calls = self.calls(stage, &methods)?;
+        let allowed_calling_positions = self.allowed_calling_positions(&calls)?;
         let params = monument::parameters::Parameters {
-            length: self.length.as_total_length_range(),
+            length: self.length.as_total_length_range(stage),
             stage,
             num_comps: self.num_comps,
             require_truth: self.require_truth,
-
-            methods: self.build_methods(parsed_methods, &part_head, stage)?,
+            max_row_repeats: self.max_row_repeats,
+            extent: self.length.is_extent(),
+            prune_dominated_chunks: self.prune_dominated_chunks,
+            min_total_score: self.min_total_score,
+            min_music_score: self.min_music_score,
+            max_calls: self.max_calls,
+            min_conductability: self.min_conductability,
+            shortlist: self.shortlist(&music_types)?,
+            ranking: match self.ranking {
+                None | Some(TomlRanking::AvgScore) => Ranking::AvgScore,
+                Some(TomlRanking::TotalScore) => Ranking::TotalScore,
+                Some(TomlRanking::MusicPerRow) => Ranking::MusicPerRow,
+            },
+            row_weight: self.row_weight,
+            target_length: self.target_length.map(|t| TargetLength {
+                length: TotalLength::new(t.length),
+                bonus: t.bonus,
+                falloff: t.falloff,
+            }),
+
+            splice_weights: self.splice_weights(&methods)?,
+            methods,
             splice_style: self.splice_style.into(),
             splice_weight: self.splice_weight,
+            method_counts_in_leads: self.method_count_unit == MethodCountUnit::Leads,
             atw_weight: self.atw_weight,
             require_atw: self.require_atw,
 
-            calls: self.calls(stage)?,
+            calls,
             call_display_style,
             calling_bell,
+            calling_position_names: self.calling_position_names.clone(),
+            allowed_calling_positions,
+            multipart_call_display: self.multipart_call_display.into(),
+            strict_labels: self.strict_labels,
 
             part_head_group: PartHeadGroup::new(&part_head),
             start_row: parse_row("start row", &self.start_row, stage)?,
             end_row: parse_row("end row", &self.end_row, stage)?,
             course_weights: self.course_weights(stage)?,
+            no_repeated_courses: self.no_repeated_courses,
             calling: self.calling.clone(),
             omit_round_blocks: self.omit_round_blocks,
-
-            music_types: self.music(toml_path, stage)?,
+            calling_filters: self.calling_filters()?,
+
+            music_types,
+            no_duffers: self.no_duffers,
+            max_duffer_rows: self.max_duffer_rows,
+            non_duffer_music: self.non_duffer_music.into(),
+            required_rows: self
+                .required_rows
+                .iter()
+                .map(|s| parse_row("required row", s, stage))
+                .collect::<anyhow::Result<_>>()?,
             start_stroke: self.start_stroke,
         };
         Ok(params)
     }
 
+    fn shortlist(
+        &self,
+        music_types: &MusicTypeVec<MusicType>,
+    ) -> anyhow::Result<Option<ShortlistMetric>> {
+        Ok(match &self.shortlist {
+            None => None,
+            Some(Shortlist::TotalScore) => Some(ShortlistMetric::TotalScore),
+            Some(Shortlist::Music(name)) => {
+                let idx = music_types.position(|ty| &ty.name == name).ok_or_else(|| {
+                    let names = music_types
+                        .iter()
+                        .filter(|ty| !ty.name.is_empty())
+                        .map(|ty| ty.name.as_str());
+                    anyhow!(
+                        "Can't shortlist by music {name:?}: no music type has that name.  {}",
+                        crate::utils::suggest_or_list(name, names)
+                    )
+                })?;
+                Some(ShortlistMetric::MusicCount(idx))
+            }
+        })
+    }
+
+    fn splice_weights(
+        &self,
+        methods: &MethodVec<monument::parameters::Method>,
+    ) -> anyhow::Result<HashMap<(MethodId, MethodId), f32>> {
+        let find_method = |name: &str| find_method_id(name, "splice_weights", methods);
+        let mut splice_weights = HashMap::new();
+        for SpliceWeight { from, to, weight } in &self.splice_weights {
+            splice_weights.insert((find_method(from)?, find_method(to)?), *weight);
+        }
+        Ok(splice_weights)
+    }
+
+    /// Parse `allowed_calling_positions`, checking that every position belongs to at least one
+    /// of the `calls` (after applying `calling_position_names`).
+    fn allowed_calling_positions(
+        &self,
+        calls: &CallVec<monument::parameters::Call>,
+    ) -> anyhow::Result<Option<Vec<char>>> {
+        let Some(positions) = &self.allowed_calling_positions else {
+            return Ok(None);
+        };
+        let known_positions = calls
+            .iter()
+            .flat_map(|call| &call.calling_positions)
+            .map(|pos| {
+                self.calling_position_names
+                    .get(pos)
+                    .unwrap_or(pos)
+                    .to_string()
+            })
+            .unique()
+            .collect_vec();
+        let mut allowed_positions = Vec::new();
+        for pos in positions.chars().filter(|c| !c.is_whitespace()) {
+            if !known_positions.contains(&pos.to_string()) {
+                return Err(anyhow!(
+                    "Unknown calling position {pos:?} in `allowed_calling_positions`.  {}",
+                    crate::utils::suggest_or_list(
+                        &pos.to_string(),
+                        known_positions.iter().map(String::as_str)
+                    )
+                ));
+            }
+            allowed_positions.push(pos);
+        }
+        Ok(Some(allowed_positions))
+    }
+
+    fn calling_filters(&self) -> anyhow::Result<Vec<CallingFilter>> {
+        let filters = [
+            ("calling_matches", &self.calling_matches, true),
+            ("calling_excludes", &self.calling_excludes, false),
+        ];
+        let mut calling_filters = Vec::new();
+        for (name, regex, must_match) in filters {
+            if let Some(regex) = regex {
+                let regex =
+                    Regex::new(regex).map_err(|e| anyhow!("Invalid regex for `{name}`: {e}"))?;
+                calling_filters.push(CallingFilter { regex, must_match });
+            }
+        }
+        Ok(calling_filters)
+    }
+
+    pub fn exhaustive(&self) -> bool {
+        self.exhaustive
+    }
+
+    pub fn monte_carlo(&self) -> bool {
+        self.monte_carlo
+    }
+
+    /// Returns `true` if `ranking` is set, in which case it should also be used to sort the output
+    pub fn has_ranking(&self) -> bool {
+        self.ranking.is_some()
+    }
+
+    pub fn min_call_distance(&self) -> Option<usize> {
+        self.min_call_distance
+    }
+
     pub fn should_print_atw(&self) -> bool {
         self.atw_weight.is_some() && !self.require_atw
     }
@@ -262,6 +619,18 @@ impl TomlFile {
         let mut config = Config {
             thread_limit: opts.num_threads,
             mem_limit: opts.mem_limit,
+            queue_limit: opts.queue_limit,
+            max_memory_bytes: opts.max_memory_bytes,
+            truncation_policy: opts.truncation_policy,
+            auto_tune: opts.auto_tune,
+            start_chunk_time_budget: opts
+                .start_chunk_time_budget
+                .or(self.start_chunk_time_budget)
+                .map(Duration::from_secs_f32),
+            a_star: opts.a_star || self.a_star,
+            seed: opts.seed.or(self.seed),
+            temperature: opts.temperature.or(self.temperature),
+            collect_stats: opts.stats,
             leak_search_memory,
             ..Default::default()
         };
@@ -272,13 +641,17 @@ impl TomlFile {
     }
 
     /// Also check that `bobs_only` and `singles_only` aren't set at the same time.
-    fn calls(&self, stage: Stage) -> anyhow::Result<CallVec<monument::parameters::Call>> {
+    fn calls(
+        &self,
+        stage: Stage,
+        methods: &MethodVec<monument::parameters::Method>,
+    ) -> anyhow::Result<CallVec<monument::parameters::Call>> {
         let mut call_id_generator = IdGenerator::<CallId>::starting_at_zero();
         // Convert base calls
         let mut calls = self.base_calls(&mut call_id_generator, stage)?;
         // Convert custom calls
         for custom_call in &self.calls {
-            calls.push(custom_call.as_monument_call(call_id_generator.next(), stage)?);
+            calls.push(custom_call.as_monument_call(call_id_generator.next(), stage, methods)?);
         }
         Ok(calls)
     }
@@ -322,6 +695,43 @@ impl TomlFile {
         ))
     }
 
+    /// Find the value in this `TomlFile` which specified the method or music type at `location`
+    /// in `params` (which must have been built from this `TomlFile`).  Returns `None` if it didn't
+    /// come from this file (e.g. music from `base_music`).
+    pub(crate) fn toml_path_of(
+        &self,
+        location: ErrorLocation,
+        params: &Parameters,
+    ) -> Option<TomlPath> {
+        match location {
+            ErrorLocation::Method(idx) => match idx.index().cmp(&self.methods.len()) {
+                Ordering::Less => Some(TomlPath::index("methods", idx.index())),
+                Ordering::Equal if self.method.is_some() => Some(TomlPath::key("method")),
+                _ => None, // Added by `method_filter`
+            },
+            ErrorLocation::MusicType(idx) => {
+                // Types from `music` are added last, and each entry can generate several types
+                let counts = (self.music.iter())
+                    .map(|m| {
+                        m.to_music_types(params.stage)
+                            .map_or(0, |types| types.len())
+                    })
+                    .collect_vec();
+                // `params` might not have been built from this file, in which case the counts
+                // needn't add up
+                let first_idx = (params.music_types.len()).checked_sub(counts.iter().sum())?;
+                let mut idx = idx.index().checked_sub(first_idx)?;
+                for (entry_idx, count) in counts.into_iter().enumerate() {
+                    if idx < count {
+                        return Some(TomlPath::index("music", entry_idx));
+                    }
+                    idx -= count;
+                }
+                None
+            }
+        }
+    }
+
     fn music(&self, toml_path: &Path, stage: Stage) -> anyhow::Result<MusicTypeVec<MusicType>> {
         // Load TOML for the music file
         let music_file_str = match &self.music_file {
@@ -360,6 +770,25 @@ impl TomlFile {
                 }
             }
         }
+        // Coursing order weights
+        for pattern in &self.coursing_order_weights {
+            use CoursingOrderWeightPattern::*;
+            let (coursing_orders, weight) = match pattern {
+                CoursingOrder {
+                    coursing_order,
+                    weight,
+                } => (std::slice::from_ref(coursing_order), weight),
+                CoursingOrders {
+                    coursing_orders,
+                    weight,
+                } => (coursing_orders.as_slice(), weight),
+            };
+            for order in coursing_orders {
+                for mask in coursing_order_masks(order, stage)? {
+                    course_weights.push((mask, *weight));
+                }
+            }
+        }
         Ok(course_weights)
     }
 
@@ -375,6 +804,12 @@ impl TomlFile {
             let (ch_masks, weight) = match pattern {
                 Pattern { pattern, weight } => (std::slice::from_ref(pattern), weight),
                 Patterns { patterns, weight } => (patterns.as_slice(), weight),
+                Preset { preset, weight } => {
+                    for mask in preset.course_head_masks(stage)? {
+                        weights.push((mask, *weight));
+                    }
+                    continue;
+                }
             };
             // Add the patterns
             for mask_str in ch_masks {
@@ -406,38 +841,77 @@ impl TomlFile {
 
         /* DEFAULT VALUES */
 
-        let default_method_count = OptionalRangeInclusive::from(self.method_count);
-        let default_start_indices = match &self.start_indices {
-            Some(indices) => indices.clone(),
-            // TODO: Compute actual snaps for multi-treble-dodge methods
-            None if self.snap_start => vec![2],
-            None => vec![0],
-        };
+        let num_parts = PartHeadGroup::new(part_head).size();
+
         let default_allowed_courses = match &self.courses {
             // If the user specifies some courses, use them
             Some(ch_strings) => parse_masks("course mask", ch_strings, stage)?,
             // If the user specifies no courses but sets `split_tenors` then allow every course
             None if self.split_tenors => vec![Mask::any(stage)],
-            // If no courses are set, fix any bell >=7 which aren't affected by the part head.
-            // Usually this will be either all (e.g. 1-part or a part head of `1342` or `124365`)
-            // or all (e.g. cyclic), but any other combinations are possible.  E.g. a composition
-            // of Maximus with part head of `1765432` will still preserve 8 through 12.
+            // If no courses are set, fix any bell >=7 which the part head keeps within the
+            // tenors.  Usually this will be either all (e.g. 1-part or a part head of `1342` or
+            // `124365`) or none (e.g. cyclic), but any other combinations are possible.  E.g. a
+            // composition of Maximus with part head of `1765432` will still preserve 8 through 12.
+            // Tenors which the part head swaps amongst themselves (e.g. `12345687`) stay fixed,
+            // and `lib/` will add the corresponding courses for the other parts.
             None => {
-                let tenors_unaffected_by_part_head =
-                    stage.bells().skip(6).filter(|&b| part_head.is_fixed(b));
-                vec![Mask::with_fixed_bells(
-                    stage,
-                    tenors_unaffected_by_part_head,
-                )]
+                let part_heads = part_head.closure();
+                let tenors_kept_by_part_head = stage
+                    .bells()
+                    .skip(6)
+                    .filter(|b| part_heads.iter().all(|ph| ph[b.index()].index() >= 6));
+                vec![Mask::with_fixed_bells(stage, tenors_kept_by_part_head)]
             }
         };
 
+        // Only the methods flagged with `start`/`end` can start/end the composition, unless no
+        // methods are flagged
+        let any_start_methods = parsed_methods.iter().any(|(_, common)| common.start);
+        let any_end_methods = parsed_methods.iter().any(|(_, common)| common.end);
+        // Start indices, with per-method values overriding global ones
+        let start_indices_per_method = parsed_methods
+            .iter()
+            .map(|(method, common)| {
+                match (
+                    &common.start_indices,
+                    common.snap_start,
+                    &self.start_indices,
+                ) {
+                    (Some(indices), _, _) => indices.clone(),
+                    (None, Some(snap_start), _) => default_start_indices(method, snap_start),
+                    (None, None, Some(indices)) => indices.clone(),
+                    (None, None, None) => default_start_indices(method, self.snap_start),
+                }
+            })
+            .collect_vec();
+        // Which stroke each end index is on depends on how many rows have been rung since the
+        // start, so `end_stroke` only works if that is consistent between different starts
+        let uses_end_stroke =
+            self.end_stroke.is_some() || parsed_methods.iter().any(|(_, c)| c.end_stroke.is_some());
+        let start_idx_for_strokes = match uses_end_stroke {
+            true => Some(start_idx_for_strokes(
+                &parsed_methods,
+                &start_indices_per_method,
+            )?),
+            false => None,
+        };
+        // Labels used by calls, which are inferred for any methods which don't define them
+        let mut call_labels = Vec::new();
+        for call in &self.calls {
+            let (label_from, label_to) = call.labels(stage)?;
+            call_labels.extend([label_from, label_to]);
+        }
+        call_labels.sort();
+        call_labels.dedup();
+
         /* BUILD METHODS */
 
         let id_gen = IdGenerator::<MethodId>::starting_at_zero();
         let mut methods = MethodVec::new();
         // TODO: Add dummy unused method, to make sure that Monument handles them correctly
-        for (mut method, common) in parsed_methods {
+        for ((mut method, common), start_indices) in
+            parsed_methods.into_iter().zip_eq(start_indices_per_method)
+        {
             let lead_len_isize = method.lead_len() as isize;
             let wrap_idxs = |idxs: Vec<isize>| -> Vec<usize> {
                 let mut wrapped_idxs = Vec::new();
@@ -466,29 +940,67 @@ impl TomlFile {
                     method.add_label(idx, label.clone());
                 }
             }
+            for label in &call_labels {
+                if method.label_indices(label).next().is_some() {
+                    continue; // Labels given explicitly take precedence
+                }
+                if let Some(indices) = crate::calls::infer_label_indices(label, &method)? {
+                    for idx in indices {
+                        method.add_label(idx, label.clone());
+                    }
+                }
+            }
             // Build method
             let allowed_courses = match common.courses {
                 Some(ch_strings) => parse_masks("course mask", &ch_strings, stage)?,
                 None => default_allowed_courses.clone(),
             };
-            let start_indices = common
-                .start_indices
-                .unwrap_or_else(|| default_start_indices.clone());
-            let end_indices = common
+            let mut end_indices = common
                 .end_indices
                 .unwrap_or_else(|| match &self.end_indices {
                     Some(idxs) => idxs.clone(),
                     None => (0..method.lead_len() as isize).collect_vec(),
                 });
+            if let (Some(end_stroke), Some(start_idx)) =
+                (common.end_stroke.or(self.end_stroke), start_idx_for_strokes)
+            {
+                let start_row_stroke = !self.start_stroke;
+                end_indices.retain(|&idx| start_row_stroke.offset_i(idx - start_idx) == end_stroke);
+            }
+            let lead_len = method.lead_len();
+            let length_range = self.length.range(stage);
+            let part_length_range =
+                length_range.start() / num_parts..=length_range.end() / num_parts;
+            let counts_in_leads = self.method_count_unit == MethodCountUnit::Leads;
+            let count_range = common
+                .count_range
+                .as_rows(lead_len, &length_range, counts_in_leads)
+                .or(self
+                    .method_count
+                    .as_rows(lead_len, &length_range, counts_in_leads));
+            let count_range_per_part =
+                common
+                    .count_range_per_part
+                    .as_rows(lead_len, &part_length_range, counts_in_leads);
             methods.push(monument::parameters::Method {
                 id: id_gen.next(),
                 inner: method,
 
                 custom_shorthand: common.shorthand.unwrap_or_default(),
-                count_range: OptionalRangeInclusive::from(common.count_range)
-                    .or(default_method_count),
+                count_range,
+                count_range_per_part,
                 start_indices,
                 end_indices,
+                // Link methods at the start/end of each part must be able to start/end the
+                // composition, because that's the only place they can be rung
+                can_start: common.start
+                    || common.link == Some(LinkPosition::Start)
+                    || !any_start_methods,
+                can_end: common.end || common.link == Some(LinkPosition::End) || !any_end_methods,
+                link_position: common.link.map(|position| match position {
+                    LinkPosition::Start => Boundary::Start,
+                    LinkPosition::End => Boundary::End,
+                }),
                 allowed_courses: vec![monument::parameters::CourseSet::from(allowed_courses)],
             });
         }
@@ -496,6 +1008,86 @@ impl TomlFile {
     }
 }
 
+/// Find the [`MethodId`] of the method whose title or shorthand is `name`, or return an error
+/// (mentioning the TOML `field` where `name` was written) if there's no such method.
+pub(crate) fn find_method_id(
+    name: &str,
+    field: &str,
+    methods: &MethodVec<monument::parameters::Method>,
+) -> anyhow::Result<MethodId> {
+    let method = methods
+        .iter()
+        .find(|m| m.shorthand() == name || m.title() == name)
+        .ok_or_else(|| {
+            let titles = methods.iter().map(|m| m.title());
+            let shorthands = methods.iter().map(|m| m.shorthand()).collect_vec();
+            let names = titles.chain(shorthands.iter().cloned()).collect_vec();
+            match ringing_utils::closest_match(name, names.iter().map(String::as_str)) {
+                Some(suggestion) => {
+                    anyhow!("Unknown method {name:?} in `{field}`.  Did you mean {suggestion:?}?")
+                }
+                None => anyhow!(
+                    "Unknown method {name:?} in `{field}`.  Expected the title or shorthand of one of {}",
+                    shorthands.iter().map(|s| format!("{s:?}")).join(", ")
+                ),
+            }
+        })?;
+    Ok(method.id)
+}
+
+/// Generate a set of course head masks which, when expanded to every lead head in their course,
+/// match every lead where the bells in `coursing_order` are coursing in that (cyclic) order.  For
+/// example, `53246` will match any course where the bells `2` to `6` are in the same coursing order
+/// as the plain course, regardless of where the other bells are.
+///
+/// The masks assume Plain Bob lead heads, and each course generates exactly one matching mask per
+/// lead (so the weight isn't applied multiple times).
+fn coursing_order_masks(coursing_order: &str, stage: Stage) -> anyhow::Result<Vec<Mask>> {
+    // Parse the bells
+    let mut bells = Vec::<Bell>::new();
+    for c in coursing_order.chars() {
+        let bell = Bell::from_name(c)
+            .filter(|b| *b <= stage.tenor())
+            .ok_or_else(|| anyhow!("Invalid bell {c:?} in coursing order {coursing_order:?}"))?;
+        if bell == Bell::TREBLE {
+            anyhow::bail!("Coursing order {coursing_order:?} can't contain the treble");
+        }
+        if bells.contains(&bell) {
+            anyhow::bail!("Bell {c:?} appears twice in coursing order {coursing_order:?}");
+        }
+        bells.push(bell);
+    }
+    let (first_bell, other_bells) = match bells.split_first() {
+        Some(v) => v,
+        None => anyhow::bail!("Coursing orders can't be empty"),
+    };
+
+    // The places which make up the coursing order of a lead head (e.g. `2468753` for Major)
+    let num_bells = stage.num_bells();
+    let coursing_places = (1..num_bells)
+        .step_by(2)
+        .chain((2..num_bells).step_by(2).rev())
+        .collect_vec();
+    // Moving to the next lead head rotates the coursing order along the coursing places, so we
+    // only generate masks where `first_bell` is in the first coursing place.  Each of these gets
+    // expanded to cover all of its course, so all possible rotations of the coursing order will
+    // be matched exactly once.
+    let mut masks = Vec::new();
+    for places in coursing_places[1..]
+        .iter()
+        .copied()
+        .combinations(other_bells.len())
+    {
+        let mut mask = Mask::empty(stage);
+        mask.set_bell(*first_bell, coursing_places[0]).unwrap();
+        for (&bell, place) in other_bells.iter().zip_eq(places) {
+            mask.set_bell(bell, place).unwrap();
+        }
+        masks.push(mask);
+    }
+    Ok(masks)
+}
+
 fn parse_row(name: &str, s: &str, stage: Stage) -> Result<RowBuf, anyhow::Error> {
     RowBuf::parse_with_stage(s, stage)
         .map_err(|e| anyhow::Error::msg(format!("Can't parse {} {:?}: {}", name, s, e)))
@@ -521,8 +1113,96 @@ fn parse_mask(mask_kind: &str, string: &str, stage: Stage) -> anyhow::Result<Mas
 #[derive(Debug, Deserialize)]
 #[serde(untagged, deny_unknown_fields)]
 pub enum CourseWeightPattern {
-    Pattern { pattern: String, weight: f32 },
-    Patterns { patterns: Vec<String>, weight: f32 },
+    Pattern {
+        pattern: String,
+        weight: f32,
+    },
+    Patterns {
+        patterns: Vec<String>,
+        weight: f32,
+    },
+    Preset {
+        preset: CourseWeightPreset,
+        weight: f32,
+    },
+}
+
+/// Named sets of musical course heads, generated for whichever [`Stage`] is being rung
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub enum CourseWeightPreset {
+    /// Course heads which are rounds with one pair of adjacent working bells swapped
+    /// (e.g. `12354678`)
+    #[serde(rename = "near misses")]
+    NearMisses,
+    /// Course heads with `65` in front of the tenors (e.g. `*6578` on Major)
+    #[serde(rename = "65s")]
+    SixFives,
+    /// The Queens course head (e.g. `13572468` on Major)
+    #[serde(rename = "queens")]
+    Queens,
+    /// The Whittingtons course head (e.g. `12753468` on Major)
+    #[serde(rename = "whittingtons")]
+    Whittingtons,
+}
+
+impl CourseWeightPreset {
+    /// Generate the course head [`Mask`]s matched by this preset
+    fn course_head_masks(self, stage: Stage) -> anyhow::Result<Vec<Mask>> {
+        let bell = |n: u8| Bell::from_number(n).unwrap();
+        let num_bells = stage.num_bells_u8();
+        let rows: Vec<Vec<Bell>> = match self {
+            Self::NearMisses => (1..stage.num_bells() - 1)
+                .map(|swap_idx| {
+                    let mut bells = stage.bells().collect_vec();
+                    bells.swap(swap_idx, swap_idx + 1);
+                    bells
+                })
+                .collect_vec(),
+            Self::SixFives => {
+                if stage < Stage::TRIPLES {
+                    anyhow::bail!("Can't have 65s course heads on less than 7 bells");
+                }
+                // `*6578...` is a course head pattern, not a full row, so is handled separately
+                let pattern = format!("*65{}", stage.bells().skip(6).join(""));
+                return Ok(vec![Mask::parse_with_stage(&pattern, stage).unwrap()]);
+            }
+            Self::Queens => {
+                if !stage.is_even() {
+                    anyhow::bail!("Queens course heads only make sense for even stages");
+                }
+                let odds = (1..=num_bells).step_by(2);
+                let evens = (2..=num_bells).step_by(2);
+                vec![odds.chain(evens).map(bell).collect_vec()]
+            }
+            Self::Whittingtons => {
+                if !stage.is_even() || stage < Stage::MAJOR {
+                    anyhow::bail!("Whittingtons course heads only make sense for Major and above");
+                }
+                // Rounds at the front, then the last 6 bells in the order e.g. `753468`
+                let a = num_bells - 5;
+                let mut bells = (1..a).map(bell).collect_vec();
+                bells.extend([a + 4, a + 2, a, a + 1, a + 3, a + 5].map(bell));
+                vec![bells]
+            }
+        };
+        Ok(rows
+            .into_iter()
+            .map(|bells| Mask::from_bells(bells.into_iter().map(Some)).unwrap())
+            .collect_vec())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged, deny_unknown_fields)]
+pub enum CoursingOrderWeightPattern {
+    CoursingOrder {
+        coursing_order: String,
+        weight: f32,
+    },
+    CoursingOrders {
+        coursing_orders: Vec<String>,
+        weight: f32,
+    },
 }
 
 /// The contents of the `[method]` header in the input TOML file
@@ -538,12 +1218,23 @@ pub enum TomlMethod {
     Custom {
         name: String,
         place_notation: String,
-        stage: Stage,
+        /// If not given, this is inferred from the place notation
+        stage: Option<Stage>,
         #[serde(flatten)]
         common: MethodCommon,
     },
 }
 
+/// A custom method in the `method_library`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LibraryMethod {
+    name: String,
+    place_notation: String,
+    /// If not given, this is inferred from the place notation
+    stage: Option<Stage>,
+}
+
 /// Common values for all method variants
 #[derive(Debug, Clone, Default, Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -552,7 +1243,10 @@ pub struct MethodCommon {
 
     /// Optional override for method count range
     #[serde(default, rename = "count")]
-    count_range: OptRangeInclusive,
+    count_range: MethodCountRange,
+    /// Optional range for how much of this method must be rung in each part
+    #[serde(default, rename = "count_per_part")]
+    count_range_per_part: MethodCountRange,
     /// Maps labels to where in the lead they occur
     labels: Option<HashMap<String, LeadLabels>>,
     /// Deprecated name for `labels` (deprecated since `v0.11.0`)
@@ -562,6 +1256,56 @@ pub struct MethodCommon {
     course_heads: Option<Vec<String>>, // Deprecated spelling of `courses`
     start_indices: Option<Vec<isize>>,
     end_indices: Option<Vec<isize>>,
+    /// Overrides the global `snap_start` for this method
+    snap_start: Option<bool>,
+    /// Overrides the global `end_stroke` for this method
+    end_stroke: Option<Stroke>,
+    /// If any method sets `start = true`, then only those methods can start the composition
+    #[serde(default)]
+    start: bool,
+    /// If any method sets `end = true`, then only those methods can end the composition
+    #[serde(default)]
+    end: bool,
+    /// If set, this is a link method which can only be rung for one lead at the start or end of
+    /// each part
+    link: Option<LinkPosition>,
+}
+
+/// The units in which method counts are written (when no unit is given) and displayed
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MethodCountUnit {
+    #[default]
+    Rows,
+    Leads,
+}
+
+/// Where in each part a link method can be rung
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkPosition {
+    Start,
+    End,
+}
+
+/// Which music stops a chunk from being a duffer
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NonDufferMusic {
+    /// Only music with `non_duffer = true`
+    #[default]
+    Marked,
+    /// Any music which adds to the score, as well as music with `non_duffer = true`
+    Scoring,
+}
+
+impl From<NonDufferMusic> for monument::parameters::NonDufferMusic {
+    fn from(music: NonDufferMusic) -> Self {
+        match music {
+            NonDufferMusic::Marked => monument::parameters::NonDufferMusic::Marked,
+            NonDufferMusic::Scoring => monument::parameters::NonDufferMusic::Scoring,
+        }
+    }
 }
 
 /// The different styles of spliced that can be generated.
@@ -591,6 +1335,164 @@ impl Default for SpliceStyle {
     }
 }
 
+/// How the calling of multi-part compositions should be displayed
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MultipartCallDisplay {
+    #[default]
+    FirstPart,
+    Repeated,
+    Expanded,
+}
+
+impl From<MultipartCallDisplay> for monument::parameters::MultipartCallDisplay {
+    fn from(display: MultipartCallDisplay) -> Self {
+        match display {
+            MultipartCallDisplay::FirstPart => Self::FirstPart,
+            MultipartCallDisplay::Repeated => Self::Repeated,
+            MultipartCallDisplay::Expanded => Self::Expanded,
+        }
+    }
+}
+
+/// Score applied to every splice from one method to another
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpliceWeight {
+    /// Title or shorthand of the method before the splice
+    from: String,
+    /// Title or shorthand of the method after the splice
+    to: String,
+    weight: f32,
+}
+
+/// A filter over the CC library, used to add many methods at once
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MethodFilter {
+    stage: Stage,
+    /// Name of the method class (e.g. `"Surprise"` or `"Treble Bob"`)
+    class: Option<String>,
+    lead_head_code: Option<String>,
+}
+
+impl MethodFilter {
+    /// Adds every method in `cc_lib` which matches this filter to `parsed_methods`, skipping any
+    /// methods which have already been added.  Each new method is given a unique shorthand.
+    fn add_methods(
+        &self,
+        cc_lib: &MethodLib,
+        parsed_methods: &mut Vec<(bellframe::Method, MethodCommon)>,
+    ) -> anyhow::Result<()> {
+        const CLASSES: [Class; 9] = [
+            Class::Principle,
+            Class::Place,
+            Class::Bob,
+            Class::TrebleBob,
+            Class::Delight,
+            Class::Surprise,
+            Class::TreblePlace,
+            Class::Alliance,
+            Class::Hybrid,
+        ];
+
+        let class = match &self.class {
+            Some(name) => Some(Class::from_name(name).ok_or_else(|| {
+                anyhow!(
+                    "Unknown method class {name:?} in `method_filter`.  {}",
+                    crate::utils::suggest_or_list(name, CLASSES.iter().map(|c| c.name()))
+                )
+            })?),
+            None => None,
+        };
+        let filter = bellframe::method_lib::MethodFilter {
+            stage: Some(self.stage),
+            class,
+            lead_head_code: self.lead_head_code.clone(),
+        };
+        let methods = cc_lib.search(&filter);
+        if methods.is_empty() {
+            anyhow::bail!("No methods in the CC library match `method_filter`");
+        }
+
+        let mut used_shorthands = parsed_methods
+            .iter()
+            .map(|(m, common)| {
+                common
+                    .shorthand
+                    .clone()
+                    .unwrap_or_else(|| default_shorthand(&m.title()))
+            })
+            .collect::<HashSet<_>>();
+        let num_methods_before = parsed_methods.len();
+        for method in methods {
+            let title = method.title();
+            if parsed_methods.iter().any(|(m, _)| m.title() == title) {
+                continue; // Method was already given explicitly
+            }
+            let shorthand = unique_shorthand(&method.name, &used_shorthands);
+            used_shorthands.insert(shorthand.clone());
+            let common = MethodCommon {
+                shorthand: Some(shorthand),
+                ..MethodCommon::default()
+            };
+            parsed_methods.push((method, common));
+        }
+        log::info!(
+            "Added {} methods from `method_filter`",
+            parsed_methods.len() - num_methods_before
+        );
+        Ok(())
+    }
+}
+
+/// Returns the shortest prefix of `name` which isn't in `used_shorthands`, adding numbers if every
+/// prefix is taken.
+fn unique_shorthand(name: &str, used_shorthands: &HashSet<String>) -> String {
+    let name = name.replace(' ', "");
+    let prefixes = name
+        .char_indices()
+        .skip(1)
+        .map(|(idx, _)| name[..idx].to_owned())
+        .chain(std::iter::once(name.clone()));
+    let numbered = (2..).map(|n| format!("{name}{n}"));
+    prefixes
+        .chain(numbered)
+        .find(|s| !s.is_empty() && !used_shorthands.contains(s))
+        .unwrap()
+}
+
+/// Which metric is used to choose the compositions in the shortlist
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Shortlist {
+    /// Keep the compositions with the highest total score
+    TotalScore,
+    /// Keep the compositions with the most of the music type with this name
+    Music(String),
+}
+
+/// The key used to rank compositions
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TomlRanking {
+    AvgScore,
+    TotalScore,
+    MusicPerRow,
+}
+
+/// A bonus for compositions which are close to a target length
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TomlTargetLength {
+    /// The ideal length of a composition
+    length: usize,
+    /// The score given to compositions of exactly `length` rows
+    bonus: f32,
+    /// How many rows away from `length` a composition can be before it gets no bonus
+    falloff: usize,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(untagged)]
 pub enum LeadLabels {
@@ -610,25 +1512,37 @@ impl LeadLabels {
 const NUM_METHOD_SUGGESTIONS: usize = 10;
 
 impl TomlMethod {
-    fn as_bellframe_method(&self, cc_lib: &MethodLib) -> anyhow::Result<bellframe::Method> {
+    fn as_bellframe_method(
+        &self,
+        cc_lib: &MethodLib,
+        method_library: &[bellframe::Method],
+    ) -> anyhow::Result<bellframe::Method> {
         match self {
-            TomlMethod::JustTitle(title) | TomlMethod::FromCcLib { title, .. } => cc_lib
-                .get_by_title_with_suggestions(title, NUM_METHOD_SUGGESTIONS)
-                .map_err(|error| match error {
-                    SearchError::PnParseErr { pn, error } => {
-                        panic!("Error parsing {pn} in CCCBR library: {error}")
-                    }
-                    SearchError::NotFound(suggestions) => {
-                        anyhow::Error::msg(method_suggestion_message(title, suggestions))
-                    }
-                }),
+            // Methods in the file's own library take precedence over the CC library
+            TomlMethod::JustTitle(title) | TomlMethod::FromCcLib { title, .. } => {
+                let library_method = method_library
+                    .iter()
+                    .find(|m| &m.name == title || &m.title() == title);
+                if let Some(method) = library_method {
+                    return Ok(method.clone());
+                }
+                cc_lib
+                    .get_by_title_with_suggestions(title, NUM_METHOD_SUGGESTIONS)
+                    .map_err(|error| match error {
+                        SearchError::PnParseErr { pn, error } => {
+                            panic!("Error parsing {pn} in CCCBR library: {error}")
+                        }
+                        SearchError::NotFound(suggestions) => {
+                            anyhow::Error::msg(method_suggestion_message(title, suggestions))
+                        }
+                    })
+            }
             TomlMethod::Custom {
                 name,
                 place_notation,
                 stage,
                 common: _,
-            } => bellframe::Method::from_place_not_string(name.to_owned(), *stage, place_notation)
-                .map_err(|error| anyhow::Error::msg(pn_parse_err_msg(name, place_notation, error))),
+            } => method_from_place_notation(name, place_notation, *stage),
         }
     }
 
@@ -641,6 +1555,123 @@ impl TomlMethod {
     }
 }
 
+/// Parse a custom method from its place notation, inferring its [`Stage`] if it isn't given
+fn method_from_place_notation(
+    name: &str,
+    place_notation: &str,
+    stage: Option<Stage>,
+) -> anyhow::Result<bellframe::Method> {
+    let stage = match stage {
+        Some(stage) => stage,
+        None => infer_stage(place_notation).ok_or_else(|| {
+            anyhow!("Can't work out the stage of {name:?} from its place notation.  Try setting `stage`.")
+        })?,
+    };
+    bellframe::Method::from_place_not_string(name.to_owned(), stage, place_notation)
+        .map_err(|error| anyhow::Error::msg(pn_parse_err_msg(name, place_notation, error)))
+}
+
+/// Load the methods in a `method_library_file`.  Files ending in `.xml` are read as CCCBR XML, and
+/// anything else is read as a JSON list of [`LibraryMethod`]s.
+fn load_method_library_file(
+    toml_path: &Path,
+    relative_path: &Path,
+) -> anyhow::Result<Vec<bellframe::Method>> {
+    let mut path = toml_path
+        .parent()
+        .expect("files should always have a parent")
+        .to_owned();
+    path.push(relative_path);
+    let file_str = crate::utils::read_file_to_string(&path)?;
+
+    let is_xml = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("xml"));
+    if is_xml {
+        let library = MethodLib::from_cccbr_xml(&file_str)
+            .map_err(|e| anyhow!("Error parsing method library {path:?}: {e}"))?;
+        library.all_methods().map_err(|(title, e)| {
+            anyhow!("Error parsing place notation of {title:?} in {path:?}: {e}")
+        })
+    } else {
+        let library_methods: Vec<LibraryMethod> = serde_json::from_str(&file_str)
+            .map_err(|e| anyhow!("Error parsing method library {path:?}: {e}"))?;
+        library_methods
+            .iter()
+            .map(|m| method_from_place_notation(&m.name, &m.place_notation, m.stage))
+            .collect()
+    }
+}
+
+/// The start indices used when `start_indices` isn't given, i.e. either just the snap start or
+/// just the lead end.  Methods without a snap (e.g. plain methods) can't have snap starts.
+fn default_start_indices(method: &bellframe::Method, snap_start: bool) -> Vec<isize> {
+    match snap_start {
+        true => snap_start_index(method)
+            .map(|idx| idx as isize)
+            .into_iter()
+            .collect(),
+        false => vec![0],
+    }
+}
+
+/// Returns the index of the snap start in `method`, i.e. the last row where the treble leads
+/// before it hunts out of 1-2 (e.g. `2` for Surprise Major, or `4` if the treble double dodges).
+/// Returns `None` if the treble doesn't lead more than once at the start of the lead.
+fn snap_start_index(method: &bellframe::Method) -> Option<usize> {
+    if method.lead_head().place_of(Bell::TREBLE) != 0 {
+        return None; // Snaps only make sense if the treble is a hunt bell
+    }
+    let mut snap_idx = None;
+    for (idx, row) in method.first_lead().rows().enumerate() {
+        match row.place_of(Bell::TREBLE) {
+            0 if idx > 0 => snap_idx = Some(idx),
+            0 | 1 => {}
+            _ => break,
+        }
+    }
+    snap_idx
+}
+
+/// Returns the start index which the strokes of `end_indices` should be computed from.  This is
+/// only possible if every start index has the same parity and every lead has an even length, so
+/// that the number of rows rung between any start and end row has a consistent parity.
+fn start_idx_for_strokes(
+    parsed_methods: &[(bellframe::Method, MethodCommon)],
+    start_indices_per_method: &[Vec<isize>],
+) -> anyhow::Result<isize> {
+    if let Some((method, _)) = parsed_methods.iter().find(|(m, _)| m.lead_len() % 2 != 0) {
+        anyhow::bail!(
+            "Can't use `end_stroke` with {:?}, because its lead length is odd.  Try setting `end_indices` instead.",
+            method.title()
+        );
+    }
+    let all_start_indices = start_indices_per_method.iter().flatten().copied();
+    match all_start_indices.map(|idx| idx.rem_euclid(2)).dedup().at_most_one() {
+        Ok(parity) => Ok(parity.unwrap_or(0)),
+        Err(_) => anyhow::bail!(
+            "Can't use `end_stroke` when starts are on different strokes.  Try setting `end_indices` instead."
+        ),
+    }
+}
+
+/// Guess the [`Stage`] of some place notation, assuming that it explicitly makes the highest place
+/// at least once (usually at the lead end or half-lead).  Cross changes can only happen on even
+/// stages, so if the notation contains any then an odd highest place is rounded up.
+fn infer_stage(place_notation: &str) -> Option<Stage> {
+    let highest_place = place_notation
+        .chars()
+        .filter_map(Bell::from_name)
+        .map(|b| b.number())
+        .max()?;
+    let has_cross = place_notation.chars().any(|c| matches!(c, 'x' | 'X' | '-'));
+    let num_bells = match has_cross && highest_place % 2 == 1 {
+        true => highest_place + 1,
+        false => highest_place,
+    };
+    Some(Stage::new(num_bells))
+}
+
 /////////////////
 // NON DUFFERS //
 /////////////////
@@ -693,6 +1724,7 @@ mod length {
         ops::{Range, RangeInclusive},
     };
 
+    use bellframe::Stage;
     use monument::utils::TotalLength;
     use serde::{
         de::{Error, MapAccess, Visitor},
@@ -706,19 +1738,35 @@ mod length {
     pub const HALF_PEAL: RangeInclusive<usize> = 2500..=2600;
     pub const PEAL: RangeInclusive<usize> = 5000..=5200;
 
-    /// A new-typed range with human-friendly deserialisation
+    /// A range of lengths with human-friendly deserialisation
     #[derive(Debug, Clone)]
-    #[repr(transparent)]
-    pub(super) struct Length {
-        pub(super) range: RangeInclusive<usize>,
+    pub(super) enum Length {
+        Range(RangeInclusive<usize>),
+        /// Exactly one extent, whose length depends on the [`Stage`]
+        Extent,
     }
 
     impl Length {
-        pub(super) fn as_total_length_range(&self) -> RangeInclusive<TotalLength> {
-            let start = TotalLength::new(*self.range.start());
-            let end = TotalLength::new(*self.range.end());
+        pub(super) fn range(&self, stage: Stage) -> RangeInclusive<usize> {
+            match self {
+                Length::Range(range) => range.clone(),
+                Length::Extent => {
+                    let extent_len = (1..=stage.num_bells()).product::<usize>();
+                    extent_len..=extent_len
+                }
+            }
+        }
+
+        pub(super) fn as_total_length_range(&self, stage: Stage) -> RangeInclusive<TotalLength> {
+            let range = self.range(stage);
+            let start = TotalLength::new(*range.start());
+            let end = TotalLength::new(*range.end());
             start..=end
         }
+
+        pub(super) fn is_extent(&self) -> bool {
+            matches!(self, Length::Extent)
+        }
     }
 
     /////////////
@@ -728,23 +1776,21 @@ mod length {
     impl From<usize> for Length {
         #[inline(always)]
         fn from(v: usize) -> Length {
-            Length { range: v..=v }
+            Length::Range(v..=v)
         }
     }
 
     impl From<Range<usize>> for Length {
         #[inline(always)]
         fn from(r: Range<usize>) -> Length {
-            Length {
-                range: r.start..=r.end - 1,
-            }
+            Length::Range(r.start..=r.end - 1)
         }
     }
 
     impl From<RangeInclusive<usize>> for Length {
         #[inline(always)]
         fn from(range: RangeInclusive<usize>) -> Length {
-            Length { range }
+            Length::Range(range)
         }
     }
 
@@ -755,7 +1801,7 @@ mod length {
 
         fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
             formatter.write_str(
-                r#"a positive integer, a 'min/max' range, "practice", "qp", "half peal" or "peal""#,
+                r#"a positive integer, a 'min/max' range, "practice", "qp", "half peal", "peal" or "extent""#,
             )
         }
 
@@ -821,6 +1867,7 @@ mod length {
                 "quarter peal" => QP,
                 "peal" => PEAL,
                 "half peal" => HALF_PEAL,
+                "extent" => return Ok(Length::Extent),
                 _ => return Err(E::custom(format!("unknown length name '{}'", v))),
             }))
         }
@@ -959,3 +2006,7 @@ fn mask_parse_error(
 fn default_num_comps() -> usize {
     100
 }
+
+fn default_max_row_repeats() -> usize {
+    1
+}
@@ -0,0 +1,253 @@
+//! An optional full-screen display of a running search (enabled with `--tui`), which shows live
+//! statistics about the search and the best compositions found so far.  Key presses can abort the
+//! search or change its controls (see [`Search::set_num_comps`] and [`Search::set_queue_limit`])
+//! while it's running.
+
+use std::{
+    fmt::Write as _,
+    io::{IsTerminal, Read, Write},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use colored::Colorize;
+use monument::{
+    composition::ParamsData, display::CompositionPrinter, Composition, Progress, Search, Update,
+};
+use ordered_float::OrderedFloat;
+use ringing_utils::{BigNumInt, PrettyDuration};
+
+/// How many of the best compositions are shown
+const NUM_COMPS_SHOWN: usize = 20;
+/// The shortest time between two redraws of the screen
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+/// How much `+` and `-` change `num_comps` by
+const NUM_COMPS_STEP: usize = 10;
+
+const KEY_HELP: &str = "q: abort   +/-: more/fewer comps   ]/[: double/halve queue limit";
+
+/// Full-screen replacement for [`SingleLineProgressLogger`](crate::logging::SingleLineProgressLogger).
+/// The terminal is restored when this is dropped.
+pub struct Tui {
+    search: Arc<Search>,
+    comp_printer: CompositionPrinter,
+
+    /// The best compositions so far (by average score), best first, along with their score
+    best_comps: Vec<(OrderedFloat<f32>, String)>,
+    last_progress: Progress,
+    /// The length of the queue in the last [`Progress`] update.  This is shared with the thread
+    /// reading key presses, so that the queue limit can be halved even if there isn't one yet.
+    queue_len: Arc<AtomicUsize>,
+
+    start_time: Instant,
+    last_draw: Option<Instant>,
+    _raw_mode: Option<raw_mode::RawMode>,
+}
+
+impl Tui {
+    /// Take over the terminal to display `search`, or return `None` if the output isn't going to
+    /// a terminal
+    pub fn new(
+        search: Arc<Search>,
+        comp_printer: CompositionPrinter,
+        abort_flag: Arc<AtomicBool>,
+    ) -> Option<Self> {
+        if !std::io::stderr().is_terminal() {
+            return None;
+        }
+        let queue_len = Arc::new(AtomicUsize::new(0));
+        // Only read key presses if we can stop them being echoed over the display
+        let raw_mode = raw_mode::RawMode::enable();
+        if raw_mode.is_some() {
+            spawn_key_reader(search.clone(), abort_flag, queue_len.clone());
+        }
+        eprint!("\x1b[?1049h\x1b[?25l"); // Switch to the alternate screen and hide the cursor
+        Some(Self {
+            search,
+            comp_printer,
+
+            best_comps: Vec::new(),
+            last_progress: Progress::START,
+            queue_len,
+
+            start_time: Instant::now(),
+            last_draw: None,
+            _raw_mode: raw_mode,
+        })
+    }
+
+    /// Handle an [`Update`] from the search, returning the [`Composition`] if one was generated
+    pub fn log(&mut self, update: Update, generation_index: usize) -> Option<Composition> {
+        let comp = match update {
+            Update::Comp(comp) => {
+                self.add_comp(&comp, generation_index);
+                Some(comp)
+            }
+            Update::Progress(progress) => {
+                self.last_progress = progress;
+                self.queue_len.store(progress.queue_len, Ordering::Relaxed);
+                None
+            }
+            Update::NoComps(_) | Update::Stats(_) | Update::Complete => None,
+        };
+        if self
+            .last_draw
+            .is_none_or(|t| t.elapsed() >= REDRAW_INTERVAL)
+        {
+            self.draw();
+        }
+        comp
+    }
+
+    fn add_comp(&mut self, comp: &Composition, generation_index: usize) {
+        let values = comp.values(&ParamsData::new(self.search.parameters()));
+        let score = OrderedFloat(values.map_or(f32::MIN, |v| v.score_per_row()));
+        let idx = self.best_comps.partition_point(|(s, _)| *s >= score);
+        if idx < NUM_COMPS_SHOWN {
+            let line = self.comp_printer.comp_string(comp, generation_index);
+            self.best_comps.insert(idx, (score, line));
+            self.best_comps.truncate(NUM_COMPS_SHOWN);
+        }
+    }
+
+    fn draw(&mut self) {
+        let progress = &self.last_progress;
+        let queue_limit = match self.search.queue_limit() {
+            Some(limit) => BigNumInt(limit).to_string(),
+            None => "none".to_owned(),
+        };
+
+        let mut s = "\x1b[H\x1b[2J".to_owned(); // Move to the top-left and clear the screen
+        writeln!(s, "{}   {KEY_HELP}\n", "Monument".bold()).unwrap();
+        writeln!(
+            s,
+            "   elapsed: {}",
+            PrettyDuration(self.start_time.elapsed())
+        )
+        .unwrap();
+        writeln!(s, "iterations: {}", BigNumInt(progress.iter_count)).unwrap();
+        writeln!(
+            s,
+            "     comps: {} / {}",
+            progress.num_comps,
+            self.search.num_comps()
+        )
+        .unwrap();
+        writeln!(
+            s,
+            "     queue: {} (limit {queue_limit}), avg/max length {:.0}/{}",
+            BigNumInt(progress.queue_len),
+            progress.avg_length,
+            progress.max_length
+        )
+        .unwrap();
+        writeln!(
+            s,
+            "    memory: {} (queue {}B, graph {}B)",
+            crate::logging::memory_string(progress),
+            BigNumInt(progress.queue_bytes),
+            BigNumInt(progress.graph_bytes),
+        )
+        .unwrap();
+        if progress.truncating_queue {
+            writeln!(s, "{}", "Truncating queue...".yellow()).unwrap();
+        }
+        if progress.aborting {
+            writeln!(s, "{}", "Aborting...".red()).unwrap();
+        }
+        writeln!(s, "\nBest compositions so far:").unwrap();
+        writeln!(s, "{}", self.comp_printer.header_lines()).unwrap();
+        for (_score, line) in &self.best_comps {
+            writeln!(s, "{line}").unwrap();
+        }
+
+        let mut stderr = std::io::stderr().lock();
+        write!(stderr, "{s}").unwrap();
+        stderr.flush().unwrap();
+        self.last_draw = Some(Instant::now());
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        eprint!("\x1b[?25h\x1b[?1049l"); // Show the cursor and return to the normal screen
+    }
+}
+
+/// Spawn a thread which changes the search's controls when keys are pressed.  This thread blocks
+/// on `stdin` forever, so lives until Monument exits.
+fn spawn_key_reader(search: Arc<Search>, abort_flag: Arc<AtomicBool>, queue_len: Arc<AtomicUsize>) {
+    std::thread::spawn(move || {
+        let mut stdin = std::io::stdin();
+        let mut buf = [0u8];
+        while let Ok(1) = stdin.read(&mut buf) {
+            match buf[0] {
+                b'q' => abort_flag.store(true, Ordering::SeqCst),
+                b'+' | b'=' => search.set_num_comps(search.num_comps() + NUM_COMPS_STEP),
+                b'-' => {
+                    let num_comps = search.num_comps().saturating_sub(NUM_COMPS_STEP);
+                    search.set_num_comps(num_comps.max(1));
+                }
+                b']' => {
+                    if let Some(limit) = search.queue_limit() {
+                        search.set_queue_limit(Some(limit.saturating_mul(2)));
+                    }
+                }
+                b'[' => {
+                    let limit = search
+                        .queue_limit()
+                        .unwrap_or_else(|| queue_len.load(Ordering::Relaxed));
+                    search.set_queue_limit(Some((limit / 2).max(1)));
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+#[cfg(unix)]
+mod raw_mode {
+    /// While this exists, key presses are sent straight to Monument without being echoed or
+    /// waiting for `Enter`.  `ctrl-C` still works as normal.
+    pub struct RawMode(libc::termios);
+
+    impl RawMode {
+        pub fn enable() -> Option<Self> {
+            // SAFETY: `termios` is plain old data, and is only read once `tcgetattr` has filled it
+            let mut original = unsafe { std::mem::zeroed::<libc::termios>() };
+            if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+                return None; // `stdin` isn't a terminal
+            }
+            let mut raw = original;
+            raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+            raw.c_cc[libc::VMIN] = 1;
+            raw.c_cc[libc::VTIME] = 0;
+            if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+                return None;
+            }
+            Some(Self(original))
+        }
+    }
+
+    impl Drop for RawMode {
+        fn drop(&mut self) {
+            unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.0) };
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod raw_mode {
+    /// Reading single key presses isn't supported on this platform, so the TUI only displays the
+    /// search
+    pub struct RawMode;
+
+    impl RawMode {
+        pub fn enable() -> Option<Self> {
+            None
+        }
+    }
+}
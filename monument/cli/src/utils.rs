@@ -1,38 +1,167 @@
-use std::path::Path;
+use std::{ops::RangeInclusive, path::Path};
 
 use bellframe::Stroke;
+use itertools::Itertools;
 use monument::parameters::OptionalRangeInclusive;
 use serde::{de::DeserializeOwned, Deserialize};
 
 /// A version of [`OptionalRangeInclusive`] which allows for convenient deserialisation from a
-/// single number (e.g. `count = 5` is equivalent to `count = { min = 5, max = 5 }`)
-#[derive(Debug, Clone, Copy, Deserialize)]
+/// single number (e.g. `count = 5` is equivalent to `count = { min = 5, max = 5 }`) or from a
+/// string like `"224+"`, `"<=448"` or `"224-448"`.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(try_from = "RawRange")]
+pub struct OptRangeInclusive(OptionalRangeInclusive);
+
+impl TryFrom<RawRange> for OptRangeInclusive {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: RawRange) -> anyhow::Result<Self> {
+        Ok(Self(match raw {
+            RawRange::String(s) => parse_range(&s)?,
+            raw => raw.as_numeric_range(),
+        }))
+    }
+}
+
+impl From<OptRangeInclusive> for OptionalRangeInclusive {
+    fn from(r: OptRangeInclusive) -> Self {
+        r.0
+    }
+}
+
+/// A range of method counts.  As well as everything accepted by [`OptRangeInclusive`], this can be
+/// given as a number of leads (e.g. `"7 leads"` or `"7-9 leads"`) or as a percentage of the
+/// composition's length (e.g. `"10-20%"` or `"<=25%"`).
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(try_from = "RawRange")]
+pub struct MethodCountRange {
+    range: OptionalRangeInclusive,
+    unit: CountUnit,
+}
+
+/// The units in which a [`MethodCountRange`] was given
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum CountUnit {
+    #[default]
+    Rows,
+    Leads,
+    Percent,
+}
+
+impl MethodCountRange {
+    /// Convert this into a range of rows, for a method with the given lead length.  Percentages
+    /// are taken of the shortest (for the minimum) and longest (for the maximum) allowed lengths.
+    /// If `counts_in_leads` is set, counts given without a unit are taken to be numbers of leads.
+    pub fn as_rows(
+        self,
+        lead_len: usize,
+        length: &RangeInclusive<usize>,
+        counts_in_leads: bool,
+    ) -> OptionalRangeInclusive {
+        let OptionalRangeInclusive { min, max } = self.range;
+        let unit = match self.unit {
+            CountUnit::Rows if counts_in_leads => CountUnit::Leads,
+            unit => unit,
+        };
+        match unit {
+            CountUnit::Rows => self.range,
+            CountUnit::Leads => OptionalRangeInclusive {
+                min: min.map(|n| n * lead_len),
+                max: max.map(|n| n * lead_len),
+            },
+            CountUnit::Percent => OptionalRangeInclusive {
+                min: min.map(|p| (p * length.start()).div_ceil(100)),
+                max: max.map(|p| p * length.end() / 100),
+            },
+        }
+    }
+}
+
+impl TryFrom<RawRange> for MethodCountRange {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: RawRange) -> anyhow::Result<Self> {
+        let s = match raw {
+            RawRange::String(s) => s.trim().to_lowercase(),
+            raw => {
+                return Ok(Self {
+                    range: raw.as_numeric_range(),
+                    unit: CountUnit::Rows,
+                })
+            }
+        };
+        // Allow the `+` to come after the unit (e.g. `"20%+"` as well as `"20+%"`)
+        let (s, plus) = match s.strip_suffix('+') {
+            Some(s) => (s.trim_end(), "+"),
+            None => (s.as_str(), ""),
+        };
+        let (range_str, unit) = if let Some(r) = s.strip_suffix('%') {
+            (r, CountUnit::Percent)
+        } else if let Some(r) = s.strip_suffix("leads").or_else(|| s.strip_suffix("lead")) {
+            (r, CountUnit::Leads)
+        } else {
+            (s, CountUnit::Rows)
+        };
+        Ok(Self {
+            range: parse_range(&format!("{}{plus}", range_str.trim()))?,
+            unit,
+        })
+    }
+}
+
+/// The ways a range can be written in the TOML file, before being parsed
+#[derive(Debug, Clone, Deserialize)]
 #[serde(untagged, deny_unknown_fields)]
-pub enum OptRangeInclusive {
+enum RawRange {
     SingleNumber(usize),
     Range {
         min: Option<usize>,
         max: Option<usize>,
     },
+    String(String),
 }
 
-impl Default for OptRangeInclusive {
-    fn default() -> Self {
-        Self::Range {
-            min: None,
-            max: None,
-        }
+impl RawRange {
+    fn as_numeric_range(&self) -> OptionalRangeInclusive {
+        let (min, max) = match *self {
+            RawRange::SingleNumber(n) => (Some(n), Some(n)),
+            RawRange::Range { min, max } => (min, max),
+            RawRange::String(_) => unreachable!("strings should be parsed"),
+        };
+        OptionalRangeInclusive { min, max }
     }
 }
 
-impl From<OptRangeInclusive> for OptionalRangeInclusive {
-    fn from(r: OptRangeInclusive) -> Self {
-        let (min, max) = match r {
-            OptRangeInclusive::SingleNumber(n) => (Some(n), Some(n)),
-            OptRangeInclusive::Range { min, max } => (min, max),
-        };
-        OptionalRangeInclusive { min, max }
+/// Parse a range from a string like `"224"`, `"224+"`, `">=224"`, `"<=448"` or `"224-448"`.
+fn parse_range(s: &str) -> anyhow::Result<OptionalRangeInclusive> {
+    let s = s.trim();
+    let parse_num = |num_str: &str| {
+        num_str.trim().parse::<usize>().map_err(|_| {
+            anyhow::Error::msg(format!(
+                "Can't parse {s:?} as a range; expected e.g. `224`, `224+`, `<=448` or `224-448`"
+            ))
+        })
+    };
+
+    let (min, max) = if let Some(max_str) = s.strip_prefix("<=") {
+        (None, Some(parse_num(max_str)?))
+    } else if let Some(min_str) = s.strip_prefix(">=").or_else(|| s.strip_suffix('+')) {
+        (Some(parse_num(min_str)?), None)
+    } else if let Some((min_str, max_str)) = s.split_once('-') {
+        (Some(parse_num(min_str)?), Some(parse_num(max_str)?))
+    } else {
+        let n = parse_num(s)?;
+        (Some(n), Some(n))
+    };
+    let range = OptionalRangeInclusive { min, max };
+    if let (Some(min), Some(max)) = (min, max) {
+        if min > max {
+            return Err(anyhow::Error::msg(format!(
+                "Range {range} is empty; its minimum is larger than its maximum"
+            )));
+        }
     }
+    Ok(range)
 }
 
 /// Attempt to read a file as a [`String`], returning a helpful error message on failure
@@ -43,8 +172,50 @@ pub fn read_file_to_string(path: &Path) -> anyhow::Result<String> {
 
 /// Attempt to read a file as a [`String`], returning a helpful error message on failure
 pub fn parse_toml<T: DeserializeOwned>(s: &str) -> anyhow::Result<T> {
-    toml::from_str(s)
-        .map_err(|e| anyhow::Error::msg(format!("Error parsing composition file: {}", e)))
+    toml::from_str(s).map_err(|e| {
+        let message = format!("Error parsing composition file: {}", e);
+        anyhow::Error::msg(with_suggestion(message, e.message()))
+    })
+}
+
+/// The end of an error message for an unknown `name`.  If one of the `expected` names is similar
+/// enough, it's suggested (`Did you mean "{suggestion}"?`).  Otherwise, all the `expected` names
+/// are listed (`Expected one of "{expected[0]}", "{expected[1]}", ...`).
+pub fn suggest_or_list<'e>(name: &str, expected: impl IntoIterator<Item = &'e str>) -> String {
+    let expected = expected.into_iter().collect_vec();
+    match ringing_utils::closest_match(name, expected.iter().copied()) {
+        Some(suggestion) => format!("Did you mean {suggestion:?}?"),
+        None => format!(
+            "Expected one of {}",
+            expected.iter().map(|n| format!("{n:?}")).join(", ")
+        ),
+    }
+}
+
+/// If `serde_message` is `serde`'s error for an unknown field or variant (e.g. ``unknown field
+/// `nun_comps`, expected one of `length`, `num_comps`, ...``) and one of the expected names is
+/// similar to the unknown one, replace the (often very long) list of expected names in `message`
+/// with a suggestion of which one was meant.  Otherwise, `message` is returned unchanged.
+pub fn with_suggestion(message: String, serde_message: &str) -> String {
+    let serde_message = serde_message.trim_end();
+    let Some((kind, rest)) = ["field", "variant"].into_iter().find_map(|kind| {
+        let rest = serde_message.strip_prefix(&format!("unknown {kind} "))?;
+        Some((kind, rest))
+    }) else {
+        return message;
+    };
+    // The unknown name comes first, followed by the expected names (all inside backticks)
+    let mut names = rest.split('`').skip(1).step_by(2);
+    let Some(unknown_name) = names.next() else {
+        return message;
+    };
+    match ringing_utils::closest_match(unknown_name, names) {
+        Some(suggestion) => message.replace(
+            serde_message,
+            &format!("unknown {kind} `{unknown_name}`.  Did you mean `{suggestion}`?"),
+        ),
+        None => message,
+    }
 }
 
 pub fn get_one() -> f32 {
@@ -0,0 +1,69 @@
+/*
+ * C interface to Monument, a fast and flexible composition generator.  See `src/lib.rs` for an
+ * example of how to use it.
+ *
+ * Searches are specified in the same TOML format as Monument's CLI, and each composition is
+ * returned as a JSON object with the fields `index`, `length`, `part_head`, `call_string`,
+ * `music_score`, `total_score`, `score_per_row` and `repeated_rows`.
+ */
+
+#ifndef MONUMENT_H
+#define MONUMENT_H
+
+#include <stdbool.h>
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* A search, along with the compositions it has generated so far */
+typedef struct MonumentSearch MonumentSearch;
+
+/*
+ * Create a search from the contents of a TOML specification file.  `path` is where the TOML would
+ * have been loaded from, and is used to find any other files it refers to (e.g. music files); it
+ * can be NULL if the specification doesn't refer to any other files.
+ *
+ * Returns NULL if the specification is invalid (see `monument_last_error`).  The search must be
+ * freed with `monument_search_free`.
+ */
+MonumentSearch *monument_search_new(const char *toml, const char *path);
+
+/*
+ * Run a search, blocking the current thread until it either finishes or is aborted with
+ * `monument_search_abort`.  Returns false if the search couldn't be run (see
+ * `monument_last_error`).
+ */
+bool monument_search_run(const MonumentSearch *search);
+
+/*
+ * Abort a search which is being run by `monument_search_run` on another thread.  The search stops
+ * shortly afterwards, keeping the compositions it has generated so far.
+ */
+void monument_search_abort(const MonumentSearch *search);
+
+/* The number of compositions which the search has generated so far */
+size_t monument_search_num_comps(const MonumentSearch *search);
+
+/*
+ * The composition at `index` (in the order they were generated) as a JSON object, or NULL if
+ * there are no more than `index` compositions.  The string is owned by the search, and stays valid
+ * until the search is freed.
+ */
+const char *monument_search_comp_json(const MonumentSearch *search, size_t index);
+
+/* Free a search created by `monument_search_new`.  Does nothing if `search` is NULL. */
+void monument_search_free(MonumentSearch *search);
+
+/*
+ * A description of the last error which happened on this thread, or NULL if no function has
+ * failed.  The string stays valid until another function fails on this thread.
+ */
+const char *monument_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* MONUMENT_H */
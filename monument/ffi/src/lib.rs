@@ -0,0 +1,278 @@
+//! A small C interface to Monument, so that it can be embedded in programs which aren't written
+//! in Rust.  The matching C header is `include/monument.h`.
+//!
+//! Searches are specified in exactly the same TOML format as the CLI, and every composition is
+//! returned as a JSON object.  A typical use looks like:
+//!
+//! ```c
+//! MonumentSearch *search = monument_search_new(toml, "/path/to/spec.toml");
+//! if (!search) {
+//!     fprintf(stderr, "%s\n", monument_last_error());
+//!     return;
+//! }
+//! monument_search_run(search); // Blocks until the search finishes or is aborted
+//! for (size_t i = 0; i < monument_search_num_comps(search); i++) {
+//!     printf("%s\n", monument_search_comp_json(search, i));
+//! }
+//! monument_search_free(search);
+//! ```
+//!
+//! Every function which can fail returns `NULL` (or `false`), and a description of the error can
+//! then be read with [`monument_last_error`].
+
+#![deny(rustdoc::broken_intra_doc_links, rustdoc::private_intra_doc_links)]
+// Every function taking pointers documents what they must point to in the C header
+#![allow(clippy::missing_safety_doc)]
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    panic::AssertUnwindSafe,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, MutexGuard, PoisonError,
+    },
+};
+
+use monument::{composition::ParamsData, Search, Update};
+use monument_cli::{args::Options, toml_file::TomlFile};
+
+/// A search which has been created from a TOML specification, along with the compositions it has
+/// generated so far.  This is opaque to C code.
+pub struct MonumentSearch {
+    search: Search,
+    exhaustive: bool,
+    monte_carlo: bool,
+    abort_flag: AtomicBool,
+    /// The compositions generated so far, as NUL-terminated JSON strings
+    comps: Mutex<Vec<CString>>,
+}
+
+impl MonumentSearch {
+    /// Lock the compositions generated so far.  If a thread panicked while holding the lock, the
+    /// compositions are still valid (each one is pushed in one go), so we ignore the poisoning
+    /// rather than panicking into C code.
+    fn comps(&self) -> MutexGuard<'_, Vec<CString>> {
+        self.comps.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+thread_local! {
+    /// The error returned by the last function which failed on this thread
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Create a search from the contents of a TOML specification file.  `path` is where the TOML
+/// would have been loaded from, and is used to find any other files it refers to (e.g. music
+/// files); it can be `NULL` if the specification doesn't refer to any other files.
+///
+/// Returns `NULL` if the specification is invalid.
+#[no_mangle]
+pub unsafe extern "C" fn monument_search_new(
+    toml: *const c_char,
+    path: *const c_char,
+) -> *mut MonumentSearch {
+    let result = catch_errors(|| {
+        let toml = str_arg(toml, "toml")?;
+        let path = match path.is_null() {
+            true => "",
+            false => str_arg(path, "path")?,
+        };
+        let toml_file = monument_cli::utils::parse_toml::<TomlFile>(toml)?;
+        let params = toml_file.to_params(Path::new(path))?;
+        let config = toml_file.config(&Options::default(), false);
+        Ok(MonumentSearch {
+            search: Search::new(params, config)?,
+            exhaustive: toml_file.exhaustive(),
+            monte_carlo: toml_file.monte_carlo(),
+            abort_flag: AtomicBool::new(false),
+            comps: Mutex::new(Vec::new()),
+        })
+    });
+    match result {
+        Some(search) => Box::into_raw(Box::new(search)),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Run a search, **blocking the current thread** until it either finishes or is aborted with
+/// [`monument_search_abort`].  Returns `false` if the search couldn't be run.
+#[no_mangle]
+pub unsafe extern "C" fn monument_search_run(search: *const MonumentSearch) -> bool {
+    catch_errors(|| {
+        let search = search_arg(search)?;
+        let params_data = ParamsData::new(search.search.parameters());
+        let update_fn = |update: Update| {
+            if let Update::Comp(comp) = update {
+                let mut comps = search.comps();
+                let json = comp.to_json(comps.len(), &params_data);
+                comps.push(CString::new(json).expect("JSON can't contain NUL"));
+            }
+        };
+        if search.exhaustive {
+            search.search.run_exhaustive(update_fn, &search.abort_flag);
+        } else if search.monte_carlo {
+            search.search.run_monte_carlo(update_fn, &search.abort_flag);
+        } else {
+            search.search.run(update_fn, &search.abort_flag);
+        }
+        Ok(())
+    })
+    .is_some()
+}
+
+/// Abort a search which is being run by [`monument_search_run`] on another thread.  The search
+/// stops shortly afterwards, keeping the compositions it has generated so far.
+#[no_mangle]
+pub unsafe extern "C" fn monument_search_abort(search: *const MonumentSearch) {
+    catch_errors(|| {
+        search_arg(search)?.abort_flag.store(true, Ordering::SeqCst);
+        Ok(())
+    });
+}
+
+/// The number of compositions which the search has generated so far.
+#[no_mangle]
+pub unsafe extern "C" fn monument_search_num_comps(search: *const MonumentSearch) -> usize {
+    catch_errors(|| Ok(search_arg(search)?.comps().len())).unwrap_or(0)
+}
+
+/// The composition at `index` (in the order they were generated) as a JSON object, or `NULL` if
+/// there are no more than `index` compositions.  The string is owned by the search, and stays
+/// valid until the search is freed.
+#[no_mangle]
+pub unsafe extern "C" fn monument_search_comp_json(
+    search: *const MonumentSearch,
+    index: usize,
+) -> *const c_char {
+    catch_errors(|| {
+        Ok(match search_arg(search)?.comps().get(index) {
+            // `CString`s own their contents on the heap, so the pointer stays valid even if
+            // `comps` is reallocated
+            Some(json) => json.as_ptr(),
+            None => std::ptr::null(),
+        })
+    })
+    .unwrap_or(std::ptr::null())
+}
+
+/// Free a search created by [`monument_search_new`].  `search` can be `NULL`, in which case this
+/// does nothing.
+#[no_mangle]
+pub unsafe extern "C" fn monument_search_free(search: *mut MonumentSearch) {
+    if !search.is_null() {
+        drop(Box::from_raw(search));
+    }
+}
+
+/// A description of the last error which happened on this thread, or `NULL` if no function has
+/// failed.  The string stays valid until another function fails on this thread.
+#[no_mangle]
+pub extern "C" fn monument_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| match &*e.borrow() {
+        Some(error) => error.as_ptr(),
+        None => std::ptr::null(),
+    })
+}
+
+/// Run `f`, storing any error or panic in [`LAST_ERROR`] (panics can't unwind into C code)
+fn catch_errors<T>(f: impl FnOnce() -> anyhow::Result<T>) -> Option<T> {
+    let error = match std::panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => return Some(value),
+        Ok(Err(error)) => format!("{error:#}"),
+        Err(panic) => match panic.downcast_ref::<&str>() {
+            Some(msg) => format!("Monument panicked: {msg}"),
+            None => match panic.downcast_ref::<String>() {
+                Some(msg) => format!("Monument panicked: {msg}"),
+                None => "Monument panicked".to_owned(),
+            },
+        },
+    };
+    let error = CString::new(error.replace('\0', "")).unwrap();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(error));
+    None
+}
+
+/// Convert a string argument into a `&str`, failing if it's `NULL` or not UTF-8
+unsafe fn str_arg<'a>(s: *const c_char, name: &str) -> anyhow::Result<&'a str> {
+    if s.is_null() {
+        anyhow::bail!("`{name}` is NULL");
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| anyhow::Error::msg(format!("`{name}` isn't valid UTF-8")))
+}
+
+unsafe fn search_arg<'a>(search: *const MonumentSearch) -> anyhow::Result<&'a MonumentSearch> {
+    search
+        .as_ref()
+        .ok_or_else(|| anyhow::Error::msg("`search` is NULL"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_error() -> String {
+        let error = monument_last_error();
+        assert!(!error.is_null());
+        unsafe { CStr::from_ptr(error) }
+            .to_str()
+            .unwrap()
+            .to_owned()
+    }
+
+    #[test]
+    fn invalid_toml() {
+        let toml = CString::new("length = ").unwrap();
+        let search = unsafe { monument_search_new(toml.as_ptr(), std::ptr::null()) };
+        assert!(search.is_null());
+        assert!(last_error().contains("Error parsing composition file"));
+    }
+
+    #[test]
+    fn null_args() {
+        let search = unsafe { monument_search_new(std::ptr::null(), std::ptr::null()) };
+        assert!(search.is_null());
+        assert_eq!(last_error(), "`toml` is NULL");
+
+        assert!(!unsafe { monument_search_run(std::ptr::null()) });
+        assert_eq!(last_error(), "`search` is NULL");
+        assert_eq!(unsafe { monument_search_num_comps(std::ptr::null()) }, 0);
+        assert!(unsafe { monument_search_comp_json(std::ptr::null(), 0) }.is_null());
+        unsafe { monument_search_free(std::ptr::null_mut()) };
+    }
+
+    #[test]
+    fn poisoned_comps() {
+        let toml = CString::new(
+            r#"
+            length = { min = 0, max = 60 }
+            method = { name = "Plain Bob", place_notation = "x16x16x16,12", stage = 6 }
+            "#,
+        )
+        .unwrap();
+        let search = unsafe { monument_search_new(toml.as_ptr(), std::ptr::null()) };
+        assert!(!search.is_null());
+        assert!(unsafe { monument_search_run(search) });
+        let num_comps = unsafe { monument_search_num_comps(search) };
+        assert!(num_comps > 0);
+
+        // Poison the lock around the compositions by panicking while holding it
+        let search_ref = unsafe { &*search };
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            let _comps = search_ref.comps.lock().unwrap();
+            panic!("Poisoning the lock");
+        }));
+        assert!(result.is_err());
+        assert!(search_ref.comps.is_poisoned());
+
+        // The compositions can still be read
+        assert_eq!(unsafe { monument_search_num_comps(search) }, num_comps);
+        assert!(!unsafe { monument_search_comp_json(search, 0) }.is_null());
+        assert!(unsafe { monument_search_comp_json(search, num_comps) }.is_null());
+        unsafe { monument_search_abort(search) };
+        unsafe { monument_search_free(search) };
+    }
+}
@@ -171,7 +171,7 @@ impl super::Parameters {
         } else {
             format!("{}->{}", call.label_from, call.label_to)
         };
-        let heading = format!("{} @ {} ({})", call.symbol, label_str, call.place_notation);
+        let heading = format!("{} @ {} ({})", call.symbol, label_str, call.transposition);
 
         egui::CollapsingHeader::new(heading)
             .id_source(call.id)
@@ -184,7 +184,7 @@ impl super::Parameters {
                         "Weight",
                         egui::Slider::new(&mut call.weight, -20.0..=2.0),
                     );
-                    table.add_label("Place notation", &call.place_notation);
+                    table.add_label("Transposition", &call.transposition);
                     table.add_label("Lead location (from)", &call.label_from);
                     table.add_label("Lead location (to)", &call.label_to);
                     table.add_label(
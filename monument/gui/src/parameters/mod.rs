@@ -1,10 +1,13 @@
 mod gui;
 
+use std::collections::HashMap;
+
 use bellframe::{music::AtRowPositions, Mask, PlaceNot, RowBuf, Stage, Stroke};
 use itertools::Itertools;
 use monument::{
     parameters::{
-        Call, CallDisplayStyle, CallId, Method, MusicTypeVec, OptionalRangeInclusive, SpliceStyle,
+        Call, CallDisplayStyle, CallId, Method, MultipartCallDisplay, MusicTypeVec, NonDufferMusic,
+        OptionalRangeInclusive, Ranking, SpliceStyle,
     },
     PartHeadGroup,
 };
@@ -58,8 +61,12 @@ impl Parameters {
                 custom_shorthand: String::new(),
                 inner: method,
                 count_range: OptionalRangeInclusive::OPEN,
+                count_range_per_part: OptionalRangeInclusive::OPEN,
                 start_indices: vec![0],
                 end_indices: (0..32).collect_vec(),
+                can_start: true,
+                can_end: true,
+                link_position: None,
                 allowed_courses: vec![Mask::parse_with_stage("1*", stage).unwrap().into()],
             }
         };
@@ -94,6 +101,7 @@ impl Parameters {
                     inner,
                     weights: AtRowPositions::front_and_back(weight),
                     count_range: OptionalRangeInclusive::OPEN,
+                    non_duffer: false,
                 });
             };
         for pattern in ["5678", "8765", "6578"] {
@@ -136,6 +144,7 @@ impl Parameters {
             inner: bellframe::MusicType::reversed_tenors_at_back(stage),
             weights: AtRowPositions::new(0.0, 0.0, -1.0, 0.0),
             count_range: OptionalRangeInclusive::OPEN,
+            non_duffer: false,
         });
 
         // Construct parameters
@@ -144,13 +153,30 @@ impl Parameters {
             stage,
             num_comps: 100,
             require_truth: true,
+            max_row_repeats: 1,
+            extent: false,
+            prune_dominated_chunks: false,
+            min_total_score: None,
+            min_music_score: None,
+            max_calls: None,
+            min_conductability: None,
+            shortlist: None,
+            ranking: Ranking::AvgScore,
+            row_weight: 0.0,
+            target_length: None,
 
             methods: index_vec::index_vec![],
             splice_style: SpliceStyle::LeadLabels,
             splice_weight: -1.0,
+            splice_weights: HashMap::new(),
+            method_counts_in_leads: false,
             calls: index_vec::index_vec![],
             call_display_style: CallDisplayStyle::CallingPositions,
             calling_bell: stage.tenor(),
+            calling_position_names: HashMap::new(),
+            allowed_calling_positions: None,
+            strict_labels: false,
+            multipart_call_display: MultipartCallDisplay::FirstPart,
             atw_weight: None, // Don't calculate atw
             require_atw: false,
 
@@ -158,11 +184,17 @@ impl Parameters {
             end_row: RowBuf::rounds(stage),
             part_head_group: PartHeadGroup::one_part(stage),
             course_weights: vec![],
+            no_repeated_courses: false,
             // TODO: GUI for custom callings
             calling: None,
             omit_round_blocks: false,
+            calling_filters: vec![],
 
             music_types,
+            no_duffers: false,
+            max_duffer_rows: None,
+            non_duffer_music: NonDufferMusic::Marked,
+            required_rows: vec![],
             start_stroke: Stroke::Hand,
         };
         crate::Parameters {
@@ -73,6 +73,8 @@ impl Project {
                 table.add_label("Prefixes in queue", progress.queue_len); // TODO: Truncating
                 table.add_label("Mean prefix length", progress.avg_length);
                 table.add_label("Max prefix length", progress.max_length);
+                let megabytes = progress.total_bytes() as f64 / 1_000_000.0;
+                table.add_label("Memory used", format!("{megabytes:.1}MB"));
             });
             if progress.truncating_queue {
                 ui.label("Truncating queue...");
@@ -181,6 +183,8 @@ impl Project {
         match update {
             monument::Update::Comp(comp) => self.compositions.push(comp),
             monument::Update::Progress(progress) => self.search_progress = Some(progress),
+            monument::Update::Stats(stats) => log::info!("{stats}"),
+            monument::Update::NoComps(reason) => log::warn!("No compositions found: {reason}"),
             monument::Update::Complete => self.search_progress = None,
         }
     }
@@ -2,7 +2,7 @@
 
 use std::{
     collections::{HashMap, HashSet},
-    ops::Deref,
+    ops::{Deref, Range},
 };
 
 use bellframe::{music::AtRowPositions, Bell, Block, Mask, Row, RowBuf, Stage, Stroke, Truth};
@@ -11,12 +11,12 @@ use lazy_st::lazy;
 
 use crate::{
     parameters::{
-        Call, CallDisplayStyle, CallId, CallIdx, MethodId, MethodIdx, MethodVec, MusicTypeVec,
-        Parameters, SpliceStyle,
+        Call, CallDisplayStyle, CallId, CallIdx, MethodId, MethodIdx, MethodVec,
+        MultipartCallDisplay, MusicTypeVec, Parameters, SpliceStyle,
     },
     utils::{
         lengths::{PerPartLength, TotalLength},
-        Boundary,
+        Boundary, IdGenerator,
     },
     PartHead,
 };
@@ -38,6 +38,8 @@ pub struct Composition {
     // Cached values which don't change, even if a different set of params are used
     pub(crate) unique_place_bell_rows_per_bell: Vec<usize>, // TODO: Type-safe bell-vec
     pub(crate) truth: Truth,
+    pub(crate) max_row_repeats: usize,
+    pub(crate) num_repeated_rows: usize,
     pub(crate) length: TotalLength,
     pub(crate) end_row: RowBuf,
     pub(crate) part_head: RowBuf,
@@ -69,6 +71,8 @@ impl Composition {
             path,
 
             truth: block.truth(),
+            max_row_repeats: block.max_row_repeats(),
+            num_repeated_rows: block.num_repeated_rows(),
             part_head: params.part_head_group.get_row(part_head).to_owned(),
             unique_place_bell_rows_per_bell: Self::unique_place_bell_rows_per_bell(&block),
             end_row: params.end_row.clone(),
@@ -230,18 +234,7 @@ impl<'params> ParamsData<'params> {
             Block::<(MethodId, usize)>::with_leftover_row(self.params.start_row.clone());
         for elem in path {
             assert_eq!(first_part.leftover_row(), elem.start_row.as_row());
-            // Copy the corresponding part of this method's (double) plain course
-            let double_plain_course = &self.method_map[&elem.method_id].double_plain_course;
-            let start_idx = elem.start_sub_lead_idx;
-            let end_idx = start_idx + elem.length.as_usize();
-            first_part.extend_range(double_plain_course, start_idx..end_idx);
-            // If this PathElem ends in a call, then change the `leftover_row` to suit
-            if let Some(call_id) = elem.call_to_end {
-                let last_non_leftover_row = first_part.rows().next_back().unwrap();
-                let new_leftover_row =
-                    last_non_leftover_row * self.get_call(call_id).place_notation.transposition();
-                first_part.leftover_row_mut().copy_from(&new_leftover_row);
-            }
+            self.extend_with_path_elem(&mut first_part, elem);
         }
 
         // Generate the other parts from the first
@@ -254,6 +247,33 @@ impl<'params> ParamsData<'params> {
         comp
     }
 
+    /// Add the [`Row`]s of a [`PathElem`] to the end of a [`Block`], starting at the `Block`'s
+    /// `leftover_row` (which is assumed to be `elem.start_row`).
+    fn extend_with_path_elem(&self, block: &mut Block<(MethodId, usize)>, elem: &PathElem) {
+        // Copy the corresponding part of this method's (double) plain course
+        let double_plain_course = &self.method_map[&elem.method_id].double_plain_course;
+        let start_idx = elem.start_sub_lead_idx;
+        let end_idx = start_idx + elem.length.as_usize();
+        block.extend_range(double_plain_course, start_idx..end_idx);
+        // If this PathElem ends in a call, then change the `leftover_row` to suit
+        if let Some(call_id) = elem.call_to_end {
+            let transposition = &self.get_call(call_id).transposition;
+            let last_non_leftover_row = block.rows().next_back().unwrap().to_owned();
+            let new_leftover_row = &last_non_leftover_row * transposition.transposition();
+            // Calls covering several changes also add the rows in the middle of the call, which
+            // are labelled as being at the call's position in the lead
+            let mut call_rows = transposition.extra_rows(&last_non_leftover_row);
+            if let Some(first_call_row) = call_rows.first() {
+                block.leftover_row_mut().copy_from(first_call_row);
+                call_rows.push(&new_leftover_row);
+                let annot = (elem.method_id, elem.end_sub_lead_idx(self.params));
+                block.extend(&Block::with_annots_from_indices(call_rows, |_| annot).unwrap());
+            } else {
+                block.leftover_row_mut().copy_from(&new_leftover_row);
+            }
+        }
+    }
+
     fn get_method(&self, id: MethodId) -> &Method {
         &self.params.methods[self.method_map[&id].idx]
     }
@@ -482,19 +502,51 @@ impl Composition {
     /// example, [this composition](https://complib.org/composition/87419) would have a
     /// `call_string` of `D[B]BL[W]N[M]SE[sH]NCYW[sH]`.
     fn call_string(&self, params: &ParamsData) -> String {
-        let needs_brackets =
-            params.is_spliced() || params.call_display_style == CallDisplayStyle::Positional;
         let is_snap_start = self.path[0].start_sub_lead_idx > 0;
         let is_snap_finish = self.path.last().unwrap().end_sub_lead_idx(params) > 0;
 
-        let mut path_iter = self.path.iter().peekable();
-
         let mut s = String::new();
         if params.call_display_style == CallDisplayStyle::Positional {
             s.push('#');
         }
         s.push_str(if is_snap_start { "<" } else { "" });
-        while let Some(path_elem) = path_iter.next() {
+        match params.multipart_call_display {
+            MultipartCallDisplay::FirstPart => {
+                s.extend(self.path_elem_strings(&RowBuf::rounds(self.stage), params));
+            }
+            MultipartCallDisplay::Repeated => {
+                s.extend(self.path_elem_strings(&RowBuf::rounds(self.stage), params));
+                if params.is_multipart() {
+                    s.push_str(&format!(" ({} parts)", params.num_parts()));
+                }
+            }
+            MultipartCallDisplay::Expanded => {
+                let mut part_head = RowBuf::rounds(self.stage);
+                let mut part_strings = Vec::with_capacity(params.num_parts());
+                for _ in 0..params.num_parts() {
+                    let next_part_head = &part_head * &self.part_head;
+                    part_strings.push(self.path_elem_strings(&part_head, params).concat());
+                    part_head = next_part_head;
+                }
+                s.push_str(&part_strings.join(" / "));
+            }
+        }
+        s.push_str(if is_snap_finish { ">" } else { "" });
+
+        s
+    }
+
+    /// For each [`PathElem`] in this composition, generate the section of the
+    /// [`call_string`](Self::call_string) which corresponds to it (i.e. its method shorthands and
+    /// the call at its end).  `part_head` is the first row of the part being displayed, so that
+    /// calling positions are computed from that part's rows.
+    fn path_elem_strings(&self, part_head: &Row, params: &ParamsData) -> Vec<String> {
+        let needs_brackets =
+            params.is_spliced() || params.call_display_style == CallDisplayStyle::Positional;
+
+        let mut strings = Vec::with_capacity(self.path.len());
+        for (path_idx, path_elem) in self.path.iter().enumerate() {
+            let mut s = String::new();
             // Method text
             if params.is_spliced() || params.call_display_style == CallDisplayStyle::Positional {
                 // Add one shorthand for every lead *covered* (not number of lead heads reached)
@@ -511,30 +563,39 @@ impl Composition {
                 }
             }
             // Call text
-            if let Some(call_id) = path_elem.call_to_end {
-                let call = params.get_call(call_id);
+            if let Some(call_text) = self.call_text(path_idx, part_head, params) {
                 s.push_str(if needs_brackets { "[" } else { "" });
-                // Call position
-                match params.call_display_style {
-                    CallDisplayStyle::CallingPositions => {
-                        let row_after_call = path_iter
-                            .peek()
-                            .map_or(&self.part_head, |path_elem| &path_elem.start_row);
-                        let place_of_calling_bell = row_after_call.place_of(params.calling_bell);
-                        let calling_position =
-                            call.calling_positions[place_of_calling_bell as usize];
-                        s.extend(call.short_symbol());
-                        s.push(calling_position);
-                    }
-                    // TODO: Compute actual counts for positional calls
-                    CallDisplayStyle::Positional => s.push(call.symbol),
-                }
+                s.push_str(&call_text);
                 s.push_str(if needs_brackets { "]" } else { "" });
             }
+            strings.push(s);
         }
-        s.push_str(if is_snap_finish { ">" } else { "" });
+        strings
+    }
 
-        s
+    /// The text used to display the call at the end of `self.path[path_idx]` (e.g. `sH`), or
+    /// `None` if that [`PathElem`] doesn't end with a call.  `part_head` is the first row of the
+    /// part being displayed.
+    fn call_text(&self, path_idx: usize, part_head: &Row, params: &ParamsData) -> Option<String> {
+        let call = params.get_call(self.path[path_idx].call_to_end?);
+        let mut s = String::new();
+        match params.call_display_style {
+            CallDisplayStyle::CallingPositions => {
+                let row_after_call = self
+                    .path
+                    .get(path_idx + 1)
+                    .map_or(&self.part_head, |path_elem| &path_elem.start_row);
+                let row_after_call = part_head * row_after_call;
+                let place_of_calling_bell = row_after_call.place_of(params.calling_bell);
+                let calling_position =
+                    params.calling_position(call, place_of_calling_bell as usize);
+                s.extend(call.short_symbol());
+                s.push(calling_position);
+            }
+            // TODO: Compute actual counts for positional calls
+            CallDisplayStyle::Positional => s.push(call.symbol),
+        }
+        Some(s)
     }
 
     /// A slice containing the number of [`Row`]s generated for each [`Method`] used in the
@@ -590,6 +651,12 @@ impl Composition {
         if let Some(atw_weight) = params.atw_weight {
             total_score += atw_factor * atw_weight;
         }
+        // Length
+        let length = self.length.as_usize();
+        total_score += params.row_weight * length as f32;
+        if let Some(target_length) = &params.target_length {
+            total_score += target_length.bonus_for(length);
+        }
         // Calls
         for elem in &self.path {
             if let Some(call_id) = elem.call_to_end {
@@ -598,19 +665,21 @@ impl Composition {
             }
         }
         // Splices
-        let mut changes_of_method = 0;
+        let num_parts = params.num_parts() as f32;
         for (e1, e2) in self.path.iter().tuple_windows() {
             if PathElem::is_splice_between(e1, e2, params) {
-                changes_of_method += params.num_parts();
+                total_score += params.splice_weight_between(e1.method_id, e2.method_id) * num_parts;
             }
         }
         let first_elem = self.path.first().unwrap();
         let last_elem = self.path.last().unwrap();
-        if PathElem::is_splice_between(last_elem, first_elem, params) {
+        // Checking `is_multipart` avoids computing `0 * -inf` for banned splices in single parts
+        if params.is_multipart() && PathElem::is_splice_between(last_elem, first_elem, params) {
             // -1 because there's no splice around the end/start of the composition
-            changes_of_method += params.num_parts() - 1;
+            let splice_weight =
+                params.splice_weight_between(last_elem.method_id, first_elem.method_id);
+            total_score += splice_weight * (num_parts - 1.0);
         }
-        total_score += changes_of_method as f32 * params.splice_weight;
         // Course weights
         // TODO: Cache this
         for elem in &self.path {
@@ -638,7 +707,7 @@ impl Composition {
     /// The main reason to do this is to quickly reject compositions which are being generated by the
     /// search routine
     #[must_use]
-    fn do_cheap_checks(&self, params: &ParamsData) -> bool {
+    pub(crate) fn do_cheap_checks(&self, params: &ParamsData) -> bool {
         if self.stage != params.stage {
             return false; // Stage mismatch
         }
@@ -664,6 +733,24 @@ impl Composition {
 
         true // Can't reject composition this easily
     }
+
+    /// Returns `true` if this composition leaves a course and later comes back to it.  Coming back
+    /// to the course containing the start of the composition (in order to finish) is allowed.
+    fn repeats_a_course(&self, params: &ParamsData) -> bool {
+        let mut courses = self
+            .path
+            .iter()
+            .map(|elem| {
+                let method_idx = params.method_map[&elem.method_id].idx;
+                params.normalised_course_head(method_idx, &elem.lead_head(&params.method_map))
+            })
+            .dedup()
+            .collect_vec();
+        if courses.len() > 1 && courses.first() == courses.last() {
+            courses.pop(); // The start and end of the composition are in the same course
+        }
+        !courses.iter().all_unique()
+    }
 }
 
 impl CompositionValues<'_> {
@@ -674,11 +761,17 @@ impl CompositionValues<'_> {
         if !self.is_splice_style_satisfied(params) {
             return false;
         }
+        if self.has_banned_splice(params) {
+            return false; // Composition splices between methods which can't be spliced
+        }
         if params.require_atw && !self.is_atw() {
             return false;
         }
-        if params.require_truth && !self.is_true() {
-            return false; // Composition is false but we needed it to be true
+        if params.require_truth && self.max_row_repeats() > params.max_row_repeats {
+            return false; // Composition repeats some row more often than we allow
+        }
+        if params.no_repeated_courses && self.repeats_a_course(params) {
+            return false; // Composition rings some course more than once
         }
         if self.end_row != params.end_row {
             return false; // Comps ends on the wrong row
@@ -715,6 +808,9 @@ impl CompositionValues<'_> {
                 // If the composition ends with a call, then the situation is more complex; we need
                 // to check that the call leads to a method which could end immediately
                 // (conceptually, this introduces an imaginary 0-length 'path-elem' at the end)
+                if !params.get_method(last_elem.method_id).can_end {
+                    return false; // Composition's last method isn't allowed to end it
+                }
                 let end_label = &params.get_call(call_id).label_to;
                 if !params.valid_end_labels.contains(end_label) {
                     return false; // Call's label_to can't correspond to a valid end idx
@@ -793,6 +889,26 @@ impl CompositionValues<'_> {
             }
         }
     }
+
+    /// Returns `true` if this composition contains a splice which
+    /// [`Parameters::splice_weights`] bans (including over the part head)
+    fn has_banned_splice(&self, params: &ParamsData) -> bool {
+        let path = &self.composition.path;
+        let is_banned_splice = |e1: &PathElem, e2: &PathElem| -> bool {
+            PathElem::is_splice_between(e1, e2, params)
+                && params.is_splice_banned(e1.method_id, e2.method_id)
+        };
+        if path
+            .iter()
+            .tuple_windows()
+            .any(|(e1, e2)| is_banned_splice(e1, e2))
+        {
+            return true;
+        }
+        params.is_multipart()
+            && !path.is_empty()
+            && is_banned_splice(path.last().unwrap(), path.first().unwrap())
+    }
 }
 
 /////////////
@@ -813,6 +929,18 @@ impl<'comp> CompositionValues<'comp> {
         self.composition.truth.is_true()
     }
 
+    /// The largest number of times that any [`Row`] is rung in this composition (`1` if it's
+    /// true).
+    pub fn max_row_repeats(&self) -> usize {
+        self.composition.max_row_repeats
+    }
+
+    /// The number of [`Row`]s in this composition which repeat a [`Row`] rung earlier on (`0` if
+    /// it's true).
+    pub fn num_repeated_rows(&self) -> usize {
+        self.composition.num_repeated_rows
+    }
+
     /// The average score generated by each [`Row`] in this composition.  This is equal to
     /// `self.total_score() / self.length() as f32`.
     pub fn score_per_row(&self) -> f32 {
@@ -822,6 +950,1106 @@ impl<'comp> CompositionValues<'comp> {
     pub fn is_atw(&self) -> bool {
         self.atw_factor == 1.0
     }
+
+    /// The total count of every [`MusicType`] which should be shown, labelled with that
+    /// [`MusicType`]'s name (e.g. `[("4-bell runs", 32), ("5678s", 12)]`).  `params` must be the
+    /// [`Parameters`] used to compute these values.
+    pub fn music_counts_by_name<'p>(&self, params: &'p Parameters) -> Vec<(&'p str, usize)> {
+        params
+            .music_types_to_show()
+            .into_iter()
+            .map(|(idx, music_type)| {
+                let total = music_type.masked_total(self.music_counts[idx]);
+                (music_type.name.as_str(), total)
+            })
+            .collect_vec()
+    }
+}
+
+//////////
+// JSON //
+//////////
+
+impl Composition {
+    /// Describe this composition as a JSON object, for the bindings to other languages.  `index`
+    /// is the number of compositions which were generated before this one.  If this composition
+    /// can't be described with `params` (i.e. [`Composition::values`] returns `None`), the object
+    /// only contains `index`.
+    pub fn to_json(&self, index: usize, params: &ParamsData) -> String {
+        let Some(values) = self.values(params) else {
+            return format!("{{\"index\":{index}}}");
+        };
+        format!(
+            "{{\"index\":{index},\"length\":{},\"part_head\":{},\"call_string\":{},\
+             \"music_score\":{},\"total_score\":{},\"score_per_row\":{},\"repeated_rows\":{}}}",
+            values.length(),
+            json_string(&values.part_head().to_string()),
+            json_string(&values.call_string),
+            json_number(values.music_score),
+            json_number(values.total_score),
+            json_number(values.score_per_row()),
+            values.num_repeated_rows(),
+        )
+    }
+}
+
+/// Quote and escape `s` as a JSON string
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Write `value` as a JSON number.  JSON can't represent infinities or NaN, so they become `null`.
+fn json_number(value: f32) -> String {
+    match value.is_finite() {
+        true => value.to_string(),
+        false => "null".to_owned(),
+    }
+}
+
+/////////////
+// RANKING //
+/////////////
+
+/// The different ways that the scores of a set of [`Composition`]s can be normalised before
+/// they're ranked against each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ScoreNormalisation {
+    /// Rank compositions by their total score.  This favours longer compositions, since they have
+    /// more rows in which to generate score.
+    Total,
+    /// Rank compositions by their [average score per row](CompositionValues::score_per_row).  This
+    /// is what Monument's search uses by default (see [`Ranking`](crate::parameters::Ranking)), but
+    /// it biases against longer compositions in searches which allow a wide range of lengths.
+    #[default]
+    PerRow,
+    /// Rank compositions by the [z-score](https://en.wikipedia.org/wiki/Standard_score) of their
+    /// total score, relative to the distribution of scores in the search which generated them.
+    /// When [merging results](merge_results), this makes compositions from searches with very
+    /// different scores (e.g. different methods or lengths) comparable.  Within a single search,
+    /// this ranks compositions in the same order as [`ScoreNormalisation::Total`].
+    ZScore,
+}
+
+impl ScoreNormalisation {
+    /// Normalise the scores of a set of compositions according to this scheme.  The returned
+    /// [`Vec`] contains the normalised score of each composition in `comps`, in the same order.
+    /// Because [`ScoreNormalisation::ZScore`] depends on the distribution of scores, **all**
+    /// compositions which are being ranked together should be passed at the same time.
+    pub fn normalised_scores(self, comps: &[CompositionValues]) -> Vec<f32> {
+        match self {
+            Self::Total => comps.iter().map(|c| c.total_score).collect_vec(),
+            Self::PerRow => comps.iter().map(|c| c.score_per_row()).collect_vec(),
+            Self::ZScore => {
+                let totals = comps.iter().map(|c| c.total_score).collect_vec();
+                let (mean, std_dev) = mean_and_std_dev(&totals);
+                totals
+                    .iter()
+                    .map(|&t| z_score(t, mean, std_dev))
+                    .collect_vec()
+            }
+        }
+    }
+}
+
+/// The mean and (population) standard deviation of some `values`
+fn mean_and_std_dev(values: &[f32]) -> (f32, f32) {
+    let num_values = values.len() as f32;
+    let mean = values.iter().sum::<f32>() / num_values;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / num_values;
+    (mean, variance.sqrt())
+}
+
+fn z_score(value: f32, mean: f32, std_dev: f32) -> f32 {
+    match std_dev == 0.0 {
+        true => 0.0, // If all values are equal, every value is exactly average
+        false => (value - mean) / std_dev,
+    }
+}
+
+/// A [`Composition`] taken from one of several sets of results by [`merge_results`].
+#[derive(Debug)]
+pub struct MergedComposition<'comp> {
+    /// The values of this composition, computed using the merged weights
+    pub values: CompositionValues<'comp>,
+    /// The index of the result set which generated this composition
+    pub source_idx: usize,
+    /// The score of this composition, normalised according to the chosen [`ScoreNormalisation`]
+    pub normalised_score: f32,
+}
+
+/// Merge several sets of [`Composition`]s, each generated from its own [`Parameters`], into one
+/// consolidated result set.  This is useful for combining searches over the same methods which
+/// differ in some way that Monument can't search in one go (e.g. different part heads).
+///
+/// Compositions are deduplicated by their [`Row`]s, so a composition found by several searches is
+/// only included once (labelled with the first result set that generated it).  Compositions which
+/// ring the same round block from a different starting row (e.g. starting in a different part)
+/// count as duplicates.  However, rotations which are transposed to start from the same row
+/// (e.g. `HHH` and `WWW` in Plain Bob Minor) ring different rows and music, so aren't duplicates,
+/// and neither are compositions which ring the same rows in a different order.  All compositions
+/// are then re-scored using the music and weights from `weights` (see
+/// [`Parameters::with_weights_from`]) and sorted best-first according to `normalisation`.  Any
+/// composition which isn't valid under its own [`Parameters`] is ignored.
+///
+/// [`ScoreNormalisation::ZScore`] normalises each result set separately (including any
+/// compositions which are removed as duplicates), so the best compositions of every search are
+/// ranked near the top even if one search produced much higher scores than the others.
+pub fn merge_results<'comp>(
+    result_sets: &[(&Parameters, &'comp [Composition])],
+    weights: &Parameters,
+    normalisation: ScoreNormalisation,
+) -> Vec<MergedComposition<'comp>> {
+    // Collect and deduplicate the compositions
+    let mut rows_seen = HashSet::<Vec<RowBuf>>::new();
+    let mut values = Vec::new();
+    let mut source_idxs = Vec::new();
+    let mut source_totals = vec![Vec::new(); result_sets.len()];
+    for (source_idx, (params, comps)) in result_sets.iter().enumerate() {
+        let reweighted_params = params.with_weights_from(weights);
+        let params_data = ParamsData::new(&reweighted_params);
+        for comp in comps.iter() {
+            let Some(comp_values) = comp.values(&params_data) else {
+                continue; // Composition isn't valid under its own params
+            };
+            source_totals[source_idx].push(comp_values.total_score);
+            let rows = canonical_rows(&params_data.get_block(&comp.path));
+            if rows_seen.insert(rows) {
+                values.push(comp_values);
+                source_idxs.push(source_idx);
+            }
+        }
+    }
+
+    // Rank the compositions
+    let scores = match normalisation {
+        ScoreNormalisation::ZScore => {
+            let distributions = source_totals
+                .iter()
+                .map(|totals| mean_and_std_dev(totals))
+                .collect_vec();
+            values
+                .iter()
+                .zip_eq(&source_idxs)
+                .map(|(v, &source_idx)| {
+                    let (mean, std_dev) = distributions[source_idx];
+                    z_score(v.total_score, mean, std_dev)
+                })
+                .collect_vec()
+        }
+        _ => normalisation.normalised_scores(&values),
+    };
+    let mut merged_comps = values
+        .into_iter()
+        .zip_eq(source_idxs)
+        .zip_eq(scores)
+        .map(
+            |((values, source_idx), normalised_score)| MergedComposition {
+                values,
+                source_idx,
+                normalised_score,
+            },
+        )
+        .collect_vec();
+    merged_comps.sort_by(|c1, c2| c2.normalised_score.total_cmp(&c1.normalised_score));
+    merged_comps
+}
+
+/// The [`Row`]s of `block`, rotated to start at the smallest row if `block` is a round block.
+/// Rotating a round block doesn't change the rows it rings, or the order it rings them in, so
+/// this is the same for every rotation of a composition.
+fn canonical_rows<A>(block: &Block<A>) -> Vec<RowBuf> {
+    let rows = block.rows().map(Row::to_owned).collect_vec();
+    if rows.first().map(RowBuf::as_row) != Some(block.leftover_row()) {
+        return rows; // Not a round block, so rotating it would ring different changes
+    }
+    // Find the smallest rotation in linear time, by comparing the two best candidate starts and
+    // skipping every start which can't be smallest
+    let n = rows.len();
+    let (mut i, mut j, mut k) = (0, 1, 0);
+    while i < n && j < n && k < n {
+        let (a, b) = (&rows[(i + k) % n], &rows[(j + k) % n]);
+        if a == b {
+            k += 1;
+            continue;
+        }
+        if a > b {
+            i += k + 1;
+        } else {
+            j += k + 1;
+        }
+        if i == j {
+            j += 1;
+        }
+        k = 0;
+    }
+    let start = i.min(j);
+    rows[start..]
+        .iter()
+        .chain(&rows[..start])
+        .cloned()
+        .collect()
+}
+
+impl std::str::FromStr for ScoreNormalisation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
+            "total" | "raw" => Self::Total,
+            "per-row" | "avg" => Self::PerRow,
+            "z-score" => Self::ZScore,
+            _ => {
+                return Err(format!(
+                    "Unknown score normalisation {s:?}. Expected `total`, `per-row` or `z-score`."
+                ))
+            }
+        })
+    }
+}
+
+///////////
+// DIFFS //
+///////////
+
+/// The differences between two [`Composition`]s, as computed by [`Composition::diff`].
+///
+/// Both compositions are split into sections which each stay in a single course (in the first
+/// part), and these are lined up so that as many courses as possible are matched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompositionDiff {
+    pub courses: Vec<CourseDiff>,
+}
+
+/// How one course differs between two [`Composition`]s.  The `calling` strings are the sections
+/// of each composition's call string which are rung in that course.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CourseDiff {
+    /// This course is rung in the same way by both compositions
+    Same {
+        course_head: RowBuf,
+        calling: String,
+    },
+    /// Both compositions ring this course, but with different calls or methods
+    Changed {
+        course_head: RowBuf,
+        old_calling: String,
+        new_calling: String,
+    },
+    /// Only the first composition rings this course
+    Removed {
+        course_head: RowBuf,
+        calling: String,
+    },
+    /// Only the second composition rings this course
+    Added {
+        course_head: RowBuf,
+        calling: String,
+    },
+}
+
+impl CompositionDiff {
+    /// Returns `true` if both compositions are rung in exactly the same way
+    pub fn is_identical(&self) -> bool {
+        self.courses
+            .iter()
+            .all(|c| matches!(c, CourseDiff::Same { .. }))
+    }
+}
+
+impl CourseDiff {
+    pub fn course_head(&self) -> &Row {
+        match self {
+            Self::Same { course_head, .. }
+            | Self::Changed { course_head, .. }
+            | Self::Removed { course_head, .. }
+            | Self::Added { course_head, .. } => course_head,
+        }
+    }
+}
+
+impl Composition {
+    /// Compute where `self` and `other` diverge, in terms of the courses they ring and the calls
+    /// made in those courses.  Both compositions are interpreted using the same [`Parameters`], so
+    /// they should have been generated by similar searches.  Returns `None` if either composition
+    /// uses methods or calls which aren't in the [`Parameters`].
+    pub fn diff(&self, other: &Self, params: &ParamsData) -> Option<CompositionDiff> {
+        if !self.do_cheap_checks(params) || !other.do_cheap_checks(params) {
+            return None;
+        }
+
+        let sections1 = self.course_sections(params);
+        let sections2 = other.course_sections(params);
+        let courses = align_by_key(&sections1, &sections2, |section| &section.course_head)
+            .into_iter()
+            .map(|aligned| match aligned {
+                (Some(s1), Some(s2)) if s1.calling == s2.calling => CourseDiff::Same {
+                    course_head: s1.course_head.clone(),
+                    calling: s1.calling.clone(),
+                },
+                (Some(s1), Some(s2)) => CourseDiff::Changed {
+                    course_head: s1.course_head.clone(),
+                    old_calling: s1.calling.clone(),
+                    new_calling: s2.calling.clone(),
+                },
+                (Some(s), None) => CourseDiff::Removed {
+                    course_head: s.course_head.clone(),
+                    calling: s.calling.clone(),
+                },
+                (None, Some(s)) => CourseDiff::Added {
+                    course_head: s.course_head.clone(),
+                    calling: s.calling.clone(),
+                },
+                (None, None) => unreachable!("`align_by_key` never aligns two gaps"),
+            })
+            .collect_vec();
+        Some(CompositionDiff { courses })
+    }
+
+    /// The number of calls which would have to be added, removed or replaced to turn `self`'s
+    /// first part into `other`'s, plus the number of pieces (usually leads) which would have to
+    /// be rung in a different method.  Calls are matched by their row index, so moving a call
+    /// counts as removing it and adding it somewhere else.  Methods are compared at the start of
+    /// every [`PathElem`] of either composition, up to the end of the shorter one.  This doesn't need any [`Parameters`], so it's
+    /// cheap enough to compare many pairs of compositions.
+    pub fn call_distance(&self, other: &Self) -> usize {
+        let calls1 = self.calls_by_row();
+        let calls2 = other.calls_by_row();
+        let rows = calls1.keys().chain(calls2.keys()).collect::<HashSet<_>>();
+        let num_call_changes = rows
+            .into_iter()
+            .filter(|row_idx| calls1.get(row_idx) != calls2.get(row_idx))
+            .count();
+
+        let methods1 = self.methods_by_row();
+        let methods2 = other.methods_by_row();
+        let rows = (methods1.iter().chain(&methods2))
+            .map(|(range, _)| range.start)
+            .collect::<HashSet<_>>();
+        let num_method_changes = rows
+            .into_iter()
+            .filter(|&row_idx| {
+                let method1 = method_at_row(&methods1, row_idx);
+                let method2 = method_at_row(&methods2, row_idx);
+                // Rows past the end of the shorter composition are covered by its calls
+                method1.is_some() && method2.is_some() && method1 != method2
+            })
+            .count();
+
+        num_call_changes + num_method_changes
+    }
+
+    /// Maps the number of rows before every call in the first part of this composition to the
+    /// call which is made there
+    fn calls_by_row(&self) -> HashMap<usize, CallId> {
+        let mut calls = HashMap::new();
+        let mut row_idx = 0;
+        for elem in &self.path {
+            row_idx += elem.length.as_usize();
+            if let Some(call) = elem.call_to_end {
+                calls.insert(row_idx, call);
+            }
+        }
+        calls
+    }
+
+    /// The range of row indices covered by every [`PathElem`] in the first part of this
+    /// composition, along with that elem's method
+    fn methods_by_row(&self) -> Vec<(Range<usize>, MethodId)> {
+        let mut methods = Vec::with_capacity(self.path.len());
+        let mut row_idx = 0;
+        for elem in &self.path {
+            let end_idx = row_idx + elem.length.as_usize();
+            methods.push((row_idx..end_idx, elem.method_id));
+            row_idx = end_idx;
+        }
+        methods
+    }
+
+    /// Split the first part of this composition into sections of consecutive [`PathElem`]s which
+    /// are all in the same course.
+    fn course_sections(&self, params: &ParamsData) -> Vec<CourseSection> {
+        let mut sections = Vec::<CourseSection>::new();
+        for (elem, elem_string) in self
+            .path
+            .iter()
+            .zip_eq(self.path_elem_strings(&RowBuf::rounds(self.stage), params))
+        {
+            let course_head = elem.course_head(params);
+            match sections.last_mut() {
+                Some(section) if section.course_head == course_head => {
+                    section.calling.push_str(&elem_string);
+                    section.length += elem.length.as_usize();
+                }
+                _ => sections.push(CourseSection {
+                    course_head,
+                    calling: elem_string,
+                    length: elem.length.as_usize(),
+                }),
+            }
+        }
+        sections
+    }
+}
+
+/// A section of the first part of a [`Composition`] which stays within one course
+struct CourseSection {
+    course_head: RowBuf,
+    /// The section of the [`Composition`]'s call string which is rung in this section
+    calling: String,
+    /// The number of rows in this section
+    length: usize,
+}
+
+impl PathElem {
+    /// The course head of the course containing this `PathElem` (see [`Method::course_head`]).
+    fn course_head(&self, params: &ParamsData) -> RowBuf {
+        params
+            .get_method(self.method_id)
+            .course_head(&self.lead_head(&params.method_map))
+    }
+}
+
+/// The method rung at `row_idx`, given the output of [`Composition::methods_by_row`].  Returns
+/// `None` if `row_idx` is past the end of the part.
+fn method_at_row(methods: &[(Range<usize>, MethodId)], row_idx: usize) -> Option<MethodId> {
+    let idx = methods.partition_point(|(range, _)| range.end <= row_idx);
+    methods.get(idx).map(|(_, method_id)| *method_id)
+}
+
+/// Line up two sequences so that as many elements as possible are paired with an element with the
+/// same key, keeping both sequences in order (i.e. a longest common subsequence).  Unpaired
+/// elements are paired with `None`.
+fn align_by_key<'a, T, K: PartialEq>(
+    xs: &'a [T],
+    ys: &'a [T],
+    key: impl Fn(&T) -> &K,
+) -> Vec<(Option<&'a T>, Option<&'a T>)> {
+    // `lcs_lens[i][j]` is the length of the longest common subsequence of `xs[i..]` and `ys[j..]`
+    let mut lcs_lens = vec![vec![0usize; ys.len() + 1]; xs.len() + 1];
+    for i in (0..xs.len()).rev() {
+        for j in (0..ys.len()).rev() {
+            lcs_lens[i][j] = if key(&xs[i]) == key(&ys[j]) {
+                lcs_lens[i + 1][j + 1] + 1
+            } else {
+                lcs_lens[i + 1][j].max(lcs_lens[i][j + 1])
+            };
+        }
+    }
+
+    // Walk through the table to build the alignment
+    let mut aligned = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < xs.len() || j < ys.len() {
+        if i < xs.len() && j < ys.len() && key(&xs[i]) == key(&ys[j]) {
+            aligned.push((Some(&xs[i]), Some(&ys[j])));
+            i += 1;
+            j += 1;
+        } else if j == ys.len() || (i < xs.len() && lcs_lens[i + 1][j] >= lcs_lens[i][j + 1]) {
+            aligned.push((Some(&xs[i]), None));
+            i += 1;
+        } else {
+            aligned.push((None, Some(&ys[j])));
+            j += 1;
+        }
+    }
+    aligned
+}
+
+/////////////////////
+// MUSIC BY COURSE //
+/////////////////////
+
+/// The music rung in one course of a [`Composition`], as computed by
+/// [`Composition::music_by_course`].
+#[derive(Debug, Clone)]
+pub struct CourseMusic {
+    /// The course head of this course, in the part where it's rung
+    pub course_head: RowBuf,
+    /// The sections of the [`Composition`]'s call string which are rung in this course
+    pub calling: String,
+    /// The number of rows rung in this course
+    pub length: usize,
+    pub music_counts: MusicTypeVec<AtRowPositions<usize>>,
+    pub music_score: f32,
+}
+
+impl Composition {
+    /// Split this `Composition`'s music between the courses it's rung in.  Courses are returned in
+    /// the order they're first rung, and a course which is visited several times has the music
+    /// from every visit combined.  Music which spans two courses isn't counted in either of them.
+    ///
+    /// Like [`Composition::values`], this computes every row of the composition so is fairly
+    /// expensive.  Returns `None` if this composition uses methods or calls which aren't in the
+    /// [`Parameters`].
+    pub fn music_by_course(&self, params: &ParamsData) -> Option<Vec<CourseMusic>> {
+        if !self.do_cheap_checks(params) {
+            return None;
+        }
+
+        let block = params.get_block(&self.path);
+        let part_len = block.len() / params.num_parts();
+        let sections = self.course_sections(params);
+        let mut courses = Vec::<CourseMusic>::new();
+        for part_idx in 0..params.num_parts() {
+            let part_start = part_idx * part_len;
+            let part_head =
+                Row::solve_xa_equals_b(&params.start_row, block.get_row(part_start).unwrap());
+            let mut row_idx = part_start;
+            for section in &sections {
+                // Count the music in this section
+                let rows = block
+                    .row_vec()
+                    .as_slice_range(row_idx..row_idx + section.length);
+                let stroke_of_first_row = (!self.start_stroke).offset(row_idx);
+                let music_counts: MusicTypeVec<_> = params
+                    .music_types
+                    .iter()
+                    .map(|mt| mt.count(rows, stroke_of_first_row))
+                    .collect();
+                row_idx += section.length;
+                // Add it to the right course
+                let course_head = &part_head * &section.course_head;
+                match courses.iter_mut().find(|c| c.course_head == course_head) {
+                    Some(course) => {
+                        course.calling.push(' ');
+                        course.calling.push_str(&section.calling);
+                        course.length += section.length;
+                        for (total, count) in course.music_counts.iter_mut().zip_eq(music_counts) {
+                            *total += count;
+                        }
+                    }
+                    None => courses.push(CourseMusic {
+                        course_head,
+                        calling: section.calling.clone(),
+                        length: section.length,
+                        music_counts,
+                        music_score: 0.0, // Computed once all the counts are known
+                    }),
+                }
+            }
+        }
+        for course in &mut courses {
+            course.music_score = music_counts_to_score(&course.music_counts, params);
+        }
+        Some(courses)
+    }
+}
+
+//////////
+// ROWS //
+//////////
+
+impl Composition {
+    /// Generate every [`Row`] of this `Composition`, each annotated with the [`MethodId`] it
+    /// belongs to and its index within that method's lead.  The [`Block`]'s `leftover_row` is the
+    /// composition's [`end_row`](Parameters::end_row).  Returns `None` if this composition uses
+    /// methods or calls which aren't in the [`Parameters`].
+    pub fn rows(&self, params: &ParamsData) -> Option<Block<(MethodId, usize)>> {
+        if !self.do_cheap_checks(params) {
+            return None;
+        }
+        Some(params.get_block(&self.path))
+    }
+}
+
+//////////////////
+// COURSE TABLE //
+//////////////////
+
+/// The first part of a [`Composition`] laid out as a traditional table of calls, with one
+/// [`CourseTableRow`] per course and one column per calling position.  Courses end whenever the
+/// [`calling_bell`](Parameters::calling_bell) returns home at a lead head.  Returned by
+/// [`Composition::course_table`].
+#[derive(Debug, Clone)]
+pub struct CourseTable {
+    /// The calling positions used in the composition (e.g. `['M', 'W', 'H']`), in the order that
+    /// they would be reached in a plain course.
+    pub columns: Vec<char>,
+    pub courses: Vec<CourseTableRow>,
+}
+
+/// A single course of a [`CourseTable`]
+#[derive(Debug, Clone)]
+pub struct CourseTableRow {
+    /// The row at the end of this course (i.e. the first row of the next course)
+    pub course_end: RowBuf,
+    /// For each of the [`CourseTable`]'s `columns`, the calls made at that position in this
+    /// course (e.g. `"-"`, `"s"` or `"2"` for two bobs).  Positions with no calls are empty.
+    pub calls: Vec<String>,
+    /// The number of rows rung in this course
+    pub length: usize,
+}
+
+impl Composition {
+    /// Lay out the first part of this `Composition` as a traditional table of calling positions
+    /// against course ends.  Returns `None` if this `Composition` doesn't match the
+    /// [`Parameters`], or if its calls can't be described by calling positions (e.g. in spliced
+    /// or when the [`calling_bell`](Parameters::calling_bell) is moved by the part head).
+    pub fn course_table(&self, params: &ParamsData) -> Option<CourseTable> {
+        if !self.do_cheap_checks(params)
+            || params.is_spliced()
+            || params.call_display_style != CallDisplayStyle::CallingPositions
+        {
+            return None;
+        }
+
+        let block = params.get_block(&self.path);
+        let part_len = block.len() / params.num_parts();
+        let row_at = |idx: usize| block.get_row(idx).unwrap_or_else(|| block.leftover_row());
+        // Find the calling position of every call in the first part, keyed by the index of the
+        // row after the call
+        let mut calls = HashMap::<usize, (CallId, char)>::new();
+        let mut row_idx = 0;
+        for elem in &self.path {
+            row_idx += elem.length.as_usize();
+            if let Some(call_id) = elem.call_to_end {
+                let place = row_at(row_idx).place_of(params.calling_bell);
+                let position = params.calling_position(params.get_call(call_id), place as usize);
+                calls.insert(row_idx, (call_id, position));
+            }
+        }
+
+        // Split the first part into courses
+        let home_place = params.start_row.place_of(params.calling_bell);
+        let mut courses = Vec::<(RowBuf, Vec<(char, CallId)>, usize)>::new();
+        // For each calling position, how far through a plain course the `calling_bell` reaches
+        // the place it's in just before that call
+        let mut column_orders = HashMap::<char, usize>::new();
+        let mut course_calls = Vec::new();
+        let mut course_start = 0;
+        for idx in 1..=part_len {
+            if let Some(&(call_id, position)) = calls.get(&idx) {
+                course_calls.push((position, call_id));
+                let &(method_id, sub_lead_idx) = block.get_annot(idx - 1).unwrap();
+                let place_before_call = row_at(idx - 1).place_of(params.calling_bell);
+                let plain_course = &params.method_map[&method_id].double_plain_course;
+                let order = plain_course
+                    .annot_rows()
+                    .position(|(&(_, sub_lead), row)| {
+                        sub_lead == sub_lead_idx
+                            && row.place_of(params.calling_bell) == place_before_call
+                    })
+                    .unwrap_or(usize::MAX);
+                column_orders
+                    .entry(position)
+                    .and_modify(|o| *o = (*o).min(order))
+                    .or_insert(order);
+            }
+            let is_lead_head = block.get_annot(idx).map_or(0, |(_, sub_lead)| *sub_lead) == 0;
+            let is_home = row_at(idx).place_of(params.calling_bell) == home_place;
+            if (is_lead_head && is_home) || idx == part_len {
+                let course_calls = std::mem::take(&mut course_calls);
+                courses.push((row_at(idx).to_owned(), course_calls, idx - course_start));
+                course_start = idx;
+            }
+        }
+
+        let columns = column_orders
+            .into_iter()
+            .sorted_by_key(|&(position, order)| (order, position))
+            .map(|(position, _)| position)
+            .collect_vec();
+        let courses = courses
+            .into_iter()
+            .map(|(course_end, course_calls, length)| CourseTableRow {
+                course_end,
+                calls: columns
+                    .iter()
+                    .map(|&column| {
+                        let calls_in_column = course_calls
+                            .iter()
+                            .filter(|(position, _)| *position == column)
+                            .map(|(_, call_id)| *call_id);
+                        calls_cell_string(calls_in_column, params)
+                    })
+                    .collect_vec(),
+                length,
+            })
+            .collect_vec();
+        Some(CourseTable { columns, courses })
+    }
+}
+
+/// Summarise a sequence of calls made at the same calling position, merging repeated calls
+/// (e.g. `[-, -]` becomes `"2"`, and `[s, s]` becomes `"2s"`).
+fn calls_cell_string(calls: impl IntoIterator<Item = CallId>, params: &ParamsData) -> String {
+    let mut s = String::new();
+    for (count, call_id) in calls.into_iter().dedup_with_count() {
+        let call = params.get_call(call_id);
+        match (count, call.short_symbol()) {
+            (1, None) => s.push(call.symbol),
+            (_, symbol) => {
+                if count > 1 {
+                    s.push_str(&count.to_string());
+                }
+                s.extend(symbol);
+            }
+        }
+    }
+    s
+}
+
+////////////////
+// CALL EDITS //
+////////////////
+
+/// A point in the first part of a [`Composition`] where a call could be added, removed or
+/// replaced.  Returned by [`Composition::call_sites`].
+#[derive(Debug, Clone)]
+pub struct CallSite {
+    /// The number of rows between the start of the composition and this call site.
+    pub row_idx: usize,
+    /// The call currently made at this site, or `None` if nothing is called here.
+    pub call: Option<CallId>,
+    /// Every call in the [`Parameters`] which could be made at this site.
+    pub possible_calls: Vec<CallId>,
+}
+
+/// The effect of changing a single call in a [`Composition`].  Returned by
+/// [`Composition::change_call`].
+#[derive(Debug, Clone)]
+pub struct CallChange {
+    /// The [`Composition`] with the call changed, or `None` if the changed calling doesn't come
+    /// round with the same number of parts.  This doesn't check any of the other requirements of
+    /// the [`Parameters`]; use [`Composition::values`] for that.
+    pub composition: Option<Composition>,
+    /// `true` if the changed calling reaches the [`Parameters::end_row`] within the maximum
+    /// length.  The calling is repeated as many times as needed to come round, so this may use a
+    /// different number of parts to the original composition.
+    pub comes_round: bool,
+    /// How many more rows the changed calling has than the original composition.
+    pub length_delta: isize,
+    /// The [`Truth`] of the changed calling.
+    pub truth: Truth,
+    /// The change in the total count of every [`MusicType`].
+    pub music_deltas: MusicTypeVec<isize>,
+    /// The change in the total music score.
+    pub music_score_delta: f32,
+}
+
+impl Composition {
+    /// Every place in this `Composition`'s first part where a call is made, or where one of the
+    /// [`Parameters`]' calls could be made.  Returns an empty list if this `Composition` wasn't
+    /// generated from (something compatible with) these [`Parameters`].
+    pub fn call_sites(&self, params: &ParamsData) -> Vec<CallSite> {
+        if !self.do_cheap_checks(params) {
+            return Vec::new();
+        }
+        self.call_site_locations(params)
+            .into_iter()
+            .map(|loc| {
+                let elem = &self.path[loc.elem_idx];
+                let rows_before_elem: usize = self.path[..loc.elem_idx]
+                    .iter()
+                    .map(|e| e.length.as_usize())
+                    .sum();
+                CallSite {
+                    row_idx: rows_before_elem + loc.rows_into_elem,
+                    call: match loc.rows_into_elem == elem.length.as_usize() {
+                        true => elem.call_to_end,
+                        false => None, // Calls are only made at the ends of `PathElem`s
+                    },
+                    possible_calls: loc.possible_calls,
+                }
+            })
+            .collect_vec()
+    }
+
+    /// Determine what would happen if the call at the `position`th [`CallSite`] (as returned by
+    /// [`Composition::call_sites`]) was replaced with `call` (where `None` means removing the
+    /// call).  Every row after this site is changed, and the calling is repeated until it comes
+    /// round.
+    ///
+    /// This works by regenerating the rows of the changed composition rather than by following
+    /// links in the search graph.  The graph only contains the chunks which could be part of a
+    /// composition that meets every requirement of the search (length, falseness, music and so
+    /// on), so changing one call would usually lead to chunks which aren't in it.  Also, a
+    /// `Composition` doesn't keep its graph (editors often only have a `Composition` and its
+    /// [`Parameters`]), and regenerating a few thousand rows is quick enough for interactive use.
+    ///
+    /// Returns `None` if there's no call site at `position`, or `call` can't be made there.
+    pub fn change_call(
+        &self,
+        position: usize,
+        call: Option<CallId>,
+        params: &ParamsData,
+        id_generator: &IdGenerator<CompositionId>,
+    ) -> Option<CallChange> {
+        if !self.do_cheap_checks(params) {
+            return None;
+        }
+        let location = self.call_site_locations(params).into_iter().nth(position)?;
+        if let Some(call_id) = call {
+            if !location.possible_calls.contains(&call_id) {
+                return None; // Call can't be placed here
+            }
+        }
+
+        // Change the call, splitting the `PathElem` if the call site is part-way through it
+        let mut path = self.path.clone();
+        let elem = &mut path[location.elem_idx];
+        let rows_after_site = elem.length.as_usize() - location.rows_into_elem;
+        if rows_after_site > 0 {
+            let elem_after_site = PathElem {
+                start_row: elem.start_row.clone(), // Will be overwritten
+                method_id: elem.method_id,
+                start_sub_lead_idx: params.get_method(elem.method_id).add_sub_lead_idx(
+                    elem.start_sub_lead_idx,
+                    PerPartLength::new(location.rows_into_elem),
+                ),
+                length: PerPartLength::new(rows_after_site),
+                call_to_end: elem.call_to_end,
+            };
+            elem.length = PerPartLength::new(location.rows_into_elem);
+            path.insert(location.elem_idx + 1, elem_after_site);
+        }
+        path[location.elem_idx].call_to_end = call;
+
+        // Generate the new first part, recomputing where every `PathElem` starts
+        let mut block = Block::<(MethodId, usize)>::with_leftover_row(params.start_row.clone());
+        for elem in &mut path {
+            elem.start_row = block.leftover_row().to_owned();
+            params.extend_with_path_elem(&mut block, elem);
+        }
+        let part_head = Row::solve_xa_equals_b(&params.start_row, block.leftover_row());
+
+        // Repeat the first part until the calling comes round (or gets too long)
+        let part_len = block.len();
+        let mut num_parts = 1;
+        while block.leftover_row() != params.end_row.as_row()
+            && block.len() + part_len <= params.max_length().as_usize()
+        {
+            block.extend_from_within(..part_len);
+            num_parts += 1;
+        }
+        let comes_round = block.leftover_row() == params.end_row.as_row();
+
+        // Compare the music to the original composition
+        let old_music_counts = self.calculate_music_counts(params);
+        let new_music_counts: MusicTypeVec<_> = params
+            .music_types
+            .iter()
+            .map(|mt| mt.count(&block, !self.start_stroke))
+            .collect();
+        let music_deltas = params
+            .music_types
+            .iter()
+            .zip_eq(old_music_counts.iter().zip_eq(&new_music_counts))
+            .map(|(mt, (old, new))| mt.masked_total(*new) as isize - mt.masked_total(*old) as isize)
+            .collect();
+        let music_score_delta = music_counts_to_score(&new_music_counts, params)
+            - music_counts_to_score(&old_music_counts, params);
+
+        // Only create a new composition if it fits into the same part head group
+        let composition = match params.part_head_group.get_part_head(&part_head) {
+            Some(ph) if comes_round && num_parts == params.num_parts() => {
+                Some(Composition::new(id_generator.next(), path, ph, params))
+            }
+            _ => None,
+        };
+
+        Some(CallChange {
+            composition,
+            comes_round,
+            length_delta: block.len() as isize - self.length.as_usize() as isize,
+            truth: block.truth(),
+            music_deltas,
+            music_score_delta,
+        })
+    }
+
+    /// Finds every [`CallSite`] in the first part of this `Composition`.  A call site is either
+    /// the end of a [`PathElem`] or a labelled row within one (e.g. a plain lead end).
+    fn call_site_locations(&self, params: &ParamsData) -> Vec<CallSiteLocation> {
+        let mut locations = Vec::new();
+        for (elem_idx, elem) in self.path.iter().enumerate() {
+            let method = params.get_method(elem.method_id);
+            // Transposes rows of the method's plain course onto the rows of this `PathElem`
+            let double_plain_course = &params.method_map[&elem.method_id].double_plain_course;
+            let plain_course_row = |idx: usize| double_plain_course.get_row(idx).unwrap();
+            let transposition =
+                Row::solve_xa_equals_b(plain_course_row(elem.start_sub_lead_idx), &elem.start_row);
+            for rows_into_elem in 1..=elem.length.as_usize() {
+                let sub_lead_idx = method
+                    .add_sub_lead_idx(elem.start_sub_lead_idx, PerPartLength::new(rows_into_elem));
+                let is_elem_end = rows_into_elem == elem.length.as_usize();
+                // Determine which labels a call here could lead to.  Calls within a `PathElem`
+                // lead straight back into the same method, whereas the last `PathElem` leads
+                // either into the next part or the end of the composition.
+                let next_elem = match self.path.get(elem_idx + 1) {
+                    _ if !is_elem_end => None,
+                    Some(next_elem) => Some(next_elem),
+                    None if params.is_multipart() => Some(&self.path[0]),
+                    None => None,
+                };
+                let is_valid_label_to = |label: &String| match next_elem {
+                    _ if !is_elem_end => method.get_labels(sub_lead_idx).contains(label),
+                    Some(next) => params
+                        .get_method(next.method_id)
+                        .get_labels(next.start_sub_lead_idx)
+                        .contains(label),
+                    None => params.valid_end_labels.contains(label),
+                };
+
+                // Calls at banned calling positions can't be made, just like in the graph
+                let row_before_call =
+                    &transposition * plain_course_row(elem.start_sub_lead_idx + rows_into_elem - 1);
+                let is_calling_position_allowed = |call_idx: CallIdx, call: &Call| {
+                    let row_after_call = &row_before_call * call.transposition.transposition();
+                    params.is_calling_position_allowed(call_idx, &row_after_call)
+                };
+
+                let labels = method.get_labels(sub_lead_idx);
+                let possible_calls = params
+                    .calls
+                    .iter_enumerated()
+                    .filter(|&(call_idx, call)| {
+                        labels.contains(&call.label_from)
+                            && is_valid_label_to(&call.label_to)
+                            && call.can_be_made_in(method.id)
+                            && is_calling_position_allowed(call_idx, call)
+                    })
+                    .map(|(_, call)| call.id)
+                    .collect_vec();
+                let has_call = is_elem_end && elem.call_to_end.is_some();
+                if has_call || !possible_calls.is_empty() {
+                    locations.push(CallSiteLocation {
+                        elem_idx,
+                        rows_into_elem,
+                        possible_calls,
+                    });
+                }
+            }
+        }
+        locations
+    }
+}
+
+/// The location of a [`CallSite`] within a [`Composition`]'s `path`
+struct CallSiteLocation {
+    elem_idx: usize,
+    /// How many rows of `path[elem_idx]` are rung before the call site
+    rows_into_elem: usize,
+    possible_calls: Vec<CallId>,
+}
+
+////////////////
+// DIFFICULTY //
+////////////////
+
+/// Metrics describing how hard a [`Composition`] is to call, as computed by
+/// [`Composition::difficulty`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difficulty {
+    /// The total number of calls, over every part
+    pub num_calls: usize,
+    /// The number of different calls (e.g. `H`, `sH` and `W`) used, as written in the call string
+    pub num_calling_positions: usize,
+    /// The largest number of rows rung without a call.  In one-part compositions, this includes
+    /// the rows before the first call and after the last call.  In multi-part compositions, the
+    /// gap between the last call of one part and the first call of the next is also counted.
+    pub max_gap: usize,
+    /// A simple score in `0.0..=1.0`, where higher values are easier to call.  Compositions with
+    /// no calls score `1.0`, and the score decreases with the number of calls per lead and the
+    /// number of different calling positions.
+    pub conductability: f32,
+}
+
+impl Composition {
+    /// Compute the [`Difficulty`] of calling this `Composition`
+    pub fn difficulty(&self, params: &ParamsData) -> Difficulty {
+        let rounds = RowBuf::rounds(self.stage);
+        let mut calling_positions = HashSet::<String>::new();
+        let mut call_ends = Vec::<usize>::new(); // Row indices of calls, within the first part
+        let mut rows_so_far = 0;
+        let mut num_leads = 0.0;
+        for (path_idx, elem) in self.path.iter().enumerate() {
+            rows_so_far += elem.length.as_usize();
+            num_leads +=
+                elem.length.as_usize() as f32 / params.get_method(elem.method_id).lead_len() as f32;
+            if let Some(call_text) = self.call_text(path_idx, &rounds, params) {
+                calling_positions.insert(call_text);
+                call_ends.push(rows_so_far);
+            }
+        }
+        let part_len = rows_so_far;
+
+        // Work out the gaps between each pair of adjacent calls
+        let max_gap = match (call_ends.first(), call_ends.last()) {
+            (Some(first), Some(last)) => {
+                let gaps_between_calls = call_ends.iter().tuple_windows().map(|(a, b)| b - a);
+                let edge_gaps = if params.is_multipart() {
+                    vec![part_len - last + first]
+                } else {
+                    vec![*first, part_len - last]
+                };
+                gaps_between_calls.chain(edge_gaps).max().unwrap()
+            }
+            _ => self.length.as_usize(), // No calls, so the whole composition is one gap
+        };
+
+        let num_calls = call_ends.len() * params.num_parts();
+        let calls_per_lead = num_calls as f32 / (num_leads * params.num_parts() as f32);
+        let extra_calling_positions = calling_positions.len().saturating_sub(1) as f32;
+        Difficulty {
+            num_calls,
+            num_calling_positions: calling_positions.len(),
+            max_gap,
+            conductability: 1.0 / (1.0 + calls_per_lead + 0.1 * extra_calling_positions),
+        }
+    }
+}
+
+/// The different [`Difficulty`] metrics by which [`Composition`]s can be sorted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DifficultyMetric {
+    NumCalls,
+    NumCallingPositions,
+    MaxGap,
+    Conductability,
+}
+
+impl DifficultyMetric {
+    /// Returns how easy a [`Composition`] with the given [`Difficulty`] is to call, according to
+    /// this metric.  Higher values are easier to call.
+    pub fn ease(self, difficulty: &Difficulty) -> f32 {
+        match self {
+            Self::NumCalls => -(difficulty.num_calls as f32),
+            Self::NumCallingPositions => -(difficulty.num_calling_positions as f32),
+            Self::MaxGap => -(difficulty.max_gap as f32),
+            Self::Conductability => difficulty.conductability,
+        }
+    }
+}
+
+impl std::str::FromStr for DifficultyMetric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
+            "calls" => Self::NumCalls,
+            "calling-positions" => Self::NumCallingPositions,
+            "max-gap" => Self::MaxGap,
+            "conductability" => Self::Conductability,
+            _ => {
+                return Err(format!(
+                    "Unknown difficulty metric {s:?}. Expected `calls`, `calling-positions`, \
+`max-gap` or `conductability`."
+                ))
+            }
+        })
+    }
 }
 
 ///////////
@@ -847,7 +2075,21 @@ fn num_leads_covered(lead_len: usize, start_sub_lead_idx: usize, length: PerPart
 
 #[cfg(test)]
 mod tests {
-    use crate::utils::lengths::PerPartLength;
+    use std::sync::atomic::AtomicBool;
+
+    use bellframe::Stage;
+    use itertools::Itertools;
+
+    use super::{merge_results, CallChange, ParamsData, ScoreNormalisation};
+    use crate::{
+        parameters::{CallId, MethodId, Parameters},
+        test_utils::{comp_with_calling, multipart_plain_bob_params, plain_bob_params},
+        utils::{
+            lengths::{PerPartLength, TotalLength},
+            IdGenerator,
+        },
+        Composition, Config, Search, Update,
+    };
 
     #[test]
     fn num_leads_covered() {
@@ -857,4 +2099,338 @@ mod tests {
         assert_eq!(super::num_leads_covered(32, 0, PerPartLength::new(2)), 1);
         assert_eq!(super::num_leads_covered(32, 16, PerPartLength::new(24)), 2);
     }
+
+    #[test]
+    fn align_by_key() {
+        let align = |xs: &str, ys: &str| -> String {
+            let xs = xs.chars().collect::<Vec<_>>();
+            let ys = ys.chars().collect::<Vec<_>>();
+            super::align_by_key(&xs, &ys, |c| c)
+                .into_iter()
+                .map(|pair| match pair {
+                    (Some(x), Some(_)) => *x,
+                    (Some(_), None) => '-',
+                    (None, Some(_)) => '+',
+                    (None, None) => unreachable!(),
+                })
+                .collect()
+        };
+        assert_eq!(align("", ""), "");
+        assert_eq!(align("abc", "abc"), "abc");
+        assert_eq!(align("abc", ""), "---");
+        assert_eq!(align("", "ab"), "++");
+        assert_eq!(align("abcd", "acd"), "a-cd");
+        assert_eq!(align("acd", "abcd"), "a+cd");
+        assert_eq!(align("abcd", "axcd"), "a-+cd");
+    }
+
+    #[test]
+    fn merge_z_scores_are_per_source() {
+        let params = plain_bob_params(Stage::MINOR);
+        let comp = |calling: &str| comp_with_calling(&params, calling);
+        // Two searches, where every composition from the second has a higher total score than
+        // every composition from the first
+        let low_scoring_comps = [comp("WHWH"), comp("BH")];
+        let high_scoring_comps = [comp("HHH"), comp("WWW")];
+        let result_sets = [
+            (&params, &low_scoring_comps[..]),
+            (&params, &high_scoring_comps[..]),
+        ];
+
+        let ranked_sources = |normalisation: ScoreNormalisation| {
+            merge_results(&result_sets, &params, normalisation)
+                .iter()
+                .map(|c| c.source_idx)
+                .collect_vec()
+        };
+        // Ranking by total score puts both compositions from the second search first ...
+        assert_eq!(ranked_sources(ScoreNormalisation::Total), [1, 1, 0, 0]);
+        // ... but z-scores rank the best composition of each search above the worst of either
+        let z_score_sources = ranked_sources(ScoreNormalisation::ZScore);
+        assert_eq!(z_score_sources[..2].iter().sorted().collect_vec(), [&0, &1]);
+        assert_eq!(z_score_sources[2..].iter().sorted().collect_vec(), [&0, &1]);
+        // Each search's z-scores are symmetric about its own mean
+        for c in merge_results(&result_sets, &params, ScoreNormalisation::ZScore) {
+            assert!((c.normalised_score.abs() - 1.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn merge_dedupes_rotations() {
+        let params = plain_bob_params(Stage::MINOR);
+        let comp = |calling: &str| comp_with_calling(&params, calling);
+        let bhhhh = comp("BHHHH");
+        assert_eq!(bhhhh.length, TotalLength::new(72));
+        // Every 72-row composition which starts and ends at the second lead head of `BHHHH`.  One
+        // of these rings the same round block as `BHHHH`, but from a different row
+        let lead_head = ParamsData::new(&params)
+            .get_block(&bhhhh.path)
+            .get_row(12)
+            .unwrap()
+            .to_owned();
+        let rotated_params = Parameters {
+            start_row: lead_head.clone(),
+            end_row: lead_head,
+            length: TotalLength::new(72)..=TotalLength::new(72),
+            ..params.clone()
+        };
+        let mut rotated_comps = Vec::new();
+        Search::new(rotated_params.clone(), Config::default())
+            .unwrap()
+            .run_exhaustive(
+                |update| {
+                    if let Update::Comp(comp) = update {
+                        rotated_comps.push(comp);
+                    }
+                },
+                &AtomicBool::new(false),
+            );
+
+        let comps = [bhhhh, comp("HHH"), comp("WWW")];
+        let result_sets = [(&params, &comps[..]), (&rotated_params, &rotated_comps[..])];
+        let merged = merge_results(&result_sets, &params, ScoreNormalisation::Total);
+        // `HHH` and `WWW` are rotations of each other, but ring different rows so are both kept
+        let sources = merged.iter().map(|c| c.source_idx).collect_vec();
+        assert_eq!(sources.iter().filter(|&&idx| idx == 0).count(), 3);
+        assert_eq!(merged.len(), comps.len() + rotated_comps.len() - 1);
+    }
+
+    /// The [`CallId`]s of the bob and single in `params`
+    fn bob_and_single(params: &Parameters) -> (CallId, CallId) {
+        let call_id = |symbol: char| params.calls.iter().find(|c| c.symbol == symbol).unwrap().id;
+        (call_id('-'), call_id('s'))
+    }
+
+    fn changed_call(
+        comp: &Composition,
+        position: usize,
+        call: Option<CallId>,
+        params: &Parameters,
+    ) -> CallChange {
+        let params = ParamsData::new(params);
+        (comp.change_call(position, call, &params, &IdGenerator::starting_at_zero()))
+            .expect("Call site should exist")
+    }
+
+    #[test]
+    fn call_sites() {
+        let params = plain_bob_params(Stage::MINOR);
+        let (bob, single) = bob_and_single(&params);
+        let sites_of = |calling: &str| {
+            let comp = comp_with_calling(&params, calling);
+            comp.call_sites(&ParamsData::new(&params))
+        };
+
+        // Every lead end of the plain course is a call site, and bobs and singles can be made at
+        // all of them
+        let plain_course = sites_of("");
+        assert_eq!(
+            plain_course.iter().map(|s| s.row_idx).collect_vec(),
+            [12, 24, 36, 48, 60]
+        );
+        for site in &plain_course {
+            assert_eq!(site.call, None);
+            assert_eq!(site.possible_calls, [bob, single]);
+        }
+        // Sites which already have calls report them
+        let bh = sites_of("BH");
+        assert_eq!(
+            bh.iter().map(|s| (s.row_idx, s.call)).collect_vec(),
+            [
+                (12, None),
+                (24, None),
+                (36, Some(bob)),
+                (48, None),
+                (60, None),
+                (72, Some(bob))
+            ]
+        );
+    }
+
+    #[test]
+    fn call_sites_at_allowed_calling_positions() {
+        let mut params = plain_bob_params(Stage::MINOR);
+        let (bob, single) = bob_and_single(&params);
+        // The plain course has one lead end at each of `B` and `H`, and none at `M`
+        let cases = [
+            ("H", vec![60]),
+            ("B", vec![36]),
+            ("BH", vec![36, 60]),
+            ("M", vec![]),
+        ];
+        for (positions, exp_site_rows) in cases {
+            params.allowed_calling_positions = Some(positions.chars().collect_vec());
+            let plain_course = comp_with_calling(&params, "");
+            let sites = plain_course.call_sites(&ParamsData::new(&params));
+            assert_eq!(
+                sites.iter().map(|s| s.row_idx).collect_vec(),
+                exp_site_rows,
+                "{positions}"
+            );
+            for site in &sites {
+                assert_eq!(site.possible_calls, [bob, single]);
+            }
+        }
+    }
+
+    #[test]
+    fn change_call_single_part() {
+        let params = plain_bob_params(Stage::MINOR);
+        let (bob, single) = bob_and_single(&params);
+        let plain_course = comp_with_calling(&params, "");
+        let bh = comp_with_calling(&params, "BH");
+
+        // Adding a bob at Home turns the plain course into three courses, which only comes round
+        // with a different number of parts
+        let add_bob = changed_call(&plain_course, 4, Some(bob), &params);
+        assert!(add_bob.comes_round);
+        assert!(add_bob.truth.is_true());
+        assert_eq!(add_bob.length_delta, 120);
+        assert_eq!(add_bob.music_deltas.raw, [2]);
+        assert_eq!(add_bob.music_score_delta, 2.0);
+        assert!(add_bob.composition.is_none());
+
+        // Replacing the bob at Before with a single
+        let replace_bob = changed_call(&bh, 2, Some(single), &params);
+        assert!(replace_bob.comes_round);
+        assert!(replace_bob.truth.is_true());
+        assert_eq!(replace_bob.length_delta, 72);
+        assert!(replace_bob.composition.is_none());
+
+        // Removing the bob at Home makes the calling repeat so that it becomes false
+        let remove_bob = changed_call(&bh, 5, None, &params);
+        assert!(remove_bob.comes_round);
+        assert!(!remove_bob.truth.is_true());
+        assert_eq!(remove_bob.length_delta, 144);
+
+        // Changes which aren't possible return `None`
+        let params_data = ParamsData::new(&params);
+        let id_gen = IdGenerator::starting_at_zero();
+        assert!(bh
+            .change_call(6, Some(bob), &params_data, &id_gen)
+            .is_none());
+        assert!(bh
+            .change_call(0, Some(CallId(100)), &params_data, &id_gen)
+            .is_none());
+    }
+
+    #[test]
+    fn change_call_multipart() {
+        let params = multipart_plain_bob_params(Stage::MINOR, "124653");
+        let (bob, _single) = bob_and_single(&params);
+        let comp = comp_with_calling(&params, "WH");
+
+        // Adding a bob at the third lead end keeps the same part head, so the new calling is a
+        // valid composition with the same number of parts
+        let change = changed_call(&comp, 2, Some(bob), &params);
+        assert!(change.comes_round);
+        assert!(change.truth.is_true());
+        assert_eq!(change.length_delta, 0);
+        let new_comp = change.composition.unwrap();
+        let expected_comp = comp_with_calling(&params, "WBI");
+        let params_data = ParamsData::new(&params);
+        assert_eq!(new_comp.part_head, expected_comp.part_head);
+        assert_eq!(
+            new_comp.rows(&params_data),
+            expected_comp.rows(&params_data)
+        );
+    }
+
+    #[test]
+    fn call_distance() {
+        let params = plain_bob_params(Stage::MINOR);
+        let wbhww = comp_with_calling(&params, "WBHWW");
+        let wwbhw = comp_with_calling(&params, "WWBHW");
+        let www = comp_with_calling(&params, "WWW");
+        let hhh = comp_with_calling(&params, "HHH");
+
+        // Identical compositions
+        assert_eq!(wbhww.call_distance(&wbhww), 0);
+        assert_eq!(www.call_distance(&comp_with_calling(&params, "WWW")), 0);
+        // Calls at different rows never match, even if they're the same call
+        assert_eq!(www.call_distance(&hhh), 6);
+        assert_eq!(hhh.call_distance(&www), 6);
+        // Both start with the same `W` and end with the same `W`, but the other three calls are
+        // at different rows
+        assert_eq!(wbhww.call_distance(&wwbhw), 4);
+        assert_eq!(wwbhw.call_distance(&wbhww), 4);
+
+        // Adding one call
+        let params = multipart_plain_bob_params(Stage::MINOR, "124653");
+        let wh = comp_with_calling(&params, "WH");
+        let wbi = comp_with_calling(&params, "WBI");
+        assert_eq!(wh.call_distance(&wbi), 1);
+        assert_eq!(wbi.call_distance(&wh), 1);
+    }
+
+    #[test]
+    fn to_json() {
+        let params = plain_bob_params(Stage::MINOR);
+        let comp = comp_with_calling(&params, "HHH");
+        let json = comp.to_json(3, &ParamsData::new(&params));
+        assert_eq!(
+            json,
+            r#"{"index":3,"length":180,"part_head":"123456","call_string":"HHH","music_score":12,"#
+                .to_owned()
+                + r#""total_score":6.5999994,"score_per_row":0.036666665,"repeated_rows":0}"#
+        );
+        // Compositions which aren't valid with these parameters only have an index
+        let short_params = Parameters {
+            length: TotalLength::ZERO..=TotalLength::new(60),
+            ..params
+        };
+        assert_eq!(
+            comp.to_json(3, &ParamsData::new(&short_params)),
+            r#"{"index":3}"#
+        );
+
+        assert_eq!(super::json_string("sH"), r#""sH""#);
+        assert_eq!(super::json_string("a\"b\\c\n"), r#""a\"b\\c\u000a""#);
+        assert_eq!(super::json_number(1.5), "1.5");
+        assert_eq!(super::json_number(f32::NAN), "null");
+    }
+
+    #[test]
+    fn call_distance_spliced() {
+        let params = plain_bob_params(Stage::MINOR);
+        let wbhww = comp_with_calling(&params, "WBHWW");
+        let wwbhw = comp_with_calling(&params, "WWBHW");
+        // Ring a single `PathElem` (a lead of Plain Bob) in a different method, without changing
+        // any calls
+        let with_method = |comp: &Composition, elem_idx: usize| {
+            let mut comp = comp.clone();
+            comp.path[elem_idx].method_id = MethodId(1);
+            comp
+        };
+        let last_idx = wbhww.path.len() - 1;
+
+        // Ringing one lead in a different method is one change, wherever it is
+        for elem_idx in [0, 1, last_idx] {
+            let spliced = with_method(&wbhww, elem_idx);
+            assert_eq!(wbhww.call_distance(&spliced), 1);
+            assert_eq!(spliced.call_distance(&wbhww), 1);
+        }
+        assert_eq!(
+            wbhww.call_distance(&with_method(&with_method(&wbhww, 1), 2)),
+            2
+        );
+        // Changes to calls and methods are added together
+        assert_eq!(wbhww.call_distance(&wwbhw), 4);
+        assert_eq!(wbhww.call_distance(&with_method(&wwbhw, last_idx)), 5);
+        // Ringing the same lead in the same different method isn't a change
+        assert_eq!(
+            with_method(&wbhww, 1).call_distance(&with_method(&wwbhw, 1)),
+            4
+        );
+        // Methods are only compared up to the end of the shorter composition, so compositions of
+        // different lengths in the same method only differ by their calls
+        let bh = comp_with_calling(&params, "BH");
+        let (calls1, calls2) = (wbhww.calls_by_row(), bh.calls_by_row());
+        let num_call_changes = (calls1.keys().chain(calls2.keys()))
+            .unique()
+            .filter(|row_idx| calls1.get(row_idx) != calls2.get(row_idx))
+            .count();
+        assert_eq!(wbhww.call_distance(&bh), num_call_changes);
+        assert_eq!(bh.call_distance(&wbhww), num_call_changes);
+    }
 }
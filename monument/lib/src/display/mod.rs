@@ -0,0 +1,670 @@
+//! Human-readable text renderings of [`Composition`]s, shared by every frontend so that results
+//! are formatted identically wherever they're displayed.
+
+mod svg;
+
+pub use svg::blue_line_svg;
+
+use std::fmt::Write;
+
+use bellframe::{row::ShortRow, Block, PlaceNot};
+use itertools::Itertools;
+
+use crate::{
+    composition::{CourseMusic, CourseTable, ParamsData},
+    parameters::{CallDisplayStyle, CallTransposition, MethodId},
+    Composition, Parameters, Search,
+};
+
+/// Formats [`Composition`]s as lines of a table, with columns for length, music, score, calling,
+/// etc.
+#[derive(Debug, Clone)]
+pub struct CompositionPrinter {
+    params: Parameters, // TODO: Make this `ParamsData`
+    /// Counter which records how many compositions have been printed so far
+    comps_printed: usize,
+
+    /* COLUMN WIDTH INFORMATION */
+    /// The maximum width of a composition's number (i.e. the width of the total number of
+    /// compositions).  If `None`, no composition numbers are printed.
+    comp_count_width: Option<usize>,
+    /// The maximum width of a composition's (total) length
+    length_width: usize,
+    /// For each method in the composition:
+    /// ```text
+    /// (
+    ///     maximum width of method count (in rows or leads),
+    ///     shorthand
+    /// )
+    /// ```
+    method_count_widths: Vec<(usize, String)>,
+    /// `true` if the user gave some weight to atw
+    print_atw: bool,
+    /// If false compositions are allowed, then the width of the column counting their repeated
+    /// rows
+    repeats_width: Option<usize>,
+    /// If a part head should be displayed, then what's its width
+    part_head_width: Option<usize>,
+    /// Styling applied to the `atw` marker of compositions which are all the work (e.g. to
+    /// colour it in a terminal)
+    atw_style: fn(&str) -> String,
+}
+
+impl CompositionPrinter {
+    pub fn new(search: &Search, print_atw: bool, print_comp_widths: bool) -> Self {
+        let params = search.parameters().clone();
+        Self {
+            comp_count_width: print_comp_widths.then_some(params.num_comps.to_string().len()),
+            length_width: params.max_length().to_string().len().max(3),
+            method_count_widths: params
+                .methods
+                .iter_enumerated()
+                .map(|(idx, method)| {
+                    let shorthand = method.shorthand();
+                    let max_count = *search.method_count_range(method.id).end();
+                    let max_count_width = params.method_count_string(idx, max_count).len();
+                    let max_width = max_count_width.max(shorthand.len());
+                    (max_width, shorthand)
+                })
+                .collect_vec(),
+            comps_printed: 0,
+
+            print_atw,
+            repeats_width: (!params.rows_are_unique())
+                .then(|| params.max_length().to_string().len().max(4)),
+            part_head_width: (params.num_parts() > 2)
+                .then(|| params.part_head_group.effective_stage().num_bells()),
+            atw_style: str::to_owned,
+
+            params,
+        }
+    }
+
+    /// Set the styling applied to the `atw` marker of compositions which are all the work
+    pub fn with_atw_style(mut self, atw_style: fn(&str) -> String) -> Self {
+        self.atw_style = atw_style;
+        self
+    }
+
+    /// Create some lines which summarise the given [`Composition`].  This may include additional
+    /// lines for headers or ruleoffs, depending on how many compositions have been printed so far.
+    pub fn comp_string_with_possible_headers(
+        &mut self,
+        comp: &Composition,
+        generation_index: usize,
+    ) -> String {
+        let mut update_string = String::new();
+
+        // Add a header every 50 lines
+        if self.comps_printed % 50 == 0 {
+            if self.comps_printed > 0 {
+                update_string.push_str(&self.ruleoff());
+                update_string.push('\n');
+            }
+            update_string.push_str(&self.header());
+            update_string.push('\n');
+            update_string.push_str(&self.ruleoff());
+            update_string.push('\n');
+        }
+        // Add the composition
+        update_string.push_str(&self.comp_string(comp, generation_index));
+        self.comps_printed += 1;
+
+        update_string
+    }
+
+    /// Return some lines which end a composition list.  This includes one line for a ruleoff and
+    /// one for the column headers, like:
+    /// ```text
+    /// --------------|---------|-----------|-----------
+    /// len    Y   C  |  music  | avg score | calling
+    /// ```
+    pub fn footer_lines(&self) -> String {
+        let mut s = String::new();
+        s.push_str(&self.ruleoff());
+        s.push('\n');
+        s.push_str(&self.header());
+        s
+    }
+
+    /// Return some lines which start a composition list: one line for the column headers and one
+    /// for a ruleoff (i.e. [`Self::footer_lines`] upside down).
+    pub fn header_lines(&self) -> String {
+        format!("{}\n{}", self.header(), self.ruleoff())
+    }
+
+    fn ruleoff(&self) -> String {
+        // Ruleoff is the same as header, but with every non-'|' char replaced with '-'
+        let mut ruleoff = self
+            .header()
+            .chars()
+            .map(|c| if c == '|' { '|' } else { '-' })
+            .collect::<String>();
+        ruleoff.push_str("---"); // Add a couple of extra `-`s to make the ruleoff a bit longer
+        ruleoff
+    }
+
+    fn header(&self) -> String {
+        let mut s = String::new();
+        // Comp index
+        if let Some(c) = self.comp_count_width {
+            write_centered_text(&mut s, "#", c);
+            s.push_str(" | ");
+        }
+        // Length
+        write_centered_text(&mut s, "len", self.length_width);
+        s.push(' ');
+        // Method shorthands (for counts)
+        if self.method_count_widths.len() > 1 {
+            s.push_str("  ");
+            for (width, shorthand) in &self.method_count_widths {
+                write_centered_text(&mut s, shorthand, *width);
+                s.push(' ');
+            }
+        }
+        s.push('|');
+        // Atw
+        if self.print_atw {
+            s.push_str(" atw |");
+        }
+        // Repeated rows
+        if let Some(w) = self.repeats_width {
+            s.push(' ');
+            write_centered_text(&mut s, "reps", w);
+            s.push_str(" |");
+        }
+        // Part head
+        if let Some(w) = self.part_head_width {
+            // Add 2 to the width to get one char of extra padding on either side
+            write_centered_text(&mut s, "PH", w + 2);
+            s.push('|');
+        }
+        // Music
+        let music_types_to_display = self.params.music_types_to_show();
+        s.push_str("  music  ");
+        if !music_types_to_display.is_empty() {
+            s.push(' ');
+        }
+        for (_idx, music_type) in music_types_to_display {
+            s.push_str("  ");
+            write_centered_text(
+                &mut s,
+                &music_type.name,
+                music_type.col_width(self.params.stage),
+            );
+            s.push(' ');
+        }
+        // Everything else
+        s.push_str("| avg score | calling");
+        // If calling positions aren't relative to the tenor (e.g. because the part head moves the
+        // tenor), then say which bell they're relative to
+        if self.params.call_display_style == CallDisplayStyle::CallingPositions
+            && self.params.calling_bell != self.params.stage.tenor()
+        {
+            write!(s, " (relative to {})", self.params.calling_bell).unwrap();
+        }
+        s
+    }
+
+    /// Create a single line summarising the given [`Composition`]
+    pub fn comp_string(&self, comp: &Composition, generation_index: usize) -> String {
+        let comp = comp.values(&ParamsData::new(&self.params)).unwrap();
+
+        let mut s = String::new();
+        // Comp index
+        if let Some(c) = self.comp_count_width {
+            write!(s, "{:>width$} | ", generation_index + 1, width = c).unwrap();
+        }
+        // Length
+        write!(s, "{:>width$} ", comp.length(), width = self.length_width).unwrap();
+        // Method counts (for spliced)
+        if self.method_count_widths.len() > 1 {
+            s.push_str(": ");
+            let counts = comp.method_counts.iter_enumerated();
+            for ((width, _), (idx, count)) in self.method_count_widths.iter().zip_eq(counts) {
+                let count = self.params.method_count_string(idx, count.as_usize());
+                write!(s, "{:>width$} ", count, width = *width).unwrap();
+            }
+        }
+        s.push('|');
+        // Atw
+        if self.print_atw {
+            if comp.atw_factor > 0.999999 {
+                write!(s, " {} |", (self.atw_style)("atw")).unwrap();
+            } else {
+                write!(s, " {:>2}% |", (comp.atw_factor * 100.0).floor() as usize).unwrap();
+            }
+        }
+        // Repeated rows (if false compositions are allowed)
+        if let Some(w) = self.repeats_width {
+            write!(s, " {:>w$} |", comp.num_repeated_rows()).unwrap();
+        }
+        // Part head (if >2 parts; up to 2-parts must always have the same part head)
+        if self.part_head_width.is_some() {
+            write!(s, " {} |", ShortRow(comp.part_head())).unwrap();
+        }
+        // Music
+        let music_types_to_show = self.params.music_types_to_show();
+        write!(s, " {:>7.2} ", comp.music_score).unwrap();
+        if !music_types_to_show.is_empty() {
+            s.push(':');
+        }
+        for (idx, music_type) in music_types_to_show {
+            s.push_str("  ");
+            write_left_centered_text(
+                &mut s,
+                &music_type.display_counts(comp.music_counts[idx], self.params.stage),
+                music_type.col_width(self.params.stage),
+            );
+            s.push(' ');
+        }
+        // avg score, call string
+        write!(s, "| {:>9.6} | {}", comp.score_per_row(), comp.call_string).unwrap();
+
+        s
+    }
+}
+
+/// Render the music of each course in a composition as a table, with one line per course.
+pub fn course_music_string(courses: &[CourseMusic], params: &Parameters) -> String {
+    let music_types_to_show = params.music_types_to_show();
+    let ch_width = (courses.iter().map(|c| c.course_head.to_string().len()))
+        .chain([2])
+        .max()
+        .unwrap();
+    let length_width = (courses.iter().map(|c| c.length.to_string().len()))
+        .chain([3])
+        .max()
+        .unwrap();
+
+    let mut s = String::new();
+    // Header
+    write_centered_text(&mut s, "CH", ch_width);
+    s.push_str(" | ");
+    write_centered_text(&mut s, "len", length_width);
+    s.push_str(" |  music  ");
+    if !music_types_to_show.is_empty() {
+        s.push(' ');
+    }
+    for (_idx, music_type) in &music_types_to_show {
+        s.push_str("  ");
+        write_centered_text(&mut s, &music_type.name, music_type.col_width(params.stage));
+        s.push(' ');
+    }
+    s.push_str("| calling\n");
+    // Courses
+    for course in courses {
+        write!(
+            s,
+            "{:ch_width$} | {:>length_width$} | {:>7.2} ",
+            course.course_head, course.length, course.music_score
+        )
+        .unwrap();
+        if !music_types_to_show.is_empty() {
+            s.push(':');
+        }
+        for (idx, music_type) in &music_types_to_show {
+            s.push_str("  ");
+            write_left_centered_text(
+                &mut s,
+                &music_type.display_counts(course.music_counts[*idx], params.stage),
+                music_type.col_width(params.stage),
+            );
+            s.push(' ');
+        }
+        writeln!(s, "| {}", course.calling).unwrap();
+    }
+    s.pop(); // Remove the trailing newline
+    s
+}
+
+/// Render a [`CourseTable`] as the traditional table of course ends against calling positions:
+///
+/// ```text
+///  23456 | M | W | H
+/// -------|---|---|---
+///  42356 | - |   | -
+///  23456 |   | s | 2
+/// ```
+pub fn course_table_string(table: &CourseTable, params: &Parameters) -> String {
+    // Only show the bells which move in some course end (but always at least 5 of them, so that
+    // course ends look like `23456`)
+    let is_home = |row: &bellframe::Row, place: usize| {
+        row.bell_iter().nth(place) == params.start_row.bell_iter().nth(place)
+    };
+    let last_moving_place = (1..params.stage.num_bells())
+        .rev()
+        .find(|&place| table.courses.iter().any(|c| !is_home(&c.course_end, place)))
+        .unwrap_or(0);
+    let last_shown_place = last_moving_place.max(5.min(params.stage.num_bells() - 1));
+    let course_end_string = |row: &bellframe::Row| {
+        row.bell_iter()
+            .skip(1)
+            .take(last_shown_place)
+            .map(|b| b.to_string())
+            .collect::<String>()
+    };
+    let ce_width = last_shown_place;
+    let column_widths = table
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(idx, _)| {
+            (table.courses.iter().map(|c| c.calls[idx].len()))
+                .chain([1])
+                .max()
+                .unwrap()
+        })
+        .collect_vec();
+
+    let mut s = String::new();
+    // Header
+    write!(s, " {} ", course_end_string(&params.start_row)).unwrap();
+    for (&column, &width) in table.columns.iter().zip_eq(&column_widths) {
+        s.push_str("| ");
+        write_centered_text(&mut s, &column.to_string(), width);
+        s.push(' ');
+    }
+    s.push('\n');
+    push_multiple('-', ce_width + 2, &mut s);
+    for &width in &column_widths {
+        s.push('|');
+        push_multiple('-', width + 2, &mut s);
+    }
+    // Courses
+    for course in &table.courses {
+        write!(s, "\n {} ", course_end_string(&course.course_end)).unwrap();
+        for (calls, &width) in course.calls.iter().zip_eq(&column_widths) {
+            s.push_str("| ");
+            write_centered_text(&mut s, calls, width);
+            s.push(' ');
+        }
+    }
+    if params.is_multipart() {
+        write!(s, "\n({} part)", params.num_parts()).unwrap();
+    }
+    s
+}
+
+/// Render every row of a composition (as returned by [`Composition::rows`]), with a line between
+/// each lead and the method's shorthand next to the first row of each lead:
+///
+/// ```text
+/// 12345678  Y
+/// 21436587
+///   ...
+/// 18264735
+/// --------
+/// 12647583  Y
+///   ...
+/// ```
+pub fn rows_string(rows: &Block<(MethodId, usize)>, params: &ParamsData) -> String {
+    let lead_starts = lead_start_indices(rows);
+    let mut s = String::new();
+    for (idx, (&(method_id, _), row)) in rows.annot_rows().enumerate() {
+        let is_new_lead = lead_starts.contains(&idx);
+        if is_new_lead && idx > 0 {
+            push_multiple('-', params.stage.num_bells(), &mut s);
+            s.push('\n');
+        }
+        write!(s, "{row}").unwrap();
+        if is_new_lead {
+            write!(s, "  {}", params.get_method(method_id).shorthand()).unwrap();
+        }
+        s.push('\n');
+    }
+    // The `leftover_row` starts a new lead, but not of any method
+    push_multiple('-', params.stage.num_bells(), &mut s);
+    write!(s, "\n{}", rows.leftover_row()).unwrap();
+    s
+}
+
+/// The indices of the rows which start a new lead in some rows returned by
+/// [`Composition::rows`].  These are the first row, every lead head, and any row where the method
+/// changes mid-lead.
+fn lead_start_indices(rows: &Block<(MethodId, usize)>) -> Vec<usize> {
+    let mut lead_starts = vec![0];
+    for (idx, ((method1, sub_lead1), (method2, sub_lead2))) in
+        rows.annots().tuple_windows().enumerate()
+    {
+        if *sub_lead2 == 0 || method1 != method2 || *sub_lead2 != sub_lead1 + 1 {
+            lead_starts.push(idx + 1);
+        }
+    }
+    lead_starts
+}
+
+/// Render the first part of a [`Composition`] in the leadwise text format accepted by
+/// [CompLib](https://complib.org), so that it can be submitted without reformatting:
+///
+/// ```text
+/// 5056 Spliced Major
+/// Part head: 15234678
+/// B = Bristol Surprise Major
+/// Y = Yorkshire Surprise Major
+///
+/// YBB-YsBY
+/// ```
+///
+/// Each lead is written as its method's shorthand (only in spliced) followed by the symbol of the
+/// call at its end.  Plain leads of single-method compositions are written as `p`.  Returns `None`
+/// if the `Composition` doesn't match the [`Parameters`], or if it has any calls, splices, starts
+/// or finishes which aren't at lead heads (e.g. snap starts or half-lead spliced).
+pub fn complib_string(comp: &Composition, params: &ParamsData) -> Option<String> {
+    if !comp.do_cheap_checks(params) {
+        return None;
+    }
+    if comp.path[0].start_sub_lead_idx != 0
+        || comp
+            .path
+            .iter()
+            .any(|elem| elem.end_sub_lead_idx(params) != 0)
+    {
+        return None; // Something happens away from a lead head
+    }
+
+    let mut calling = String::new();
+    let mut method_ids_used = Vec::new();
+    for elem in &comp.path {
+        let method = params.get_method(elem.method_id);
+        if !method_ids_used.contains(&elem.method_id) {
+            method_ids_used.push(elem.method_id);
+        }
+        let num_leads = elem.length.as_usize() / method.lead_len();
+        for lead_idx in 0..num_leads {
+            if params.is_spliced() {
+                calling.push_str(&method.shorthand());
+            }
+            match elem.call_to_end.filter(|_| lead_idx == num_leads - 1) {
+                Some(call_id) => calling.push(params.get_call(call_id).symbol),
+                None if !params.is_spliced() => calling.push('p'),
+                None => {}
+            }
+        }
+    }
+
+    let mut s = String::new();
+    write!(s, "{} ", comp.length.as_usize()).unwrap();
+    match method_ids_used.as_slice() {
+        [method_id] if !params.is_spliced() => {
+            s.push_str(&params.get_method(*method_id).title());
+        }
+        _ => write!(s, "Spliced {}", params.stage).unwrap(),
+    }
+    if params.is_multipart() {
+        write!(s, "\nPart head: {}", comp.part_head).unwrap();
+    }
+    if params.is_spliced() {
+        for method_id in method_ids_used {
+            let method = params.get_method(method_id);
+            write!(s, "\n{} = {}", method.shorthand(), method.title()).unwrap();
+        }
+    }
+    write!(s, "\n\n{calling}").unwrap();
+    if params.is_multipart() {
+        write!(s, "\n({} part)", params.num_parts()).unwrap();
+    }
+    Some(s)
+}
+
+/// Render a [`Composition`] as a [MicroSIRIL](http://www.ringing.org/main/pages/microsiril)
+/// script (which is also understood by BYROC and other SIRIL-based provers):
+///
+/// ```text
+/// // 72 Plain Bob Minor
+/// 6 bells;
+///
+/// P = +-.16.-.16.-.16.-.16.-.16.-;
+/// plain = +12;
+/// bob = +14;
+///
+/// part = P, plain, P, bob, P, plain;
+/// prove = 2(part);
+/// ```
+///
+/// Each lead is written as its method followed by its lead end (`plain` or a call).  Returns
+/// `None` if the `Composition` doesn't match the [`Parameters`], doesn't start from rounds, uses
+/// calls which aren't given by place notation, or has any calls, splices, starts or finishes away
+/// from the lead heads.
+pub fn microsiril_string(comp: &Composition, params: &ParamsData) -> Option<String> {
+    if !comp.do_cheap_checks(params) || !params.start_row.is_rounds() {
+        return None;
+    }
+    if comp.path[0].start_sub_lead_idx != 0
+        || comp
+            .path
+            .iter()
+            .any(|elem| elem.end_sub_lead_idx(params) != 0)
+    {
+        return None; // Something happens away from a lead head
+    }
+
+    // Give every method and call used by the composition a name
+    let mut method_ids_used = Vec::new();
+    let mut call_ids_used = Vec::new();
+    for elem in &comp.path {
+        if !method_ids_used.contains(&elem.method_id) {
+            method_ids_used.push(elem.method_id);
+        }
+        if let Some(call_id) = elem.call_to_end {
+            if !call_ids_used.contains(&call_id) {
+                call_ids_used.push(call_id);
+            }
+        }
+    }
+    let method_name = |method_id| -> String {
+        let method = params.get_method(method_id);
+        let shorthand = method.shorthand();
+        match shorthand.chars().all(|c| c.is_ascii_alphanumeric()) {
+            true => shorthand,
+            false => format!(
+                "m{}",
+                method_ids_used
+                    .iter()
+                    .position(|&id| id == method_id)
+                    .unwrap()
+            ),
+        }
+    };
+    let call_name = |call_id| -> String {
+        let call = params.get_call(call_id);
+        match call.symbol {
+            '-' => "bob".to_owned(),
+            's' => "single".to_owned(),
+            _ => format!(
+                "call{}",
+                call_ids_used.iter().position(|&id| id == call_id).unwrap()
+            ),
+        }
+    };
+    // Split each method into the place notation of its lead, and of its plain lead end.  Plain
+    // lead ends are only named after their methods if they differ between methods.
+    let mut method_pns = Vec::new();
+    for &method_id in &method_ids_used {
+        let first_lead = params.get_method(method_id).first_lead();
+        let mut pns = (first_lead.rows().chain([first_lead.leftover_row()]))
+            .tuple_windows()
+            .map(|(r1, r2)| PlaceNot::pn_between(r1, r2))
+            .collect::<Option<Vec<_>>>()?;
+        let lead_end_pn = pns.pop()?;
+        method_pns.push((method_id, pns, lead_end_pn));
+    }
+    let has_single_plain = method_pns.iter().map(|(_, _, le)| le).all_equal();
+    let plain_name = |method_id| match has_single_plain {
+        true => "plain".to_owned(),
+        false => format!("{}plain", method_name(method_id)),
+    };
+
+    let mut s = String::new();
+    // Header
+    write!(s, "// {} ", comp.length.as_usize()).unwrap();
+    match method_ids_used.as_slice() {
+        [method_id] if !params.is_spliced() => {
+            s.push_str(&params.get_method(*method_id).title());
+        }
+        _ => write!(s, "Spliced {}", params.stage).unwrap(),
+    }
+    writeln!(s, "\n{} bells;\n", params.stage.num_bells()).unwrap();
+    // Definitions of methods and calls
+    for (method_id, pns, _) in &method_pns {
+        let pn_string = pns.iter().map(ToString::to_string).join(".");
+        writeln!(s, "{} = +{};", method_name(*method_id), pn_string).unwrap();
+    }
+    for (idx, (method_id, _, lead_end_pn)) in method_pns.iter().enumerate() {
+        if has_single_plain && idx > 0 {
+            break; // All methods share the same plain lead end
+        }
+        writeln!(s, "{} = +{};", plain_name(*method_id), lead_end_pn).unwrap();
+    }
+    for &call_id in &call_ids_used {
+        let call = params.get_call(call_id);
+        let CallTransposition::PlaceNot(pn) = &call.transposition else {
+            return None; // SIRIL can only express calls as place notation
+        };
+        writeln!(s, "{} = +{};", call_name(call_id), pn).unwrap();
+    }
+    // Calling
+    let mut leads = Vec::new();
+    for elem in &comp.path {
+        let num_leads = elem.length.as_usize() / params.get_method(elem.method_id).lead_len();
+        for lead_idx in 0..num_leads {
+            let lead_end = match elem.call_to_end.filter(|_| lead_idx == num_leads - 1) {
+                Some(call_id) => call_name(call_id),
+                None => plain_name(elem.method_id),
+            };
+            leads.push(format!("{}, {}", method_name(elem.method_id), lead_end));
+        }
+    }
+    match params.is_multipart() {
+        true => write!(
+            s,
+            "\npart = {};\nprove = {}(part);",
+            leads.join(", "),
+            params.num_parts()
+        ),
+        false => write!(s, "\nprove = {};", leads.join(", ")),
+    }
+    .unwrap();
+    Some(s)
+}
+
+/// Write some `string` to `out`, centering it among `width` spaces (rounding to the right).
+fn write_centered_text(out: &mut String, text: &str, width: usize) {
+    let w = width.saturating_sub(text.len());
+    push_multiple(' ', w - (w / 2), out);
+    out.push_str(text);
+    push_multiple(' ', w / 2, out);
+}
+
+/// Write some `string` to `out`, centering it among `width` spaces (rounding to the left).
+fn write_left_centered_text(out: &mut String, text: &str, width: usize) {
+    let w = width.saturating_sub(text.len());
+    push_multiple(' ', w / 2, out);
+    out.push_str(text);
+    push_multiple(' ', w - (w / 2), out);
+}
+
+/// Push `n` copies of `c` to the end of `out`
+fn push_multiple(c: char, n: usize, out: &mut String) {
+    out.extend(std::iter::repeat(c).take(n));
+}
@@ -0,0 +1,117 @@
+//! Rendering of [`Composition`]s as SVG images
+
+use std::fmt::Write;
+
+use bellframe::{Bell, Block};
+use itertools::Itertools;
+
+use crate::{composition::ParamsData, parameters::MethodId};
+
+#[allow(unused_imports)] // Used by doc comments
+use crate::Composition;
+
+/// Horizontal distance between the centres of adjacent places
+const PLACE_WIDTH: f32 = 10.0;
+/// Vertical distance between adjacent rows
+const ROW_HEIGHT: f32 = 12.0;
+/// Horizontal gap between the columns of leads
+const COLUMN_GAP: f32 = 20.0;
+/// Vertical space above each column, which holds the method's shorthand
+const COLUMN_HEADER_HEIGHT: f32 = 20.0;
+/// The maximum number of leads drawn side by side before wrapping onto another line of columns
+const LEADS_PER_LINE: usize = 12;
+
+/// Draw the paths of some bells through every row of a composition (as returned by
+/// [`Composition::rows`]) as an SVG image.  Each lead is drawn as a separate column, labelled with
+/// its method's shorthand.  `lines` gives each bell to draw a line for, along with the (CSS)
+/// colour of that line.  Every other bell is written as a faint number, so passing only the
+/// treble and a working bell gives a blue line, whereas passing every bell gives a grid.
+pub fn blue_line_svg(
+    rows: &Block<(MethodId, usize)>,
+    lines: &[(Bell, &str)],
+    params: &ParamsData,
+) -> String {
+    // Split the rows into leads.  Each column also repeats the first row of the next lead so that
+    // the lines join up
+    let lead_starts = super::lead_start_indices(rows);
+    let leads = lead_starts
+        .iter()
+        .copied()
+        .chain([rows.len()])
+        .tuple_windows()
+        .collect_vec();
+    let column_width = params.stage.num_bells() as f32 * PLACE_WIDTH + COLUMN_GAP;
+    let max_lead_len = leads
+        .iter()
+        .map(|(start, end)| end - start)
+        .max()
+        .unwrap_or(0);
+    let line_height = COLUMN_HEADER_HEIGHT + (max_lead_len + 1) as f32 * ROW_HEIGHT;
+    let num_lines = crate::utils::div_rounding_up(leads.len(), LEADS_PER_LINE);
+    let width = leads.len().min(LEADS_PER_LINE) as f32 * column_width + COLUMN_GAP;
+    let height = num_lines as f32 * line_height + COLUMN_GAP;
+
+    let mut s = String::new();
+    writeln!(
+        s,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" font-family="monospace" font-size="10" text-anchor="middle">"#
+    )
+    .unwrap();
+    writeln!(s, r#"<rect width="100%" height="100%" fill="white"/>"#).unwrap();
+    for (lead_idx, &(start, end)) in leads.iter().enumerate() {
+        let x = COLUMN_GAP + (lead_idx % LEADS_PER_LINE) as f32 * column_width;
+        let y = COLUMN_GAP + (lead_idx / LEADS_PER_LINE) as f32 * line_height;
+        writeln!(s, r#"<g transform="translate({x},{y})">"#).unwrap();
+        // Method label
+        let (method_id, _) = rows.get_annot(start).unwrap();
+        let label_x = params.stage.num_bells() as f32 * PLACE_WIDTH / 2.0;
+        writeln!(
+            s,
+            r#"<text x="{label_x}" y="{}" font-weight="bold">{}</text>"#,
+            COLUMN_HEADER_HEIGHT / 2.0,
+            params.get_method(*method_id).shorthand()
+        )
+        .unwrap();
+        // Rows, writing only the bells which don't have lines
+        let row_at = |idx: usize| rows.get_row(idx).unwrap_or(rows.leftover_row());
+        let column_rows = (start..=end).map(row_at);
+        for (row_idx, row) in column_rows.clone().enumerate() {
+            let row_y = COLUMN_HEADER_HEIGHT + (row_idx as f32 + 0.5) * ROW_HEIGHT;
+            for (place, bell) in row.bell_iter().enumerate() {
+                if lines.iter().any(|(b, _)| *b == bell) {
+                    continue;
+                }
+                writeln!(
+                    s,
+                    r##"<text x="{}" y="{row_y}" dy="0.35em" fill="#999">{bell}</text>"##,
+                    place_x(place)
+                )
+                .unwrap();
+            }
+        }
+        // Lines
+        for (bell, colour) in lines {
+            let points = column_rows
+                .clone()
+                .enumerate()
+                .map(|(row_idx, row)| {
+                    let row_y = COLUMN_HEADER_HEIGHT + (row_idx as f32 + 0.5) * ROW_HEIGHT;
+                    format!("{},{row_y}", place_x(row.place_of(*bell) as usize))
+                })
+                .join(" ");
+            writeln!(
+                s,
+                r#"<polyline points="{points}" fill="none" stroke="{colour}" stroke-width="1.5"/>"#
+            )
+            .unwrap();
+        }
+        writeln!(s, "</g>").unwrap();
+    }
+    s.push_str("</svg>");
+    s
+}
+
+/// The x-coordinate of the centre of a given place, relative to the left of its column
+fn place_x(place: usize) -> f32 {
+    (place as f32 + 0.5) * PLACE_WIDTH
+}
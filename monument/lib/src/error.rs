@@ -3,13 +3,17 @@
 use std::{
     fmt::{Display, Formatter},
     ops::RangeInclusive,
+    path::PathBuf,
 };
 
-use bellframe::{PlaceNot, Stage};
+use bellframe::{Bell, RowBuf, Stage};
 
 #[allow(unused_imports)] // Only used for doc comments
 use crate::parameters::{Call, Method, MusicType};
-use crate::{parameters::OptionalRangeInclusive, utils::TotalLength};
+use crate::{
+    parameters::{CallTransposition, MethodIdx, MusicTypeIdx, OptionalRangeInclusive},
+    utils::TotalLength,
+};
 
 /// Alias for `Result<T, monument::Error>`.
 pub type Result<T> = std::result::Result<T, Error>;
@@ -41,16 +45,32 @@ pub enum Error {
     /* QUERY VERIFICATION ERRORS */
     /// Different start/end rows were specified in a multi-part
     DifferentStartEndRowInMultipart,
-    /// Some [`Call`] refers to a label that doesn't exist
-    UndefinedLabel { call_symbol: char, label: String },
+    /// Some [`Call`] refers to a label that doesn't exist.  `suggestion` is the defined label
+    /// which is most similar to `label` (if any are similar enough to be a likely typo).
+    UndefinedLabel {
+        call_symbol: char,
+        label: String,
+        suggestion: Option<String>,
+    },
     /// No methods were defined
     NoMethods,
-    /// Two [`Method`]s use the same shorthand
+    /// Two [`Method`]s use the same shorthand.  `method_idx` is the later of the two methods.
     DuplicateShorthand {
+        method_idx: MethodIdx,
         shorthand: String,
         title1: String,
         title2: String,
     },
+    /// Two spliced [`Method`]s have different hunt bells, so can never be spliced together.  The
+    /// methods' lead head codes (if they have them) are included to help explain why.
+    /// `method_idx` is the later of the two methods.
+    IncompatibleSplice {
+        method_idx: MethodIdx,
+        title1: String,
+        lead_head_code1: Option<String>,
+        title2: String,
+        lead_head_code2: Option<String>,
+    },
     /// Some [`Call`] doesn't have enough calling positions to cover the [`Stage`]
     WrongCallingPositionsLength {
         call_symbol: char,
@@ -60,8 +80,27 @@ pub enum Error {
     /// Two [`Call`]s have the same lead location and name
     DuplicateCall {
         symbol: char,
-        pn1: PlaceNot,
-        pn2: PlaceNot,
+        transposition1: CallTransposition,
+        transposition2: CallTransposition,
+    },
+    /// [`Parameters::strict_labels`](crate::Parameters::strict_labels) is set, and some [`Call`]
+    /// produces the same row as a plain lead at one of its labels
+    CallSameAsPlain {
+        method_idx: MethodIdx,
+        call_symbol: char,
+        label: String,
+        method_title: String,
+        sub_lead_idx: usize,
+    },
+    /// [`Parameters::strict_labels`](crate::Parameters::strict_labels) is set, and some [`Call`]
+    /// moves a hunt bell away from where it would be at the call's `label_to`
+    CallMovesHuntBell {
+        method_idx: MethodIdx,
+        call_symbol: char,
+        label: String,
+        method_title: String,
+        sub_lead_idx: usize,
+        bell: Bell,
     },
     /// An error was found when parsing the custom calling
     CustomCallingParse {
@@ -70,13 +109,27 @@ pub enum Error {
         /// What went wrong
         reason: String,
     },
+    /// [`Parameters::no_duffers`](crate::Parameters::no_duffers) or
+    /// [`Parameters::max_duffer_rows`](crate::Parameters::max_duffer_rows) is set, but no
+    /// [`MusicType`]s can make a chunk a non-duffer (e.g. none are marked as
+    /// [`non_duffer`](MusicType::non_duffer))
+    NoNonDufferMusic,
+    /// [`Parameters::extent`](crate::Parameters::extent) is set, but rows can be repeated (either
+    /// because [`Parameters::require_truth`](crate::Parameters::require_truth) isn't set or
+    /// because [`Parameters::max_row_repeats`](crate::Parameters::max_row_repeats) is more than 1)
+    ExtentWithoutTruth,
+    /// [`Config::temperature`](crate::Config::temperature) is set, but isn't positive
+    NonPositiveTemperature(f32),
 
     /* GRAPH BUILD ERRORS */
     /// The given maximum graph size limit was reached
     SizeLimit(usize),
-    /// The same chunk of ringing could start at two different strokes, and some
-    /// [`MusicType`] relies on the strokes always being the same
-    InconsistentStroke,
+    /// Some [`Parameters::required_rows`](crate::Parameters::required_rows) isn't contained in
+    /// any chunk, so can never be rung
+    RequiredRowNotRung(RowBuf),
+    /// [`Parameters::extent`](crate::Parameters::extent) is set, but some row isn't contained in
+    /// any chunk, so no extents are possible
+    ExtentRowNotRung(RowBuf),
 
     /* LENGTH PROVING ERRORS */
     /// The requested length range isn't achievable
@@ -87,10 +140,13 @@ pub enum Error {
     },
     /// Some method range isn't achievable
     UnachievableMethodCount {
+        method_idx: MethodIdx,
         method_name: String,
         requested_range: OptionalRangeInclusive,
         next_shorter_len: Option<usize>,
         next_longer_len: Option<usize>,
+        /// If method counts are being given in leads, then the lead length of this method
+        lead_len: Option<usize>,
     },
     /// The total of the minimum method counts is longer than the composition
     TooMuchMethodCount {
@@ -101,6 +157,109 @@ pub enum Error {
         max_total_method_count: usize,
         min_length: usize,
     },
+    /// Some [`MusicType`] requires a minimum count which no composition can reach
+    UnachievableMusicCount {
+        music_type_idx: MusicTypeIdx,
+        name: String,
+        min_count: usize,
+        /// An upper bound on the count of this music in any composition
+        max_count: usize,
+    },
+
+    /* GRAPH FILE ERRORS */
+    /// A graph file (see [`BuiltGraph::load`](crate::BuiltGraph::load)) couldn't be read
+    GraphFileRead {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    /// A graph file wasn't in the format written by [`BuiltGraph::save`](crate::BuiltGraph::save)
+    /// (e.g. because it was written by a different version of Monument)
+    InvalidGraphFile(PathBuf),
+    /// A [`BuiltGraph`](crate::BuiltGraph) was searched with [`Parameters`](crate::Parameters)
+    /// which would build a different graph
+    GraphParamsMismatch,
+}
+
+/// A stable code which identifies each kind of [`Error`], so that tools can recognise errors
+/// without parsing their messages.  Codes are displayed like `E0204`, where the first two digits
+/// give the stage at which the error happened (e.g. `02` for query verification).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ErrorCode(pub u16);
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "E{:04}", self.0)
+    }
+}
+
+/// The part of the [`Parameters`](crate::Parameters) which caused an [`Error`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorLocation {
+    /// An index into [`Parameters::methods`](crate::Parameters::methods)
+    Method(MethodIdx),
+    /// An index into [`Parameters::music_types`](crate::Parameters::music_types)
+    MusicType(MusicTypeIdx),
+}
+
+impl Error {
+    /// The [`ErrorCode`] identifying this kind of error
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode(match self {
+            /* QUERY BUILD ERRORS */
+            Error::MethodNotFound { .. } => 101,
+            Error::MethodPnParse { .. } => 102,
+            Error::CustomCourseMaskParse { .. } => 103,
+
+            /* QUERY VERIFICATION ERRORS */
+            Error::DifferentStartEndRowInMultipart => 201,
+            Error::UndefinedLabel { .. } => 202,
+            Error::NoMethods => 203,
+            Error::DuplicateShorthand { .. } => 204,
+            Error::IncompatibleSplice { .. } => 205,
+            Error::WrongCallingPositionsLength { .. } => 206,
+            Error::DuplicateCall { .. } => 207,
+            Error::CallSameAsPlain { .. } => 208,
+            Error::CallMovesHuntBell { .. } => 209,
+            Error::CustomCallingParse { .. } => 210,
+            Error::NoNonDufferMusic => 211,
+            Error::ExtentWithoutTruth => 212,
+            Error::NonPositiveTemperature(_) => 213,
+
+            /* GRAPH BUILD ERRORS */
+            Error::SizeLimit(_) => 301,
+            Error::RequiredRowNotRung(_) => 302,
+            Error::ExtentRowNotRung(_) => 303,
+
+            /* LENGTH PROVING ERRORS */
+            Error::UnachievableLength { .. } => 401,
+            Error::UnachievableMethodCount { .. } => 402,
+            Error::TooMuchMethodCount { .. } => 403,
+            Error::TooLittleMethodCount { .. } => 404,
+            Error::UnachievableMusicCount { .. } => 405,
+
+            /* GRAPH FILE ERRORS */
+            Error::GraphFileRead { .. } => 501,
+            Error::InvalidGraphFile(_) => 502,
+            Error::GraphParamsMismatch => 503,
+        })
+    }
+
+    /// The method or music type which caused this error, if it was caused by just one
+    pub fn location(&self) -> Option<ErrorLocation> {
+        match self {
+            Error::DuplicateShorthand { method_idx, .. }
+            | Error::IncompatibleSplice { method_idx, .. }
+            | Error::CallSameAsPlain { method_idx, .. }
+            | Error::CallMovesHuntBell { method_idx, .. }
+            | Error::UnachievableMethodCount { method_idx, .. } => {
+                Some(ErrorLocation::Method(*method_idx))
+            }
+            Error::UnachievableMusicCount { music_type_idx, .. } => {
+                Some(ErrorLocation::MusicType(*music_type_idx))
+            }
+            _ => None,
+        }
+    }
 }
 
 impl Display for Error {
@@ -147,27 +306,95 @@ impl Display for Error {
                 shorthand,
                 title1,
                 title2,
+                ..
             } => write!(
                 f,
                 "Methods {:?} and {:?} share a shorthand ({})",
                 title1, title2, shorthand
             ),
+            Error::IncompatibleSplice {
+                title1,
+                lead_head_code1,
+                title2,
+                lead_head_code2,
+                ..
+            } => {
+                let code_string = |code: &Option<String>| match code {
+                    Some(code) => format!("lead head code {code}"),
+                    None => "no standard lead head code".to_owned(),
+                };
+                write!(
+                    f,
+                    "Methods {:?} ({}) and {:?} ({}) have different hunt bells, so can't be spliced",
+                    title1,
+                    code_string(lead_head_code1),
+                    title2,
+                    code_string(lead_head_code2)
+                )
+            }
             Error::UndefinedLabel {
                 call_symbol: call_name,
                 label,
+                suggestion,
+            } => {
+                write!(
+                    f,
+                    "Call {:?} refers to a label {:?}, which doesn't exist",
+                    call_name, label
+                )?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, ".  Did you mean {:?}?", suggestion)?;
+                }
+                Ok(())
+            }
+            Error::DuplicateCall {
+                symbol,
+                transposition1,
+                transposition2,
             } => write!(
-                f,
-                "Call {:?} refers to a label {:?}, which doesn't exist",
-                call_name, label
-            ), // TODO: Suggest one that does exist
-            Error::DuplicateCall { symbol, pn1, pn2 } => write!(
                 f,
                 "Call symbol {:?} is used for both {} and {}",
-                symbol, pn1, pn2
+                symbol, transposition1, transposition2
+            ),
+            Error::CallSameAsPlain {
+                call_symbol,
+                label,
+                method_title,
+                sub_lead_idx,
+                ..
+            } => write!(
+                f,
+                "Call {call_symbol:?} has no effect at label {label:?} (row {sub_lead_idx} of {method_title:?}), \
+because it's the same as the plain lead.  Check the method's `labels` or the call's place notation."
+            ),
+            Error::CallMovesHuntBell {
+                call_symbol,
+                label,
+                method_title,
+                sub_lead_idx,
+                bell,
+                ..
+            } => write!(
+                f,
+                "Call {call_symbol:?} moves hunt bell {bell} at label {label:?} (row {sub_lead_idx} of {method_title:?}).  \
+Check the method's `labels` or the call's place notation."
             ),
             Error::CustomCallingParse { reason, .. } => {
                 write!(f, "Error parsing calling: {reason}")
             }
+            Error::NoNonDufferMusic => write!(
+                f,
+                "Duffers are restricted, but no music types are marked as `non_duffer`"
+            ),
+            Error::ExtentWithoutTruth => {
+                write!(
+                f,
+                "Extents must be true, so `require_truth` can't be `false` and `max_row_repeats` must be 1"
+            )
+            }
+            Error::NonPositiveTemperature(temperature) => {
+                write!(f, "`temperature` must be positive, not {temperature}")
+            }
 
             /* GRAPH BUILD ERRORS */
             Error::SizeLimit(limit) => write!(
@@ -176,11 +403,12 @@ impl Display for Error {
 higher with `--graph-size-limit <n>`.",
                 limit
             ),
-            Error::InconsistentStroke => write!(
-                f,
-                "The same chunk of ringing can be at multiple strokes, probably \
-because you're using a method with odd-length leads"
-            ),
+            Error::RequiredRowNotRung(row) => {
+                write!(f, "Required row {} can't be rung by any composition", row)
+            }
+            Error::ExtentRowNotRung(row) => {
+                write!(f, "Row {} can't be rung by any composition, so no extents are possible", row)
+            }
 
             /* LENGTH PROVING ERRORS */
             Error::UnachievableLength {
@@ -209,6 +437,8 @@ because you're using a method with odd-length leads"
                 requested_range,
                 next_shorter_len,
                 next_longer_len,
+                lead_len,
+                ..
             } => {
                 assert_ne!((requested_range.min, requested_range.max), (None, None));
                 write!(
@@ -216,9 +446,22 @@ because you're using a method with odd-length leads"
                     "No method counts for {:?} satisfy the requested range (",
                     method_name,
                 )?;
-                write_range(f, "count", requested_range.min, requested_range.max)?;
+                // Convert row counts into the units requested by the user
+                let count_string = |rows: usize| match lead_len {
+                    Some(lead_len) => crate::utils::leads_string(rows, *lead_len),
+                    None => rows.to_string(),
+                };
+                let name = if lead_len.is_some() { "leads" } else { "count" };
+                write_range(
+                    f,
+                    name,
+                    requested_range.min.map(count_string),
+                    requested_range.max.map(count_string),
+                )?;
                 write!(f, ").  ")?;
                 // Describe the nearest method counts
+                let next_shorter_len = next_shorter_len.map(count_string);
+                let next_longer_len = next_longer_len.map(count_string);
                 match (next_shorter_len, next_longer_len) {
                     (Some(l1), Some(l2)) => write!(f, "The nearest counts are {l1} and {l2}."),
                     (Some(l), None) | (None, Some(l)) => write!(f, "The nearest count is {l}."),
@@ -248,6 +491,28 @@ because you're using a method with odd-length leads"
                     " but the methods can make at most {max_total_method_count}."
                 )
             }
+            Error::UnachievableMusicCount {
+                name,
+                min_count,
+                max_count,
+                ..
+            } => write!(
+                f,
+                "Music type {name:?} requires a count of at least {min_count}, but no composition can have more than {max_count}."
+            ),
+
+            /* GRAPH FILE ERRORS */
+            Error::GraphFileRead { path, error } => {
+                write!(f, "Can't read graph file {path:?}: {error}")
+            }
+            Error::InvalidGraphFile(path) => write!(
+                f,
+                "{path:?} isn't a valid graph file.  It may have been built by a different version of Monument."
+            ),
+            Error::GraphParamsMismatch => write!(
+                f,
+                "The graph was built for a different search.  Graphs can only be re-used if the methods, calls, music, lengths and other constraints are the same."
+            ),
         }
     }
 }
@@ -0,0 +1,881 @@
+//! Code for saving and loading optimised [`Graph`]s, so that a graph can be built once and then
+//! searched many times (see [`BuiltGraph`]).
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    ops::RangeInclusive,
+    path::Path,
+    str::FromStr,
+    sync::Arc,
+};
+
+use bellframe::{music::AtRowPositions, Row, RowBuf, Stroke};
+use itertools::Itertools;
+
+use crate::{
+    group::PhRotation,
+    parameters::{CallIdx, MethodIdx, MethodVec, MusicTypeVec, OptionalRangeInclusive, Parameters},
+    prove_length::{prove_lengths, RefinedRanges},
+    search::{BuildProgress, Config},
+    utils::{
+        counts::Counts,
+        fnv1a_hash,
+        lengths::{PerPartLength, TotalLength},
+    },
+};
+
+use super::{
+    CallSeqIdx, Chunk, ChunkId, FalsenessCache, Graph, GraphStats, Link, LinkId, LinkSet, LinkSide,
+    RowIdx,
+};
+
+/// A composition graph which has been built and optimised for some [`Parameters`], ready to be
+/// searched by [`Search::from_graph`](crate::Search::from_graph).  Building the graph is often
+/// slower than searching it, so a `BuiltGraph` can be saved to a file and then searched many
+/// times.
+///
+/// The graph only depends on the [`Parameters`] which decide which rows can be rung and how
+/// they're scored.  So the same graph can be searched with a different `num_comps`, score
+/// thresholds, `max_calls`, `ranking`, `shortlist`, `target_length` or calling filters, but
+/// changing anything else (e.g. the methods, calls, music or lengths) requires a new graph.
+#[derive(Debug, Clone)]
+pub struct BuiltGraph {
+    pub(crate) graph: Graph,
+    pub(crate) refined_ranges: RefinedRanges,
+    stats: GraphStats,
+    /// The [`params_hash`] of the [`Parameters`] used to build this graph
+    params_hash: u64,
+}
+
+impl BuiltGraph {
+    /// Build and optimise the graph for the given [`Parameters`].  This also verifies that the
+    /// [`Parameters`] can produce compositions; if not, an [`Error`](crate::Error) describing the
+    /// problem is returned.
+    pub fn new(params: &Parameters, config: &Config) -> crate::Result<Self> {
+        Self::with_build_progress(params, config, |_| {})
+    }
+
+    /// Same as [`BuiltGraph::new`], but calls `progress_fn` with [`BuildProgress`] updates while
+    /// the graph is being built.  `progress_fn` is always called on the current thread.
+    pub fn with_build_progress(
+        params: &Parameters,
+        config: &Config,
+        mut progress_fn: impl FnMut(BuildProgress),
+    ) -> crate::Result<Self> {
+        Self::build(params, config, &FalsenessCache::default(), &mut progress_fn)
+    }
+
+    /// Build and optimise the graph, reusing any falseness tables in `falseness_cache`
+    pub(crate) fn build(
+        params: &Parameters,
+        config: &Config,
+        falseness_cache: &FalsenessCache,
+        progress_fn: &mut dyn FnMut(BuildProgress),
+    ) -> crate::Result<Self> {
+        let mut graph = Graph::unoptimised(params, config, falseness_cache, progress_fn)?;
+        // Prove which lengths are impossible, and use that to refine the length and method count
+        // ranges
+        let refined_ranges = prove_lengths(&graph, params)?;
+        graph.optimise(params, &refined_ranges);
+        Ok(Self::from_parts(graph, refined_ranges, params))
+    }
+
+    fn from_parts(graph: Graph, refined_ranges: RefinedRanges, params: &Parameters) -> Self {
+        Self {
+            stats: graph.stats(params),
+            graph,
+            refined_ranges,
+            params_hash: params_hash(params),
+        }
+    }
+
+    /// Statistics about this (optimised) graph.
+    pub fn stats(&self) -> GraphStats {
+        self.stats
+    }
+
+    /// The lengths which compositions in this graph could have.  This is often narrower than the
+    /// [`Parameters::length`] which was requested (e.g. a peal of Royal can often only be exactly
+    /// 5040 changes).
+    pub fn length_range(&self) -> RangeInclusive<TotalLength> {
+        self.refined_ranges.length.clone()
+    }
+
+    /// The number of rows of each method which compositions in this graph could have, refined in
+    /// the same way as [`BuiltGraph::length_range`].
+    pub fn method_count_ranges(&self) -> &MethodVec<RangeInclusive<TotalLength>> {
+        &self.refined_ranges.method_counts
+    }
+
+    /// Returns `true` if `params` would build exactly this graph, and therefore can be used to
+    /// search it.
+    pub fn is_compatible_with(&self, params: &Parameters) -> bool {
+        self.params_hash == params_hash(params)
+    }
+
+    /// Write this graph to a file, so it can be loaded again with [`BuiltGraph::load`].
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        // Write to a temporary file and then rename it, so that other processes never see a
+        // partially written file
+        let temp_path = path.with_extension(format!("tmp{}", std::process::id()));
+        std::fs::write(&temp_path, self.serialize())?;
+        std::fs::rename(&temp_path, path).inspect_err(|_| {
+            let _ = std::fs::remove_file(&temp_path);
+        })
+    }
+
+    /// Load a graph saved by [`BuiltGraph::save`].  `params` must be compatible with the
+    /// [`Parameters`] used to build the graph (see [`BuiltGraph::is_compatible_with`]).
+    pub fn load(path: &Path, params: &Parameters) -> crate::Result<Self> {
+        let contents =
+            std::fs::read_to_string(path).map_err(|error| crate::Error::GraphFileRead {
+                path: path.to_owned(),
+                error,
+            })?;
+        let invalid = || crate::Error::InvalidGraphFile(path.to_owned());
+
+        let mut lines = contents.lines();
+        if lines.next() != Some(GRAPH_FILE_HEADER) {
+            return Err(invalid());
+        }
+        let hash = lines
+            .next()
+            .and_then(|line| line.strip_prefix("P "))
+            .and_then(|hash| u64::from_str_radix(hash, 16).ok())
+            .ok_or_else(invalid)?;
+        if hash != params_hash(params) {
+            return Err(crate::Error::GraphParamsMismatch);
+        }
+        let (graph, refined_ranges) = deserialize(lines, params).ok_or_else(invalid)?;
+        // The file parsed, but the values in it could still be inconsistent with `params` (e.g.
+        // if it was edited by hand).  Catch that here, rather than panicking during the search
+        validate(&graph, params).map_err(|reason| crate::Error::GraphFileRead {
+            path: path.to_owned(),
+            error: std::io::Error::new(std::io::ErrorKind::InvalidData, reason),
+        })?;
+        Ok(Self::from_parts(graph, refined_ranges, params))
+    }
+}
+
+/// Hash everything in `params` which affects the graph, so that a graph can't be searched with
+/// [`Parameters`] which would have built a different one.  This uses [`fnv1a_hash`], because the
+/// hashes are saved in graph files.
+///
+/// Each field is written out explicitly (rather than hashing the [`Debug`] output of `params`)
+/// so that the hash doesn't depend on the iteration order of `HashMap`s or how floats are
+/// printed.  Fields which only decide which compositions are generated (`num_comps`, the score
+/// thresholds, `max_calls`, `min_conductability`, `shortlist`, `ranking`, `target_length` and
+/// `calling_filters`) or how they're displayed (`call_display_style`,
+/// `multipart_call_display` and music types' names and `show_*` settings) are left out, so they
+/// can be changed without rebuilding the graph.
+pub(crate) fn params_hash(params: &Parameters) -> u64 {
+    let mut s = String::new();
+    // Floats are hashed by their bits, so that e.g. `0.1` always gives the same hash
+    let float = |f: f32| f.to_bits();
+    let opt_float = |f: Option<f32>| f.map(f32::to_bits);
+    let range = |r: &OptionalRangeInclusive| (r.min, r.max);
+    macro_rules! field {
+        ($($arg:tt)*) => { writeln!(s, $($arg)*).unwrap() };
+    }
+
+    // General
+    field!("length {} {}", params.length.start(), params.length.end());
+    field!("stage {}", params.stage.num_bells());
+    field!("require_truth {}", params.require_truth);
+    field!("max_row_repeats {}", params.max_row_repeats);
+    field!("extent {}", params.extent);
+    field!("prune_dominated_chunks {}", params.prune_dominated_chunks);
+    field!("row_weight {}", float(params.row_weight));
+
+    // Methods & calling
+    for method in &params.methods {
+        field!(
+            "method {} {:?} {:?}",
+            method.id.0,
+            method.title(),
+            method.custom_shorthand
+        );
+        for (labels, row) in method.first_lead().annot_rows() {
+            field!("  row {row} {labels:?}");
+        }
+        field!("  leftover_row {}", method.first_lead().leftover_row());
+        field!("  count_range {:?}", range(&method.count_range));
+        field!(
+            "  count_range_per_part {:?}",
+            range(&method.count_range_per_part)
+        );
+        field!("  start_indices {:?}", method.start_indices);
+        field!("  end_indices {:?}", method.end_indices);
+        field!(
+            "  can_start {} can_end {}",
+            method.can_start,
+            method.can_end
+        );
+        field!("  link_position {:?}", method.link_position);
+        for course_set in &method.allowed_courses {
+            field!(
+                "  courses {} {} {}",
+                course_set.masks.iter().join(" "),
+                course_set.any_stroke,
+                course_set.any_bells
+            );
+        }
+    }
+    field!("splice_style {:?}", params.splice_style);
+    field!("splice_weight {}", float(params.splice_weight));
+    let splice_weights = params
+        .splice_weights
+        .iter()
+        .map(|((from, to), weight)| (from.0, to.0, float(*weight)))
+        .sorted()
+        .collect_vec();
+    field!("splice_weights {splice_weights:?}");
+    field!("method_counts_in_leads {}", params.method_counts_in_leads);
+    for call in &params.calls {
+        field!(
+            "call {} {:?} {:?} {} {:?} {:?} {}",
+            call.id.0,
+            call.label_from,
+            call.label_to,
+            call.transposition,
+            call.symbol,
+            call.calling_positions,
+            float(call.weight)
+        );
+        let allowed_methods = call
+            .allowed_methods
+            .as_ref()
+            .map(|ids| ids.iter().map(|id| id.0).collect_vec());
+        field!("  allowed_methods {allowed_methods:?}");
+    }
+    field!("calling_bell {}", params.calling_bell);
+    let calling_position_names = params.calling_position_names.iter().sorted().collect_vec();
+    field!("calling_position_names {calling_position_names:?}");
+    field!(
+        "allowed_calling_positions {:?}",
+        params.allowed_calling_positions
+    );
+    field!("strict_labels {}", params.strict_labels);
+    field!("atw_weight {:?}", opt_float(params.atw_weight));
+    field!("require_atw {}", params.require_atw);
+
+    // Courses
+    field!("start_row {}", params.start_row);
+    field!("end_row {}", params.end_row);
+    field!("part_heads {}", params.part_head_group.rows().join(" "));
+    for (mask, weight) in &params.course_weights {
+        field!("course_weight {mask} {}", float(*weight));
+    }
+    field!("no_repeated_courses {}", params.no_repeated_courses);
+    field!("calling {:?}", params.calling);
+    field!("omit_round_blocks {}", params.omit_round_blocks);
+
+    // Music
+    for music_type in &params.music_types {
+        field!(
+            "music {} {:?}",
+            music_type.patterns().iter().join(" "),
+            music_type.strokes()
+        );
+        let weights = music_type.weights;
+        field!(
+            "  weights {} {} {} {}",
+            float(weights.front),
+            float(weights.internal),
+            float(weights.back),
+            float(weights.wrap)
+        );
+        field!("  count_range {:?}", range(&music_type.count_range));
+        field!("  non_duffer {}", music_type.non_duffer);
+    }
+    field!("no_duffers {}", params.no_duffers);
+    field!("max_duffer_rows {:?}", params.max_duffer_rows);
+    field!("non_duffer_music {:?}", params.non_duffer_music);
+    field!("required_rows {}", params.required_rows.iter().join(" "));
+    field!("start_stroke {:?}", params.start_stroke);
+
+    fnv1a_hash(s.as_bytes())
+}
+
+/////////////////
+// FILE FORMAT //
+/////////////////
+
+/// The first line of every graph file.  This should be changed whenever the format changes, so
+/// that old graph files are rejected rather than misinterpreted.
+const GRAPH_FILE_HEADER: &str = "monument graph v1";
+
+impl BuiltGraph {
+    /// Convert `self` to a simple line-based text format:
+    /// ```text
+    /// <GRAPH_FILE_HEADER>
+    /// P <params hash>
+    /// R <length range>;<method count ranges>
+    /// Q <call sequence length>
+    /// N <num chunks> <num links>
+    /// C <chunk id> <lengths> <score> <distances>;<method counts>;<music counts>;<required rows>;<false chunks>;<predecessors>;<successors>
+    /// L <call> <from> <to> <part head rotation> <call sequence index> <lengths>;<music counts>
+    /// S <link> <chunk>                  (for each start)
+    /// E <link> <chunk>                  (for each end)
+    /// D <chunks>                        (for each required chunk set)
+    /// X <chunks>                        (for each extent chunk set)
+    /// END
+    /// ```
+    /// Chunks and links are referred to by their index in the file, and `-` means `None` (or
+    /// start/end for the sides of links).  References to chunks which aren't in the graph are
+    /// dropped, since the search ignores them anyway.
+    ///
+    /// This is text rather than a binary format for the same reasons as the falseness cache: the
+    /// library has no serialisation dependencies (which keeps it small and easy to build for
+    /// WASM), the files can be inspected and diffed when debugging, and optimised graphs are
+    /// small enough that the extra size and parsing time don't matter next to building the
+    /// graph.
+    fn serialize(&self) -> String {
+        let graph = &self.graph;
+        let chunk_ids = graph.chunks.keys().sorted().collect_vec();
+        let chunk_idxs: HashMap<&ChunkId, usize> = chunk_ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, i))
+            .collect();
+        let side_string = |side: &LinkSide<ChunkId>| match side {
+            LinkSide::StartOrEnd => Some("-".to_owned()),
+            LinkSide::Chunk(id) => chunk_idxs.get(id).map(usize::to_string),
+        };
+        // Only save the links which connect chunks that are in the graph
+        let link_strings = graph
+            .links
+            .iter()
+            .filter_map(|(id, link)| {
+                let s = format!(
+                    "L {} {} {} {} {} {} {};{}",
+                    option_string(link.call.map(|idx| idx.index())),
+                    side_string(&link.from)?,
+                    side_string(&link.to)?,
+                    link.ph_rotation.index(),
+                    option_string(link.call_sequence_idx.map(|idx| idx.index())),
+                    link.per_part_length,
+                    link.total_length,
+                    music_counts_string(&link.music_counts),
+                );
+                Some((*id, s))
+            })
+            .collect_vec();
+        let link_idxs: HashMap<LinkId, usize> = link_strings
+            .iter()
+            .enumerate()
+            .map(|(i, (id, _))| (*id, i))
+            .collect();
+        let chunk_list = |ids: &[ChunkId]| {
+            ids.iter()
+                .filter_map(|id| chunk_idxs.get(id))
+                .map(usize::to_string)
+                .join(" ")
+        };
+        let link_list = |ids: &[LinkId]| {
+            ids.iter()
+                .filter_map(|id| link_idxs.get(id))
+                .map(usize::to_string)
+                .join(" ")
+        };
+
+        let mut s = String::new();
+        writeln!(s, "{GRAPH_FILE_HEADER}").unwrap();
+        writeln!(s, "P {:016x}", self.params_hash).unwrap();
+        let range_string =
+            |range: &RangeInclusive<TotalLength>| format!("{} {}", range.start(), range.end());
+        writeln!(
+            s,
+            "R {};{}",
+            range_string(&self.refined_ranges.length),
+            self.refined_ranges
+                .method_counts
+                .iter()
+                .map(range_string)
+                .join(" ")
+        )
+        .unwrap();
+        writeln!(s, "Q {}", graph.call_sequence_length).unwrap();
+        writeln!(s, "N {} {}", chunk_ids.len(), link_strings.len()).unwrap();
+        for id in &chunk_ids {
+            let chunk = &graph.chunks[*id];
+            let stroke = match id.stroke {
+                None => "-",
+                Some(Stroke::Hand) => "H",
+                Some(Stroke::Back) => "B",
+            };
+            writeln!(
+                s,
+                "C {} {} {} {stroke} {} {} {} {} {};{};{};{};{};{};{}",
+                id.lead_head,
+                id.method.index(),
+                id.sub_lead_idx,
+                chunk.per_part_length,
+                chunk.total_length,
+                chunk.score,
+                chunk.lb_distance_from_rounds,
+                chunk.lb_distance_to_rounds,
+                (0..chunk.method_counts.len())
+                    .map(|i| chunk.method_counts[i])
+                    .join(" "),
+                music_counts_string(&chunk.music_counts),
+                chunk.required_rows.iter().join(" "),
+                chunk_list(&chunk.false_chunks),
+                link_list(&chunk.predecessors),
+                link_list(&chunk.successors),
+            )
+            .unwrap();
+        }
+        for (_, link_string) in &link_strings {
+            writeln!(s, "{link_string}").unwrap();
+        }
+        for (kind, links) in [("S", &graph.starts), ("E", &graph.ends)] {
+            for (link_id, chunk_id) in links {
+                if let (Some(link_idx), Some(chunk_idx)) =
+                    (link_idxs.get(link_id), chunk_idxs.get(chunk_id))
+                {
+                    writeln!(s, "{kind} {link_idx} {chunk_idx}").unwrap();
+                }
+            }
+        }
+        for set in &graph.required_chunk_sets {
+            writeln!(s, "D {}", chunk_list(set)).unwrap();
+        }
+        for set in &graph.extent_chunk_sets {
+            writeln!(s, "X {}", chunk_list(set)).unwrap();
+        }
+        // Mark the end of the file, so that files which have been cut short aren't loaded
+        writeln!(s, "END").unwrap();
+        s
+    }
+}
+
+/// Parse the lines of a graph file written by [`BuiltGraph::serialize`], after the header and the
+/// [`params_hash`].  Returns `None` if the lines are invalid.
+fn deserialize<'s>(
+    mut lines: impl Iterator<Item = &'s str>,
+    params: &Parameters,
+) -> Option<(Graph, RefinedRanges)> {
+    let mut sections = lines.next()?.strip_prefix("R ")?.split(';');
+    let length = parse_ranges(sections.next()?)?
+        .into_iter()
+        .exactly_one()
+        .ok()?;
+    let method_counts = parse_ranges(sections.next()?)?;
+    if method_counts.len() != params.methods.len() {
+        return None;
+    }
+    let refined_ranges = RefinedRanges {
+        length,
+        method_counts: MethodVec::from_vec(method_counts),
+    };
+    let call_sequence_length = parse(lines.next()?.strip_prefix("Q ")?)?;
+    let mut words = lines.next()?.strip_prefix("N ")?.split(' ');
+    let num_chunks = parse::<usize>(words.next()?)?;
+    let num_links = parse::<usize>(words.next()?)?;
+
+    // Chunk IDs are needed to parse links, which are needed to parse the rest of the chunks
+    let chunk_lines = (0..num_chunks)
+        .map(|_| lines.next()?.strip_prefix("C "))
+        .collect::<Option<Vec<_>>>()?;
+    let mut lead_heads = HashMap::<RowBuf, Arc<Row>>::new();
+    let mut chunk_ids = Vec::with_capacity(num_chunks);
+    for line in &chunk_lines {
+        let mut words = line.split(' ');
+        let lead_head = RowBuf::parse_with_stage(words.next()?, params.stage).ok()?;
+        let lead_head = lead_heads
+            .entry(lead_head)
+            .or_insert_with_key(|row| row.to_arc())
+            .clone();
+        let method = parse::<usize>(words.next()?)?;
+        if method >= params.methods.len() {
+            return None;
+        }
+        let row_idx = RowIdx::new(MethodIdx::new(method), parse(words.next()?)?);
+        let stroke = match words.next()? {
+            "-" => None,
+            "H" => Some(Stroke::Hand),
+            "B" => Some(Stroke::Back),
+            _ => return None,
+        };
+        chunk_ids.push(ChunkId {
+            lead_head,
+            row_idx,
+            stroke,
+        });
+    }
+    let chunk_id = |idx: usize| chunk_ids.get(idx).cloned();
+    let chunk_list = |s: &str| {
+        s.split_whitespace()
+            .map(|word| chunk_id(parse(word)?))
+            .collect::<Option<Vec<_>>>()
+    };
+
+    // Links
+    let num_music_types = params.music_types.len();
+    let rotations = params
+        .part_head_group
+        .rotations()
+        .map(|(_, r)| r)
+        .collect_vec();
+    let mut links = LinkSet::new();
+    let mut link_ids = Vec::with_capacity(num_links);
+    for _ in 0..num_links {
+        let (fields, music_counts) = lines.next()?.strip_prefix("L ")?.split_once(';')?;
+        let mut words = fields.split(' ');
+        let call = parse_option::<usize>(words.next()?)?;
+        if call.is_some_and(|idx| idx >= params.calls.len()) {
+            return None;
+        }
+        let parse_side = |word: &str| match parse_option::<usize>(word)? {
+            None => Some(LinkSide::StartOrEnd),
+            Some(idx) => Some(LinkSide::Chunk(chunk_id(idx)?)),
+        };
+        let from = parse_side(words.next()?)?;
+        let to = parse_side(words.next()?)?;
+        let ph_rotation: PhRotation = *rotations.get(parse::<usize>(words.next()?)?)?;
+        let call_sequence_idx = parse_option::<usize>(words.next()?)?;
+        link_ids.push(links.add(Link {
+            call: call.map(CallIdx::new),
+            from,
+            to,
+            ph_rotation,
+            call_sequence_idx: call_sequence_idx.map(CallSeqIdx::new),
+            per_part_length: PerPartLength::new(parse(words.next()?)?),
+            total_length: TotalLength::new(parse(words.next()?)?),
+            music_counts: parse_music_counts(music_counts, num_music_types)?,
+        }));
+    }
+    let link_list = |s: &str| {
+        s.split_whitespace()
+            .map(|word| link_ids.get(parse::<usize>(word)?).copied())
+            .collect::<Option<Vec<_>>>()
+    };
+
+    // Chunks
+    let mut chunks = HashMap::with_capacity(num_chunks);
+    for (line, id) in chunk_lines.iter().zip_eq(&chunk_ids) {
+        let mut sections = line.split(';');
+        let mut words = sections.next()?.split(' ').skip(4); // Skip the chunk's ID
+        let per_part_length = PerPartLength::new(parse(words.next()?)?);
+        let total_length = TotalLength::new(parse(words.next()?)?);
+        let score = parse::<f32>(words.next()?)?;
+        let lb_distance_from_rounds = TotalLength::new(parse(words.next()?)?);
+        let lb_distance_to_rounds = TotalLength::new(parse(words.next()?)?);
+        let method_counts = parse_list::<usize>(sections.next()?)?;
+        if method_counts.len() != params.methods.len() {
+            return None;
+        }
+        let chunk = Chunk {
+            per_part_length,
+            total_length,
+            score,
+            lb_distance_from_rounds,
+            lb_distance_to_rounds,
+            method_counts: Counts::from(method_counts),
+            music_counts: parse_music_counts(sections.next()?, num_music_types)?,
+            required_rows: parse_list(sections.next()?)?,
+            false_chunks: chunk_list(sections.next()?)?,
+            predecessors: link_list(sections.next()?)?,
+            successors: link_list(sections.next()?)?,
+        };
+        chunks.insert(id.clone(), chunk);
+    }
+
+    // Starts, ends and chunk sets
+    let mut starts = Vec::new();
+    let mut ends = Vec::new();
+    let mut required_chunk_sets = HashSet::new();
+    let mut extent_chunk_sets = Vec::new();
+    loop {
+        let line = lines.next()?;
+        let (kind, rest) = line.split_once(' ').unwrap_or((line, ""));
+        match kind {
+            "END" => break,
+            "S" | "E" => {
+                let mut words = rest.split(' ');
+                let link_id = *link_ids.get(parse::<usize>(words.next()?)?)?;
+                let chunk_id = chunk_id(parse(words.next()?)?)?;
+                match kind {
+                    "S" => starts.push((link_id, chunk_id)),
+                    _ => ends.push((link_id, chunk_id)),
+                }
+            }
+            "D" => {
+                required_chunk_sets.insert(chunk_list(rest)?);
+            }
+            "X" => extent_chunk_sets.push(chunk_list(rest)?),
+            _ => return None,
+        }
+    }
+    if lines.next().is_some() {
+        return None;
+    }
+
+    let graph = Graph {
+        chunks,
+        links,
+        starts,
+        ends,
+        call_sequence_length,
+        required_chunk_sets,
+        extent_chunk_sets,
+    };
+    Some((graph, refined_ranges))
+}
+
+/// Check that the values in a deserialized [`Graph`] are consistent with `params`, returning a
+/// description of the first problem found.
+fn validate(graph: &Graph, params: &Parameters) -> Result<(), String> {
+    let part_heads = &params.part_head_group;
+    for (id, chunk) in &graph.chunks {
+        let lead_len = params.methods[id.method].lead_len();
+        if id.sub_lead_idx >= lead_len {
+            return Err(format!(
+                "chunk {id} starts at sub-lead index {}, but the lead is only {lead_len} rows long",
+                id.sub_lead_idx
+            ));
+        }
+        if chunk.total_length != chunk.per_part_length.as_total(part_heads) {
+            return Err(format!(
+                "chunk {id} has invalid lengths {}/{}",
+                chunk.per_part_length, chunk.total_length
+            ));
+        }
+        if let Some(idx) = chunk
+            .required_rows
+            .iter()
+            .find(|idx| **idx >= params.required_rows.len())
+        {
+            return Err(format!(
+                "chunk {id} contains required row #{idx}, but only {} are required",
+                params.required_rows.len()
+            ));
+        }
+    }
+    for link in graph.links.values() {
+        if link.total_length != link.per_part_length.as_total(part_heads) {
+            return Err(format!(
+                "link has invalid lengths {}/{}",
+                link.per_part_length, link.total_length
+            ));
+        }
+        if let Some(idx) = link.call_sequence_idx {
+            if idx.index() >= graph.call_sequence_length {
+                return Err(format!(
+                    "link has call sequence index {}, but the call sequence has length {}",
+                    idx.index(),
+                    graph.call_sequence_length
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn option_string(value: Option<usize>) -> String {
+    match value {
+        Some(v) => v.to_string(),
+        None => "-".to_owned(),
+    }
+}
+
+fn music_counts_string(counts: &MusicTypeVec<AtRowPositions<usize>>) -> String {
+    counts
+        .iter()
+        .flat_map(|c| [c.front, c.internal, c.back, c.wrap])
+        .join(" ")
+}
+
+fn parse<T: FromStr>(word: &str) -> Option<T> {
+    word.parse().ok()
+}
+
+/// Parse a value written by [`option_string`]
+fn parse_option<T: FromStr>(word: &str) -> Option<Option<T>> {
+    match word {
+        "-" => Some(None),
+        _ => parse(word).map(Some),
+    }
+}
+
+fn parse_list<T: FromStr>(s: &str) -> Option<Vec<T>> {
+    s.split_whitespace().map(parse).collect()
+}
+
+/// Parse pairs of lengths into [`RangeInclusive`]s
+fn parse_ranges(s: &str) -> Option<Vec<RangeInclusive<TotalLength>>> {
+    let lengths = parse_list::<usize>(s)?;
+    if lengths.len() % 2 != 0 {
+        return None;
+    }
+    let ranges = lengths
+        .chunks(2)
+        .map(|pair| TotalLength::new(pair[0])..=TotalLength::new(pair[1]))
+        .collect();
+    Some(ranges)
+}
+
+/// Parse counts written by [`music_counts_string`]
+fn parse_music_counts(
+    s: &str,
+    num_music_types: usize,
+) -> Option<MusicTypeVec<AtRowPositions<usize>>> {
+    let counts = parse_list::<usize>(s)?;
+    if counts.len() != num_music_types * 4 {
+        return None;
+    }
+    let counts = counts
+        .chunks(4)
+        .map(|c| AtRowPositions {
+            front: c[0],
+            internal: c[1],
+            back: c[2],
+            wrap: c[3],
+        })
+        .collect();
+    Some(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashSet,
+        path::{Path, PathBuf},
+        sync::atomic::AtomicBool,
+    };
+
+    use bellframe::Stage;
+    use itertools::Itertools;
+
+    use super::BuiltGraph;
+    use crate::{
+        composition::PathElem, test_utils::plain_bob_params, utils::lengths::TotalLength, Config,
+        Error, Parameters, Search, Update,
+    };
+
+    fn params() -> Parameters {
+        Parameters {
+            length: TotalLength::ZERO..=TotalLength::new(120),
+            ..plain_bob_params(Stage::MINOR)
+        }
+    }
+
+    fn config() -> Config {
+        Config {
+            thread_limit: Some(1),
+            seed: Some(0),
+            ..Default::default()
+        }
+    }
+
+    /// A directory for this test process to save graph files in
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir_name = format!("monument-graph-{name}-{}", std::process::id());
+        std::env::temp_dir().join(dir_name)
+    }
+
+    /// Save the graph for [`params`] to a new file in `dir`, returning the file's path
+    fn save_graph(dir: &Path) -> PathBuf {
+        let path = dir.join("graph.txt");
+        let graph = BuiltGraph::new(&params(), &config()).unwrap();
+        graph.save(&path).unwrap();
+        path
+    }
+
+    /// The paths of every composition found by searching `graph`
+    fn comp_paths(graph: &BuiltGraph) -> HashSet<Vec<PathElem>> {
+        let search = Search::from_graph(params(), config(), graph).unwrap();
+        let mut paths = HashSet::new();
+        search.run_exhaustive(
+            |update| {
+                if let Update::Comp(comp) = update {
+                    paths.insert(comp.path);
+                }
+            },
+            &AtomicBool::new(false),
+        );
+        paths
+    }
+
+    #[test]
+    fn round_trip() {
+        let dir = temp_dir("round-trip");
+        let path = save_graph(&dir);
+        let loaded = BuiltGraph::load(&path, &params()).unwrap();
+        // Only the final file should be left behind
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let built = BuiltGraph::new(&params(), &config()).unwrap();
+        assert_eq!(loaded.stats(), built.stats());
+        assert_eq!(loaded.length_range(), built.length_range());
+        let paths = comp_paths(&built);
+        assert!(!paths.is_empty());
+        assert_eq!(comp_paths(&loaded), paths);
+    }
+
+    #[test]
+    fn truncated_file() {
+        let dir = temp_dir("truncated");
+        let path = save_graph(&dir);
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines = contents.lines().collect_vec();
+        // Cutting the file short at any line should make it invalid
+        for num_lines in 0..lines.len() {
+            std::fs::write(&path, lines[..num_lines].join("\n")).unwrap();
+            let result = BuiltGraph::load(&path, &params());
+            assert!(matches!(result, Err(Error::InvalidGraphFile(_))));
+        }
+        std::fs::write(&path, contents).unwrap();
+        assert!(BuiltGraph::load(&path, &params()).is_ok());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn out_of_range_sub_lead_idx() {
+        let dir = temp_dir("sub-lead-idx");
+        let path = save_graph(&dir);
+        // Move every chunk to a sub-lead index past the end of the lead.  This parses fine, but
+        // would make the search panic
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let corrupted = contents
+            .lines()
+            .map(|line| match line.strip_prefix("C ") {
+                Some(rest) => {
+                    let mut words = rest.split(' ').collect_vec();
+                    words[2] = "1000";
+                    format!("C {}", words.join(" "))
+                }
+                _ => line.to_owned(),
+            })
+            .join("\n");
+        std::fs::write(&path, corrupted).unwrap();
+        let result = BuiltGraph::load(&path, &params());
+        assert!(matches!(result, Err(Error::GraphFileRead { .. })));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn params_mismatch() {
+        let dir = temp_dir("mismatch");
+        let path = save_graph(&dir);
+        // Search-only parameters can change without rebuilding the graph ...
+        let search_params = Parameters {
+            num_comps: 5,
+            max_calls: Some(3),
+            ..params()
+        };
+        assert!(BuiltGraph::load(&path, &search_params).is_ok());
+        // ... but anything else can't
+        let mut graph_params = params();
+        graph_params.music_types[0].weights.front = 2.0;
+        let result = BuiltGraph::load(&path, &graph_params);
+        assert!(matches!(result, Err(Error::GraphParamsMismatch)));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
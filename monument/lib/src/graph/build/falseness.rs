@@ -5,35 +5,42 @@
 #![allow(clippy::type_complexity)]
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeSet, HashMap, HashSet},
     fmt::{Debug, Formatter, Write},
-    time::Instant,
+    path::Path,
+    sync::{mpsc, Arc, Mutex},
 };
 
-use bellframe::{Mask, Row, RowBuf, SameStageVec, Truth};
+use bellframe::{Bell, Mask, Method, Row, RowBuf, SameStageVec, Stage, Truth};
 use itertools::Itertools;
 
 use super::{ChunkEquivalenceMap, UnnormalizedChunkId};
 use crate::{
     graph::{Chunk, ChunkId, PerPartLength, RowIdx},
-    parameters::Parameters,
+    parameters::{MethodIdx, Parameters},
+    search::{BuildProgress, Config},
+    utils::{fnv1a_hash, time::Instant},
 };
 
 /// Set the falseness links for some [`Chunk`]s, removing any which are false against themselves.
 ///
-/// This is the *only* item exported by this module, so the entire module should be considered
-/// implementation detail of this function.
+/// Apart from [`false_course_head_groups`], this is the *only* item exported by this module, so
+/// the rest of the module should be considered implementation detail of these two functions.
 pub(super) fn set_links(
     chunks: &mut HashMap<ChunkId, Chunk>,
     chunk_equiv_map: &mut ChunkEquivalenceMap,
     params: &Parameters,
+    config: &Config,
+    cache: &FalsenessCache,
+    progress_fn: &mut dyn FnMut(BuildProgress),
 ) {
     let start = Instant::now();
     let chunk_ids_and_lengths = chunks
         .iter()
         .map(|(id, chunk)| (id.clone(), chunk.per_part_length))
         .collect::<HashSet<_>>();
-    let falseness_table = FalsenessTable::new(&chunk_ids_and_lengths, params);
+    let falseness_table =
+        FalsenessTable::new(&chunk_ids_and_lengths, params, config, cache, progress_fn);
     log::debug!("  Falseness table built in {:.2?}", start.elapsed());
 
     let start = Instant::now();
@@ -51,6 +58,117 @@ pub(super) fn set_links(
     log::debug!("  Falseness links set in {:.2?}", start.elapsed());
 }
 
+/// Computes the false course heads of a [`Method`] (i.e. the course heads, with every fixed bell
+/// at home, of courses which share rows with the plain course), grouped into false course
+/// head groups.  Two false course heads are in the same group if one is the inverse of the other,
+/// or if they're related by the symmetry of the method's lead heads, so the members of a group
+/// always appear together.  Every group is sorted, and the groups are sorted by their first course
+/// head.  Rounds is never included, so this returns no groups for a clean-proof method.
+pub(crate) fn false_course_head_groups(method: &Method, fixed_bells: &[Bell]) -> Vec<Vec<RowBuf>> {
+    let plain_course = method.plain_course();
+    let mut fixed_bell_mask = Mask::any(method.stage());
+    for bell in fixed_bells {
+        fixed_bell_mask
+            .set_bell(*bell, bell.index())
+            .expect("Fixed bells shouldn't repeat");
+    }
+
+    // Group the rows of the plain course by the places of the fixed bells (see `group_rows`), then
+    // find the false course heads of the plain course against itself
+    let mut row_groups: RowGroups = HashMap::new();
+    for row in plain_course.rows() {
+        row_groups
+            .entry(&fixed_bell_mask * row)
+            .or_insert_with(|| SameStageVec::new(method.stage()))
+            .push(row);
+    }
+    let mut false_course_heads = BTreeSet::<RowBuf>::new();
+    add_false_course_heads(&row_groups, &row_groups, &mut false_course_heads);
+    false_course_heads.retain(|ch| !ch.is_rounds()); // Every course is false against itself
+
+    // Split the false course heads into groups.  If `ch` is false then so is its inverse and, for
+    // every lead head `lh`, so is the course containing `lh * ch`
+    let course_rows = plain_course.rows().collect_vec();
+    let lead_heads = course_rows
+        .iter()
+        .copied()
+        .step_by(method.lead_len())
+        .collect_vec();
+    let mut groups = Vec::<Vec<RowBuf>>::new();
+    while let Some(first_ch) = false_course_heads.pop_first() {
+        let mut group = vec![first_ch.clone()];
+        let mut frontier = vec![first_ch];
+        while let Some(ch) = frontier.pop() {
+            let lh_transpositions = lead_heads.iter().map(|lh| *lh * &ch).collect_vec();
+            let related_chs = lh_transpositions
+                .iter()
+                .cartesian_product(&course_rows)
+                .map(|(lh_ch, row)| lh_ch * *row)
+                .chain(std::iter::once(ch.inv()));
+            for related_ch in related_chs {
+                if false_course_heads.remove(&related_ch) {
+                    group.push(related_ch.clone());
+                    frontier.push(related_ch);
+                }
+            }
+        }
+        group.sort();
+        groups.push(group);
+    }
+    groups
+}
+
+/// Computes the course heads (with every fixed bell at home) of the courses of `method2` which
+/// contain a lead that's false against a lead of `method1`'s plain course.  Leads which start at
+/// the same row are ignored, since at most one of them can be rung.  So this includes rounds if,
+/// when splicing the two methods, some lead of one would be false against a different lead of the
+/// same course.
+pub(crate) fn mutual_false_course_heads(
+    method1: &Method,
+    method2: &Method,
+    fixed_bells: &[Bell],
+) -> Vec<RowBuf> {
+    // Find the lead head transpositions `t` where the lead of `method2` starting at `t` is false
+    // against the first lead of `method1`
+    let group_first_lead = |method: &Method| -> RowGroups {
+        let mut rows = SameStageVec::new(method.stage());
+        for row in method.first_lead().rows() {
+            rows.push(row);
+        }
+        HashMap::from([(Mask::any(method.stage()), rows)])
+    };
+    let mut false_lh_transpositions = HashSet::<RowBuf>::new();
+    add_false_course_heads(
+        &group_first_lead(method1),
+        &group_first_lead(method2),
+        &mut false_lh_transpositions,
+    );
+    false_lh_transpositions.retain(|t| !t.is_rounds()); // Ignore leads starting at the same row
+
+    // Lead `j` of course `ch` of `method2` starts at `ch * lh2[j]`, so it's false against lead `i`
+    // of `method1`'s plain course iff `ch = lh1[i] * t * !lh2[j]` for some transposition `t`
+    let lead_heads = |method: &Method| -> Vec<RowBuf> {
+        let plain_course = method.plain_course();
+        (plain_course.rows().step_by(method.lead_len()))
+            .map(Row::to_owned)
+            .collect_vec()
+    };
+    let inv_lead_heads2 = lead_heads(method2).iter().map(|lh| lh.inv()).collect_vec();
+    let mut false_course_heads = BTreeSet::<RowBuf>::new();
+    for lh1 in lead_heads(method1) {
+        for t in &false_lh_transpositions {
+            let lh1_t = lh1.as_row() * t;
+            for inv_lh2 in &inv_lead_heads2 {
+                let course_head = lh1_t.as_row() * inv_lh2;
+                if fixed_bells.iter().all(|b| course_head.is_fixed(*b)) {
+                    false_course_heads.insert(course_head);
+                }
+            }
+        }
+    }
+    false_course_heads.into_iter().collect_vec()
+}
+
 /// A pre-computed table used to quickly determine the falseness in an entire
 /// [`Graph`](crate::Graph).
 ///
@@ -84,8 +202,14 @@ enum FalsenessEntry {
 
 impl FalsenessTable {
     /// Creates a `FalsenessTable` capable of efficiently generating falseness between a given set
-    /// of chunks.
-    fn new(chunks: &HashSet<(ChunkId, PerPartLength)>, params: &Parameters) -> Self {
+    /// of chunks.  If an identical table is already in `cache`, that table is returned instead.
+    fn new(
+        chunks: &HashSet<(ChunkId, PerPartLength)>,
+        params: &Parameters,
+        config: &Config,
+        cache: &FalsenessCache,
+        progress_fn: &mut dyn FnMut(BuildProgress),
+    ) -> Arc<Self> {
         // Determine which (lead head mask, range) pairs are **actually** used in the graph.  We
         // will produce a 'FCH' tables for every one of these, which will be used as lookups when
         // generating false links.
@@ -115,19 +239,59 @@ impl FalsenessTable {
         // (see doc comment of `reduce_masks` for more info)
         reduce_masks(&mut masks_used, &mut masks_used_in_all_parts, params);
 
+        // The rest of the table is completely determined by the masks and the methods, so if
+        // we've built this table before then we can reuse it or load it from the cache
+        let key = cache_key(&masks_used, &masks_used_in_all_parts, params);
+        // If a cached table is missing any of the ranges we need (e.g. because of a hash
+        // collision), then it's treated as a cache miss and the table is rebuilt
+        if let Some(table) = cache.get(key) {
+            if table.covers(&masks_used) {
+                log::debug!("  Reused falseness table from an earlier graph");
+                return table;
+            }
+        }
+        let cache_path = (config.falseness_cache_dir.as_ref())
+            .map(|dir| dir.join(format!("falseness-{key:016x}.txt")));
+        if let Some(path) = &cache_path {
+            if let Some(table) = Self::load(path, params.stage) {
+                if table.covers(&masks_used) {
+                    log::debug!("  Loaded falseness table from {path:?}");
+                    return cache.insert(key, table);
+                }
+                log::debug!("  Ignoring falseness table in {path:?} which is missing some ranges");
+            }
+        }
+
         // Group rows and compute self-falseness
         let (self_false_ranges, row_groups) = group_rows(masks_used_in_all_parts, params);
 
         // Compute FCHs between every `(range, le_mask)` combination
-        let false_chunk_transpositions =
-            generate_false_chunk_transpositions(&masks_used, &row_groups);
+        let false_chunk_transpositions = generate_false_chunk_transpositions(
+            &masks_used,
+            &row_groups,
+            config.num_threads(),
+            progress_fn,
+        );
 
         // Combine `self_false_ranges` and `false_chunk_transpositions` into the final
         // `FalsenessEntry`s
         let falseness_entries =
             generate_falseness_entries(self_false_ranges, false_chunk_transpositions);
 
-        Self { falseness_entries }
+        let table = Self { falseness_entries };
+        if let Some(path) = &cache_path {
+            // Caching is only an optimisation, so failing to save the table isn't an error
+            match table.save(path) {
+                Ok(()) => log::debug!("  Saved falseness table to {path:?}"),
+                Err(e) => log::debug!("  Couldn't save falseness table to {path:?}: {e}"),
+            }
+        }
+        cache.insert(key, table)
+    }
+
+    /// Returns `true` if `self` has an entry for every [`ChunkRange`] in `masks_used`.
+    fn covers(&self, masks_used: &HashSet<(ChunkRange, Mask)>) -> bool {
+        (masks_used.iter()).all(|(range, _mask)| self.falseness_entries.contains_key(range))
     }
 
     /// Set the falseness links for a given [`Chunk`].  If the [`Chunk`] is false against itself in
@@ -141,10 +305,15 @@ impl FalsenessTable {
         chunk_equiv_map: &mut ChunkEquivalenceMap,
         chunk_ids_and_lengths: &HashSet<(ChunkId, PerPartLength)>,
     ) -> Truth {
-        // Get the false chunk transpositions for this chunk's range, or return on self-falseness
-        let fchs = match &self.falseness_entries[&ChunkRange::new(id.row_idx, length)] {
-            FalsenessEntry::FalseCourseHeads(fchs) => fchs,
-            FalsenessEntry::SelfFalse => return Truth::False,
+        // Get the false chunk transpositions for this chunk's range, or return on self-falseness.
+        // `FalsenessTable::new` makes sure that every range in the graph has an entry
+        let fchs = match self
+            .falseness_entries
+            .get(&ChunkRange::new(id.row_idx, length))
+        {
+            Some(FalsenessEntry::FalseCourseHeads(fchs)) => fchs,
+            Some(FalsenessEntry::SelfFalse) => return Truth::False,
+            None => unreachable!("Falseness table has no entry for {id:?} (len {length:?})"),
         };
 
         false_chunk_vec.clear();
@@ -181,6 +350,170 @@ impl FalsenessTable {
     }
 }
 
+/////////////
+// CACHING //
+/////////////
+
+/// [`FalsenessTable`]s which have already been built, indexed by their [`cache_key`].  This lets
+/// several graphs share falseness tables without going through the files in
+/// [`Config::falseness_cache_dir`] (see [`Session`](crate::Session)).
+#[derive(Default)]
+pub(crate) struct FalsenessCache {
+    tables: Mutex<HashMap<u64, Arc<FalsenessTable>>>,
+}
+
+impl FalsenessCache {
+    fn get(&self, key: u64) -> Option<Arc<FalsenessTable>> {
+        self.tables.lock().unwrap().get(&key).cloned()
+    }
+
+    fn insert(&self, key: u64, table: FalsenessTable) -> Arc<FalsenessTable> {
+        let table = Arc::new(table);
+        self.tables.lock().unwrap().insert(key, table.clone());
+        table
+    }
+}
+
+impl Debug for FalsenessCache {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // The tables themselves are far too big to print
+        let num_tables = self.tables.lock().unwrap().len();
+        write!(f, "FalsenessCache({num_tables} tables)")
+    }
+}
+
+/// The first line of every cache file.  This should be changed whenever the format changes, so
+/// that old cache files are ignored rather than misinterpreted.
+const CACHE_FILE_HEADER: &str = "monument falseness table v2";
+
+impl FalsenessTable {
+    /// Write `self` to a file in a simple line-based text format:
+    /// ```text
+    /// <CACHE_FILE_HEADER>
+    /// <num entries>
+    /// S <range>                         (for self-false ranges)
+    /// F <range> <num masks>             (for other ranges, followed by each mask:)
+    /// M <mask> <num false chunks>       (followed by each false chunk:)
+    /// <range> <lead head transposition>
+    /// ```
+    /// where `<range>` is `<method index> <sub-lead index> <length>`.  The number of entries lets
+    /// [`FalsenessTable::load`] detect files which were cut short.
+    ///
+    /// The table is written to a temporary file which is then renamed over `path`, so other
+    /// processes never see a partially written table.
+    fn save(&self, path: &Path) -> std::io::Result<()> {
+        let mut s = String::new();
+        s.push_str(CACHE_FILE_HEADER);
+        s.push('\n');
+        writeln!(s, "{}", self.falseness_entries.len()).unwrap();
+        for (range, entry) in &self.falseness_entries {
+            match entry {
+                FalsenessEntry::SelfFalse => writeln!(s, "S {}", range.serialize()).unwrap(),
+                FalsenessEntry::FalseCourseHeads(masks) => {
+                    writeln!(s, "F {} {}", range.serialize(), masks.len()).unwrap();
+                    for (mask, false_ranges) in masks {
+                        writeln!(s, "M {mask} {}", false_ranges.len()).unwrap();
+                        for (false_range, transposition) in false_ranges {
+                            writeln!(s, "{} {transposition}", false_range.serialize()).unwrap();
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let temp_path = path.with_extension(format!("tmp{}", std::process::id()));
+        std::fs::write(&temp_path, s)?;
+        std::fs::rename(&temp_path, path).inspect_err(|_| {
+            let _ = std::fs::remove_file(&temp_path);
+        })
+    }
+
+    /// Load a table saved by [`FalsenessTable::save`], returning `None` if the file doesn't exist,
+    /// is invalid or is incomplete.
+    fn load(path: &Path, stage: Stage) -> Option<Self> {
+        let contents = std::fs::read_to_string(path).ok()?;
+        let mut lines = contents.lines();
+        if lines.next()? != CACHE_FILE_HEADER {
+            return None;
+        }
+        let num_entries = lines.next()?.parse::<usize>().ok()?;
+
+        let mut falseness_entries = HashMap::new();
+        while let Some(line) = lines.next() {
+            let mut words = line.split(' ');
+            let kind = words.next()?;
+            let range = ChunkRange::deserialize(&mut words)?;
+            let entry = match kind {
+                "S" => FalsenessEntry::SelfFalse,
+                "F" => {
+                    let num_masks = words.next()?.parse::<usize>().ok()?;
+                    let mut masks = Vec::with_capacity(num_masks);
+                    for _ in 0..num_masks {
+                        let mut words = lines.next()?.split(' ');
+                        if words.next()? != "M" {
+                            return None;
+                        }
+                        let mask = Mask::parse_with_stage(words.next()?, stage).ok()?;
+                        let num_false_ranges = words.next()?.parse::<usize>().ok()?;
+                        let mut false_ranges = Vec::with_capacity(num_false_ranges);
+                        for _ in 0..num_false_ranges {
+                            let mut words = lines.next()?.split(' ');
+                            let false_range = ChunkRange::deserialize(&mut words)?;
+                            let transposition =
+                                RowBuf::parse_with_stage(words.next()?, stage).ok()?;
+                            false_ranges.push((false_range, transposition));
+                        }
+                        masks.push((mask, false_ranges));
+                    }
+                    FalsenessEntry::FalseCourseHeads(masks)
+                }
+                _ => return None,
+            };
+            falseness_entries.insert(range, entry);
+        }
+        if falseness_entries.len() != num_entries {
+            return None; // File was cut short (or has duplicate entries)
+        }
+        Some(Self { falseness_entries })
+    }
+}
+
+/// Compute a hash of everything that determines the contents of a [`FalsenessTable`].  This uses
+/// [`fnv1a_hash`] rather than [`std::hash::DefaultHasher`], because the cache keys must stay the
+/// same between different builds of Monument.
+fn cache_key(
+    masks_used: &HashSet<(ChunkRange, Mask)>,
+    masks_used_in_all_parts: &HashSet<(ChunkRange, Mask)>,
+    params: &Parameters,
+) -> u64 {
+    let mut bytes = Vec::<u8>::new();
+    bytes.extend_from_slice(CACHE_FILE_HEADER.as_bytes());
+    bytes.push(params.stage.num_bells_u8());
+    // The rows of every method
+    for method in &params.methods {
+        bytes.push(b'|');
+        for row in method.plain_course().rows() {
+            bytes.extend(row.bell_iter().map(|b| b.index_u8()));
+        }
+    }
+    // The masks, in a consistent order
+    for mask_set in [masks_used, masks_used_in_all_parts] {
+        bytes.push(b'|');
+        let sorted_masks = mask_set
+            .iter()
+            .map(|(range, mask)| format!("{} {mask}", range.serialize()))
+            .sorted();
+        for mask_string in sorted_masks {
+            bytes.extend_from_slice(mask_string.as_bytes());
+            bytes.push(b';');
+        }
+    }
+
+    fnv1a_hash(&bytes)
+}
+
 /// Combine the [set](HashSet) of self-false [`ChunkRange`]s with the computed
 /// [`FalseTranspositions`] into a set of [`FalsenessEntry`]s which summarise all the falseness in
 /// the [`Graph`].
@@ -369,42 +702,125 @@ fn group_rows(
 /// transpositions against every (range, mask) in **every part** of the composition.
 ///
 /// Note that this is the section that causes the quadratic behaviour (created by the heavy use of
-/// `cartesian_product`s).
+/// `cartesian_product`s).  Each `(range, mask)` in `masks_used` is independent of the others, so
+/// they are shared between `num_threads` worker threads.  Progress is reported to `progress_fn`
+/// (on the calling thread) every time one `(range, mask)` is completed.
 fn generate_false_chunk_transpositions<'masks, 'groups>(
     masks_used: &'masks HashSet<(ChunkRange, Mask)>,
     row_groups: &'groups HashMap<(ChunkRange, Mask), RowGroups>,
+    num_threads: usize,
+    progress_fn: &mut dyn FnMut(BuildProgress),
 ) -> FalseTranspositions<'masks, 'groups> {
-    let mut false_chunk_transpositions: FalseTranspositions = HashMap::new();
-    // For every pair of `(range, mask)`s ...
-    for (range_mask1, (range_mask2, row_groups2)) in masks_used.iter().cartesian_product(row_groups)
-    {
-        let row_groups1 = match row_groups.get(range_mask1) {
-            Some(rg) => rg,
-            None => continue, // Anything not in `row_groups` is self-false
-        };
+    let masks_used = masks_used.iter().collect_vec();
+    let total = masks_used.len();
+    let num_threads = num_threads.clamp(1, total.max(1));
+
+    // With only one thread (e.g. because the `threads` feature is disabled), do all the work on the
+    // calling thread
+    if num_threads == 1 {
+        let mut false_chunk_transpositions: FalseTranspositions = HashMap::new();
+        progress_fn(BuildProgress::Falseness { done: 0, total });
+        for (done, range_mask1) in masks_used.into_iter().enumerate() {
+            if let Some(row_groups1) = row_groups.get(range_mask1) {
+                false_chunk_transpositions.insert(
+                    range_mask1,
+                    false_transpositions_for(row_groups1, row_groups),
+                );
+            } // Anything not in `row_groups` is self-false
+            progress_fn(BuildProgress::Falseness {
+                done: done + 1,
+                total,
+            });
+        }
+        return false_chunk_transpositions;
+    }
 
-        let fch_entry = false_chunk_transpositions
-            .entry(range_mask1)
-            .or_default()
-            .entry(range_mask2)
-            .or_default();
+    std::thread::scope(|scope| {
+        let (done_tx, done_rx) = mpsc::channel::<()>();
+        let workers = (0..num_threads)
+            .map(|thread_idx| {
+                let done_tx = done_tx.clone();
+                // Stripe the `(range, mask)`s across the threads, so that expensive ranges (which
+                // tend to be clustered together) are spread out
+                let masks_for_thread = masks_used
+                    .iter()
+                    .skip(thread_idx)
+                    .step_by(num_threads)
+                    .copied()
+                    .collect_vec();
+                scope.spawn(move || {
+                    let mut false_chunk_transpositions: FalseTranspositions = HashMap::new();
+                    for range_mask1 in masks_for_thread {
+                        if let Some(row_groups1) = row_groups.get(range_mask1) {
+                            false_chunk_transpositions.insert(
+                                range_mask1,
+                                false_transpositions_for(row_groups1, row_groups),
+                            );
+                        } // Anything not in `row_groups` is self-false
+                        let _ = done_tx.send(()); // Main thread only stops listening if it panics
+                    }
+                    false_chunk_transpositions
+                })
+            })
+            .collect_vec();
+        drop(done_tx); // Only the workers hold senders, so `done_rx` closes once they all finish
+
+        // Report progress until every worker has finished
+        progress_fn(BuildProgress::Falseness { done: 0, total });
+        for (done, ()) in done_rx.iter().enumerate() {
+            progress_fn(BuildProgress::Falseness {
+                done: done + 1,
+                total,
+            });
+        }
 
-        // ... for every pair of row groups within them ...
-        for ((row_mask1, rows1), (row_mask2, rows2)) in
-            row_groups1.iter().cartesian_product(row_groups2)
-        {
-            // ... if the masks are compatible ...
-            if row_mask1.is_compatible_with(row_mask2) {
-                // ... then falseness is possible and every pair of rows in `rows1 x rows2`
-                // will generate a false course head between `i1` and `i2`
-                for (row1, row2) in rows1.iter().cartesian_product(rows2) {
-                    let false_course_head = Row::solve_xa_equals_b(row2, row1);
-                    fch_entry.insert(false_course_head);
-                }
-            }
+        // Combine the tables from each thread.  Each `range_mask1` is handled by exactly one
+        // thread, so the tables are disjoint
+        let mut false_chunk_transpositions: FalseTranspositions = HashMap::new();
+        for worker in workers {
+            false_chunk_transpositions.extend(worker.join().expect("Falseness thread panicked"));
+        }
+        false_chunk_transpositions
+    })
+}
+
+/// Compute the false chunk transpositions between one `(range, mask)` (which has been grouped into
+/// `row_groups1`) and every `(range, mask)` in `row_groups`.
+fn false_transpositions_for<'groups>(
+    row_groups1: &RowGroups,
+    row_groups: &'groups HashMap<(ChunkRange, Mask), RowGroups>,
+) -> HashMap<&'groups (ChunkRange, Mask), HashSet<RowBuf>> {
+    let mut fch_entries = HashMap::<&(ChunkRange, Mask), HashSet<RowBuf>>::new();
+    // For every other `(range, mask)` ...
+    for (range_mask2, row_groups2) in row_groups {
+        let fch_entry = fch_entries.entry(range_mask2).or_default();
+        add_false_course_heads(row_groups1, row_groups2, fch_entry);
+    }
+    fch_entries
+}
+
+/// Add every false course head between two sets of [`RowGroups`] to `false_course_heads`.
+fn add_false_course_heads(
+    row_groups1: &RowGroups,
+    row_groups2: &RowGroups,
+    false_course_heads: &mut impl Extend<RowBuf>,
+) {
+    // For every pair of row groups ...
+    for ((row_mask1, rows1), (row_mask2, rows2)) in
+        row_groups1.iter().cartesian_product(row_groups2)
+    {
+        // ... if the masks are compatible ...
+        if row_mask1.is_compatible_with(row_mask2) {
+            // ... then falseness is possible and every pair of rows in `rows1 x rows2` will
+            // generate a false course head between `i1` and `i2`
+            false_course_heads.extend(
+                rows1
+                    .iter()
+                    .cartesian_product(rows2)
+                    .map(|(row1, row2)| Row::solve_xa_equals_b(row2, row1)),
+            );
         }
     }
-    false_chunk_transpositions
 }
 
 /// The range of rows covered by some [`Chunk`].
@@ -418,6 +834,25 @@ impl ChunkRange {
     fn new(start: RowIdx, len: PerPartLength) -> Self {
         Self { start, len }
     }
+
+    /// Convert `self` into the string `"<method index> <sub-lead index> <length>"`
+    fn serialize(&self) -> String {
+        format!(
+            "{} {} {}",
+            self.start.method.index(),
+            self.start.sub_lead_idx,
+            self.len
+        )
+    }
+
+    /// Parse a `ChunkRange` from the next three words, as generated by [`ChunkRange::serialize`]
+    fn deserialize<'s>(words: &mut impl Iterator<Item = &'s str>) -> Option<Self> {
+        let mut next_usize = || words.next()?.parse::<usize>().ok();
+        let method = MethodIdx::new(next_usize()?);
+        let sub_lead_idx = next_usize()?;
+        let len = PerPartLength::new(next_usize()?);
+        Some(Self::new(RowIdx::new(method, sub_lead_idx), len))
+    }
 }
 
 impl Debug for ChunkRange {
@@ -429,3 +864,142 @@ impl Debug for ChunkRange {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use bellframe::{Bell, Mask, Method, RowBuf, Stage};
+    use itertools::Itertools;
+
+    use super::{ChunkRange, FalsenessEntry, FalsenessTable};
+    use crate::{
+        graph::{PerPartLength, RowIdx},
+        parameters::MethodIdx,
+    };
+
+    const PLAIN_BOB: &str = "x18x18x18x18,12";
+    const YORKSHIRE: &str = "x38x14x58x16x12x38x14x78,12";
+
+    fn method(place_notation: &str) -> Method {
+        Method::from_place_not_string(String::new(), Stage::MAJOR, place_notation).unwrap()
+    }
+
+    fn tenors_together() -> [Bell; 3] {
+        [1, 7, 8].map(|n| Bell::from_number(n).unwrap())
+    }
+
+    fn fch_groups(place_notation: &str) -> Vec<Vec<String>> {
+        super::false_course_head_groups(&method(place_notation), &tenors_together())
+            .iter()
+            .map(|group| group.iter().map(RowBuf::to_string).collect_vec())
+            .collect_vec()
+    }
+
+    #[test]
+    fn false_course_head_groups() {
+        assert!(fch_groups(PLAIN_BOB).is_empty());
+        assert_eq!(
+            fch_groups(YORKSHIRE),
+            vec![
+                vec!["12365478", "12436578", "12543678", "13245678", "14325678"],
+                vec!["13524678", "13625478", "14253678", "14265378"],
+            ]
+        );
+    }
+
+    #[test]
+    fn mutual_false_course_heads() {
+        // A method's falseness against itself is its false course heads (unless its plain course is
+        // false)
+        for pn in [PLAIN_BOB, YORKSHIRE] {
+            let m = method(pn);
+            let fchs = super::false_course_head_groups(&m, &tenors_together()).concat();
+            let mut mutual_fchs = super::mutual_false_course_heads(&m, &m, &tenors_together());
+            mutual_fchs.sort();
+            assert_eq!(mutual_fchs, fchs.into_iter().sorted().collect_vec());
+        }
+    }
+
+    fn range(sub_lead_idx: usize, len: usize) -> ChunkRange {
+        ChunkRange::new(
+            RowIdx::new(MethodIdx::new(0), sub_lead_idx),
+            PerPartLength::new(len),
+        )
+    }
+
+    fn table() -> FalsenessTable {
+        let stage = Stage::MAJOR;
+        let false_ranges = vec![
+            (
+                range(0, 32),
+                RowBuf::parse_with_stage("13245678", stage).unwrap(),
+            ),
+            (range(16, 16), RowBuf::rounds(stage)),
+        ];
+        let masks = vec![(
+            Mask::parse_with_stage("1xxxxx78", stage).unwrap(),
+            false_ranges,
+        )];
+        FalsenessTable {
+            falseness_entries: HashMap::from([
+                (range(0, 32), FalsenessEntry::FalseCourseHeads(masks)),
+                (range(16, 16), FalsenessEntry::SelfFalse),
+            ]),
+        }
+    }
+
+    /// A directory for this test process to save cache files in
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir_name = format!("monument-falseness-{name}-{}", std::process::id());
+        std::env::temp_dir().join(dir_name)
+    }
+
+    #[test]
+    fn cache_file_round_trip() {
+        let dir = temp_dir("round-trip");
+        let path = dir.join("falseness.txt");
+        table().save(&path).unwrap();
+        let loaded = FalsenessTable::load(&path, Stage::MAJOR).unwrap();
+        // Only the final file should be left behind
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            format!("{:?}", loaded.falseness_entries.get(&range(0, 32))),
+            format!("{:?}", table().falseness_entries.get(&range(0, 32))),
+        );
+        assert!(matches!(
+            loaded.falseness_entries.get(&range(16, 16)),
+            Some(FalsenessEntry::SelfFalse)
+        ));
+    }
+
+    #[test]
+    fn incomplete_cache_file() {
+        let dir = temp_dir("incomplete");
+        let path = dir.join("falseness.txt");
+        table().save(&path).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines = contents.lines().collect_vec();
+        // Cutting the file short at any line (even between entries) should make it invalid
+        for num_lines in 0..lines.len() {
+            std::fs::write(&path, lines[..num_lines].join("\n")).unwrap();
+            assert!(FalsenessTable::load(&path, Stage::MAJOR).is_none());
+        }
+        std::fs::write(&path, contents).unwrap();
+        assert!(FalsenessTable::load(&path, Stage::MAJOR).is_some());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn table_covers_ranges() {
+        let any_mask = Mask::any(Stage::MAJOR);
+        let masks_used = |ranges: &[ChunkRange]| -> HashSet<(ChunkRange, Mask)> {
+            ranges.iter().map(|r| (*r, any_mask.clone())).collect()
+        };
+        assert!(table().covers(&masks_used(&[range(0, 32), range(16, 16)])));
+        // A range which is missing from the table means that the table can't be used
+        assert!(!table().covers(&masks_used(&[range(0, 32), range(0, 16)])));
+    }
+}
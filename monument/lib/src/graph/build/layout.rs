@@ -4,7 +4,7 @@ use std::{
     ops::Deref,
 };
 
-use bellframe::{Mask, Row, RowBuf};
+use bellframe::{music::AtRowPositions, Mask, Row, RowBuf};
 use itertools::Itertools;
 
 use crate::{
@@ -61,6 +61,9 @@ pub(super) fn chunk_lengths<'p>(
             to: LinkSide::Chunk(start_id.clone()),
             ph_rotation,
             call_sequence_idx: None,
+            per_part_length: PerPartLength::ZERO,
+            total_length: TotalLength::ZERO,
+            music_counts: index_vec::index_vec![AtRowPositions::ZERO; params.music_types.len()],
         });
         frontier.push(Reverse(FrontierItem::new(
             ChunkToExpand {
@@ -111,6 +114,11 @@ pub(super) fn chunk_lengths<'p>(
         // Create the successor links and add the corresponding `ChunkId`s to the frontier
         let mut links_from_this_chunk = HashSet::<(LinkSide<ChunkId>, PhRotation)>::new();
         for (id_to, call, is_end) in successors {
+            // Calls covering several changes add extra rows between the chunks
+            let link_length = PerPartLength::new(
+                call.map_or(0, |idx| params.calls[idx].transposition.num_extra_rows()),
+            );
+            let link_total_length = link_length.as_total(&params.part_head_group);
             let lead_head_transposition =
                 Row::solve_ax_equals_b(&chunk_id.lead_head, &id_to.lead_head);
             let new_lead_head_in_first_part = &lead_head_in_first_part * lead_head_transposition;
@@ -120,6 +128,25 @@ pub(super) fn chunk_lengths<'p>(
                 true => LinkSide::StartOrEnd,
                 false => LinkSide::Chunk(id_to.clone()),
             };
+            // Splices which the user has banned are never added to the graph
+            if !is_end && params.is_splice(&chunk_id, per_part_length, &id_to) {
+                let method_id = |c: &ChunkId| params.methods[c.method].id;
+                if params.is_splice_banned(method_id(&chunk_id), method_id(&id_to)) {
+                    continue;
+                }
+            }
+            // Link methods can only be left/entered at the end/start of the part
+            if !is_end && !params.is_link_allowed(&chunk_id, per_part_length, &id_to) {
+                continue;
+            }
+            // Calls at banned calling positions are never added to the graph
+            if let Some(call) = call {
+                let row_after_call = &new_lead_head_in_first_part
+                    * params.methods[id_to.method].row_in_plain_lead(id_to.sub_lead_idx);
+                if !params.is_calling_position_allowed(call, &row_after_call) {
+                    continue;
+                }
+            }
             // Only store one link between every pair of `LinkSide`s
             // TODO: Always preserve the links with the *highest* score
             if !links_from_this_chunk.insert((link_side_to.clone(), ph_rotation)) {
@@ -138,21 +165,16 @@ pub(super) fn chunk_lengths<'p>(
                 }
 
                 if let Some(call) = call {
-                    let Some(&(expected_next_call, expected_place)) =
-                        sequence.get(call_sequence_idx)
-                    else {
+                    let Some(expected_calls) = sequence.get(call_sequence_idx) else {
                         continue; // Skip a call which is past the end of the composition
                     };
 
-                    // Skip use of the wrong call
-                    if expected_next_call != call {
-                        continue;
-                    }
-
-                    // Skip use of the correct call but at the wrong calling position
+                    // Skip use of the wrong call, or the correct call but at the wrong calling
+                    // position.  If several calls share a symbol, only the one which can be made
+                    // in this method will ever appear here.
                     let calling_bell_place =
                         new_lead_head_in_first_part.place_of(params.calling_bell);
-                    if calling_bell_place != expected_place {
+                    if !expected_calls.contains(&(call, calling_bell_place)) {
                         continue;
                     }
 
@@ -173,6 +195,12 @@ pub(super) fn chunk_lengths<'p>(
                     to: link_side_to,
                     ph_rotation,
                     call_sequence_idx: link_sequence_idx,
+                    per_part_length: link_length,
+                    total_length: link_total_length,
+                    music_counts: index_vec::index_vec![
+                        AtRowPositions::ZERO;
+                        params.music_types.len()
+                    ],
                 });
             }
             // If this isn't an end, add the new chunk to the frontier so it becomes part of the
@@ -184,7 +212,7 @@ pub(super) fn chunk_lengths<'p>(
                         lead_head_in_first_part: new_lead_head_in_first_part,
                         call_sequence_idx: next_call_sequence_idx,
                     },
-                    min_distance_after_chunk,
+                    min_distance_after_chunk + link_total_length,
                 )));
             }
         }
@@ -433,11 +461,11 @@ impl LinkLookupTable {
                     //
                     // ... for every call that can be placed there ...
                     for (call_idx, call) in params.calls.iter_enumerated() {
-                        if &call.label_from == label {
+                        if &call.label_from == label && call.can_be_made_in(method.id) {
                             let row_before_call =
                                 plain_course.get_row(dist_from_lead_head - 1).unwrap();
                             let row_after_call =
-                                row_before_call * call.place_notation.transposition();
+                                row_before_call * call.transposition.transposition();
 
                             create_links(
                                 dist_from_lead_head,
@@ -3,28 +3,35 @@
 mod falseness;
 mod layout;
 
+pub(crate) use falseness::{false_course_head_groups, mutual_false_course_heads, FalsenessCache};
+
 use std::{
     collections::{BTreeMap, HashMap, HashSet},
     ops::Deref,
     sync::Arc,
-    time::Instant,
 };
 
-use bellframe::{music::AtRowPositions, Block, Mask, Row, RowBuf, Stroke, StrokeSet};
+use bellframe::{music::AtRowPositions, Block, Mask, Row, RowBuf, SameStageVec, Stroke, StrokeSet};
 use itertools::Itertools;
 
 use crate::{
-    group::{PartHeadGroup, PhRotation},
+    group::{PartHead, PartHeadGroup, PhRotation},
     parameters::{Call, Method, MethodIdx, MethodVec, Parameters},
-    search::Config,
-    utils::counts::Counts,
+    search::{BuildProgress, Config},
+    utils::{counts::Counts, time::Instant},
 };
 
-use super::{Chunk, ChunkId, Graph, LinkSet, LinkSide, PerPartLength, RowIdx, TotalLength};
+use super::{Chunk, ChunkId, Graph, Link, LinkSet, LinkSide, PerPartLength, RowIdx, TotalLength};
 
 impl Graph {
-    /// Generate a graph of all chunks which are reachable within a given length constraint.
-    pub(crate) fn unoptimised(params: &Parameters, config: &Config) -> crate::Result<Self> {
+    /// Generate a graph of all chunks which are reachable within a given length constraint.  Any
+    /// falseness table which is already in `falseness_cache` will be reused.
+    pub(crate) fn unoptimised(
+        params: &Parameters,
+        config: &Config,
+        falseness_cache: &FalsenessCache,
+        progress_fn: &mut dyn FnMut(BuildProgress),
+    ) -> crate::Result<Self> {
         log::debug!("Building unoptimised graph:");
         let graph_build_start = Instant::now();
 
@@ -34,7 +41,7 @@ impl Graph {
         let start = Instant::now();
         let layout::ChunkLengths {
             chunk_lengths,
-            links,
+            mut links,
             mut chunk_equiv_map,
 
             call_sequence_length,
@@ -54,18 +61,7 @@ impl Graph {
 
         // Assign `successor`/`predecessor` links
         let start = Instant::now();
-        for (link_id, link) in links.iter() {
-            if let LinkSide::Chunk(id) = &link.from {
-                if let Some(chunk) = chunks.get_mut(id) {
-                    chunk.successors.push(*link_id);
-                }
-            }
-            if let LinkSide::Chunk(id) = &link.to {
-                if let Some(chunk) = chunks.get_mut(id) {
-                    chunk.predecessors.push(*link_id);
-                }
-            }
-        }
+        set_succ_pred_links(&mut chunks, &links);
         log::debug!(
             "  Successor/predecessor links set in {:.2?}",
             start.elapsed()
@@ -73,7 +69,14 @@ impl Graph {
 
         // Assign falseness links
         if params.require_truth {
-            falseness::set_links(&mut chunks, &mut chunk_equiv_map, params);
+            falseness::set_links(
+                &mut chunks,
+                &mut chunk_equiv_map,
+                params,
+                config,
+                falseness_cache,
+                progress_fn,
+            );
         }
 
         // Count music
@@ -82,10 +85,15 @@ impl Graph {
             .music_types
             .iter()
             .any(|ty| ty.strokes() != StrokeSet::Both);
-        let start_strokes = get_start_strokes(&chunks, &links, params);
-        if start_strokes.is_none() && relies_on_stroke {
-            return Err(crate::Error::InconsistentStroke);
-        }
+        let start_strokes = match get_start_strokes(&chunks, &links, params) {
+            Some(start_strokes) => Some(start_strokes),
+            // If some chunks can start at either stroke (e.g. because of odd-length leads), then
+            // stroke-dependent music needs a separate copy of each chunk for each stroke
+            None if relies_on_stroke => {
+                Some(split_chunks_by_stroke(&mut chunks, &mut links, params))
+            }
+            None => None,
+        };
         // Now we know the starting strokes, count the music on each chunk
         let method_caches: MethodVec<MethodCacheData> = params
             .methods
@@ -95,6 +103,9 @@ impl Graph {
         for (id, chunk) in &mut chunks {
             count_scores(id, chunk, &method_caches, &start_strokes, params);
         }
+        for link in links.values_mut() {
+            count_link_music(link, &chunks, &method_caches, &start_strokes, params);
+        }
         log::debug!("  Music counted in {:.2?}", start.elapsed());
 
         log::debug!(
@@ -115,6 +126,15 @@ impl Graph {
             }
         }
 
+        // Every composition must contain one of the chunks containing each required row.  For
+        // extents, that's every row
+        let mut required_chunk_sets = required_row_chunk_sets(&chunks, params)?;
+        let extent_chunk_sets = match params.extent {
+            true => extent_row_chunk_sets(&chunks, &method_caches, params)?,
+            false => Vec::new(),
+        };
+        required_chunk_sets.extend(extent_chunk_sets.iter().cloned());
+
         // Finally construct the graph
         let graph = Graph {
             chunks,
@@ -124,7 +144,8 @@ impl Graph {
             ends,
 
             call_sequence_length,
-            required_chunk_sets: HashSet::new(),
+            required_chunk_sets,
+            extent_chunk_sets,
         };
         Ok(graph)
     }
@@ -171,6 +192,7 @@ fn expand_chunk(id: &ChunkId, per_part_length: PerPartLength, params: &Parameter
         false_chunks: Vec::new(),
         score: 0.0,
         music_counts: index_vec::index_vec![AtRowPositions::ZERO; params.music_types.len()],
+        required_rows: Vec::new(),
 
         // Used by optimisation passes
         lb_distance_from_rounds: TotalLength::ZERO,
@@ -178,11 +200,84 @@ fn expand_chunk(id: &ChunkId, per_part_length: PerPartLength, params: &Parameter
     }
 }
 
+/// For each of the [`Parameters::required_rows`], collect the set of chunks which contain it.  Every
+/// composition has to contain at least one chunk from each of these sets.
+fn required_row_chunk_sets(
+    chunks: &HashMap<ChunkId, Chunk>,
+    params: &Parameters,
+) -> crate::Result<HashSet<Vec<ChunkId>>> {
+    let mut required_chunk_sets = HashSet::new();
+    for (idx, row) in params.required_rows.iter().enumerate() {
+        let chunk_ids = chunks
+            .iter()
+            .filter(|(_id, chunk)| chunk.required_rows.contains(&idx))
+            .map(|(id, _chunk)| id.clone())
+            .sorted()
+            .collect_vec();
+        if chunk_ids.is_empty() {
+            return Err(crate::Error::RequiredRowNotRung(row.clone()));
+        }
+        required_chunk_sets.insert(chunk_ids);
+    }
+    Ok(required_chunk_sets)
+}
+
+/// For [`Parameters::extent`] searches, group the rows of the extent by the set of chunks which
+/// contain them.  Every extent has to contain exactly one chunk from each of these sets.
+fn extent_row_chunk_sets(
+    chunks: &HashMap<ChunkId, Chunk>,
+    method_caches: &MethodVec<MethodCacheData>,
+    params: &Parameters,
+) -> crate::Result<Vec<Vec<ChunkId>>> {
+    let mut chunks_per_row = HashMap::<RowBuf, Vec<ChunkId>>::new();
+    for (id, chunk) in chunks {
+        for part_head in params.part_head_group.rows() {
+            let rows = rows_in_part(id, chunk.per_part_length, part_head, method_caches, params);
+            for row in rows.rows() {
+                chunks_per_row
+                    .entry(row.to_owned())
+                    .or_default()
+                    .push(id.clone());
+            }
+        }
+    }
+    // If some row isn't in any chunk, then no extent can contain it
+    for row in &params.stage.extent() {
+        if !chunks_per_row.contains_key(row) {
+            return Err(crate::Error::ExtentRowNotRung(row.to_owned()));
+        }
+    }
+    let chunk_sets = chunks_per_row
+        .into_values()
+        .map(|mut ids| {
+            ids.sort();
+            ids.dedup();
+            ids
+        })
+        .collect::<HashSet<_>>();
+    // Sort the sets so that the search doesn't depend on `HashMap` iteration order
+    Ok(chunk_sets.into_iter().sorted().collect_vec())
+}
+
+/// Add every [`Link`] to the `successors`/`predecessors` of the [`Chunk`]s on either side of it
+fn set_succ_pred_links(chunks: &mut HashMap<ChunkId, Chunk>, links: &LinkSet) {
+    for (link_id, link) in links.iter() {
+        if let LinkSide::Chunk(id) = &link.from {
+            if let Some(chunk) = chunks.get_mut(id) {
+                chunk.successors.push(*link_id);
+            }
+        }
+        if let LinkSide::Chunk(id) = &link.to {
+            if let Some(chunk) = chunks.get_mut(id) {
+                chunk.predecessors.push(*link_id);
+            }
+        }
+    }
+}
+
 /// Attempt to assign a single starting [`Stroke`] to every [`Chunk`].  If such a mapping is
 /// ambiguous (i.e. there's a cycle in the graph which doesn't preserve [`Stroke`]) then [`None`]
 /// is returned.
-// TODO: Add `Stroke` as part of `ChunkId`, so that the same chunk on two different strokes are
-// treated differently
 fn get_start_strokes(
     chunks: &HashMap<ChunkId, Chunk>,
     links: &LinkSet,
@@ -214,12 +309,14 @@ fn get_start_strokes(
             None => {
                 // Chunk hasn't been expanded before, so continue the search
                 if let Some(chunk) = chunks.get(&id) {
-                    let stroke_after_chunk = new_stroke.offset(chunk.per_part_length.as_usize());
                     for succ_link_id in &chunk.successors {
                         let succ_link = &links[*succ_link_id];
                         assert_eq!(succ_link.from, LinkSide::Chunk(id.clone()));
                         if let LinkSide::Chunk(succ_id) = &succ_link.to {
-                            frontier.push((succ_id.to_owned(), stroke_after_chunk));
+                            let stroke_after_link = new_stroke.offset(
+                                (chunk.per_part_length + succ_link.per_part_length).as_usize(),
+                            );
+                            frontier.push((succ_id.to_owned(), stroke_after_link));
                         }
                     }
                 }
@@ -230,8 +327,133 @@ fn get_start_strokes(
     Some(start_strokes)
 }
 
+/// Replace every [`Chunk`] with a separate copy for each [`Stroke`] that its first row can be rung
+/// at (see [`ChunkId::stroke`]), so that every chunk has a single start stroke.  Links are copied
+/// to join the copies with consistent strokes, and each copy is false against every copy of the
+/// chunks that the original was false against.  Returns the start stroke of every new chunk.
+fn split_chunks_by_stroke(
+    chunks: &mut HashMap<ChunkId, Chunk>,
+    links: &mut LinkSet,
+    params: &Parameters,
+) -> HashMap<ChunkId, Stroke> {
+    // Find every (chunk, stroke) pair which can be reached from the start of the composition
+    let stroke_of_start_row = !params.start_stroke; // See `get_start_strokes`
+    let mut reachable = HashSet::<(ChunkId, Stroke)>::new();
+    let mut frontier = Vec::<(ChunkId, Stroke)>::new();
+    for link in links.values() {
+        if let (LinkSide::StartOrEnd, LinkSide::Chunk(id)) = (&link.from, &link.to) {
+            frontier.push((id.clone(), stroke_of_start_row));
+        }
+    }
+    while let Some((id, stroke)) = frontier.pop() {
+        let Some(chunk) = chunks.get(&id) else {
+            continue; // Links to non-existent chunks will be removed anyway
+        };
+        if reachable.insert((id.clone(), stroke)) {
+            for succ_link_id in &chunk.successors {
+                let succ_link = &links[*succ_link_id];
+                if let LinkSide::Chunk(succ_id) = &succ_link.to {
+                    let stroke_after_link = stroke
+                        .offset((chunk.per_part_length + succ_link.per_part_length).as_usize());
+                    frontier.push((succ_id.clone(), stroke_after_link));
+                }
+            }
+        }
+    }
+    let copies_of = |id: &ChunkId| -> Vec<ChunkId> {
+        [Stroke::Hand, Stroke::Back]
+            .into_iter()
+            .filter(|stroke| reachable.contains(&(id.clone(), *stroke)))
+            .map(|stroke| id.with_stroke(stroke))
+            .collect_vec()
+    };
+
+    // Copy the links, so that each one joins copies of chunks whose strokes agree
+    let mut new_links = LinkSet::new();
+    for link in links.values() {
+        let (from, to) = match (&link.from, &link.to) {
+            (LinkSide::StartOrEnd, LinkSide::Chunk(to)) => {
+                new_links.add(Link {
+                    to: LinkSide::Chunk(to.with_stroke(stroke_of_start_row)),
+                    ..link.clone()
+                });
+                continue;
+            }
+            (LinkSide::Chunk(from), to) => (from, to),
+            (LinkSide::StartOrEnd, LinkSide::StartOrEnd) => unreachable!(),
+        };
+        for new_from in copies_of(from) {
+            let new_to = match to {
+                LinkSide::Chunk(to) => {
+                    let stroke_after_link = new_from
+                        .stroke
+                        .unwrap()
+                        .offset((chunks[from].per_part_length + link.per_part_length).as_usize());
+                    LinkSide::Chunk(to.with_stroke(stroke_after_link))
+                }
+                LinkSide::StartOrEnd => LinkSide::StartOrEnd,
+            };
+            new_links.add(Link {
+                from: LinkSide::Chunk(new_from),
+                to: new_to,
+                ..link.clone()
+            });
+        }
+    }
+
+    // Copy the chunks, expanding their falseness to cover every copy of each false chunk
+    let mut new_chunks = HashMap::<ChunkId, Chunk>::with_capacity(reachable.len());
+    let mut start_strokes = HashMap::<ChunkId, Stroke>::with_capacity(reachable.len());
+    for (id, stroke) in &reachable {
+        let chunk = &chunks[id];
+        let new_chunk = Chunk {
+            predecessors: Vec::new(),
+            successors: Vec::new(),
+            false_chunks: chunk.false_chunks.iter().flat_map(copies_of).collect_vec(),
+            ..chunk.clone()
+        };
+        let new_id = id.with_stroke(*stroke);
+        new_chunks.insert(new_id.clone(), new_chunk);
+        start_strokes.insert(new_id, *stroke);
+    }
+    set_succ_pred_links(&mut new_chunks, &new_links);
+
+    log::debug!(
+        "  Split {} chunks into {} to give every chunk a single start stroke",
+        chunks.len(),
+        new_chunks.len()
+    );
+    *chunks = new_chunks;
+    *links = new_links;
+    start_strokes
+}
+
+/// The rows covered by a [`Chunk`] in the part starting with `part_head`
+fn rows_in_part<'params>(
+    id: &ChunkId,
+    per_part_length: PerPartLength,
+    part_head: &Row,
+    method_caches: &MethodVec<MethodCacheData<'params>>,
+    params: &Parameters,
+) -> Block<bellframe::method::RowAnnot<'params>> {
+    let method_cache = &method_caches[id.method];
+    let lead_head_in_part = part_head * id.lead_head.as_ref();
+    let start_row = &lead_head_in_part
+        * method_cache
+            .double_plain_course
+            .get_row(id.sub_lead_idx)
+            .unwrap();
+    let mut rows = Block::empty(params.stage);
+    rows.extend_range(
+        &method_cache.double_plain_course,
+        id.sub_lead_idx..(id.sub_lead_idx + per_part_length.as_usize()),
+    );
+    rows.pre_multiply(&start_row);
+    rows
+}
+
 /// Count the [`Score`] contributed by this [`Chunk`].  This includes both music and course head
-/// weights.
+/// weights.  This also records which of the [`Parameters::required_rows`] the chunk contains.
 fn count_scores(
     id: &ChunkId,
     chunk: &mut Chunk,
@@ -244,6 +466,7 @@ fn count_scores(
     // panic.
     chunk.score = 0.0;
     chunk.music_counts = index_vec::index_vec![AtRowPositions::ZERO; params.music_types.len()];
+    chunk.required_rows = Vec::new();
 
     let start_stroke = match start_strokes {
         Some(map) => match map.get(id) {
@@ -263,18 +486,14 @@ fn count_scores(
 
     for part_head in params.part_head_group.rows() {
         let lead_head_in_part = part_head * id.lead_head.as_ref();
-        let start_row = &lead_head_in_part
-            * method_cache
-                .double_plain_course
-                .get_row(id.sub_lead_idx)
-                .unwrap();
         // Determine the rows that this chunk contains
-        let mut rows = Block::empty(params.stage);
-        rows.extend_range(
-            &method_cache.double_plain_course,
-            id.sub_lead_idx..(id.sub_lead_idx + chunk.per_part_length.as_usize()),
-        );
-        rows.pre_multiply(&start_row);
+        let rows = rows_in_part(id, chunk.per_part_length, part_head, method_caches, params);
+        // Record which required rows are rung in this part
+        for (idx, required_row) in params.required_rows.iter().enumerate() {
+            if !chunk.required_rows.contains(&idx) && rows.rows().any(|r| r == required_row) {
+                chunk.required_rows.push(idx);
+            }
+        }
         // Count weight from music
         for (count_so_far, music_type) in chunk.music_counts.iter_mut().zip_eq(&params.music_types)
         {
@@ -294,6 +513,90 @@ fn count_scores(
                 chunk.score += *weight * chunk.per_part_length.as_usize() as f32;
             }
         }
+        // Count weight from `row_weight`, which applies to every row
+        chunk.score += params.row_weight * chunk.per_part_length.as_usize() as f32;
+    }
+}
+
+/// Count the music which spans a [`Link`] between two chunks (e.g. wrapped runs over a lead end),
+/// or which occurs in the rows rung during the link's call (see [`Link::per_part_length`]).  None
+/// of this is counted by either of the [`Chunk`]s on their own.
+fn count_link_music(
+    link: &mut Link,
+    chunks: &HashMap<ChunkId, Chunk>,
+    method_caches: &MethodVec<MethodCacheData>,
+    start_strokes: &Option<HashMap<ChunkId, Stroke>>,
+    params: &Parameters,
+) {
+    link.music_counts = index_vec::index_vec![AtRowPositions::ZERO; params.music_types.len()];
+
+    let LinkSide::Chunk(id_from) = &link.from else {
+        return; // Start links have no rows before them
+    };
+    let Some(chunk_from) = chunks.get(id_from) else {
+        return; // Link is dangling, so will be removed anyway
+    };
+    // Like in `count_scores`, chunks which are unreachable don't need an exact score
+    let stroke_of_first_row_from = match start_strokes {
+        Some(map) => match map.get(id_from) {
+            Some(stroke) => *stroke,
+            None => return,
+        },
+        None => Stroke::Back,
+    };
+    let last_row_idx_from = chunk_from.per_part_length.as_usize() - 1;
+    let stroke_of_last_row_from = stroke_of_first_row_from.offset(last_row_idx_from);
+
+    // The last row of `id_from` and the first row of `id_to` (if it exists), both in the first
+    // part.  End links have no first row after them, so only count the call's extra rows.
+    let last_row_from = id_from.lead_head.as_ref()
+        * method_caches[id_from.method]
+            .double_plain_course
+            .get_row(id_from.sub_lead_idx + last_row_idx_from)
+            .unwrap();
+    let first_row_to = match &link.to {
+        LinkSide::Chunk(id_to) => {
+            let part_head_to = params
+                .part_head_group
+                .get_row(PartHead::rounds() * link.ph_rotation);
+            let row_in_lead = method_caches[id_to.method]
+                .double_plain_course
+                .get_row(id_to.sub_lead_idx)
+                .unwrap();
+            Some(part_head_to * id_to.lead_head.as_ref() * row_in_lead)
+        }
+        LinkSide::StartOrEnd => None,
+    };
+    let extra_rows = match link.call {
+        Some(call_idx) => params.calls[call_idx]
+            .transposition
+            .extra_rows(&last_row_from),
+        None => SameStageVec::new(params.stage),
+    };
+    if first_row_to.is_none() && extra_rows.is_empty() {
+        return; // End link with no rows to count
+    }
+
+    for part_head in params.part_head_group.rows() {
+        let mut extra_rows_in_part = SameStageVec::with_capacity(params.stage, extra_rows.len());
+        for extra_row in extra_rows.iter() {
+            extra_rows_in_part.push(&(part_head * extra_row));
+        }
+        let mut rows = SameStageVec::with_capacity(params.stage, extra_rows.len() + 2);
+        rows.push(&(part_head * &last_row_from));
+        rows.extend_from_buf(&extra_rows_in_part);
+        if let Some(first_row_to) = &first_row_to {
+            rows.push(&(part_head * first_row_to));
+        }
+        for (count_so_far, music_type) in link.music_counts.iter_mut().zip_eq(&params.music_types) {
+            // Wrapped matches span pairs of rows, so none of them are counted by either chunk
+            count_so_far.wrap += music_type.count(&rows, stroke_of_last_row_from).wrap;
+            // Other matches are only new if they're in the call's extra rows
+            let counts = music_type.count(&extra_rows_in_part, stroke_of_last_row_from.offset(1));
+            count_so_far.front += counts.front;
+            count_so_far.internal += counts.internal;
+            count_so_far.back += counts.back;
+        }
     }
 }
 
@@ -313,6 +616,7 @@ fn check_params(params: &Parameters) -> crate::Result<()> {
         for m2 in &params.methods[..i1] {
             if m1.shorthand() == m2.shorthand() {
                 return Err(crate::Error::DuplicateShorthand {
+                    method_idx: i1,
                     shorthand: m1.shorthand(),
                     title1: m1.title(),
                     title2: m2.title(),
@@ -321,6 +625,26 @@ fn check_params(params: &Parameters) -> crate::Result<()> {
         }
     }
 
+    // Two spliced methods which can never be spliced together (unless the user has explicitly
+    // banned those splices)
+    if params.is_spliced() {
+        for (i1, m1) in params.methods.iter_enumerated() {
+            for m2 in &params.methods[..i1] {
+                let are_splices_banned =
+                    params.is_splice_banned(m1.id, m2.id) && params.is_splice_banned(m2.id, m1.id);
+                if !m1.can_lead_splice_with(m2) && !are_splices_banned {
+                    return Err(crate::Error::IncompatibleSplice {
+                        method_idx: i1,
+                        title1: m2.title(),
+                        lead_head_code1: m2.lead_head_code(),
+                        title2: m1.title(),
+                        lead_head_code2: m1.lead_head_code(),
+                    });
+                }
+            }
+        }
+    }
+
     // Too short calling positions
     for call in &params.calls {
         if call.calling_positions.len() != params.stage.num_bells() {
@@ -337,9 +661,14 @@ fn check_params(params: &Parameters) -> crate::Result<()> {
     for call in &params.calls {
         for lead_label in [&call.label_from, &call.label_to] {
             if !defined_labels.contains(lead_label) {
+                let suggestion = ringing_utils::closest_match(
+                    lead_label,
+                    defined_labels.iter().map(String::as_str),
+                );
                 return Err(crate::Error::UndefinedLabel {
                     call_symbol: call.symbol,
                     label: lead_label.clone(),
+                    suggestion: suggestion.map(str::to_owned),
                 });
             }
         }
@@ -348,6 +677,24 @@ fn check_params(params: &Parameters) -> crate::Result<()> {
     // Two calls with the same name at the same lead location
     check_for_duplicate_call_names(params)?;
 
+    // Calls which don't make sense at some of their labels
+    if params.strict_labels {
+        check_calls_at_labels(params)?;
+    }
+
+    // Restricting duffers without saying which music is non-duffer would make every chunk a duffer
+    let restricts_duffers = params.no_duffers || params.max_duffer_rows.is_some();
+    let can_have_non_duffers =
+        (params.music_types.iter()).any(|ty| ty.can_be_non_duffer(params.non_duffer_music));
+    if restricts_duffers && !can_have_non_duffers {
+        return Err(crate::Error::NoNonDufferMusic);
+    }
+
+    // Falseness is what stops extents from repeating rows
+    if params.extent && !params.rows_are_unique() {
+        return Err(crate::Error::ExtentWithoutTruth);
+    }
+
     // Check which extra course head masks where added while expanding different part heads
     let mut extra_masks = BTreeMap::<Mask, BTreeMap<RowBuf, Vec<MethodIdx>>>::new();
     for (method_idx, method) in params.methods.iter_enumerated() {
@@ -376,20 +723,79 @@ fn check_params(params: &Parameters) -> crate::Result<()> {
     Ok(())
 }
 
-/// Check for two [`Call`]s which assign the same `symbol` at the same `label`.
+/// Check for two [`Call`]s which assign the same `symbol` and can be made in the same method.
+/// Calls can share a symbol if their [`allowed_methods`](Call::allowed_methods) don't overlap,
+/// since it's always clear which one is meant.
 fn check_for_duplicate_call_names(params: &Parameters) -> crate::Result<()> {
-    let sorted_calls = params
-        .calls
-        .iter()
-        .map(|call: &Call| (call.symbol, &call.place_notation))
-        .sorted_by_key(|&(sym, _pn)| sym);
-    for ((sym1, pn1), (sym2, pn2)) in sorted_calls.tuple_windows() {
-        if sym1 == sym2 {
-            return Err(crate::Error::DuplicateCall {
-                symbol: sym1,
-                pn1: pn1.clone(),
-                pn2: pn2.clone(),
-            });
+    let sorted_calls: Vec<&Call> = params.calls.iter().sorted_by_key(|c| c.symbol).collect();
+    for (idx, call1) in sorted_calls.iter().enumerate() {
+        let calls_with_same_symbol = sorted_calls[idx + 1..]
+            .iter()
+            .take_while(|call2| call2.symbol == call1.symbol);
+        for call2 in calls_with_same_symbol {
+            if call1.shares_method_with(call2) {
+                return Err(crate::Error::DuplicateCall {
+                    symbol: call1.symbol,
+                    transposition1: call1.transposition.clone(),
+                    transposition2: call2.transposition.clone(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check that every [`Call`] makes sense at every row labelled with its `label_from`, i.e. that it
+/// doesn't produce the same row as a plain lead and that it leaves every hunt bell where it would
+/// be at one of the rows labelled with `label_to` (in any method).
+fn check_calls_at_labels(params: &Parameters) -> crate::Result<()> {
+    for (method_idx, method) in params.methods.iter_enumerated() {
+        let lead_len = method.lead_len();
+        let hunt_bells = method.lead_head().fixed_bells().collect_vec();
+        for call in &params.calls {
+            if call.transposition.stage() != method.stage() {
+                continue; // Calls can't be placed in methods of a different stage
+            }
+            if !call.can_be_made_in(method.id) {
+                continue;
+            }
+            for sub_lead_idx in method.label_indices(&call.label_from) {
+                let idx_before_call = (sub_lead_idx + lead_len - 1) % lead_len;
+                let row_before_call = method.first_lead().get_row(idx_before_call).unwrap();
+                let row_after_plain = method.first_lead().get_row(idx_before_call + 1).unwrap();
+                let row_after_call = row_before_call * call.transposition.transposition();
+
+                let is_single_change = call.transposition.num_extra_rows() == 0;
+                if call.label_from == call.label_to
+                    && is_single_change
+                    && &*row_after_call == row_after_plain
+                {
+                    return Err(crate::Error::CallSameAsPlain {
+                        method_idx,
+                        call_symbol: call.symbol,
+                        label: call.label_from.clone(),
+                        method_title: method.title(),
+                        sub_lead_idx,
+                    });
+                }
+                // Find a hunt bell which isn't where it would be at any `label_to` (which could be
+                // in a different method)
+                let moved_hunt_bell = hunt_bells.iter().copied().find(|&bell| {
+                    !params
+                        .rows_at_label(&call.label_to, method.stage())
+                        .any(|row| row.place_of(bell) == row_after_call.place_of(bell))
+                });
+                if let Some(bell) = moved_hunt_bell {
+                    return Err(crate::Error::CallMovesHuntBell {
+                        method_idx,
+                        call_symbol: call.symbol,
+                        label: call.label_from.clone(),
+                        method_title: method.title(),
+                        sub_lead_idx,
+                        bell,
+                    });
+                }
+            }
         }
     }
     Ok(())
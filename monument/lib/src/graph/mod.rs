@@ -3,9 +3,15 @@
 //! This implements routines for creating and optimising such graphs, in preparation for performing
 //! tree search.
 
+mod artifact;
 mod build;
 mod optimise;
 
+pub use artifact::BuiltGraph;
+
+pub(crate) use artifact::params_hash;
+pub(crate) use build::{false_course_head_groups, mutual_false_course_heads, FalsenessCache};
+
 use std::{
     collections::{HashMap, HashSet},
     fmt::{Debug, Display, Formatter},
@@ -13,12 +19,13 @@ use std::{
     sync::Arc,
 };
 
-use bellframe::{music::AtRowPositions, Row};
+use bellframe::{music::AtRowPositions, Row, Stroke};
 use datasize::DataSize;
+use itertools::Itertools;
 
 use crate::{
     group::PhRotation,
-    parameters::{CallIdx, MethodIdx, MusicTypeVec},
+    parameters::{CallIdx, MethodIdx, MusicTypeVec, Parameters},
     utils::{
         counts::Counts,
         lengths::{PerPartLength, TotalLength},
@@ -57,6 +64,10 @@ pub struct Graph {
     call_sequence_length: usize,
     /// Exactly one chunk in each of these sets must be rung
     required_chunk_sets: HashSet<Vec<ChunkId>>,
+    /// For [`Parameters::extent`](crate::Parameters::extent) searches, the chunks containing
+    /// each row (rows contained by the same chunks share a set).  These are also included in
+    /// `required_chunk_sets`, but are kept separately because the search can use them to prune
+    pub(crate) extent_chunk_sets: Vec<Vec<ChunkId>>,
 }
 
 /// A `Chunk` in a chunk [`Graph`].  This is an indivisible chunk of ringing which cannot be split
@@ -82,6 +93,9 @@ pub(crate) struct Chunk {
     pub(crate) score: f32,
     /// The music generated by this chunk in the composition.  Optimisation passes can't change this
     pub(crate) music_counts: MusicTypeVec<AtRowPositions<usize>>,
+    /// The indices of the [`Parameters::required_rows`](crate::Parameters::required_rows) which
+    /// are rung by this chunk (in any part).  Optimisation passes can't change this
+    pub(crate) required_rows: Vec<usize>,
 
     /// A lower bound on the number of rows required to go from any rounds to the first row of
     /// `self`
@@ -105,6 +119,20 @@ pub(crate) struct Link {
     /// following.  For example, if Monument is generating "HHsWsHsW", then the first "H" will have
     /// `sequence_number = 0`, the second "H" will have `1`, the "sW" will have 2, etc.
     pub call_sequence_idx: Option<CallSeqIdx>,
+
+    /// The number of rows rung during this link's call (in one part), which aren't contained in
+    /// either [`Chunk`].  This is only non-zero for calls which cover more than one change (see
+    /// [`CallTransposition::Block`](crate::parameters::CallTransposition::Block)).  Optimisation
+    /// passes can't change this
+    pub per_part_length: PerPartLength,
+    /// The number of rows that this link adds to the composition (its total length across all
+    /// parts).  Optimisation passes can't change this
+    pub total_length: TotalLength,
+    /// The music which spans this link (i.e. which matches over the last row of `from` and the
+    /// first row of `to`) or which is in the rows rung during the link's call, summed over all
+    /// parts.  This isn't counted by either [`Chunk`].  Always zero for start links.  Optimisation
+    /// passes can't change this
+    pub music_counts: MusicTypeVec<AtRowPositions<usize>>,
 }
 
 impl Link {
@@ -115,6 +143,30 @@ impl Link {
     pub fn is_end(&self) -> bool {
         self.to.is_start_or_end()
     }
+
+    /// Gets the total score generated by this `Link`, given the [`Chunk`] that it comes from.  For
+    /// end links, this **doesn't** include the score from splices over the part end.
+    pub fn score(&self, source_chunk: &Chunk, params: &Parameters) -> f32 {
+        let splice_weight = match (&self.from, &self.to) {
+            (LinkSide::Chunk(c1), LinkSide::Chunk(c2))
+                if params.is_splice(c1, source_chunk.per_part_length, c2) =>
+            {
+                let method_id = |c: &ChunkId| params.methods[c.method].id;
+                params.splice_weight_between(method_id(c1), method_id(c2))
+            }
+            // Continuations aren't splices, and neither are starts or ends
+            _ => 0.0,
+        };
+        let call_weight = match self.call {
+            Some(idx) => params.calls[idx].weight,
+            None => 0.0, // Plain leads have no weight
+        };
+        let music_score = (params.music_types.iter().zip_eq(&self.music_counts))
+            .map(|(music_type, counts)| music_type.as_overall_score(*counts))
+            .sum::<f32>();
+        let row_score = params.row_weight * self.total_length.as_usize() as f32;
+        (call_weight + splice_weight) * params.num_parts() as f32 + music_score + row_score
+    }
 }
 
 /// What a `Link` points to.  This is either a [`StartOrEnd`](Self::StartOrEnd), or a specific
@@ -148,6 +200,18 @@ impl<Id> LinkSide<Id> {
 // ------------------------------------------------------------------------------------------
 
 impl Chunk {
+    /// Returns `true` if this chunk doesn't contain any music which makes it a non-duffer (see
+    /// [`Parameters::non_duffer_music`])
+    pub(crate) fn is_duffer(&self, params: &Parameters) -> bool {
+        !params
+            .music_types
+            .iter()
+            .zip_eq(&self.music_counts)
+            .any(|(music_type, counts)| {
+                music_type.makes_non_duffer(*counts, params.non_duffer_music)
+            })
+    }
+
     /// An [`Iterator`] over only valid predecessor [`Link`]s
     pub(crate) fn pred_links<'g>(
         &'g self,
@@ -169,6 +233,78 @@ impl Chunk {
     }
 }
 
+///////////
+// STATS //
+///////////
+
+/// Statistics about the size and shape of the chunk graph used by a [`Search`](crate::Search),
+/// taken after the graph has been optimised.  These give a rough idea of how hard a search will
+/// be, and can be used to compare different queries.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GraphStats {
+    pub num_chunks: usize,
+    pub num_links: usize,
+    /// The number of ways that a composition could start
+    pub num_starts: usize,
+    /// The number of ways that a composition could end
+    pub num_ends: usize,
+    /// The number of (unordered) pairs of distinct chunks which share rows
+    pub num_falseness_edges: usize,
+    /// The average number of successor links of each chunk
+    pub avg_successors: f32,
+    /// An estimate of the number of choices the search has to make after each chunk.  This is the
+    /// average number of successors of each chunk which lead to another chunk that isn't false
+    /// against it.
+    pub branching_factor: f32,
+    /// The highest score of any chunk, including the score of its best successor link.  If this
+    /// is negative, then every extra chunk makes a composition worse and the search will favour
+    /// the shortest compositions it can find.
+    pub max_chunk_score: f32,
+}
+
+impl Graph {
+    pub(crate) fn stats(&self, params: &Parameters) -> GraphStats {
+        let mut num_links = 0;
+        let mut num_branches = 0;
+        let mut num_falseness_edges = 0;
+        let mut max_chunk_score = f32::NEG_INFINITY;
+        for (id, chunk) in &self.chunks {
+            let mut best_link_score = f32::NEG_INFINITY;
+            for (_link_id, link) in chunk.succ_links(self) {
+                num_links += 1;
+                best_link_score = best_link_score.max(link.score(chunk, params));
+                if let LinkSide::Chunk(succ_id) = &link.to {
+                    if self.chunks.contains_key(succ_id) && !chunk.false_chunks.contains(succ_id) {
+                        num_branches += 1;
+                    }
+                }
+            }
+            // Falseness is symmetric, so only count each pair from its smallest chunk
+            num_falseness_edges += chunk
+                .false_chunks
+                .iter()
+                .filter(|false_id| *false_id > id && self.chunks.contains_key(false_id))
+                .count();
+            max_chunk_score = max_chunk_score.max(chunk.score + best_link_score);
+        }
+
+        let per_chunk = |count: usize| match self.chunks.len() {
+            0 => 0.0,
+            num_chunks => count as f32 / num_chunks as f32,
+        };
+        GraphStats {
+            num_chunks: self.chunks.len(),
+            num_links,
+            num_starts: self.starts.len(),
+            num_ends: self.ends.len(),
+            num_falseness_edges,
+            avg_successors: per_chunk(num_links),
+            branching_factor: per_chunk(num_branches),
+            max_chunk_score,
+        }
+    }
+}
+
 ////////////////////////
 // UTILITY DATA TYPES //
 ////////////////////////
@@ -178,11 +314,27 @@ impl Chunk {
 pub(crate) struct ChunkId {
     pub lead_head: Arc<Row>, // `Arc` is used to make cloning cheaper
     pub row_idx: RowIdx,
+    /// If the same rows can be reached on different strokes (e.g. because of odd-length leads)
+    /// and the music depends on stroke, then a separate chunk is made for each stroke of the
+    /// chunk's first row.  Otherwise, this is `None`.
+    pub stroke: Option<Stroke>,
 }
 
 impl ChunkId {
     pub fn new(lead_head: Arc<Row>, row_idx: RowIdx) -> Self {
-        Self { lead_head, row_idx }
+        Self {
+            lead_head,
+            row_idx,
+            stroke: None,
+        }
+    }
+
+    /// Returns a copy of `self` whose first row is at the given [`Stroke`]
+    pub fn with_stroke(&self, stroke: Stroke) -> Self {
+        Self {
+            stroke: Some(stroke),
+            ..self.clone()
+        }
     }
 }
 
@@ -207,6 +359,9 @@ impl Display for ChunkId {
             "{},{:?}:{}",
             self.lead_head, self.method, self.sub_lead_idx,
         )?;
+        if let Some(stroke) = self.stroke {
+            write!(f, ",{stroke:?}")?;
+        }
         Ok(())
     }
 }
@@ -290,6 +445,10 @@ mod link_set {
             self.map.values()
         }
 
+        pub fn values_mut(&mut self) -> std::collections::hash_map::ValuesMut<'_, LinkId, Link> {
+            self.map.values_mut()
+        }
+
         /// Remove any [`Link`]s from `self` which don't satisfy a given predicate
         pub fn retain(&mut self, mut pred: impl FnMut(LinkId, &mut Link) -> bool) {
             self.map.retain(|id, link| pred(*id, link))
@@ -5,13 +5,12 @@ use std::{
     collections::{BinaryHeap, HashMap, HashSet},
     fmt::Debug,
     ops::Not,
-    time::Instant,
 };
 
 use crate::{
     parameters::Parameters,
     prove_length::RefinedRanges,
-    utils::{lengths::TotalLength, FrontierItem},
+    utils::{lengths::TotalLength, time::Instant, FrontierItem},
 };
 
 use super::{Chunk, ChunkId, Graph, Link, LinkId, LinkSide};
@@ -36,16 +35,26 @@ impl Graph {
             passes::strip_long_chunks(self, params);
             // Required chunk optimisation
             self.run_bidirectional_pass(passes::mark_start_or_ends_as_required);
-            passes::remove_chunks_false_against_required(self);
+            if params.rows_are_unique() {
+                passes::remove_chunks_false_against_required(self);
+            }
             // Required calls
             self.run_bidirectional_pass(|view| {
                 passes::remove_links_conflicting_with_calling(view, params)
             });
             // Misc optimisations
-            passes::remove_links_between_false_chunks(self);
+            if params.rows_are_unique() {
+                passes::remove_links_between_false_chunks(self);
+            }
             passes::remove_chunks_with_long_method_counts(self, ranges);
             passes::remove_links_with_long_method_counts(self, ranges);
             passes::remove_chunks_which_exceed_music_limits(self, params);
+            if params.no_duffers {
+                passes::remove_duffer_chunks(self, params);
+            }
+            if params.prune_dominated_chunks {
+                passes::remove_dominated_chunks(self, params, ranges);
+            }
             // Cleanup
             passes::remove_dangling_refs(self);
 
@@ -212,6 +221,170 @@ mod passes {
         });
     }
 
+    /// Remove any chunk which is a duffer (see [`Chunk::is_duffer`](super::Chunk::is_duffer))
+    pub(super) fn remove_duffer_chunks(graph: &mut Graph, params: &Parameters) {
+        graph.chunks.retain(|_id, chunk| !chunk.is_duffer(params));
+    }
+
+    /* Dominance */
+
+    /// Remove any chunk which is 'dominated' by another chunk.  Chunk `a` dominates chunk `b` if
+    /// `a` can replace `b` in any composition (i.e. they have the same length, method counts and
+    /// neighbouring chunks, and `a` is false against a subset of what `b` is false against), and
+    /// doing so always makes a strictly better composition.  Thus `b` can never appear in an
+    /// optimal composition.
+    pub(super) fn remove_dominated_chunks(
+        graph: &mut Graph,
+        params: &Parameters,
+        ranges: &RefinedRanges,
+    ) {
+        // Which rows are rung by a chunk affects the atw score in ways that `Chunk::score` can't
+        // capture, so we can't compare chunks if atw is being considered
+        if params.atw_weight.is_some() || params.require_atw {
+            return;
+        }
+
+        // If no method count is restricted, then chunks of different methods are interchangeable
+        // (e.g. leads of Cambridge and Yorkshire starting at the same lead head)
+        let method_counts_matter = ranges
+            .method_counts
+            .iter()
+            .any(|range| *range.start() > TotalLength::ZERO || *range.end() < *ranges.length.end());
+
+        // Group chunks by their length, method counts and neighbours.  Only chunks in the same
+        // group can dominate each other
+        let mut groups = HashMap::<_, Vec<&ChunkId>>::new();
+        for (id, chunk) in &graph.chunks {
+            let neighbours = |links: &[LinkId], side: fn(&Link) -> &LinkSide<ChunkId>| {
+                links
+                    .iter()
+                    .filter_map(|link_id| graph.links.get(*link_id))
+                    .map(|link| side(link).clone())
+                    .sorted()
+                    .dedup()
+                    .collect_vec()
+            };
+            let key = (
+                chunk.per_part_length,
+                method_counts_matter.then(|| chunk.method_counts.clone()),
+                neighbours(&chunk.predecessors, |link| &link.from),
+                neighbours(&chunk.successors, |link| &link.to),
+            );
+            groups.entry(key).or_default().push(id);
+        }
+
+        // Find dominated chunks.  Dominance is transitive and a chunk can't dominate itself, so
+        // at least one chunk in every group will remain
+        let required_chunks = graph
+            .required_chunk_sets
+            .iter()
+            .flatten()
+            .collect::<HashSet<_>>();
+        let mut dominated_chunks = HashSet::<ChunkId>::new();
+        for ids in groups.values().filter(|ids| ids.len() > 1) {
+            for &id_b in ids {
+                if required_chunks.contains(id_b) {
+                    continue; // Never remove required chunks
+                }
+                if ids
+                    .iter()
+                    .any(|&id_a| id_a != id_b && dominates(graph, params, id_a, id_b))
+                {
+                    dominated_chunks.insert(id_b.clone());
+                }
+            }
+        }
+
+        graph
+            .chunks
+            .retain(|id, _chunk| !dominated_chunks.contains(id));
+    }
+
+    /// Returns `true` if chunk `id_a` dominates chunk `id_b`, assuming that both chunks have the
+    /// same length, neighbouring chunks and (if they matter) method counts.
+    fn dominates(graph: &Graph, params: &Parameters, id_a: &ChunkId, id_b: &ChunkId) -> bool {
+        let chunk_a = &graph.chunks[id_a];
+        let chunk_b = &graph.chunks[id_b];
+
+        // `a` must be strictly better than `b`...
+        if chunk_a.score <= chunk_b.score {
+            return false;
+        }
+        // ... without changing the counts of any music which has a required count range ...
+        for ((music_type, counts_a), counts_b) in params
+            .music_types
+            .iter()
+            .zip_eq(&chunk_a.music_counts)
+            .zip_eq(&chunk_b.music_counts)
+        {
+            if music_type.count_range.is_set()
+                && music_type.masked_total(*counts_a) != music_type.masked_total(*counts_b)
+            {
+                return false;
+            }
+        }
+        // ... and `a` can't be false against anything that `b` isn't also false against
+        let is_false_against_b =
+            |id: &ChunkId| id == id_a || id == id_b || chunk_b.false_chunks.contains(id);
+        if !chunk_a.false_chunks.iter().all(is_false_against_b) {
+            return false;
+        }
+
+        // Replacing `b` with `a` must also not make the calls or splices any worse.  Every link to
+        // or from `b` must have an equivalent link for `a` with at least as good a score.
+        let link_weights = |chunk: &Chunk, links: &[LinkId]| {
+            let mut weights = HashMap::<_, f32>::new();
+            for link in links.iter().filter_map(|link_id| graph.links.get(*link_id)) {
+                let source_chunk = match &link.from {
+                    LinkSide::Chunk(id) => match graph.chunks.get(id) {
+                        Some(source_chunk) => source_chunk,
+                        None => continue, // Link is dangling, so will be removed anyway
+                    },
+                    // Start links are never splices, so any chunk will do
+                    LinkSide::StartOrEnd => chunk,
+                };
+                let key = (
+                    link.from.clone(),
+                    link.to.clone(),
+                    link.ph_rotation,
+                    link.call_sequence_idx,
+                    link.total_length,
+                );
+                let weight = link.score(source_chunk, params);
+                let best_weight = weights.entry(key).or_insert(f32::NEG_INFINITY);
+                *best_weight = best_weight.max(weight);
+            }
+            weights
+        };
+        let swap_links_to_a = |weights: HashMap<_, f32>| {
+            weights
+                .into_iter()
+                .map(|((from, to, ph_rotation, seq_idx, length), weight)| {
+                    let swap = |side: LinkSide<ChunkId>| match side {
+                        LinkSide::Chunk(id) if id == *id_b => LinkSide::Chunk(id_a.clone()),
+                        side => side,
+                    };
+                    ((swap(from), swap(to), ph_rotation, seq_idx, length), weight)
+                })
+                .collect::<HashMap<_, _>>()
+        };
+        for (links_a, links_b) in [
+            (&chunk_a.predecessors, &chunk_b.predecessors),
+            (&chunk_a.successors, &chunk_b.successors),
+        ] {
+            let weights_a = link_weights(chunk_a, links_a);
+            let weights_b = swap_links_to_a(link_weights(chunk_b, links_b));
+            for (key, weight_b) in weights_b {
+                match weights_a.get(&key) {
+                    Some(weight_a) if *weight_a >= weight_b => {}
+                    _ => return false, // `a` has no equivalent link, or the link is worse
+                }
+            }
+        }
+
+        true
+    }
+
     /* Distance related passes */
 
     /// Recomputes the distance to/from rounds for every chunk, and removes any chunks which can't
@@ -306,7 +479,7 @@ mod passes {
         view: DirectionalView,
         params: &Parameters,
     ) {
-        if !(params.require_truth && params.calling.is_some() && !params.omit_round_blocks) {
+        if !(params.rows_are_unique() && params.calling.is_some() && !params.omit_round_blocks) {
             return;
         }
 
@@ -604,7 +777,7 @@ fn compute_distances<'a>(
             };
             let new_frontier_item = FrontierItem {
                 item: next_chunk_id,
-                distance: distance_after_chunk,
+                distance: distance_after_chunk + succ_link.link.total_length,
             };
             frontier.push(Reverse(new_frontier_item));
         }
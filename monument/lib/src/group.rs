@@ -19,14 +19,14 @@ pub struct PartHeadGroup {
 }
 
 /// A compact representation of a single `PartHead` within a [`PartHeadGroup`]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, DataSize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, DataSize)]
 pub struct PartHead {
     /// The index into the owning [`PartHeadGroup`]'s `part_heads` list.
     index: u8,
 }
 
 /// A compact representation of a transformation between two `PartHead`s
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct PhRotation {
     /// The index into the owning [`PartHeadGroup`]'s `part_heads` list.
     rotation: u8,
@@ -153,6 +153,11 @@ impl PhRotation {
     pub fn is_identity(self) -> bool {
         self.rotation == 0
     }
+
+    /// The index of this rotation within [`PartHeadGroup::rotations`]
+    pub fn index(self) -> usize {
+        self.rotation as usize
+    }
 }
 
 impl Not for PhRotation {
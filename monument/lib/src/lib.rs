@@ -42,16 +42,25 @@
 #![allow(clippy::result_large_err)]
 
 pub mod composition;
+pub mod display;
 mod error;
 mod graph;
 mod group;
 pub mod parameters;
 mod prove_length;
 mod search;
+mod session;
+#[cfg(test)]
+mod test_utils;
 pub mod utils;
 
 pub use composition::Composition;
-pub use error::{Error, Result};
+pub use error::{Error, ErrorCode, ErrorLocation, Result};
+pub use graph::{BuiltGraph, GraphStats};
 pub use group::{PartHead, PartHeadGroup, PhRotation};
 pub use parameters::Parameters;
-pub use search::{Config, Progress, Search, Update};
+pub use search::{
+    BuildProgress, Config, NoCompsReason, Progress, Prune, Rejection, Search, SearchStats,
+    TruncationPolicy, Update,
+};
+pub use session::{Query, QueryResult, Session};
@@ -2,21 +2,25 @@
 
 use std::{
     collections::{HashMap, HashSet},
-    fmt::Write,
+    fmt::{Debug, Formatter, Write},
     ops::{Deref, Range, RangeInclusive},
+    sync::Arc,
 };
 
 use bellframe::{
     method::LABEL_LEAD_END,
     music::{AtRowPositions, RowPosition},
-    Bell, Mask, PlaceNot, Row, RowBuf, Stage, Stroke,
+    Bell, Mask, PlaceNot, PnBlock, Row, RowBuf, SameStageVec, Stage, Stroke,
 };
 use itertools::Itertools;
+use regex::Regex;
 
 use crate::{
+    composition::CompositionValues,
     graph::{CallSeqVec, ChunkId},
     group::PartHeadGroup,
     utils::{
+        leads_string,
         lengths::{PerPartLength, TotalLength},
         Boundary, IdGenerator,
     },
@@ -37,14 +41,75 @@ pub struct Parameters {
     pub stage: Stage,
     pub num_comps: usize,
     pub require_truth: bool,
+    /// If `require_truth` is set, the maximum number of times that any row can be rung.  `1`
+    /// gives true compositions, whereas (for example) `2` allows compositions where no row is rung
+    /// more than twice.
+    ///
+    /// Values above `1` are checked conservatively: a chunk can't be used once it shares rows
+    /// with `max_row_repeats` chunks of the composition, even if those chunks overlap it on
+    /// different rows.  So the search can miss compositions which satisfy this limit.
+    pub max_row_repeats: usize,
+    /// If `true`, only extents are generated: every row of the [`Stage`] must be rung exactly
+    /// once.  Knowing this, Monument abandons any prefix which makes some row impossible to reach,
+    /// which prunes far more than falseness checking alone.  `length` should contain the number
+    /// of rows in the extent, and `require_truth` must be `true`.
+    pub extent: bool,
+    /// If `true`, remove every chunk which can't appear in an optimal composition, because
+    /// another chunk with the same neighbours is strictly better.  This can massively shrink
+    /// large graphs (e.g. for tenors-split searches), but means that only the best of two
+    /// otherwise interchangeable compositions will be generated.
+    pub prune_dominated_chunks: bool,
+    /// If set, compositions with a lower total score aren't generated (and don't count towards
+    /// `num_comps`).
+    pub min_total_score: Option<f32>,
+    /// If set, compositions with a lower music score aren't generated (and don't count towards
+    /// `num_comps`).
+    pub min_music_score: Option<f32>,
+    /// If set, compositions with more calls (over every part) aren't generated.
+    pub max_calls: Option<usize>,
+    /// If set, compositions with a lower [`conductability`](crate::composition::Difficulty::conductability)
+    /// aren't generated.
+    pub min_conductability: Option<f32>,
+    /// If set, the search doesn't stop after finding [`Parameters::num_comps`] compositions.
+    /// Instead, it keeps searching until it runs out of compositions (or is aborted), keeping a
+    /// shortlist of the best `num_comps` compositions according to this metric.  Compositions
+    /// are only generated if they'd make it onto the shortlist, but may later be pushed off it by
+    /// better compositions.
+    pub shortlist: Option<ShortlistMetric>,
+    /// The key used to compare compositions.  The search explores the partial compositions which
+    /// rank highest first, and [`Ranking::value`] can be used to rank the final compositions.
+    pub ranking: Ranking,
+    /// Score given to every row of a composition (over every part).  Positive values favour
+    /// longer compositions, and negative values favour shorter ones.
+    pub row_weight: f32,
+    /// If set, compositions are given a bonus depending on how close their length is to a
+    /// target length.
+    pub target_length: Option<TargetLength>,
 
     // METHODS & CALLING
     pub methods: MethodVec<Method>,
     pub splice_style: SpliceStyle,
     pub splice_weight: f32,
+    /// Scores which replace `splice_weight` for splices from one method to another.  A weight of
+    /// [`f32::NEG_INFINITY`] bans that splice, so it's never generated.
+    pub splice_weights: HashMap<(MethodId, MethodId), f32>,
+    /// If `true`, method counts are reported in leads of each method, rather than in rows
+    pub method_counts_in_leads: bool,
     pub calls: CallVec<Call>,
     pub call_display_style: CallDisplayStyle, // TODO: Make this defined per-method?
     pub calling_bell: Bell,
+    /// Renames applied to the calling positions of every [`Call`], both when displaying and
+    /// parsing compositions (e.g. `'B' -> 'O'` or any ring-specific conventions).
+    pub calling_position_names: HashMap<char, char>,
+    /// If set, calls can only be made at these calling positions (after applying
+    /// [`calling_position_names`](Self::calling_position_names)), e.g. `['W', 'M', 'H']`.  Calls
+    /// at any other position are never added to the graph.
+    pub allowed_calling_positions: Option<Vec<char>>,
+    pub multipart_call_display: MultipartCallDisplay,
+    /// If `true`, check that every [`Call`] makes sense at every row labelled with its
+    /// `label_from`, i.e. that it changes something compared to a plain lead and doesn't move
+    /// any hunt bells.
+    pub strict_labels: bool,
     pub atw_weight: Option<f32>,
     pub require_atw: bool, // `true` to make Monument only output atw comps
 
@@ -57,6 +122,10 @@ pub struct Parameters {
     /// Score applied to every row in every course containing a lead head matching the
     /// corresponding [`Mask`].
     pub course_weights: Vec<(Mask, f32)>,
+    /// If `true`, compositions can't leave a course and come back to it later, so every course
+    /// (up to part head transposition) is rung in one continuous block.  The course containing
+    /// the start of the composition may still be returned to in order to finish it.
+    pub no_repeated_courses: bool,
     /// If set, force Monument to stick to a specific calling.  Useful for fitting methods to a
     /// known good calling.
     pub calling: Option<String>,
@@ -64,9 +133,26 @@ pub struct Parameters {
     /// blocks in the input calling (for example, if this is set to `true` then a calling of
     /// "WWWHHH" would generate "", "WWW", "HHH" and "WWWHHH").
     pub omit_round_blocks: bool,
+    /// Compositions are only generated if their call string passes every one of these filters.
+    /// These are checked as each composition is found, so rejected compositions don't count
+    /// towards [`Parameters::num_comps`].
+    pub calling_filters: Vec<CallingFilter>,
 
     // MUSIC
     pub music_types: MusicTypeVec<MusicType>,
+    /// If `true`, every chunk which doesn't contain any music from a
+    /// [`non_duffer`](MusicType::non_duffer) [`MusicType`] is removed before the search starts,
+    /// so every part of every composition contains some non-duffer music.
+    pub no_duffers: bool,
+    /// If set, compositions can't contain runs of more than this many consecutive rows in chunks
+    /// without any [`non_duffer`](MusicType::non_duffer) music.  Runs which continue over part
+    /// ends are counted as a single run.
+    pub max_duffer_rows: Option<usize>,
+    /// Which music stops a chunk from being a duffer
+    pub non_duffer_music: NonDufferMusic,
+    /// Rows which must each appear somewhere in every composition (e.g. Queens or Whittingtons).
+    /// In multi-part compositions, a row may be rung in any part.
+    pub required_rows: Vec<RowBuf>,
     /// The [`Stroke`] of the first [`Row`] in the composition that isn't `self.start_row`
     // TODO: Compute this automatically from sub-lead index
     pub start_stroke: Stroke,
@@ -77,6 +163,12 @@ impl Parameters {
         *self.length.end()
     }
 
+    /// `true` if no row can be rung more than once, i.e. if chunks which share rows can never
+    /// both be rung
+    pub fn rows_are_unique(&self) -> bool {
+        self.require_truth && self.max_row_repeats <= 1
+    }
+
     pub fn is_spliced(&self) -> bool {
         self.methods.len() > 1
     }
@@ -132,6 +224,30 @@ impl Parameters {
         defined_labels
     }
 
+    /// The rows in the plain leads of every method (of a given [`Stage`]) which are labelled with
+    /// `label`.  These are all the rows which a call with that `label_to` can lead to, which may be
+    /// in a different method to the call itself.
+    pub(crate) fn rows_at_label<'s>(
+        &'s self,
+        label: &'s str,
+        stage: Stage,
+    ) -> impl Iterator<Item = &'s Row> + 's {
+        self.methods
+            .iter()
+            .filter(move |m| m.stage() == stage)
+            .flat_map(move |m| m.label_indices(label).map(|idx| m.row_in_plain_lead(idx)))
+    }
+
+    /// The score applied to every splice from method `from` to method `to`
+    pub fn splice_weight_between(&self, from: MethodId, to: MethodId) -> f32 {
+        (self.splice_weights.get(&(from, to)).copied()).unwrap_or(self.splice_weight)
+    }
+
+    /// Returns `true` if splices from method `from` to method `to` can never be rung
+    pub fn is_splice_banned(&self, from: MethodId, to: MethodId) -> bool {
+        self.splice_weight_between(from, to) == f32::NEG_INFINITY
+    }
+
     pub fn method_id_to_idx(&self, id: MethodId) -> MethodIdx {
         self.methods.position(|m| m.id == id).unwrap()
     }
@@ -148,30 +264,194 @@ impl Parameters {
         &self.calls[self.call_id_to_idx(id)]
     }
 
+    /// The [`Bell`]s which are at home in every course head of a method: the
+    /// [fixed bells](Self::fixed_bells), plus any bells which are fixed by all of the method's
+    /// allowed courses (e.g. the tenors for `courses = ["*78"]`).
+    pub fn course_head_fixed_bells(&self, method_idx: MethodIdx) -> Vec<Bell> {
+        let method = &self.methods[method_idx];
+        let fixed_bells = self.fixed_bells();
+        let course_masks = method
+            .allowed_courses
+            .iter()
+            .flat_map(|c| c.as_course_masks(method, &fixed_bells))
+            .collect_vec();
+        self.stage
+            .bells()
+            .filter(|b| {
+                self.is_fixed_bell(*b)
+                    || (!course_masks.is_empty()
+                        && course_masks
+                            .iter()
+                            .all(|m| m.place_of(*b) == Some(b.index())))
+            })
+            .collect_vec()
+    }
+
+    /// Computes the false course heads of a method, where every course head has all of
+    /// `fixed_bells` at home (usually these are the
+    /// [course head fixed bells](Self::course_head_fixed_bells)).  These are grouped into false
+    /// course head groups, whose members are always false together; two course heads are in the
+    /// same group if they're inverses or related by the symmetry of the method's lead heads.  This
+    /// returns no groups if the method's plain course is clean proof (i.e. true against every other
+    /// course).
+    pub fn false_course_head_groups(
+        &self,
+        method_idx: MethodIdx,
+        fixed_bells: &[Bell],
+    ) -> Vec<Vec<RowBuf>> {
+        crate::graph::false_course_head_groups(&self.methods[method_idx], fixed_bells)
+    }
+
+    /// Computes the course heads (with all of `fixed_bells` at home) of the courses of `method2`
+    /// which contain a lead that's false against a lead of `method1`'s plain course.  Leads
+    /// starting at the same row are ignored, since only one of them can be rung.  This includes
+    /// rounds if some lead of one method is false against a different lead of the other in the same
+    /// course, in which case the two methods can't be freely spliced.
+    pub fn mutual_false_course_heads(
+        &self,
+        method1: MethodIdx,
+        method2: MethodIdx,
+        fixed_bells: &[Bell],
+    ) -> Vec<RowBuf> {
+        crate::graph::mutual_false_course_heads(
+            &self.methods[method1],
+            &self.methods[method2],
+            fixed_bells,
+        )
+    }
+
+    /// Returns a copy of `self`, but where every value which only affects the score of a
+    /// composition (music, course weights, splice/atw weights and call weights) is taken from
+    /// `weights`.  Calls are matched up by their `symbol`; any call who's symbol doesn't appear in
+    /// `weights` keeps its original weight.  Similarly, methods are matched up by their shorthand
+    /// when copying the weights of splices between specific methods.
+    pub fn with_weights_from(&self, weights: &Parameters) -> Parameters {
+        let mut params = self.clone();
+        params.music_types = weights.music_types.clone();
+        params.course_weights = weights.course_weights.clone();
+        params.splice_weight = weights.splice_weight;
+        let method_id_in_self = |weight_method_id: MethodId| -> Option<MethodId> {
+            let shorthand = weights.get_method_by_id(weight_method_id).shorthand();
+            let method = self.methods.iter().find(|m| m.shorthand() == shorthand)?;
+            Some(method.id)
+        };
+        params.splice_weights = (weights.splice_weights.iter())
+            .filter_map(|(&(from, to), &weight)| {
+                Some(((method_id_in_self(from)?, method_id_in_self(to)?), weight))
+            })
+            .collect();
+        params.atw_weight = weights.atw_weight;
+        params.row_weight = weights.row_weight;
+        params.target_length = weights.target_length.clone();
+        for call in &mut params.calls {
+            if let Some(weight_call) = weights.calls.iter().find(|c| c.symbol == call.symbol) {
+                call.weight = weight_call.weight;
+            }
+        }
+        params
+    }
+
     //////////////////////
     // HELPER FUNCTIONS //
     //////////////////////
 
+    /// Returns `true` if going from the end of the chunk `from` (of length `from_length`) to the
+    /// start of the chunk `to` would be considered a 'splice'.  This happens iff `to`'s row
+    /// doesn't directly follow from `from`'s.  For example:
+    /// - (Bristol, 16) -> (Bristol, 17)   isn't a splice
+    /// - (Bristol, 31) -> (Bristol, 0)    isn't a splice (it wraps round the lead end)
+    /// - (Bristol, 31) -> (Cambridge, 0)  **is** a splice (method changes)
+    /// - (Bristol, 17) -> (Bristol, 0)    **is** a splice (it skips half a lead)
+    pub(crate) fn is_splice(
+        &self,
+        from: &ChunkId,
+        from_length: PerPartLength,
+        to: &ChunkId,
+    ) -> bool {
+        let sub_lead_idx_after_from =
+            self.methods[from.method].add_sub_lead_idx(from.sub_lead_idx, from_length);
+        let is_continuation =
+            from.method == to.method && sub_lead_idx_after_from == to.sub_lead_idx;
+        !is_continuation
+    }
+
+    /// Returns `true` if a composition can go from the end of the chunk `from` to the start of the
+    /// chunk `to`, given the [`Method::link_position`]s.  A link method's lead can only be left by
+    /// ending the composition (if it's at the end of the part), or entered from the start of the
+    /// composition (if it's at the start of the part).
+    pub(crate) fn is_link_allowed(
+        &self,
+        from: &ChunkId,
+        from_length: PerPartLength,
+        to: &ChunkId,
+    ) -> bool {
+        let starts_new_lead = to.sub_lead_idx == 0 || self.is_splice(from, from_length, to);
+        if !starts_new_lead {
+            return true; // Links within a lead are always allowed
+        }
+        let link_position = |id: &ChunkId| self.methods[id.method].link_position;
+        let leaves_part_end_lead = link_position(from) == Some(Boundary::End);
+        let enters_part_start_lead = link_position(to) == Some(Boundary::Start);
+        !leaves_part_end_lead && !enters_part_start_lead
+    }
+
+    /// Returns `true` if `call` can be made when it produces `row_after_call`, given the
+    /// [`allowed_calling_positions`](Self::allowed_calling_positions).
+    pub(crate) fn is_calling_position_allowed(&self, call: CallIdx, row_after_call: &Row) -> bool {
+        match &self.allowed_calling_positions {
+            Some(positions) => {
+                let place = row_after_call.place_of(self.calling_bell) as usize;
+                positions.contains(&self.calling_position(&self.calls[call], place))
+            }
+            None => true,
+        }
+    }
+
     /// Returns the first row in the given `ChunkId`
     pub(crate) fn chunk_head(&self, id: &ChunkId) -> RowBuf {
         let method = &self.methods[id.method];
         id.lead_head.deref() * method.row_in_plain_lead(id.sub_lead_idx)
     }
 
-    pub(crate) fn parsed_call_string(&self) -> crate::Result<Option<CallSeqVec<(CallIdx, u8)>>> {
+    /// The course head of the course containing the lead head `lead_head` of the given method,
+    /// normalised so that the same course in every part has the same course head.
+    pub(crate) fn normalised_course_head(&self, method_idx: MethodIdx, lead_head: &Row) -> RowBuf {
+        let method = &self.methods[method_idx];
+        self.part_head_group
+            .rows()
+            .map(|part_head| method.course_head(&(part_head * lead_head)))
+            .min()
+            .unwrap()
+    }
+
+    /// The name of the calling position of `call` when the [`calling_bell`](Self::calling_bell)
+    /// ends up in `place` (0-indexed), taking [`calling_position_names`] into account.
+    ///
+    /// [`calling_position_names`]: Self::calling_position_names
+    pub fn calling_position(&self, call: &Call, place: usize) -> char {
+        let position = call.calling_positions[place];
+        self.calling_position_names
+            .get(&position)
+            .copied()
+            .unwrap_or(position)
+    }
+
+    /// Parse the [`calling`](Self::calling), if set
+    pub(crate) fn parsed_call_string(&self) -> crate::Result<Option<ParsedCalling>> {
         Ok(match &self.calling {
             Some(s) => Some(self.parse_calling(s)?),
             None => None,
         })
     }
 
-    fn parse_calling(&self, calling: &str) -> crate::Result<CallSeqVec<(CallIdx, u8)>> {
+    fn parse_calling(&self, calling: &str) -> crate::Result<ParsedCalling> {
         #[derive(Debug)]
         enum CharMeaning {
-            /// Char refers to a call (e.g. "s" in "sH")
-            Call(CallIdx),
+            /// Char refers to a call (e.g. "s" in "sH").  Calls can share a symbol if they're
+            /// made in different methods.
+            Call(Vec<CallIdx>),
             /// Char refers to a calling position of a bob (e.g. "H" as a shorthand for "-H")
-            BobCallingPosition(CallIdx, u8),
+            BobCallingPosition(Vec<(CallIdx, u8)>),
         }
 
         // Determine how to read the first char of a calls (see `CharMeaning`).  We add all calls
@@ -180,40 +460,49 @@ impl Parameters {
         let mut first_char_meanings = HashMap::<char, CharMeaning>::new();
         for (call_idx, call) in self.calls.iter_enumerated() {
             if !call.is_bob() {
-                let existing_meaning =
-                    first_char_meanings.insert(call.symbol, CharMeaning::Call(call_idx));
-                assert!(existing_meaning.is_none()); // Non-unique calls should already have errored
+                let meaning = first_char_meanings
+                    .entry(call.symbol)
+                    .or_insert_with(|| CharMeaning::Call(Vec::new()));
+                match meaning {
+                    CharMeaning::Call(call_idxs) => call_idxs.push(call_idx),
+                    CharMeaning::BobCallingPosition(_) => unreachable!(),
+                }
             }
         }
         for (call_idx, call) in self.calls.iter_enumerated() {
             if call.is_bob() {
-                for (position_place, position_char) in call.calling_positions.iter().enumerate() {
-                    let existing_meaning = first_char_meanings.insert(
-                        *position_char,
-                        CharMeaning::BobCallingPosition(call_idx, position_place as u8),
-                    );
-                    if let Some(CharMeaning::Call(_)) = existing_meaning {
-                        return Err(crate::Error::CustomCallingParse {
-                            char_idx: None,
-                            reason: format!(
-                                "A bob calling position {:?} shares its name with a different call.",
-                                position_char
-                            ),
-                        });
+                for position_place in 0..call.calling_positions.len() {
+                    let position_char = self.calling_position(call, position_place);
+                    let meaning = first_char_meanings
+                        .entry(position_char)
+                        .or_insert_with(|| CharMeaning::BobCallingPosition(Vec::new()));
+                    match meaning {
+                        CharMeaning::BobCallingPosition(bobs) => {
+                            bobs.push((call_idx, position_place as u8))
+                        }
+                        CharMeaning::Call(_) => {
+                            return Err(crate::Error::CustomCallingParse {
+                                char_idx: None,
+                                reason: format!(
+                                    "A bob calling position {:?} shares its name with a different call.",
+                                    position_char
+                                ),
+                            });
+                        }
                     }
                 }
             }
         }
 
         // Read the call string.  Each loop consumes one call (or skips over one character)
-        let mut calls = CallSeqVec::<(CallIdx, u8)>::new();
+        let mut calls = ParsedCalling::new();
         let mut char_iter = calling
             .char_indices()
             .filter(|(_idx, c)| !c.is_whitespace()); // Prefilter whitespace from the calling
         while let Some((char_idx, c)) = char_iter.next() {
             match first_char_meanings.get(&c) {
                 // Just a call name, so parse the next char as a calling position
-                Some(CharMeaning::Call(call_idx)) => {
+                Some(CharMeaning::Call(call_idxs)) => {
                     let Some((_char_idx, position_char)) = char_iter.next() else {
                         return Err(crate::Error::CustomCallingParse {
                             char_idx: Some(char_idx),
@@ -222,27 +511,46 @@ impl Parameters {
                             ),
                         });
                     };
-                    let maybe_position = self.calls[*call_idx]
-                        .calling_positions
+                    // Find this calling position in every call with this symbol
+                    let positions = call_idxs
                         .iter()
-                        .position(|p| *p == position_char);
-                    match maybe_position {
-                        Some(position) => {
-                            calls.push((*call_idx, position as u8));
-                        }
-                        None => {
-                            return Err(crate::Error::CustomCallingParse {
-                                char_idx: Some(char_idx),
-                                reason: format!(
-                                    "{position_char:?} isn't a calling position for {c:?}"
-                                ),
+                        .filter_map(|&call_idx| {
+                            let call = &self.calls[call_idx];
+                            let place = (0..call.calling_positions.len()).find(|&place| {
+                                self.calling_position(call, place) == position_char
+                            })?;
+                            Some((call_idx, place as u8))
+                        })
+                        .collect_vec();
+                    if positions.is_empty() {
+                        let positions = call_idxs
+                            .iter()
+                            .flat_map(|&call_idx| {
+                                let call = &self.calls[call_idx];
+                                (0..call.calling_positions.len())
+                                    .map(|place| self.calling_position(call, place).to_string())
                             })
+                            .unique()
+                            .collect_vec();
+                        let mut reason =
+                            format!("{position_char:?} isn't a calling position for {c:?}");
+                        let suggestion = ringing_utils::closest_match(
+                            &position_char.to_string(),
+                            positions.iter().map(String::as_str),
+                        );
+                        if let Some(suggestion) = suggestion {
+                            reason.push_str(&format!(".  Did you mean '{suggestion}'?"));
                         }
+                        return Err(crate::Error::CustomCallingParse {
+                            char_idx: Some(char_idx),
+                            reason,
+                        });
                     }
+                    calls.push(positions);
                 }
                 // Call is already fully parsed
-                Some(CharMeaning::BobCallingPosition(call_idx, place)) => {
-                    calls.push((*call_idx, *place));
+                Some(CharMeaning::BobCallingPosition(bobs)) => {
+                    calls.push(bobs.clone());
                 }
                 // Non white-space, non-call, non-bob-calling-position chars are an error
                 None => {
@@ -328,7 +636,7 @@ impl Parameters {
         for call in &self.calls {
             // For each call, remove the bells which aren't fixed by that call (e.g. the 2 in
             // Grandsire is unaffected by a plain lead, but affected by calls)
-            Self::filter_bells_fixed_by_call(method, call, &mut fixed_bells);
+            self.filter_bells_fixed_by_call(method, call, &mut fixed_bells);
         }
         fixed_bells
     }
@@ -336,33 +644,40 @@ impl Parameters {
     // For every position that this call could be placed, remove any bells which **aren't** preserved
     // by placing the call at this location.
     fn filter_bells_fixed_by_call(
+        &self,
         method: &bellframe::Method,
         call: &Call,
         set: &mut HashSet<Bell>,
     ) {
-        // Note that all calls are required to only substitute one piece of place notation.
         for sub_lead_idx_after_call in method.label_indices(&call.label_from) {
-            // TODO: Handle different from/to locations
             let idx_before_call =
                 (sub_lead_idx_after_call + method.lead_len() - 1) % method.lead_len();
             let idx_after_call = idx_before_call + 1; // in range `1..=method.lead_len()`
 
             // The row before a call in this location in the _first lead_
             let row_before_call = method.first_lead().get_row(idx_before_call).unwrap();
-            // The row after a plain call in this location in the _first lead_
-            let row_after_no_call = method.first_lead().get_row(idx_after_call).unwrap();
             // The row after a call in this location in the _first lead_
-            let mut row_after_call = row_before_call.to_owned();
-            call.place_notation.permute(&mut row_after_call);
+            let row_after_call = row_before_call * call.transposition.transposition();
+            // The rows which the call could lead to.  Calls which don't change label lead to
+            // wherever a plain lead would go, but others can lead to any row labelled with their
+            // `label_to` (possibly in another method)
+            let rows_after_no_call = if call.label_from == call.label_to {
+                vec![method.first_lead().get_row(idx_after_call).unwrap()]
+            } else {
+                self.rows_at_label(&call.label_to, method.stage())
+                    .collect_vec()
+            };
 
             // A bell is _affected_ by the call iff it's in a different place in `row_after_call` than
-            // `row_after_no_call`.  These should be removed from the set, because they are no longer
-            // fixed.
-            for (bell_after_no_call, bell_after_call) in
-                row_after_no_call.bell_iter().zip(&row_after_call)
-            {
-                if bell_after_call != bell_after_no_call {
-                    set.remove(&bell_after_call);
+            // in any row it could lead to.  These should be removed from the set, because they are
+            // no longer fixed.
+            for row_after_no_call in rows_after_no_call {
+                for (bell_after_no_call, bell_after_call) in
+                    row_after_no_call.bell_iter().zip(&row_after_call)
+                {
+                    if bell_after_call != bell_after_no_call {
+                        set.remove(&bell_after_call);
+                    }
                 }
             }
         }
@@ -381,6 +696,38 @@ impl Parameters {
         valid_labels
     }
 
+    /// The number of rows of the given method allowed in the whole composition, combining its
+    /// [`count_range`](Method::count_range) and
+    /// [`count_range_per_part`](Method::count_range_per_part).
+    pub(crate) fn method_count_range(&self, idx: MethodIdx) -> OptionalRangeInclusive {
+        let method = &self.methods[idx];
+        let per_part = method.count_range_per_part;
+        let num_parts = self.num_parts();
+        method.count_range.intersection(OptionalRangeInclusive {
+            min: per_part.min.map(|count| count * num_parts),
+            max: per_part.max.map(|count| count * num_parts),
+        })
+    }
+
+    /// Returns a string representing `rows` rows of the given method, either as a number of rows or
+    /// (if [`Self::method_counts_in_leads`] is set) a number of leads.
+    pub(crate) fn method_count_string(&self, idx: MethodIdx, rows: usize) -> String {
+        if self.method_counts_in_leads {
+            leads_string(rows, self.methods[idx].lead_len())
+        } else {
+            rows.to_string()
+        }
+    }
+
+    /// The units in which method counts are reported
+    pub(crate) fn method_count_unit(&self) -> &'static str {
+        if self.method_counts_in_leads {
+            "leads"
+        } else {
+            "rows"
+        }
+    }
+
     /// Returns a human-readable string representing the given methods.
     ///
     /// This is:
@@ -444,6 +791,10 @@ pub struct Method {
 
     /// The number of rows of this method must fit within this range
     pub count_range: OptionalRangeInclusive,
+    /// The number of rows of this method in each part must fit within this range.  Every part
+    /// contains the same rows of each method, so this is equivalent to multiplying the range by
+    /// the number of parts.
+    pub count_range_per_part: OptionalRangeInclusive,
 
     /// The indices in which we can start a composition during this `Method`.  These are guaranteed
     /// to fit within `inner.lead_len()`.
@@ -451,12 +802,28 @@ pub struct Method {
     /// The indices in which we can end a composition during this `Method`.  These are guaranteed
     /// to fit within `inner.lead_len()`.
     pub end_indices: Vec<isize>,
+    /// If `false`, compositions can't start with this method
+    pub can_start: bool,
+    /// If `false`, compositions can't end with this method
+    pub can_end: bool,
+    /// If set, this is a 'link method' which can only be rung for one lead at the start
+    /// ([`Boundary::Start`]) or end ([`Boundary::End`]) of each part.  For example, cyclic
+    /// spliced usually uses a link method at the end of each part to reach the next part head.
+    pub link_position: Option<Boundary>,
 
     /// The [`Mask`]s which *course heads* must satisfy
     pub allowed_courses: Vec<CourseSet>,
 }
 
 impl Method {
+    /// Returns `true` if `self` and `other` can be spliced at their lead ends, i.e. they have the
+    /// same hunt bells so that splicing between them doesn't disturb the hunt bells.
+    pub fn can_lead_splice_with(&self, other: &Method) -> bool {
+        self.lead_head()
+            .fixed_bells()
+            .eq(other.lead_head().fixed_bells())
+    }
+
     pub fn shorthand(&self) -> String {
         if self.custom_shorthand.is_empty() {
             default_shorthand(&self.title())
@@ -465,6 +832,36 @@ impl Method {
         }
     }
 
+    /// The course head of the course containing `lead_head`.  The course head is the lead head in
+    /// that course where the tenor is at home; if no such lead head exists (e.g. the tenor is a
+    /// hunt bell) then the smallest lead head is used.
+    pub fn course_head(&self, lead_head: &Row) -> RowBuf {
+        let tenor = lead_head.stage().tenor();
+        let course_heads = self
+            .lead_head()
+            .closure()
+            .into_iter()
+            .map(|plain_lead_head| lead_head * !&plain_lead_head)
+            .collect_vec();
+        course_heads
+            .iter()
+            .find(|ch| ch.place_of(tenor) == tenor.index_u8())
+            .or_else(|| course_heads.iter().min())
+            .unwrap()
+            .clone()
+    }
+
+    /// The number of leads in a plain course of this method
+    pub fn leads_per_course(&self) -> usize {
+        self.lead_head().order()
+    }
+
+    /// Classifies the group of lead heads generated by this method's lead head, i.e. how its
+    /// plain course is structured.
+    pub fn lead_head_group(&self) -> LeadHeadGroup {
+        LeadHeadGroup::of(self.lead_head())
+    }
+
     pub fn add_sub_lead_idx(&self, sub_lead_idx: usize, len: PerPartLength) -> usize {
         (sub_lead_idx + len.as_usize()) % self.lead_len()
     }
@@ -537,6 +934,14 @@ impl Method {
             start_indices = r#union.clone();
             end_indices = r#union;
         }
+        // Methods which can't start or end a composition have no valid starts or ends, so no
+        // start/end chunks will be generated for them
+        if !self.can_start {
+            start_indices.clear();
+        }
+        if !self.can_end {
+            end_indices.clear();
+        }
         (start_indices, end_indices)
     }
 
@@ -578,6 +983,53 @@ impl std::ops::Deref for Method {
     }
 }
 
+/// Classification of the group of lead heads generated by a [`Method`]'s lead head.  This
+/// determines how the method's plain course is structured (and therefore, for example, whether
+/// default calling positions make sense for it).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LeadHeadGroup {
+    /// One of Plain Bob's lead heads (e.g. `15738264` for Bristol Major), so the treble is the
+    /// only hunt bell and the working bells stay in Plain Bob coursing order.
+    PlainBob,
+    /// The working bells rotate through their places in order (e.g. `18234567`), as in cyclic
+    /// methods.
+    Cyclic,
+    /// Any other lead head (e.g. Grandsire's `1253746`, rounds, or differential methods).
+    Other,
+}
+
+impl LeadHeadGroup {
+    /// Classifies the group generated by a given lead head
+    pub fn of(lead_head: &Row) -> Self {
+        if lead_head.is_rounds() {
+            return Self::Other;
+        }
+        let pb_lead_head = bellframe::method::plain_bob_lead_head(lead_head.stage());
+        if let Some(pb_lead_head) = pb_lead_head {
+            if pb_lead_head.closure().iter().any(|lh| &**lh == lead_head) {
+                return Self::PlainBob;
+            }
+        }
+        // Check whether the working bells are all rotated by the same number of places
+        let working_places = (lead_head.bell_iter().enumerate())
+            .filter(|&(place, bell)| bell.index() != place)
+            .map(|(place, _bell)| place)
+            .collect_vec();
+        let num_working_bells = working_places.len();
+        let is_rotation = |shift: usize| -> bool {
+            (working_places.iter().enumerate()).all(|(i, &place)| {
+                let place_of_bell = working_places[(i + shift) % num_working_bells];
+                lead_head[place].index() == place_of_bell
+            })
+        };
+        if (1..num_working_bells).any(is_rotation) {
+            Self::Cyclic
+        } else {
+            Self::Other
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct MethodId(pub u32);
 
@@ -632,13 +1084,15 @@ pub struct Call {
     // changing the `CallId`.
     pub label_from: String,
     pub label_to: String,
-    // TODO: Allow calls to cover multiple PNs (e.g. singles in Grandsire)
-    pub place_notation: PlaceNot,
+    pub transposition: CallTransposition,
 
     pub symbol: char,
     pub calling_positions: Vec<char>,
 
     pub weight: f32,
+    /// If set, this call can only be made in these methods (e.g. big bobs which are only used in
+    /// one method of a spliced composition).  If `None`, the call can be made in every method.
+    pub allowed_methods: Option<Vec<MethodId>>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -656,6 +1110,99 @@ impl From<CallId> for u32 {
     }
 }
 
+/// How a [`Call`] changes the row immediately after it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CallTransposition {
+    /// The call replaces one piece of place notation (e.g. `14` for a Plain Bob bob).
+    PlaceNot(PlaceNot),
+    /// The call replaces the change at its label with a block of several changes (e.g. `3.123`
+    /// for a Grandsire single).  The rows in the middle of the block aren't part of any lead of a
+    /// method, so the call makes the composition longer by `block.len() - 1` rows.  If
+    /// [`Call::label_to`] refers to a different place in the lead, then this can replace a whole
+    /// section of the method (e.g. cat's ears in Stedman, or shortened courses).
+    Block(PnBlock),
+    /// The call transposes the row before the call by an arbitrary [`Row`], which doesn't have to
+    /// correspond to any single change.  This allows 'jump' calls, like those used in some cyclic
+    /// or link-method compositions.
+    Row(RowBuf),
+}
+
+impl CallTransposition {
+    /// The [`Stage`] of this transposition
+    pub fn stage(&self) -> Stage {
+        match self {
+            Self::PlaceNot(pn) => pn.stage(),
+            Self::Block(block) => block.stage(),
+            Self::Row(row) => row.stage(),
+        }
+    }
+
+    /// The [`Row`] which maps the row before the call to the row after the call
+    pub fn transposition(&self) -> RowBuf {
+        match self {
+            Self::PlaceNot(pn) => pn.transposition(),
+            Self::Block(block) => block.to_block_from_rounds::<()>().leftover_row().to_owned(),
+            Self::Row(row) => row.clone(),
+        }
+    }
+
+    /// The number of extra [`Row`]s rung during this call, which aren't part of the leads on
+    /// either side of it.  This is only non-zero for [`Block`](Self::Block)s of more than one
+    /// change.
+    pub fn num_extra_rows(&self) -> usize {
+        match self {
+            Self::PlaceNot(_) | Self::Row(_) => 0,
+            Self::Block(block) => block.len() - 1,
+        }
+    }
+
+    /// The [`Row`]s rung during this call (see [`Self::num_extra_rows`]), given the row before it
+    pub fn extra_rows(&self, row_before_call: &Row) -> SameStageVec {
+        let mut rows = SameStageVec::new(row_before_call.stage());
+        if let Self::Block(block) = self {
+            let mut row = row_before_call.to_owned();
+            for pn in block.place_nots().take(block.len() - 1) {
+                pn.permute(&mut row);
+                rows.push(&row);
+            }
+        }
+        rows
+    }
+
+    /// Returns `true` if the bell in `place` (0-indexed) is unaffected by this transposition
+    pub fn fixes_place(&self, place: u8) -> bool {
+        match self {
+            Self::PlaceNot(pn) => pn.contains(place),
+            Self::Block(_) | Self::Row(_) => {
+                let row = self.transposition();
+                row.bell_iter().nth(place as usize).map(Bell::index_u8) == Some(place)
+            }
+        }
+    }
+
+    /// Generate the calling positions which should be used for this transposition if the user
+    /// doesn't specify any.
+    pub fn default_calling_positions(&self) -> Vec<char> {
+        calling_positions_from_fixed_places(self.stage(), |place| self.fixes_place(place))
+    }
+}
+
+impl From<PlaceNot> for CallTransposition {
+    fn from(pn: PlaceNot) -> Self {
+        Self::PlaceNot(pn)
+    }
+}
+
+impl std::fmt::Display for CallTransposition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PlaceNot(pn) => write!(f, "{pn}"),
+            Self::Block(block) => write!(f, "{}", block.place_nots().join(".")),
+            Self::Row(row) => write!(f, "transposition {row}"),
+        }
+    }
+}
+
 /// How the calls in a given composition should be displayed.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum CallDisplayStyle {
@@ -665,11 +1212,178 @@ pub enum CallDisplayStyle {
     CallingPositions,
 }
 
+/// How the calling of multi-part compositions should be displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MultipartCallDisplay {
+    /// Only display the calling of the first part (e.g. `sHsH`).
+    #[default]
+    FirstPart,
+    /// Display the calling of the first part, followed by the number of parts (e.g.
+    /// `sHsH (3 parts)`).
+    Repeated,
+    /// Display the calling of every part, separated by `/` (e.g. `sHsH / sHsH / sHsH`).  Calls are
+    /// generated separately for each part, so this also shows how calls change between parts.
+    Expanded,
+}
+
+/// How [`Composition`]s are compared when deciding which ones belong in the
+/// [`shortlist`](Parameters::shortlist).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShortlistMetric {
+    /// Keep the compositions with the highest total score
+    TotalScore,
+    /// Keep the compositions with the highest (displayed) count of a given [`MusicType`]
+    MusicCount(MusicTypeIdx),
+}
+
+impl ShortlistMetric {
+    /// Get the value of this metric for a [`Composition`].  Higher values are better.
+    pub fn value(self, values: &CompositionValues, params: &Parameters) -> f32 {
+        match self {
+            Self::TotalScore => values.total_score,
+            Self::MusicCount(idx) => {
+                params.music_types[idx].masked_total(values.music_counts[idx]) as f32
+            }
+        }
+    }
+}
+
+/// The key by which [`Composition`]s are compared (see [`Parameters::ranking`]).  Higher values
+/// are better.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Ranking {
+    /// Rank compositions by their [average score per row](CompositionValues::score_per_row).  This
+    /// biases against longer compositions in searches which allow a wide range of lengths.
+    #[default]
+    AvgScore,
+    /// Rank compositions by their total score.  This favours longer compositions, since they have
+    /// more rows in which to generate score.
+    TotalScore,
+    /// Rank compositions by their music score per row, ignoring the weights of calls, splices,
+    /// courses, etc.
+    MusicPerRow,
+    /// Rank compositions with a custom function.  This can only be evaluated on complete
+    /// compositions, so the search orders its partial compositions by average score instead.
+    Custom(RankingFn),
+}
+
+impl Ranking {
+    /// Get the value of this ranking key for a [`Composition`].  Higher values are better.
+    pub fn value(&self, values: &CompositionValues) -> f32 {
+        match self {
+            Self::AvgScore => values.score_per_row(),
+            Self::TotalScore => values.total_score,
+            Self::MusicPerRow => values.music_score / values.length() as f32,
+            Self::Custom(f) => (f.0)(values),
+        }
+    }
+}
+
+/// A custom function for ranking [`Composition`]s (see [`Ranking::Custom`]).  Two `RankingFn`s
+/// are only equal if they're clones of the same function.
+#[derive(Clone)]
+#[allow(clippy::type_complexity)]
+pub struct RankingFn(Arc<dyn Fn(&CompositionValues) -> f32 + Send + Sync>);
+
+impl RankingFn {
+    pub fn new(f: impl Fn(&CompositionValues) -> f32 + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+}
+
+impl Debug for RankingFn {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("RankingFn(..)")
+    }
+}
+
+impl PartialEq for RankingFn {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// A bonus given to [`Composition`]s whose lengths are close to some target length (see
+/// [`Parameters::target_length`]).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetLength {
+    /// The ideal length of a composition
+    pub length: TotalLength,
+    /// The score given to compositions of exactly `length` rows
+    pub bonus: f32,
+    /// The bonus decreases linearly to `0` for compositions which are this many rows longer or
+    /// shorter than `length`.  If this is `0`, only compositions of exactly `length` rows get
+    /// any bonus.
+    pub falloff: usize,
+}
+
+impl TargetLength {
+    /// The bonus given to a [`Composition`] of `length` rows
+    pub fn bonus_for(&self, length: usize) -> f32 {
+        let distance = length.abs_diff(self.length.as_usize());
+        if distance >= self.falloff.max(1) {
+            return 0.0;
+        }
+        self.bonus * (1.0 - distance as f32 / self.falloff.max(1) as f32)
+    }
+
+    /// The length in `min_length..=max_length` which gets the biggest bonus
+    pub fn best_length_in(&self, min_length: usize, max_length: usize) -> usize {
+        let target = self.length.as_usize().clamp(min_length, max_length);
+        if self.bonus >= 0.0 {
+            target
+        } else if target - min_length > max_length - target {
+            min_length // Negative bonuses are smallest as far from the target as possible
+        } else {
+            max_length
+        }
+    }
+}
+
+/// A regular expression which the call string of every [`Composition`] must match (or must not
+/// match, if `must_match` is `false`).
+#[derive(Debug, Clone)]
+pub struct CallingFilter {
+    pub regex: Regex,
+    pub must_match: bool,
+}
+
+impl CallingFilter {
+    /// Returns `true` if a [`Composition`] with the given call string should be generated
+    pub fn allows(&self, call_string: &str) -> bool {
+        self.regex.is_match(call_string) == self.must_match
+    }
+}
+
+impl PartialEq for CallingFilter {
+    fn eq(&self, other: &Self) -> bool {
+        self.regex.as_str() == other.regex.as_str() && self.must_match == other.must_match
+    }
+}
+
 impl Call {
     pub fn is_bob(&self) -> bool {
         self.symbol == '-' || self.symbol == '–'
     }
 
+    /// Returns `true` if there's at least one method in which both `self` and `other` can be made
+    /// (see [`Call::allowed_methods`])
+    pub fn shares_method_with(&self, other: &Call) -> bool {
+        match (&self.allowed_methods, &other.allowed_methods) {
+            (Some(methods1), Some(methods2)) => methods1.iter().any(|id| methods2.contains(id)),
+            _ => true, // Calls without `allowed_methods` can be made in every method
+        }
+    }
+
+    /// Returns `true` if this call can be made in the method with the given [`MethodId`] (see
+    /// [`Call::allowed_methods`])
+    pub fn can_be_made_in(&self, method_id: MethodId) -> bool {
+        match &self.allowed_methods {
+            Some(method_ids) => method_ids.contains(&method_id),
+            None => true,
+        }
+    }
+
     /// Return the symbol used for this call in compact call strings.  Bobs use an empty string,
     /// while all other calls are unaffected.  Thus, compositions render like `WsWWsWH` rather than
     /// `-WsW-WsW-H`.
@@ -686,8 +1400,9 @@ impl Call {
             calling_positions: default_calling_positions(&place_not),
             label_from: LABEL_LEAD_END.to_owned(),
             label_to: LABEL_LEAD_END.to_owned(),
-            place_notation: place_not,
+            transposition: CallTransposition::PlaceNot(place_not),
             weight,
+            allowed_methods: None,
         }
     }
 }
@@ -750,8 +1465,12 @@ pub fn base_calls(
     calls
 }
 
-#[allow(clippy::branches_sharing_code)]
 pub fn default_calling_positions(place_not: &PlaceNot) -> Vec<char> {
+    calling_positions_from_fixed_places(place_not.stage(), |place| place_not.contains(place))
+}
+
+#[allow(clippy::branches_sharing_code)]
+fn calling_positions_from_fixed_places(stage: Stage, is_fixed: impl Fn(u8) -> bool) -> Vec<char> {
     let named_positions = "LIBFVXSEN"; // TODO: Does anyone know any more than this?
 
     // TODO: Replace 'B' with 'O' for calls which don't affect the tenor
@@ -764,7 +1483,7 @@ pub fn default_calling_positions(place_not: &PlaceNot) -> Vec<char> {
         // Extending forever with '?'s (for calling positions with no standard name)
         .chain(std::iter::repeat('?'))
         // But we consume one value per place in the Stage
-        .take(place_not.stage().num_bells())
+        .take(stage.num_bells())
         .collect_vec();
 
     /// A cheeky macro which generates the code to perform an in-place replacement of a calling
@@ -777,9 +1496,9 @@ pub fn default_calling_positions(place_not: &PlaceNot) -> Vec<char> {
         };
     }
 
-    // Edge case: if 2nds are made in `place_not`, then I/B are replaced with B/T.  Note that
+    // Edge case: if 2nds are made by the call, then I/B are replaced with B/T.  Note that
     // places are 0-indexed
-    if place_not.contains(1) {
+    if is_fixed(1) {
         replace_pos!(1, 'B');
         replace_pos!(2, 'T');
     }
@@ -788,7 +1507,7 @@ pub fn default_calling_positions(place_not: &PlaceNot) -> Vec<char> {
     /// position at a place indexed from the end of the stage (so 0 is the highest place)
     macro_rules! replace_mwh {
         ($ind: expr, $new_val: expr) => {
-            if let Some(place) = place_not.stage().num_bells().checked_sub(1 + $ind) {
+            if let Some(place) = stage.num_bells().checked_sub(1 + $ind) {
                 if place >= 4 {
                     if let Some(v) = positions.get_mut(place) {
                         *v = $new_val;
@@ -799,7 +1518,7 @@ pub fn default_calling_positions(place_not: &PlaceNot) -> Vec<char> {
     }
 
     // Add MWH (M and W are swapped round for odd stages)
-    if place_not.stage().is_even() {
+    if stage.is_even() {
         replace_mwh!(2, 'M');
         replace_mwh!(1, 'W');
         replace_mwh!(0, 'H');
@@ -827,9 +1546,44 @@ pub struct MusicType {
     pub weights: AtRowPositions<f32>,
     pub count_range: OptionalRangeInclusive,
     // TODO: Count ranges for front/internal/back/wrap
+    /// If `true`, any chunk containing this music is considered a 'non-duffer' (see
+    /// [`Parameters::no_duffers`]).
+    pub non_duffer: bool,
+}
+
+/// Which music stops a chunk from being a 'duffer' (see [`Parameters::no_duffers`] and
+/// [`Parameters::max_duffer_rows`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NonDufferMusic {
+    /// Only music from [`MusicType`]s with [`non_duffer`](MusicType::non_duffer) set
+    #[default]
+    Marked,
+    /// As well as marked music, any music which gives the chunk a positive score
+    Scoring,
 }
 
 impl MusicType {
+    /// Returns `true` if this music could ever stop a chunk from being a duffer
+    pub fn can_be_non_duffer(&self, non_duffer_music: NonDufferMusic) -> bool {
+        match non_duffer_music {
+            NonDufferMusic::Marked => self.non_duffer,
+            NonDufferMusic::Scoring => self.non_duffer || self.weights.map(|w| w > 0.0).any(),
+        }
+    }
+
+    /// Returns `true` if a chunk containing these `counts` of this music isn't a duffer
+    pub fn makes_non_duffer(
+        &self,
+        counts: AtRowPositions<usize>,
+        non_duffer_music: NonDufferMusic,
+    ) -> bool {
+        let is_marked_music = self.non_duffer && self.masked_total(counts) > 0;
+        match non_duffer_music {
+            NonDufferMusic::Marked => is_marked_music,
+            NonDufferMusic::Scoring => is_marked_music || self.as_overall_score(counts) > 0.0,
+        }
+    }
+
     pub fn as_overall_score(&self, counts: AtRowPositions<usize>) -> f32 {
         (counts.map(|x| x as f32) * self.weights).total()
     }
@@ -855,7 +1609,22 @@ impl MusicType {
 
     /// Generate a compact string representing a given set of music counts
     pub fn display_counts(&self, counts: AtRowPositions<usize>, stage: Stage) -> String {
-        let max_counts = self.max_possible_count(stage);
+        self.counts_string(counts, Some(self.max_possible_count(stage)))
+    }
+
+    /// Like [`Self::display_counts`], but without padding the counts to a fixed width (e.g.
+    /// `"32 (12f 4i 16b)"`).
+    pub fn display_counts_unpadded(&self, counts: AtRowPositions<usize>) -> String {
+        self.counts_string(counts, None)
+    }
+
+    /// Generate a string representing `counts`.  If `max_counts` is given, each count is padded
+    /// to be wide enough to hold the corresponding max count.
+    fn counts_string(
+        &self,
+        counts: AtRowPositions<usize>,
+        max_counts: Option<AtRowPositions<usize>>,
+    ) -> String {
         let num_items_to_show: usize = self.show_positions.map(|b| b as usize).total();
 
         let mut s = String::new();
@@ -864,7 +1633,7 @@ impl MusicType {
             Self::write_music_count(
                 &mut s,
                 self.masked_total(counts),
-                self.masked_total(max_counts),
+                max_counts.map(|c| self.masked_total(c)),
             );
         }
         // Add specific counts (if there are any)
@@ -891,7 +1660,11 @@ impl MusicType {
                 }
                 is_first_count = false;
                 // Add the number
-                Self::write_music_count(&mut s, *counts.get(position), *max_counts.get(position));
+                Self::write_music_count(
+                    &mut s,
+                    *counts.get(position),
+                    max_counts.map(|c| *c.get(position)),
+                );
                 s.push(position_char);
             }
             if self.show_total {
@@ -903,11 +1676,11 @@ impl MusicType {
     }
 
     /// Prints the width of the largest count possible for a [`MusicType`] (assuming that rows can't be
-    /// repeated).
-    fn write_music_count(s: &mut String, count: usize, max_possible_count: usize) {
+    /// repeated).  If `max_possible_count` is `None`, no padding is added.
+    fn write_music_count(s: &mut String, count: usize, max_possible_count: Option<usize>) {
         // `min(4)` because we don't expect more than 9999 instances of a music type, even
         // if more are theoretically possible
-        let max_count_width = max_possible_count.to_string().len().min(4);
+        let max_count_width = max_possible_count.map_or(0, |c| c.to_string().len().min(4));
         write!(s, "{:>width$}", count, width = max_count_width).unwrap();
     }
 }
@@ -1126,6 +1899,18 @@ impl OptionalRangeInclusive {
         }
     }
 
+    /// Returns the range of values contained in both `self` and `other`
+    pub fn intersection(self, other: Self) -> Self {
+        let max = match (self.max, other.max) {
+            (Some(max1), Some(max2)) => Some(max1.min(max2)),
+            (max1, max2) => max1.or(max2),
+        };
+        Self {
+            min: self.min.max(other.min), // `None` is less than any `Some`
+            max,
+        }
+    }
+
     pub fn or_range(self, other: &Range<usize>) -> Range<usize> {
         let min = self.min.unwrap_or(other.start);
         let max = self
@@ -1136,6 +1921,26 @@ impl OptionalRangeInclusive {
     }
 }
 
+impl std::fmt::Display for OptionalRangeInclusive {
+    /// Formats this range as e.g. `224`, `224-448`, `224+` or `<=448` (or `any` if the range is
+    /// unbounded).
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) if min == max => write!(f, "{min}"),
+            (Some(min), Some(max)) => write!(f, "{min}-{max}"),
+            (Some(min), None) => write!(f, "{min}+"),
+            (None, Some(max)) => write!(f, "<={max}"),
+            (None, None) => write!(f, "any"),
+        }
+    }
+}
+
+/// A parsed [`calling`](Parameters::calling).  Each entry contains every `(call, calling
+/// position)` which the calling could mean at that point.  There's more than one if several calls
+/// share a symbol, in which case they're restricted to different methods so which one is used
+/// depends on the method that the call is made in.
+pub(crate) type ParsedCalling = CallSeqVec<Vec<(CallIdx, u8)>>;
+
 index_vec::define_index_type! { pub struct MethodIdx = usize; }
 index_vec::define_index_type! { pub struct CallIdx = usize; }
 index_vec::define_index_type! { pub struct MusicTypeIdx = usize; }
@@ -1145,9 +1950,12 @@ pub type MusicTypeVec<T> = index_vec::IndexVec<MusicTypeIdx, T>;
 
 #[cfg(test)]
 mod tests {
-    use bellframe::{PlaceNot, Stage};
+    use bellframe::{PlaceNot, RowBuf, Stage};
     use itertools::Itertools;
 
+    use super::{CallId, CallIdx, CallTransposition, LeadHeadGroup, MethodId};
+    use crate::test_utils::plain_bob_params;
+
     /// Converts a string to a list of strings, one of each [`char`] in the input.
     fn char_vec(string: &str) -> Vec<char> {
         string.chars().collect_vec()
@@ -1185,9 +1993,71 @@ mod tests {
         ];
 
         for (pn_str, stage, exp_positions) in cases {
-            let positions =
-                super::default_calling_positions(&PlaceNot::parse(pn_str, *stage).unwrap());
+            let place_not = PlaceNot::parse(pn_str, *stage).unwrap();
+            let positions = super::default_calling_positions(&place_not);
             assert_eq!(positions, *exp_positions);
+            // Calls given directly as a row should get the same calling positions
+            let row_positions =
+                CallTransposition::Row(place_not.transposition()).default_calling_positions();
+            assert_eq!(row_positions, *exp_positions);
         }
     }
+
+    #[test]
+    fn lead_head_groups() {
+        #[rustfmt::skip]
+        let cases = [
+            ("1342", LeadHeadGroup::PlainBob),     // Plain Bob Minimus
+            ("13524", LeadHeadGroup::PlainBob),    // Plain Bob Doubles
+            ("135264", LeadHeadGroup::PlainBob),   // Plain Bob Minor
+            ("13527486", LeadHeadGroup::PlainBob), // Plain Bob Major
+            ("15738264", LeadHeadGroup::PlainBob), // Cambridge Major
+            ("14263857", LeadHeadGroup::PlainBob),
+            ("1352749608", LeadHeadGroup::PlainBob), // Plain Bob Royal
+
+            ("18234567", LeadHeadGroup::Cyclic),
+            ("13456782", LeadHeadGroup::Cyclic),
+            ("1234567890E", LeadHeadGroup::Other), // Rounds
+            ("1253746", LeadHeadGroup::Other),     // Grandsire Triples
+            ("13254768", LeadHeadGroup::Other),
+        ];
+
+        for (lead_head, exp_group) in cases {
+            let lead_head = RowBuf::parse(lead_head).unwrap();
+            assert_eq!(LeadHeadGroup::of(&lead_head), exp_group, "{lead_head}");
+        }
+    }
+
+    #[test]
+    fn shared_call_symbols() {
+        let mut params = plain_bob_params(Stage::MINOR);
+        let (bob, single) = (CallIdx::new(0), CallIdx::new(1));
+        let far_bob = CallIdx::new(2);
+        // Add a 1456 bob for a second method, which shares its symbol with the near bob
+        params.calls[bob].allowed_methods = Some(vec![MethodId(0)]);
+        let mut far_bob_call = params.calls[bob].clone();
+        far_bob_call.id = CallId(2);
+        far_bob_call.transposition =
+            CallTransposition::PlaceNot(PlaceNot::parse("1456", Stage::MINOR).unwrap());
+        far_bob_call.calling_positions = char_vec("LIBOWH");
+        far_bob_call.allowed_methods = Some(vec![MethodId(1)]);
+        params.calls.push(far_bob_call);
+
+        let calls = &params.calls;
+        assert!(!calls[bob].shares_method_with(&calls[far_bob]));
+        assert!(calls[single].shares_method_with(&calls[bob]));
+        assert!(calls[single].shares_method_with(&calls[far_bob]));
+
+        // `O` only belongs to the 1456 bob, but `H` could be either bob depending on the method
+        params.calling = Some("O sH H".to_owned());
+        let calling = params.parsed_call_string().unwrap().unwrap();
+        assert_eq!(
+            calling.raw,
+            [
+                vec![(far_bob, 3)],
+                vec![(single, 5)],
+                vec![(bob, 5), (far_bob, 5)]
+            ]
+        );
+    }
 }
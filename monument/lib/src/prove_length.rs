@@ -2,7 +2,6 @@ use std::{
     cmp::Reverse,
     collections::{BTreeMap, BinaryHeap, HashMap, HashSet},
     ops::RangeInclusive,
-    time::Instant,
 };
 
 use bellframe::Mask;
@@ -11,12 +10,12 @@ use itertools::Itertools;
 use crate::{
     graph::{ChunkId, Graph, LinkSide, RowIdx},
     parameters::{MethodIdx, MethodVec, OptionalRangeInclusive, Parameters},
-    utils::lengths::TotalLength,
+    utils::{lengths::TotalLength, time::Instant},
 };
 
 const METHOD_COUNT_RELAX_FACTOR: f32 = 0.1;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct RefinedRanges {
     pub length: RangeInclusive<TotalLength>,
     pub method_counts: MethodVec<RangeInclusive<TotalLength>>,
@@ -76,8 +75,8 @@ pub(crate) fn prove_lengths(graph: &Graph, params: &Parameters) -> crate::Result
     for (method_idx, possible_lengths) in possible_lengths_by_method.into_iter_enumerated() {
         let min_bound = method_bounds_min[method_idx];
         let max_bound = method_bounds_max[method_idx];
-        let method = &params.methods[method_idx];
-        let refined_counts = refine_method_counts(min_bound, max_bound, &possible_lengths, method)?;
+        let refined_counts =
+            refine_method_counts(min_bound, max_bound, &possible_lengths, method_idx, params)?;
         refined_method_counts.push(refined_counts);
     }
     log::debug!("  Method count ranges computed in {:.2?}", start.elapsed());
@@ -90,6 +89,10 @@ pub(crate) fn prove_lengths(graph: &Graph, params: &Parameters) -> crate::Result
     // Check for clearly impossible method bounds
     check_final_bounds(&refined_method_counts, &refined_len_range)?;
 
+    /* MUSIC COUNTS */
+
+    check_music_counts(graph, params, &refined_len_range)?;
+
     Ok(RefinedRanges {
         length: refined_len_range,
         method_counts: refined_method_counts,
@@ -259,14 +262,15 @@ fn compute_simplified_graph(params: &Parameters, graph: &Graph) -> SimpleGraph {
 
             // Add the lengths corresponding to this chunk's successors
             for (_id, succ_link) in chunk.succ_links(graph) {
+                // Calls covering several changes add their extra rows to the length
+                let length = chunk.total_length + succ_link.total_length;
                 match &succ_link.to {
                     LinkSide::StartOrEnd => {
-                        successors.insert((chunk.total_length, LinkSide::StartOrEnd));
+                        successors.insert((length, LinkSide::StartOrEnd));
                     }
                     LinkSide::Chunk(succ_id) => {
                         for succ_simple_chunk in get_simple_chunks(succ_id) {
-                            successors
-                                .insert((chunk.total_length, LinkSide::Chunk(succ_simple_chunk)));
+                            successors.insert((length, LinkSide::Chunk(succ_simple_chunk)));
                         }
                     }
                 }
@@ -435,14 +439,14 @@ fn method_bounds(
 
     let total_method_weight = params
         .methods
-        .iter()
-        .filter(|m| get_bound(m.count_range).is_none())
-        .map(|m| (m.lead_len() as f32).sqrt())
+        .iter_enumerated()
+        .filter(|(idx, _)| get_bound(params.method_count_range(*idx)).is_none())
+        .map(|(_, m)| (m.lead_len() as f32).sqrt())
         .sum::<f32>();
     let method_bounds = params
         .methods
-        .iter()
-        .map(|m| match get_bound(m.count_range) {
+        .iter_enumerated()
+        .map(|(idx, m)| match get_bound(params.method_count_range(idx)) {
             Some(count) => (BoundType::Explicit, TotalLength::new(count)),
             // For methods which don't have a set range, distribute the rows weighted by the
             // square root of each method's lead length
@@ -471,10 +475,12 @@ fn refine_method_counts(
     (min_type, mut min_len): (BoundType, TotalLength),
     (max_type, mut max_len): (BoundType, TotalLength),
     possible_lengths: &[TotalLength],
-    method: &crate::parameters::Method,
+    method_idx: MethodIdx,
+    params: &Parameters,
 ) -> crate::Result<RangeInclusive<TotalLength>> {
     use BoundType::{Explicit as Expl, Preferred as Pref};
 
+    let method = &params.methods[method_idx];
     log::trace!("Refining method counts for {}", method.shorthand());
     log::trace!(
         "  initial bounds: {}{} ..= {}{}",
@@ -490,10 +496,9 @@ fn refine_method_counts(
             // If one bound is explicit, then the explicit bound takes priority
             (Expl, Pref) => max_len = min_len,
             (Pref, Expl) => min_len = max_len,
-            // If they're both explicit, it's the user's fault
-            (Expl, Expl) => {
-                unreachable!("Don't make the min range bigger than max range, you silly billy")
-            }
+            // If they're both explicit (e.g. from conflicting total and per-part counts), then
+            // no count can match and an error will be returned below
+            (Expl, Expl) => {}
             // Otherwise, the computed bounds would have to have been the wrong way round,
             // which shouldn't be possible (because they were computed from a maximum bounds, which
             // must be ordered) and rounded away from each other.
@@ -554,10 +559,12 @@ fn refine_method_counts(
                 (None, None) => {
                     assert_ne!((min_type, max_type), (Pref, Pref));
                     return Err(crate::Error::UnachievableMethodCount {
+                        method_idx,
                         method_name: method.title(),
-                        requested_range: method.count_range,
+                        requested_range: params.method_count_range(method_idx),
                         next_shorter_len: next_smaller.map(TotalLength::as_usize),
                         next_longer_len: next_larger.map(TotalLength::as_usize),
+                        lead_len: params.method_counts_in_leads.then(|| method.lead_len()),
                     });
                 }
             }
@@ -579,14 +586,31 @@ fn print_method_counts(
             .or_default()
             .push(idx);
     }
+    // Methods with the same row counts can have different lead counts, so group by the strings
+    // which will actually be printed
+    let mut methods_by_count_strings = Vec::<((String, String), Vec<MethodIdx>)>::new();
     for ((min, max), methods) in methods_by_count_ranges {
-        let row_string = if min == max {
+        for idx in methods {
+            let min_string = params.method_count_string(idx, min.as_usize());
+            let max_string = params.method_count_string(idx, max.as_usize());
+            match methods_by_count_strings
+                .iter_mut()
+                .find(|((mn, mx), _)| *mn == min_string && *mx == max_string)
+            {
+                Some((_, methods)) => methods.push(idx),
+                None => methods_by_count_strings.push(((min_string, max_string), vec![idx])),
+            }
+        }
+    }
+    let unit = params.method_count_unit();
+    for ((min, max), methods) in methods_by_count_strings {
+        let count_string = if min == max {
             format!("exactly {min}")
         } else {
             format!("{min} to {max}")
         };
         let methods_string = params.method_list_string(&methods);
-        log::info!("Requiring {row_string} rows of {methods_string}");
+        log::info!("Requiring {count_string} {unit} of {methods_string}");
     }
 }
 
@@ -603,6 +627,92 @@ enum BoundType {
     Preferred,
 }
 
+//////////////////
+// MUSIC COUNTS //
+//////////////////
+
+/// Check that the minimum count of every [`MusicType`](crate::parameters::MusicType) can be
+/// achieved in a composition no longer than `length_range`, given the music in each chunk.
+fn check_music_counts(
+    graph: &Graph,
+    params: &Parameters,
+    length_range: &RangeInclusive<TotalLength>,
+) -> crate::Result<()> {
+    for (idx, music_type) in params.music_types.iter_enumerated() {
+        let Some(min_count) = music_type.count_range.min else {
+            continue; // No minimum count to prove
+        };
+        // Get the most music that each chunk could contribute, including the music on its best
+        // successor link (which is the only link that can follow it in a composition)
+        let chunk_counts = graph
+            .chunks
+            .values()
+            .map(|chunk| {
+                let max_link_count = chunk
+                    .successors
+                    .iter()
+                    .filter_map(|&link_id| graph.links.get(link_id))
+                    .map(|link| music_type.masked_total(link.music_counts[idx]))
+                    .max()
+                    .unwrap_or(0);
+                let count = music_type.masked_total(chunk.music_counts[idx]) + max_link_count;
+                (count, chunk.total_length.as_usize())
+            })
+            .filter(|&(count, _)| count > 0)
+            .collect_vec();
+        let max_length = length_range.end().as_usize();
+        let Some(max_count) = max_music_count(chunk_counts, max_length, params) else {
+            continue; // Music count can't be bounded
+        };
+        if max_count < min_count {
+            return Err(crate::Error::UnachievableMusicCount {
+                music_type_idx: idx,
+                name: music_type.name.clone(),
+                min_count,
+                max_count,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Given the `(count, length)` of every chunk which contains some music, compute an upper bound on
+/// how much of that music can fit into `max_length` rows (or `None` if there's no bound).
+fn max_music_count(
+    mut chunk_counts: Vec<(usize, usize)>,
+    max_length: usize,
+    params: &Parameters,
+) -> Option<usize> {
+    if chunk_counts.iter().any(|&(_, length)| length == 0) {
+        return None; // Chunks have infinite music density
+    }
+    // Sort the chunks so that the densest music comes first
+    chunk_counts.sort_by(|&(count1, len1), &(count2, len2)| (count2 * len1).cmp(&(count1 * len2)));
+    if !params.rows_are_unique() {
+        // If rows can be repeated, then so can chunks.  So no composition can do better than
+        // repeating the densest chunk over and over
+        return Some(match chunk_counts.first() {
+            Some(&(count, length)) => count * max_length / length,
+            None => 0,
+        });
+    }
+    // If rows are unique, every chunk can be rung at most once.  So the best possible composition
+    // is made by greedily taking the densest chunks (and a fraction of the last one) until the
+    // composition is full.  Falseness makes this even harder, so this is still an upper bound.
+    let mut total_count = 0;
+    let mut length_left = max_length;
+    for (count, length) in chunk_counts {
+        if length <= length_left {
+            total_count += count;
+            length_left -= length;
+        } else {
+            total_count += count * length_left / length;
+            break;
+        }
+    }
+    Some(total_count)
+}
+
 ///////////////////////////
 // FINAL BOUNDS CHECKING //
 ///////////////////////////
@@ -1,48 +1,121 @@
 use std::{
-    collections::BinaryHeap,
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
     sync::atomic::{AtomicBool, Ordering},
 };
 
 use datasize::DataSize;
+use ordered_float::OrderedFloat;
 use ringing_utils::BigNumInt;
 
-use crate::{composition::ParamsData, utils::lengths::TotalLength};
+use crate::{
+    composition::ParamsData,
+    utils::{
+        lengths::TotalLength,
+        time::{Instant, HAS_CLOCK},
+    },
+};
 
-use super::{path::Paths, prefix::CompPrefix, Progress, Search, Update};
+use super::{
+    path::{PathId, Paths},
+    prefix::CompPrefix,
+    stats::Counters,
+    Progress, Search, TruncationPolicy, Update,
+};
 
-const ITERS_BETWEEN_ABORT_CHECKS: usize = 10_000;
-const ITERS_BETWEEN_PROGRESS_UPDATES: usize = 100_000;
-const ITERS_BETWEEN_PATH_GCS: usize = 100_000_000;
+pub(super) const ITERS_BETWEEN_ABORT_CHECKS: usize = 10_000;
+const ITERS_BETWEEN_TIME_CHECKS: usize = 1_000;
+pub(super) const ITERS_BETWEEN_PROGRESS_UPDATES: usize = 100_000;
+pub(super) const ITERS_BETWEEN_PATH_GCS: usize = 100_000_000;
+/// When the queue limit is adapted to [`Config::max_memory_bytes`](super::Config::max_memory_bytes),
+/// a full queue is truncated to this fraction of the limit (rather than being halved)
+const BUDGET_TRUNCATION_FRACTION: f64 = 0.75;
+/// When truncating with [`TruncationPolicy::Stratified`], the lengths of the prefixes in the queue
+/// are split into this many equally sized ranges
+const NUM_LENGTH_STRATA: usize = 16;
 
 /// Searches a [`Graph`](m_gr::Graph) for compositions.  This function is the core of Monument, and
 /// almost all of Monument's runtime will be spent in the `while` loop in this function.
 pub(crate) fn search(search: &Search, mut update_fn: impl FnMut(Update), abort_flag: &AtomicBool) {
-    let mem_limit = search
-        .config
-        .mem_limit
-        .unwrap_or_else(super::default_mem_limit);
+    let mem_limit = search.mem_limit();
     log::info!("Limiting memory usage to {}B", BigNumInt(mem_limit));
 
     let param_data = ParamsData::new(&search.params);
 
-    // Initialise the frontier to just the start chunks
+    // Initialise the frontier(s) to just the start chunks.  If each start chunk has a time budget,
+    // then every start chunk gets its own frontier which we suspend once the start chunk's time
+    // slice runs out.  Otherwise, all the start chunks share one frontier.
     let mut paths = Paths::new();
-    let mut frontier: BinaryHeap<CompPrefix> = CompPrefix::starts(search, &mut paths);
+    let starts = CompPrefix::starts(search, &mut paths);
+    // Time budgets can't be measured without a clock, so then all start chunks share a frontier
+    let time_budget = search.config.start_chunk_time_budget.filter(|_| HAS_CLOCK);
+    let mut frontiers: Vec<BinaryHeap<CompPrefix>> = match time_budget {
+        Some(_) => starts
+            .into_iter()
+            .map(|s| BinaryHeap::from(vec![s]))
+            .collect(),
+        None => vec![starts.into_iter().collect()],
+    };
 
-    if frontier.is_empty() {
-        return; // Don't run a search if no compositions are possible
+    let mut counters = Counters::new(search);
+    if frontiers.iter().all(BinaryHeap::is_empty) {
+        // Don't run a search if no compositions are possible
+        search.send_final_updates(&mut update_fn, &counters, 0, false, false);
+        return;
     }
 
     // Number of bytes occupied by each `CompPrefix` in the frontier.
-    let prefix_size = frontier.peek().unwrap().size();
+    let prefix_size = frontiers.iter().find_map(BinaryHeap::peek).unwrap().size();
+    // Each prefix in the queue also needs (at least) one node in `paths`
+    let bytes_per_prefix = prefix_size + std::mem::size_of::<u64>();
+    // If the search has a memory budget (and no fixed queue limit), the queue limit is re-computed
+    // from the real size of the prefixes every time the queue gets truncated
+    let memory_budget = match search.config.queue_limit {
+        Some(_) => None,
+        None => search
+            .config
+            .max_memory_bytes
+            .map(|budget| budget.min(mem_limit)),
+    };
+    let mut queue_limit = search.queue_limit().or_else(|| {
+        if let Some(budget) = memory_budget {
+            return Some(budget / bytes_per_prefix);
+        }
+        search.config.auto_tune.then(|| {
+            let queue_limit = mem_limit / bytes_per_prefix;
+            log::info!(
+                "Auto-tune: limiting queue to {} prefixes ({}B each)",
+                BigNumInt(queue_limit),
+                bytes_per_prefix,
+            );
+            queue_limit
+        })
+    });
+    search.set_queue_limit(queue_limit);
+    let mut target_num_comps = search.num_comps();
 
     let mut iter_count = 0;
     let mut num_comps = 0;
+    // Whether we've had to drop prefixes from the queue.  If the search doesn't find anything,
+    // this (and the rejections in `counters`) are used to explain why
+    let mut truncated_queue = false;
+    // If we're keeping a shortlist, this stores the metric values of its compositions so that the
+    // worst one is at the top
+    let mut shortlist = BinaryHeap::<Reverse<OrderedFloat<f32>>>::new();
+    // Index of the frontier currently being expanded, along with the time when its slice expires
+    let mut frontier_idx = 0;
+    let mut slice_end = time_budget.map(|budget| Instant::now() + budget);
+    // Total length of all the suspended frontiers (i.e. all but `frontiers[frontier_idx]`).  We
+    // track this separately to avoid summing the lengths of every frontier on every iteration.
+    let mut suspended_len = frontiers.iter().skip(1).map(BinaryHeap::len).sum::<usize>();
 
     macro_rules! send_progress_update {
         (truncating_queue = $truncating_queue: expr) => {
             send_progress_update(
-                &frontier,
+                search,
+                frontiers.iter().flatten(),
+                &paths,
+                Some(mem_limit),
                 &mut update_fn,
                 iter_count,
                 num_comps,
@@ -60,8 +133,29 @@ pub(crate) fn search(search: &Search, mut update_fn: impl FnMut(Update), abort_f
     // Repeatedly choose the best prefix and expand it (i.e. add each way of extending it to the
     // frontier).  This is best-first search (and can be A* depending on the cost function used).
     // This loop is the core of Monument - almost all the runtime will be spent here.
-    while let Some(prefix) = frontier.pop() {
-        let maybe_comp = prefix.expand(search, &mut paths, &mut frontier, &param_data);
+    loop {
+        // If the current frontier has run out of prefixes or time, suspend it and resume the next
+        // non-empty frontier (round-robin).  If every frontier is empty, the search is complete.
+        let slice_expired = iter_count % ITERS_BETWEEN_TIME_CHECKS == 0
+            && slice_end.is_some_and(|end| Instant::now() >= end);
+        if frontiers[frontier_idx].is_empty() || slice_expired {
+            let num_frontiers = frontiers.len();
+            let next_idx = (1..=num_frontiers)
+                .map(|offset| (frontier_idx + offset) % num_frontiers)
+                .find(|&idx| !frontiers[idx].is_empty());
+            let next_idx = match next_idx {
+                Some(idx) => idx,
+                None => break, // All frontiers are empty
+            };
+            suspended_len += frontiers[frontier_idx].len();
+            suspended_len -= frontiers[next_idx].len();
+            frontier_idx = next_idx;
+            slice_end = time_budget.map(|budget| Instant::now() + budget);
+        }
+        let frontier = &mut frontiers[frontier_idx];
+        let prefix = frontier.pop().unwrap(); // We just checked that `frontier` is non-empty
+
+        let maybe_comp = prefix.expand(search, &mut paths, frontier, &param_data, &mut counters);
 
         // Submit new compositions when they're generated
         if let Some(comp) = maybe_comp {
@@ -70,30 +164,85 @@ pub(crate) fn search(search: &Search, mut update_fn: impl FnMut(Update), abort_f
                 comp.length.as_usize(),
                 comp.values(&param_data).unwrap().call_string
             );
-            update_fn(Update::Comp(comp));
-            num_comps += 1;
+            match search.params.shortlist {
+                Some(metric) => {
+                    let values = comp.values(&param_data).unwrap();
+                    let value = OrderedFloat(metric.value(&values, &search.params));
+                    let is_full = shortlist.len() >= target_num_comps;
+                    let makes_shortlist = !is_full
+                        || shortlist
+                            .peek()
+                            .is_some_and(|Reverse(worst)| value > *worst);
+                    if makes_shortlist {
+                        if is_full {
+                            shortlist.pop(); // Push the worst comp off to make room for this one
+                        }
+                        shortlist.push(Reverse(value));
+                        update_fn(Update::Comp(comp));
+                        num_comps += 1;
+                    }
+                }
+                None => {
+                    update_fn(Update::Comp(comp));
+                    num_comps += 1;
 
-            if num_comps == search.params.num_comps {
-                break; // Stop the search once we've got enough comps
+                    if num_comps >= target_num_comps {
+                        break; // Stop the search once we've got enough comps
+                    }
+                }
             }
         }
 
-        // If we end up using too much memory, half the size of the queue and garbage-collect the
-        // paths.
-        let mem_usage = frontier.len() * prefix_size + paths.estimate_heap_size();
-        if mem_usage >= mem_limit {
+        // If we end up using too much memory (or storing too many prefixes), shrink the queue(s)
+        // and garbage-collect the paths.  Queues are normally halved, but if we're adapting the
+        // queue limit to a memory budget then we only drop enough prefixes to get below the limit.
+        let queue_len = suspended_len + frontier.len();
+        let mem_usage = queue_len * prefix_size + paths.estimate_heap_size();
+        if mem_usage >= mem_limit || queue_limit.is_some_and(|limit| queue_len > limit) {
             send_progress_update!(truncating_queue = true);
-            truncate_queue(frontier.len() / 2, &mut frontier);
-            paths.gc(frontier.iter().map(|prefix| prefix.path_head()));
+            truncated_queue = true;
+            let budget_target_len = match (memory_budget, queue_limit) {
+                (Some(_), Some(limit)) if mem_usage < mem_limit => {
+                    Some((limit as f64 * BUDGET_TRUNCATION_FRACTION) as usize)
+                }
+                _ => None,
+            };
+            for frontier in &mut frontiers {
+                let new_len = match budget_target_len {
+                    // Shrink every frontier by the same proportion.  `queue_len` is non-zero,
+                    // because it's over the queue limit
+                    Some(target_len) => frontier.len() * target_len / queue_len,
+                    None => frontier.len() / 2,
+                };
+                let num_removed = frontier.len().saturating_sub(new_len);
+                truncate_queue(new_len, frontier, search.config.truncation_policy);
+                counters.add_truncation(num_removed);
+            }
+            suspended_len = suspended_len_of(&frontiers, frontier_idx);
+            paths.gc(path_heads(&frontiers));
+            if let Some(budget) = memory_budget {
+                queue_limit = limit_for_budget(budget, &frontiers, &paths).or(queue_limit);
+                search.set_queue_limit(queue_limit);
+            }
             send_progress_update!(truncating_queue = false);
         }
 
         iter_count += 1;
 
-        // Check for abort every so often
-        if iter_count % ITERS_BETWEEN_ABORT_CHECKS == 0 && abort_flag.load(Ordering::Relaxed) {
-            send_progress_update!(truncating_queue = false);
-            break;
+        // Check for abort (or changes to the search's controls) every so often
+        if iter_count % ITERS_BETWEEN_ABORT_CHECKS == 0 {
+            if abort_flag.load(Ordering::Relaxed) {
+                send_progress_update!(truncating_queue = false);
+                break;
+            }
+            queue_limit = search.queue_limit();
+            target_num_comps = search.num_comps();
+            if search.params.shortlist.is_none() && num_comps >= target_num_comps {
+                break; // `num_comps` has been lowered below the number we've already generated
+            }
+            while shortlist.len() > target_num_comps {
+                shortlist.pop(); // `num_comps` has been lowered, so shorten the shortlist
+            }
         }
         // Send stats every so often
         if iter_count % ITERS_BETWEEN_PROGRESS_UPDATES == 0 {
@@ -103,7 +252,7 @@ pub(crate) fn search(search: &Search, mut update_fn: impl FnMut(Update), abort_f
         // otherwise the collection structure will keep expanding even if it contains a load of
         // dead paths)
         if iter_count % ITERS_BETWEEN_PATH_GCS == 0 {
-            paths.gc(frontier.iter().map(|prefix| prefix.path_head()));
+            paths.gc(path_heads(&frontiers));
         }
     }
 
@@ -112,17 +261,28 @@ pub(crate) fn search(search: &Search, mut update_fn: impl FnMut(Update), abort_f
 
     // If we're running the CLI, then `mem::forget` the frontier to avoid tons of drop calls.  We
     // don't care about leaking because the Monument process is about to terminate and the OS will
-    // clean up the memory anyway.
-    if search.config.leak_search_memory {
-        std::mem::forget(frontier);
+    // clean up the memory anyway.  This is never true in WebAssembly, where the 'process' is a web
+    // page which may run many more searches.
+    if search.config.leak_search_memory && !cfg!(target_arch = "wasm32") {
+        std::mem::forget(frontiers);
     }
 
-    // Signal that the search is complete
-    update_fn(Update::Complete);
+    // Signal that the search is complete, explaining why if nothing was found
+    search.send_final_updates(
+        &mut update_fn,
+        &counters,
+        num_comps,
+        truncated_queue,
+        abort_flag.load(Ordering::SeqCst),
+    );
 }
 
-fn send_progress_update(
-    frontier: &BinaryHeap<CompPrefix>,
+#[allow(clippy::too_many_arguments)]
+pub(super) fn send_progress_update<'a>(
+    search: &Search,
+    queue: impl IntoIterator<Item = &'a CompPrefix>,
+    paths: &Paths,
+    mem_limit: Option<usize>,
     update_fn: &mut impl FnMut(Update),
     iter_count: usize,
     num_comps: usize,
@@ -131,33 +291,261 @@ fn send_progress_update(
 ) {
     let mut total_len = 0u64; // NOTE: We have use `u64` here to avoid overflow
     let mut max_length = TotalLength::ZERO;
-    frontier.iter().for_each(|n| {
+    let mut queue_len = 0;
+    let mut prefix_bytes = 0;
+    queue.into_iter().for_each(|n| {
+        queue_len += 1;
         total_len += n.length().as_usize() as u64;
         max_length = max_length.max(n.length());
+        prefix_bytes += n.size();
     });
     update_fn(Update::Progress(Progress {
         iter_count,
         num_comps,
 
-        queue_len: frontier.len(),
-        avg_length: if frontier.is_empty() {
+        queue_len,
+        avg_length: if queue_len == 0 {
             0.0 // Avoid returning `NaN` if the frontier is empty
         } else {
-            total_len as f32 / frontier.len() as f32
+            total_len as f32 / queue_len as f32
         },
         max_length: max_length.as_usize(),
 
+        queue_bytes: prefix_bytes + paths.estimate_heap_size(),
+        graph_bytes: search.graph_bytes,
+        mem_limit,
+
         truncating_queue,
         aborting,
     }));
 }
 
-fn truncate_queue<T: Ord>(len: usize, queue: &mut BinaryHeap<T>) {
+/// Total length of every frontier other than `frontiers[current_idx]`
+fn suspended_len_of(frontiers: &[BinaryHeap<CompPrefix>], current_idx: usize) -> usize {
+    let total_len = frontiers.iter().map(BinaryHeap::len).sum::<usize>();
+    total_len - frontiers[current_idx].len()
+}
+
+/// Measure how much memory each prefix in `frontiers` really uses (including its share of `paths`),
+/// and return the number of prefixes which would fit into `budget` bytes.  Returns `None` if the
+/// frontiers are empty, because then there's nothing to measure.
+fn limit_for_budget(
+    budget: usize,
+    frontiers: &[BinaryHeap<CompPrefix>],
+    paths: &Paths,
+) -> Option<usize> {
+    let queue_len = frontiers.iter().map(BinaryHeap::len).sum::<usize>();
+    if queue_len == 0 {
+        return None;
+    }
+    let prefix_bytes = frontiers
+        .iter()
+        .flatten()
+        .map(CompPrefix::size)
+        .sum::<usize>();
+    let bytes_per_prefix = (prefix_bytes + paths.estimate_heap_size()) / queue_len;
+    let queue_limit = budget / bytes_per_prefix.max(1);
+    log::debug!(
+        "Adapting queue limit to {} prefixes ({}B each)",
+        BigNumInt(queue_limit),
+        bytes_per_prefix,
+    );
+    Some(queue_limit)
+}
+
+fn path_heads(frontiers: &[BinaryHeap<CompPrefix>]) -> impl Iterator<Item = PathId> + '_ {
+    frontiers.iter().flatten().map(|prefix| prefix.path_head())
+}
+
+fn truncate_queue(len: usize, queue: &mut BinaryHeap<CompPrefix>, policy: TruncationPolicy) {
     let heap = std::mem::take(queue);
     let mut chunks = heap.into_vec();
     chunks.sort_by(|a, b| b.cmp(a)); // Sort highest score first
     if len < chunks.len() {
+        if policy == TruncationPolicy::Stratified {
+            chunks = stratify(chunks);
+        }
         chunks.drain(len..);
     }
     *queue = BinaryHeap::from(chunks);
 }
+
+/// Reorder `prefixes` (which are sorted best first) so that any number of prefixes taken from the
+/// front contains the same proportion of every stratum (i.e. every combination of part head and
+/// range of lengths).  Within each stratum, the prefixes stay sorted best first.
+fn stratify(prefixes: Vec<CompPrefix>) -> Vec<CompPrefix> {
+    let min_length = prefixes.iter().map(CompPrefix::length).min();
+    let max_length = prefixes.iter().map(CompPrefix::length).max();
+    let (Some(min_length), Some(max_length)) = (min_length, max_length) else {
+        return prefixes; // No prefixes to reorder
+    };
+    let length_range = max_length.as_usize() - min_length.as_usize() + 1;
+    let stratum_of = |prefix: &CompPrefix| {
+        let length_offset = prefix.length().as_usize() - min_length.as_usize();
+        (
+            prefix.part_head(),
+            length_offset * NUM_LENGTH_STRATA / length_range,
+        )
+    };
+
+    let mut stratum_sizes = HashMap::<_, usize>::new();
+    for prefix in &prefixes {
+        *stratum_sizes.entry(stratum_of(prefix)).or_default() += 1;
+    }
+    // Order every prefix by how far down its stratum it is, as a fraction of that stratum's size.
+    // The sort is stable, so ties are still broken by score
+    let mut ranks_so_far = HashMap::<_, usize>::new();
+    let mut keyed_prefixes = prefixes
+        .into_iter()
+        .map(|prefix| {
+            let stratum = stratum_of(&prefix);
+            let rank = ranks_so_far.entry(stratum).or_default();
+            let key = *rank as f64 / stratum_sizes[&stratum] as f64;
+            *rank += 1;
+            (OrderedFloat(key), prefix)
+        })
+        .collect::<Vec<_>>();
+    keyed_prefixes.sort_by_key(|(key, _)| *key);
+    keyed_prefixes
+        .into_iter()
+        .map(|(_, prefix)| prefix)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BinaryHeap, HashMap};
+
+    use bellframe::Stage;
+    use itertools::Itertools;
+
+    use super::{stratify, truncate_queue};
+    use crate::{
+        group::PartHead,
+        search::{path::Paths, prefix::CompPrefix, Search, TruncationPolicy},
+        test_utils::multipart_plain_bob_params,
+        utils::lengths::TotalLength,
+        Config,
+    };
+
+    /// Creates prefixes (sorted best first) in three part heads and two very different ranges of
+    /// lengths.  Each stratum has a different size, and their priorities are interleaved.
+    fn prefixes() -> Vec<CompPrefix> {
+        let params = multipart_plain_bob_params(Stage::MINOR, "134256");
+        let part_heads = (params.part_head_group.rows())
+            .map(|row| params.part_head_group.get_part_head(row).unwrap())
+            .collect_vec();
+        assert_eq!(part_heads.len(), 3);
+        let search = Search::new(params, Config::default()).unwrap();
+        let template = CompPrefix::starts(&search, &mut Paths::new()).remove(0);
+
+        let strata_sizes = [(0, 0, 40), (0, 1, 20), (1, 0, 12), (2, 1, 8), (2, 0, 1)];
+        let mut prefixes = Vec::new();
+        for (ph_idx, length_idx, size) in strata_sizes {
+            for i in 0..size {
+                let priority = ((i * 37 + ph_idx * 11 + length_idx * 5) % 101) as f32;
+                let length = TotalLength::new(100 + length_idx * 1000 + i);
+                prefixes.push(template.with_key(priority, length, part_heads[ph_idx]));
+            }
+        }
+        prefixes.sort_by(|a, b| b.cmp(a));
+        prefixes
+    }
+
+    /// The stratum of a prefix created by [`prefixes`]: its part head, and whether it's in the
+    /// long range of lengths
+    fn stratum(prefix: &CompPrefix) -> (PartHead, bool) {
+        (prefix.part_head(), prefix.length().as_usize() >= 1000)
+    }
+
+    /// The priorities of `prefixes` in each stratum, in the same order as `prefixes`
+    fn priorities_by_stratum(prefixes: &[CompPrefix]) -> HashMap<(PartHead, bool), Vec<f32>> {
+        prefixes
+            .iter()
+            .map(|p| (stratum(p), p.ordering_key().0))
+            .into_group_map()
+    }
+
+    fn best_first(queue: BinaryHeap<CompPrefix>) -> Vec<CompPrefix> {
+        queue.into_sorted_vec().into_iter().rev().collect_vec()
+    }
+
+    #[test]
+    fn stratify_keeps_proportions() {
+        let prefixes = prefixes();
+        let num_prefixes = prefixes.len();
+        let stratum_sizes = prefixes.iter().map(stratum).counts();
+        assert_eq!(stratum_sizes.len(), 5);
+
+        let stratified = stratify(prefixes);
+        assert_eq!(stratified.len(), num_prefixes);
+        // However many prefixes are taken from the front, every stratum has had the same fraction
+        // of its prefixes taken (to within one prefix)
+        let mut counts = HashMap::<_, usize>::new();
+        for (idx, prefix) in stratified.iter().enumerate() {
+            *counts.entry(stratum(prefix)).or_default() += 1;
+            let fraction_taken = |stratum, extra: f64| {
+                let count = counts.get(stratum).copied().unwrap_or(0) as f64;
+                (count + extra) / stratum_sizes[stratum] as f64
+            };
+            for (s1, s2) in stratum_sizes.keys().tuple_combinations() {
+                assert!(
+                    fraction_taken(s1, -1.0) <= fraction_taken(s2, 0.0)
+                        && fraction_taken(s2, -1.0) <= fraction_taken(s1, 0.0),
+                    "Strata {s1:?} and {s2:?} are out of proportion after {} prefixes",
+                    idx + 1
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn stratify_keeps_strata_best_first() {
+        let prefixes = prefixes();
+        let original_priorities = priorities_by_stratum(&prefixes);
+        // `prefixes` is sorted best first, so every stratum should be too
+        assert_eq!(
+            priorities_by_stratum(&stratify(prefixes)),
+            original_priorities
+        );
+    }
+
+    #[test]
+    fn truncate_keep_best() {
+        let prefixes = prefixes();
+        let num_prefixes = prefixes.len();
+        let best_priorities = (prefixes[..30].iter())
+            .map(|p| p.ordering_key().0)
+            .collect_vec();
+        let mut queue = BinaryHeap::from(prefixes);
+
+        // The queue is only truncated if it's too long
+        truncate_queue(100, &mut queue, TruncationPolicy::KeepBest);
+        assert_eq!(queue.len(), num_prefixes);
+        // Otherwise, only the best prefixes are kept
+        truncate_queue(30, &mut queue, TruncationPolicy::KeepBest);
+        let kept_priorities = (best_first(queue).iter())
+            .map(|p| p.ordering_key().0)
+            .collect_vec();
+        assert_eq!(kept_priorities, best_priorities);
+    }
+
+    #[test]
+    fn truncate_stratified() {
+        let prefixes = prefixes();
+        let original_priorities = priorities_by_stratum(&prefixes);
+        let mut queue = BinaryHeap::from(prefixes);
+
+        // Keep a third of the prefixes
+        truncate_queue(27, &mut queue, TruncationPolicy::Stratified);
+        assert_eq!(queue.len(), 27);
+        let kept_priorities = priorities_by_stratum(&best_first(queue));
+        for (stratum, priorities) in original_priorities {
+            let kept = kept_priorities.get(&stratum).map_or(&[][..], Vec::as_slice);
+            // Every stratum keeps about a third of its prefixes ...
+            assert!((kept.len() as f64 - priorities.len() as f64 / 3.0).abs() <= 1.0);
+            // ... and they're that stratum's best prefixes
+            assert_eq!(kept, &priorities[..kept.len()]);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::composition::ParamsData;
+
+use super::{
+    best_first::{
+        send_progress_update, ITERS_BETWEEN_ABORT_CHECKS, ITERS_BETWEEN_PROGRESS_UPDATES,
+    },
+    path::Paths,
+    prefix::CompPrefix,
+    stats::Counters,
+    Search, Update,
+};
+
+/// Depth-first search creates path nodes much faster than best-first search frees them, so we
+/// garbage-collect the paths much more often
+const ITERS_BETWEEN_PATH_GCS: usize = 10_000_000;
+
+/// Enumerates every [`Composition`](crate::Composition) in a [`Graph`](m_gr::Graph), regardless of
+/// score.  Unlike [`best_first::search`](super::best_first::search), this ignores `num_comps`,
+/// `shortlist` and any limits on memory or queue size: the search only stops once every prefix
+/// has been expanded (or the search is aborted).
+pub(crate) fn search(search: &Search, mut update_fn: impl FnMut(Update), abort_flag: &AtomicBool) {
+    let param_data = ParamsData::new(&search.params);
+
+    // Expand prefixes depth-first, so that the stack only has to hold the successors of the
+    // prefixes along the current path (rather than the whole breadth of the search)
+    let mut paths = Paths::new();
+    let mut stack = CompPrefix::starts(search, &mut paths);
+    stack.reverse(); // Explore the start chunks in order
+
+    let mut iter_count = 0;
+    let mut num_comps = 0;
+    let mut counters = Counters::new(search);
+
+    macro_rules! send_progress_update {
+        () => {
+            send_progress_update(
+                search,
+                &stack,
+                &paths,
+                None,
+                &mut update_fn,
+                iter_count,
+                num_comps,
+                false,
+                abort_flag.load(Ordering::SeqCst),
+            );
+        };
+    }
+
+    // Send 'empty' update before search starts
+    send_progress_update!();
+
+    while let Some(prefix) = stack.pop() {
+        if let Some(comp) =
+            prefix.expand(search, &mut paths, &mut stack, &param_data, &mut counters)
+        {
+            update_fn(Update::Comp(comp));
+            num_comps += 1;
+        }
+
+        iter_count += 1;
+
+        // Check for abort every so often
+        if iter_count % ITERS_BETWEEN_ABORT_CHECKS == 0 && abort_flag.load(Ordering::Relaxed) {
+            break;
+        }
+        // Send stats every so often
+        if iter_count % ITERS_BETWEEN_PROGRESS_UPDATES == 0 {
+            send_progress_update!();
+        }
+        // Garbage-collect the paths every so often, because otherwise they'll keep every prefix
+        // we've ever explored
+        if iter_count % ITERS_BETWEEN_PATH_GCS == 0 {
+            paths.gc(stack.iter().map(CompPrefix::path_head));
+        }
+    }
+
+    // Always send a final update before finishing
+    send_progress_update!();
+    search.send_final_updates(
+        &mut update_fn,
+        &counters,
+        num_comps,
+        false,
+        abort_flag.load(Ordering::SeqCst),
+    );
+}
@@ -1,16 +1,26 @@
-use std::collections::HashMap;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+    ops::Range,
+};
 
 use crate::{
     graph::{CallSeqIdx, LinkSide},
     group::{PartHead, PhRotation},
-    parameters::{CallIdx, Parameters},
+    parameters::{CallIdx, MusicTypeVec, Parameters},
     utils::{
+        bit_set::BitSet,
         counts::Counts,
         lengths::{PerPartLength, TotalLength},
+        FrontierItem, Rng,
     },
 };
 
+use bellframe::{music::AtRowPositions, RowBuf};
 use bit_vec::BitVec;
+use datasize::DataSize;
+use index_vec::IndexSlice;
+use itertools::Itertools;
 
 use super::atw::{AtwBitmap, AtwTable};
 
@@ -19,7 +29,29 @@ use super::atw::{AtwBitmap, AtwTable};
 #[derive(Debug, Clone)]
 pub(super) struct Graph {
     pub starts: StartVec<(ChunkIdx, crate::graph::LinkId, PartHead)>,
+    /// The chunks in the graph, in breadth-first order from the starts so that chunks which
+    /// follow each other in a composition are usually close together in memory
     pub chunks: ChunkVec<Chunk>,
+    /// The successor links of every chunk, stored contiguously in the order of
+    /// [`chunks`](Self::chunks) (see [`Graph::succs`])
+    pub succ_links: Vec<SuccLink>,
+    /// The distinct sets of chunks which are false against some chunk.  Chunks with exactly the
+    /// same falseness refer to the same set by index.  Every chunk is false against itself, so
+    /// such sharing is rare in practice (e.g. 582 chunks have 582 distinct sets for a quarter of
+    /// Cambridge Major, and 2518 chunks have 2517 for a 3-spliced Surprise Major peal) and this
+    /// doesn't noticeably reduce memory usage.
+    pub falseness_sets: FalsenessVec<BitSet>,
+    /// The number of distinct courses (up to part head transposition) which contain chunks
+    pub num_courses: usize,
+    /// For [`Parameters::extent`] searches, the chunks containing each row (rows contained by the
+    /// same chunks share a set).  Every extent contains exactly one chunk from each set.
+    pub extent_chunk_sets: Vec<Vec<ChunkIdx>>,
+    /// The minimum counts of every [`MusicType`](crate::parameters::MusicType) which has one.
+    /// All the music counts in the graph refer to these music types, in this order.
+    pub min_music_counts: Vec<usize>,
+    /// If rows can't be repeated, an upper bound on the score of any composition with a given
+    /// number of rows.  This is `None` if rows can be repeated.
+    pub score_table: Option<ScoreTable>,
 }
 
 #[derive(Debug, Clone)]
@@ -27,20 +59,53 @@ pub(super) struct Chunk {
     pub id: crate::graph::ChunkId,
 
     pub score: f32,
+    /// The part of `score` which comes from music
+    pub music_score: f32,
 
     pub per_part_length: PerPartLength, // PERF: Not used in search
     pub total_length: TotalLength,
     pub method_counts: Counts,
+    /// The counts of each music type in [`Graph::min_music_counts`] in this chunk
+    pub music_counts: Counts,
+    /// The most of each music type in [`Graph::min_music_counts`] on any of this chunk's
+    /// successor links
+    pub max_link_music_counts: Counts,
+    /// For each music type in [`Graph::min_music_counts`], the highest density of that music in
+    /// any chunk which can come after this one
+    pub music_densities_after: Vec<MusicDensity>,
+    /// An upper bound on the average score per row of any sequence of chunks which starts with
+    /// `self` and ends in rounds (ignoring atw scores).  This is used as the A* heuristic (see
+    /// [`Config::a_star`](crate::Config::a_star)).
+    pub max_score_per_row: f32,
     /// Minimum number of rows required to go from the end of `self` to rounds
     pub min_len_to_rounds: TotalLength,
+    /// `true` if this chunk contains no non-duffer music (see
+    /// [`Parameters::non_duffer_music`](crate::Parameters::non_duffer_music)).  This is always
+    /// `false` unless
+    /// [`Parameters::max_duffer_rows`](crate::Parameters::max_duffer_rows) is set.
+    pub duffer: bool,
+    /// A lower bound on the number of duffer rows (in one part) which must follow `self` before
+    /// reaching either a non-duffer chunk or the end of the composition
+    pub min_duffer_len_after: PerPartLength,
+    /// Index of the course containing this chunk.  Chunks in the same course (in any part) share
+    /// the same index.
+    pub course_idx: usize,
 
-    // Indices must be aligned with those from the source graph
-    pub succs: SuccVec<SuccLink>,
-    // If this chunk is added to a composition, these bits denote the set of chunks will be marked
-    // as unreachable.  This includes `Self`, because every chunk is guaranteed to be false against
-    // itself.
-    pub falseness: BitVec,
+    /// The range of [`Graph::succ_links`] containing this chunk's successors.  Indices must be
+    /// aligned with those from the source graph.
+    pub succ_range: Range<u32>,
+    // If this chunk is added to a composition, the chunks in this set (in
+    // `Graph::falseness_sets`) will be marked as unreachable.  This includes `Self`, because every
+    // chunk is guaranteed to be false against itself.
+    pub falseness: FalsenessIdx,
     pub atw_bitmap: AtwBitmap,
+    /// For every [`Parameters::required_rows`], this contains `1` if that row is rung by this
+    /// chunk
+    pub required_rows: BitVec,
+    /// Indices of the [`Graph::extent_chunk_sets`] which this chunk's falseness could leave
+    /// without any ringable chunks.  This excludes sets containing `self`, since adding `self`
+    /// rings their rows.
+    pub extent_sets_to_check: Vec<usize>,
 }
 
 /// A link between a chunk and its successor
@@ -54,7 +119,83 @@ pub(super) struct SuccLink {
     /// These links have to be added in increasing order (skipping sections if the user allows).
     pub call_sequence_idx: Option<CallSeqIdx>,
     pub score: f32,
+    /// The number of rows rung during this link's call (see
+    /// [`Link::total_length`](crate::graph::Link::total_length))
+    pub length: TotalLength,
     pub ph_rotation: PhRotation,
+    /// The counts of each music type in [`Graph::min_music_counts`] which span this link
+    pub music_counts: Counts,
+    /// The part of `score` which comes from music
+    pub music_score: f32,
+}
+
+/// How much of some music a chunk contains per row of the composition, stored as a fraction so
+/// that the bounds computed from it are exact
+#[derive(Debug, Clone, Copy, Default)]
+pub(super) struct MusicDensity {
+    count: usize,
+    length: usize,
+}
+
+impl MusicDensity {
+    /// An upper bound on how much music can fit into `length` rows at this density
+    pub fn max_count_in(self, length: TotalLength) -> usize {
+        match (self.count, self.length) {
+            (0, _) => 0,
+            (_, 0) => usize::MAX, // Music with no rows is infinitely dense
+            (count, chunk_length) => count * length.as_usize() / chunk_length,
+        }
+    }
+
+    fn is_denser_than(self, other: Self) -> bool {
+        self.count * other.length > other.count * self.length
+    }
+}
+
+/// An upper bound on how much score can be generated by any number of rows, if every chunk can
+/// only be used once.  This is computed like a fractional knapsack problem: the chunks are sorted
+/// by decreasing score per row, and the bound for `n` rows is the score of the first `n` rows of
+/// those chunks.  Therefore, the bound is a concave function of the number of rows.
+#[derive(Debug, Clone)]
+pub(super) struct ScoreTable {
+    /// The cumulative `(length, score)` after each of the chunks with positive score, starting at
+    /// `(0, 0.0)`
+    cumulative: Vec<(usize, f32)>,
+}
+
+impl ScoreTable {
+    fn new(chunks: &ChunkVec<Chunk>, succ_links: &[SuccLink]) -> Self {
+        let mut cumulative = vec![(0, 0.0)];
+        let chunks_by_score = chunks
+            .iter()
+            .map(|chunk| {
+                let score_per_row = chunk.score_per_row(succ_links);
+                (chunk.total_length.as_usize(), score_per_row)
+            })
+            .filter(|&(_, score_per_row)| score_per_row > 0.0)
+            .sorted_by(|(_, s1), (_, s2)| s2.total_cmp(s1));
+        for (length, score_per_row) in chunks_by_score {
+            let (last_length, last_score) = *cumulative.last().unwrap();
+            cumulative.push((
+                last_length + length,
+                last_score + score_per_row * length as f32,
+            ));
+        }
+        Self { cumulative }
+    }
+
+    /// An upper bound on the score generated by `length` rows of distinct chunks
+    pub fn max_score_in(&self, length: usize) -> f32 {
+        let idx = self.cumulative.partition_point(|&(l, _)| l <= length);
+        let (prev_length, prev_score) = self.cumulative[idx - 1];
+        match self.cumulative.get(idx) {
+            Some(&(next_length, next_score)) => {
+                let frac = (length - prev_length) as f32 / (next_length - prev_length) as f32;
+                prev_score + (next_score - prev_score) * frac
+            }
+            None => prev_score, // Every chunk with positive score has been used
+        }
+    }
 }
 
 ///////////////////////////////////////////
@@ -66,38 +207,88 @@ impl Graph {
         source_graph: &crate::graph::Graph,
         params: &Parameters,
         atw_table: &AtwTable,
+        seed: u64,
     ) -> Self {
         log::debug!("Lowering graph");
         let num_chunks = source_graph.chunks.len();
 
+        // The search only tracks the music types with minimum counts, so it can prune prefixes
+        // which can't reach those minimums
+        let min_music_types = params
+            .music_types
+            .iter_enumerated()
+            .filter_map(|(idx, mt)| Some((idx, mt.count_range.min.filter(|&min| min > 0)?)))
+            .collect_vec();
+        let min_music_counts_of = |counts: &MusicTypeVec<AtRowPositions<usize>>| -> Counts {
+            min_music_types
+                .iter()
+                .map(|&(idx, _)| params.music_types[idx].masked_total(counts[idx]))
+                .collect_vec()
+                .into()
+        };
+        let music_score_of = |counts: &MusicTypeVec<AtRowPositions<usize>>| -> f32 {
+            (params.music_types.iter().zip_eq(counts))
+                .map(|(music_type, counts)| music_type.as_overall_score(*counts))
+                .sum()
+        };
+
         // Assign each chunk ID to a unique `ChunkIdx`, and vice versa.  This way, we can now label
-        // the set of chunks with numbers that can be used to index into a BitVec for falseness
-        // computation.
+        // the set of chunks with numbers that can be used to index into a BitSet for falseness
+        // computation.  Chunks are ordered breadth-first from the starts, so that the search
+        // mostly reads chunks which are close together in memory.  The order doesn't depend on
+        // `HashMap` iteration order, which would make the search non-deterministic.
         let mut index_to_id = ChunkVec::<(crate::graph::ChunkId, &crate::graph::Chunk)>::new();
         let mut id_to_index = HashMap::<crate::graph::ChunkId, ChunkIdx>::new();
-        for (id, chunk) in &source_graph.chunks {
-            let index = index_to_id.push((id.to_owned(), chunk));
+        for id in breadth_first_chunk_order(source_graph) {
+            let index = index_to_id.push((id.to_owned(), &source_graph.chunks[id]));
             id_to_index.insert(id.to_owned(), index);
         }
 
-        // Now convert chunks from `monument_graph::Chunk` to `self::Chunk`
-        let chunks: ChunkVec<_> = (0..num_chunks)
+        // Now convert chunks from `monument_graph::Chunk` to `self::Chunk`.  The search explores
+        // equally good prefixes in the order that their successors/starts are listed, so we
+        // shuffle those with a seeded RNG.  This way, ties are broken differently by each seed,
+        // but the same seed always gives the same search.
+        let mut rng = Rng::new(seed);
+        let mut course_indices = HashMap::<RowBuf, usize>::new();
+        let mut falseness_sets = FalsenessVec::<BitSet>::new();
+        let mut falseness_set_indices = HashMap::<Vec<ChunkIdx>, FalsenessIdx>::new();
+        let mut succ_links = Vec::<SuccLink>::new();
+        let mut chunks: ChunkVec<_> = (0..num_chunks)
             .map(|index| {
                 // Get the source chunk and its ChunkId
                 let index = ChunkIdx::new(index);
                 let (id, source_chunk) = index_to_id[index].clone();
 
-                // Generate a BitVec with a 1 for every chunk which is false against this chunk
-                let mut falseness = BitVec::from_elem(num_chunks, false);
-                for false_id in &source_chunk.false_chunks {
-                    let false_chunk_idx = id_to_index[false_id];
-                    falseness.set(false_chunk_idx.index(), true);
+                // Find the set of chunks which are false against this chunk, only creating a new
+                // `BitSet` if no other chunk has exactly the same falseness
+                let false_chunks = (source_chunk.false_chunks.iter())
+                    .map(|false_id| id_to_index[false_id])
+                    .sorted()
+                    .dedup()
+                    .collect_vec();
+                let falseness = *falseness_set_indices
+                    .entry(false_chunks)
+                    .or_insert_with_key(|false_chunks| {
+                        let mut set = BitSet::empty(num_chunks);
+                        for false_chunk_idx in false_chunks {
+                            set.insert(false_chunk_idx.index());
+                        }
+                        falseness_sets.push(set)
+                    });
+                // Find which course this chunk is in
+                let course_head = params.normalised_course_head(id.method, &id.lead_head);
+                let num_courses = course_indices.len();
+                let course_idx = *course_indices.entry(course_head).or_insert(num_courses);
+                // Mark which required rows are rung by this chunk
+                let mut required_rows = BitVec::from_elem(params.required_rows.len(), false);
+                for &row_idx in &source_chunk.required_rows {
+                    required_rows.set(row_idx, true);
                 }
                 // Compute the bitmap form of this chunk's atw statistics
                 let atw_bitmap =
                     atw_table.bitmap_for_chunk(params, &id, source_chunk.per_part_length);
                 // Compute successors
-                let succs = source_chunk
+                let mut succs = source_chunk
                     .successors
                     .iter()
                     .filter_map(|link_id| {
@@ -108,24 +299,57 @@ impl Graph {
                         };
                         Some(SuccLink {
                             call: link.call,
-                            score: link_score(source_chunk, link, params),
+                            score: link.score(source_chunk, params),
+                            length: link.total_length,
                             call_sequence_idx: link.call_sequence_idx,
                             next,
                             ph_rotation: link.ph_rotation,
+                            music_counts: min_music_counts_of(&link.music_counts),
+                            music_score: music_score_of(&link.music_counts),
                         })
                     })
-                    .collect();
+                    .collect::<SuccVec<_>>();
+                let mut max_link_music_counts = Counts::zeros(min_music_types.len());
+                for succ in &succs {
+                    for i in 0..min_music_types.len() {
+                        max_link_music_counts[i] =
+                            max_link_music_counts[i].max(succ.music_counts[i]);
+                    }
+                }
+                // Sort by `ChunkId` (not `ChunkIdx`) so that the shuffle doesn't depend on the
+                // order of the chunks
+                succs.sort_by_key(|succ| {
+                    (
+                        succ.next.map_ref(|&idx| &index_to_id[idx].0),
+                        succ.call,
+                        succ.call_sequence_idx,
+                        succ.ph_rotation,
+                    )
+                });
+                rng.shuffle(succs.as_raw_slice_mut());
+                let succ_range = succ_links.len() as u32..(succ_links.len() + succs.len()) as u32;
+                succ_links.extend(succs);
 
                 Chunk {
                     per_part_length: source_chunk.per_part_length,
                     total_length: source_chunk.total_length,
                     method_counts: source_chunk.method_counts.clone(),
+                    music_counts: min_music_counts_of(&source_chunk.music_counts),
+                    max_link_music_counts,
+                    music_densities_after: Vec::new(), // Filled in later
+                    max_score_per_row: 0.0,            // Filled in later
                     min_len_to_rounds: source_chunk.lb_distance_to_rounds,
+                    duffer: params.max_duffer_rows.is_some() && source_chunk.is_duffer(params),
+                    min_duffer_len_after: PerPartLength::ZERO, // Filled in later
+                    course_idx,
                     score: source_chunk.score,
+                    music_score: music_score_of(&source_chunk.music_counts),
 
-                    succs,
+                    succ_range,
                     falseness,
                     atw_bitmap,
+                    required_rows,
+                    extent_sets_to_check: Vec::new(), // Filled in later
 
                     id,
                 }
@@ -148,48 +372,353 @@ impl Graph {
             }
         }
 
+        starts.sort_by_key(|&(chunk_idx, _link_id, part_head)| {
+            (index_to_id[chunk_idx].0.clone(), part_head)
+        });
+        rng.shuffle(starts.as_raw_slice_mut());
+
+        // For extents, find which row sets each chunk's falseness could make unreachable
+        let extent_chunk_sets = source_graph
+            .extent_chunk_sets
+            .iter()
+            .map(|set| {
+                set.iter()
+                    .filter_map(|id| id_to_index.get(id).copied())
+                    .collect_vec()
+            })
+            .collect_vec();
+        let mut sets_containing_chunk = ChunkVec::<Vec<usize>>::from_vec(vec![vec![]; num_chunks]);
+        for (set_idx, set) in extent_chunk_sets.iter().enumerate() {
+            for &chunk_idx in set {
+                sets_containing_chunk[chunk_idx].push(set_idx);
+            }
+        }
+        for (chunk_idx, chunk) in chunks.iter_mut_enumerated() {
+            let own_sets = &sets_containing_chunk[chunk_idx];
+            chunk.extent_sets_to_check = falseness_sets[chunk.falseness]
+                .iter_ones()
+                .flat_map(|false_idx| &sets_containing_chunk[ChunkIdx::new(false_idx)])
+                .filter(|set_idx| !own_sets.contains(set_idx))
+                .copied()
+                .sorted()
+                .dedup()
+                .collect_vec();
+        }
+
+        if params.max_duffer_rows.is_some() {
+            compute_duffer_distances(&mut chunks, &succ_links);
+        }
+        compute_music_densities(&mut chunks, &succ_links, min_music_types.len());
+        compute_max_scores_per_row(&mut chunks, &succ_links);
+        let score_table = params
+            .rows_are_unique()
+            .then(|| ScoreTable::new(&chunks, &succ_links));
+
+        log::debug!(
+            "{} chunks share {} distinct falseness sets",
+            chunks.len(),
+            falseness_sets.len()
+        );
         log::debug!("Finished lowering graph");
 
-        Graph { starts, chunks }
-    }
-}
-
-/// Gets the total [`Score`] generated by a given [`Link`].  For end links, this **doesn't**
-/// include the [`Score`] from splices over the part end.
-fn link_score(
-    source_chunk: &crate::graph::Chunk,
-    link: &crate::graph::Link,
-    params: &Parameters,
-) -> f32 {
-    let is_splice = match (&link.from, &link.to) {
-        // A link between chunks is a splice iff c2's RowIdx directly
-        // follows from c1's (i.e. it's the same method and is one row
-        // later).  For example:
-        // - (Bristol, 16) -> (Bristol, 17)   isn't a splice
-        // - (Bristol, 31) -> (Bristol, 0)    isn't a splice (it wraps round the lead end)
-        // - (Bristol, 31) -> (Cambridge, 0)  **is** a splice (method changes)
-        // - (Bristol, 17) -> (Bristol, 0)    **is** a splice (it skips half a lead)
-        (LinkSide::Chunk(c1), LinkSide::Chunk(c2)) => {
-            let sub_lead_idx_after_prev_chunk = params.methods[c1.method]
-                .add_sub_lead_idx(c1.sub_lead_idx, source_chunk.per_part_length);
-            let is_continuation =
-                c1.method == c2.method && sub_lead_idx_after_prev_chunk == c2.sub_lead_idx;
-            !is_continuation
-        }
-        // If either side is a start/end, then no splice occurs
-        _ => false,
-    };
-    let call_weight = match link.call {
-        Some(idx) => params.calls[idx].weight,
-        None => 0.0, // Plain leads have no weight
-    };
-    let splice_weight = if is_splice { params.splice_weight } else { 0.0 };
-    (call_weight + splice_weight) * params.num_parts() as f32
+        Graph {
+            starts,
+            chunks,
+            succ_links,
+            falseness_sets,
+            num_courses: course_indices.len(),
+            extent_chunk_sets,
+            min_music_counts: min_music_types.into_iter().map(|(_, min)| min).collect(),
+            score_table,
+        }
+    }
+}
+
+impl Graph {
+    /// The successor links of `chunk`, indexed by [`SuccIdx`]
+    pub fn succs(&self, chunk: &Chunk) -> &IndexSlice<SuccIdx, [SuccLink]> {
+        succs_of(&self.succ_links, chunk)
+    }
+
+    /// An estimate of the number of bytes of heap memory used by this `Graph`
+    pub fn estimate_heap_size(&self) -> usize {
+        let chunk_bytes = self
+            .chunks
+            .iter()
+            .map(|chunk| {
+                std::mem::size_of::<Chunk>()
+                    + chunk.method_counts.estimate_heap_size()
+                    + chunk.music_counts.estimate_heap_size()
+                    + chunk.max_link_music_counts.estimate_heap_size()
+                    + std::mem::size_of_val(chunk.music_densities_after.as_slice())
+                    + chunk.atw_bitmap.estimate_heap_size()
+                    + std::mem::size_of_val(chunk.required_rows.storage())
+                    + std::mem::size_of_val(chunk.extent_sets_to_check.as_slice())
+            })
+            .sum::<usize>();
+        let falseness_bytes = self
+            .falseness_sets
+            .iter()
+            .map(|set| std::mem::size_of::<BitSet>() + set.estimate_heap_size())
+            .sum::<usize>();
+        let extent_bytes = self
+            .extent_chunk_sets
+            .iter()
+            .map(|set| std::mem::size_of_val(set) + std::mem::size_of_val(set.as_slice()))
+            .sum::<usize>();
+        std::mem::size_of_val(self.starts.as_raw_slice())
+            + chunk_bytes
+            + std::mem::size_of_val(self.succ_links.as_slice())
+            + falseness_bytes
+            + extent_bytes
+            + self.score_table.as_ref().map_or(0, |table| {
+                std::mem::size_of_val(table.cumulative.as_slice())
+            })
+    }
+}
+
+/// The successor links of `chunk`, given the [`Graph::succ_links`] of every chunk
+fn succs_of<'l>(succ_links: &'l [SuccLink], chunk: &Chunk) -> &'l IndexSlice<SuccIdx, [SuccLink]> {
+    let range = chunk.succ_range.start as usize..chunk.succ_range.end as usize;
+    IndexSlice::new(&succ_links[range])
+}
+
+/// Order the chunks of `source_graph` breadth-first from the start chunks, so that successors are
+/// usually stored close to their predecessors.  Ties are broken by [`ChunkId`], and chunks which
+/// can't be reached from a start are put at the end, so the order is deterministic.
+///
+/// [`ChunkId`]: crate::graph::ChunkId
+fn breadth_first_chunk_order(source_graph: &crate::graph::Graph) -> Vec<&crate::graph::ChunkId> {
+    let mut order = Vec::with_capacity(source_graph.chunks.len());
+    let mut visited = HashSet::<&crate::graph::ChunkId>::new();
+    let mut frontier = VecDeque::new();
+    let start_ids = source_graph.starts.iter().map(|(_link_id, id)| id).sorted();
+    for id in start_ids {
+        if source_graph.chunks.contains_key(id) && visited.insert(id) {
+            frontier.push_back(id);
+        }
+    }
+    while let Some(id) = frontier.pop_front() {
+        order.push(id);
+        let succ_ids = source_graph.chunks[id]
+            .successors
+            .iter()
+            .filter_map(|link_id| match &source_graph.links.get(*link_id)?.to {
+                LinkSide::Chunk(succ_id) => Some(succ_id),
+                LinkSide::StartOrEnd => None,
+            })
+            .sorted();
+        for succ_id in succ_ids {
+            // Dangling references to chunks outside the graph are ignored
+            if source_graph.chunks.contains_key(succ_id) && visited.insert(succ_id) {
+                frontier.push_back(succ_id);
+            }
+        }
+    }
+    // Unreachable chunks still need indices, because they can still be false against others
+    let unreached_ids = (source_graph.chunks.keys())
+        .filter(|id| !visited.contains(id))
+        .sorted();
+    order.extend(unreached_ids);
+    order
+}
+
+/// Compute [`Chunk::min_duffer_len_after`] for every chunk, by running Dijkstra's algorithm
+/// backwards from the chunks which can be followed by music (or the end of the composition)
+fn compute_duffer_distances(chunks: &mut ChunkVec<Chunk>, succ_links: &[SuccLink]) {
+    let mut preds = ChunkVec::<Vec<ChunkIdx>>::from_vec(vec![vec![]; chunks.len()]);
+    let mut frontier = BinaryHeap::<Reverse<FrontierItem<ChunkIdx, PerPartLength>>>::new();
+    for (idx, chunk) in chunks.iter_enumerated() {
+        let mut can_end_duffer_run = false;
+        for succ in succs_of(succ_links, chunk) {
+            match succ.next {
+                LinkSide::Chunk(succ_idx) => {
+                    preds[succ_idx].push(idx);
+                    can_end_duffer_run |= !chunks[succ_idx].duffer;
+                }
+                LinkSide::StartOrEnd => can_end_duffer_run = true,
+            }
+        }
+        if can_end_duffer_run {
+            frontier.push(Reverse(FrontierItem::new(idx, PerPartLength::ZERO)));
+        }
+    }
+
+    // Chunks which never reach the end of the composition keep a distance of zero, which is still
+    // a valid lower bound
+    let mut expanded = BitVec::from_elem(chunks.len(), false);
+    while let Some(Reverse(FrontierItem {
+        item: idx,
+        distance,
+    })) = frontier.pop()
+    {
+        if expanded.get(idx.index()).unwrap() {
+            continue; // Already expanded with a shorter (or equal) distance
+        }
+        expanded.set(idx.index(), true);
+        chunks[idx].min_duffer_len_after = distance;
+        // Any duffer chunk's distance continues on to its predecessors
+        let chunk = &chunks[idx];
+        if chunk.duffer {
+            let distance_before_chunk = distance + chunk.per_part_length;
+            for &pred_idx in &preds[idx] {
+                frontier.push(Reverse(FrontierItem::new(pred_idx, distance_before_chunk)));
+            }
+        }
+    }
+}
+
+/// Compute [`Chunk::music_densities_after`] for every chunk.  For each music type, chunks are
+/// visited from the densest to the least dense, and each chunk's density is propagated backwards
+/// to every chunk which can reach it (and hasn't already been reached by a denser chunk).
+fn compute_music_densities(
+    chunks: &mut ChunkVec<Chunk>,
+    succ_links: &[SuccLink],
+    num_music_types: usize,
+) {
+    for chunk in chunks.iter_mut() {
+        chunk.music_densities_after = vec![MusicDensity::default(); num_music_types];
+    }
+    if num_music_types == 0 {
+        return;
+    }
+
+    let mut preds = ChunkVec::<Vec<ChunkIdx>>::from_vec(vec![vec![]; chunks.len()]);
+    for (idx, chunk) in chunks.iter_enumerated() {
+        for succ in succs_of(succ_links, chunk) {
+            if let LinkSide::Chunk(succ_idx) = succ.next {
+                preds[succ_idx].push(idx);
+            }
+        }
+    }
+
+    for i in 0..num_music_types {
+        // Each chunk's density includes the music on its best successor link, since that's the
+        // only link which can follow it in a composition
+        let density_of = |chunk: &Chunk| MusicDensity {
+            count: chunk.music_counts[i] + chunk.max_link_music_counts[i],
+            length: chunk.total_length.as_usize(),
+        };
+        let chunks_by_density = chunks
+            .indices()
+            .filter(|&idx| density_of(&chunks[idx]).count > 0)
+            .sorted_by(|&idx1, &idx2| {
+                let (d1, d2) = (density_of(&chunks[idx1]), density_of(&chunks[idx2]));
+                (d2.count * d1.length).cmp(&(d1.count * d2.length))
+            })
+            .collect_vec();
+
+        let mut visited = BitVec::from_elem(chunks.len(), false);
+        for idx in chunks_by_density {
+            let density = density_of(&chunks[idx]);
+            // Every chunk which can reach `idx` has at least this density after it
+            let mut to_visit = preds[idx].clone();
+            while let Some(pred_idx) = to_visit.pop() {
+                if visited.get(pred_idx.index()).unwrap() {
+                    continue; // Already reaches a chunk which is at least as dense
+                }
+                visited.set(pred_idx.index(), true);
+                debug_assert!(!chunks[pred_idx].music_densities_after[i].is_denser_than(density));
+                chunks[pred_idx].music_densities_after[i] = density;
+                to_visit.extend_from_slice(&preds[pred_idx]);
+            }
+        }
+    }
+}
+
+impl Chunk {
+    /// An upper bound on the score per row of this chunk, including its best successor link.  If
+    /// that total is negative, then spreading it over the rows of the longest link as well still
+    /// gives an upper bound.
+    fn score_per_row(&self, succ_links: &[SuccLink]) -> f32 {
+        let succs = succs_of(succ_links, self);
+        let link_score = succs.iter().map(|l| l.score).reduce(f32::max);
+        let score = self.score + link_score.unwrap_or(0.0);
+        let mut length = self.total_length;
+        if score < 0.0 {
+            let max_link_length = succs.iter().map(|l| l.length).max();
+            length += max_link_length.unwrap_or(TotalLength::ZERO);
+        }
+        score / length.as_usize() as f32
+    }
+}
+
+/// Compute [`Chunk::max_score_per_row`] for every chunk.  Like [`compute_music_densities`],
+/// chunks are visited in decreasing order of score per row and that score is propagated to every
+/// chunk which can reach them.
+fn compute_max_scores_per_row(chunks: &mut ChunkVec<Chunk>, succ_links: &[SuccLink]) {
+    let mut preds = ChunkVec::<Vec<ChunkIdx>>::from_vec(vec![vec![]; chunks.len()]);
+    for (idx, chunk) in chunks.iter_enumerated() {
+        for succ in succs_of(succ_links, chunk) {
+            if let LinkSide::Chunk(succ_idx) = succ.next {
+                preds[succ_idx].push(idx);
+            }
+        }
+    }
+
+    let chunks_by_score = chunks
+        .indices()
+        .map(|idx| (idx, chunks[idx].score_per_row(succ_links)))
+        .sorted_by(|(_, s1), (_, s2)| s2.total_cmp(s1))
+        .collect_vec();
+
+    let mut visited = BitVec::from_elem(chunks.len(), false);
+    for (idx, score_per_row) in chunks_by_score {
+        // `idx` and every chunk which can reach it can score at least this much per row
+        let mut to_visit = vec![idx];
+        while let Some(pred_idx) = to_visit.pop() {
+            if visited.get(pred_idx.index()).unwrap() {
+                continue; // Already reaches a chunk which scores at least as much
+            }
+            visited.set(pred_idx.index(), true);
+            chunks[pred_idx].max_score_per_row = score_per_row;
+            to_visit.extend_from_slice(&preds[pred_idx]);
+        }
+    }
 }
 
 index_vec::define_index_type! { pub struct ChunkIdx = usize; }
 index_vec::define_index_type! { pub struct StartIdx = u32; }
 index_vec::define_index_type! { pub struct SuccIdx = u32; }
+index_vec::define_index_type! { pub struct FalsenessIdx = u32; }
 type ChunkVec<T> = index_vec::IndexVec<ChunkIdx, T>;
+type FalsenessVec<T> = index_vec::IndexVec<FalsenessIdx, T>;
 type StartVec<T> = index_vec::IndexVec<StartIdx, T>;
 type SuccVec<T> = index_vec::IndexVec<SuccIdx, T>;
+
+#[cfg(test)]
+mod tests {
+    use bellframe::Stage;
+
+    use super::{Chunk, SuccLink};
+    use crate::{search::Search, test_utils::plain_bob_params, Config};
+
+    /// Builds the search graph for Plain Bob on `stage`, returning `(search, heap size estimate)`
+    fn search_and_size(stage: Stage) -> (Search, usize) {
+        let search = Search::new(plain_bob_params(stage), Config::default()).unwrap();
+        let size = search.graph.estimate_heap_size();
+        (search, size)
+    }
+
+    #[test]
+    fn estimate_heap_size() {
+        let (minor, minor_size) = search_and_size(Stage::MINOR);
+        let (major, major_size) = search_and_size(Stage::MAJOR);
+        for (search, size) in [(&minor, minor_size), (&major, major_size)] {
+            // The estimate is the one reported in `Progress::graph_bytes`
+            assert_eq!(size, search.graph_bytes);
+            // Every chunk, link and falseness set must be counted
+            let graph = &search.graph;
+            let falseness_bytes = graph.falseness_sets.iter().map(|set| set.num_bytes());
+            let min_size = graph.chunks.len() * std::mem::size_of::<Chunk>()
+                + graph.succ_links.len() * std::mem::size_of::<SuccLink>()
+                + falseness_bytes.sum::<usize>();
+            assert!(size >= min_size, "{size} < {min_size}");
+            // ... but nothing else in the graph should be anywhere near as big
+            assert!(size < min_size * 4, "{size} >= 4 * {min_size}");
+        }
+        // Bigger graphs take more memory
+        assert!(major.graph.chunks.len() > minor.graph.chunks.len());
+        assert!(major_size > minor_size);
+    }
+}
@@ -2,30 +2,39 @@
 
 mod atw;
 mod best_first;
+mod exhaustive;
 mod graph;
+mod monte_carlo;
 mod path;
 mod prefix;
+mod stats;
 
 use std::{
     convert::TryInto,
+    fmt::{Display, Formatter},
     ops::RangeInclusive,
+    path::PathBuf,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
         Arc,
     },
+    time::Duration,
 };
 
 use itertools::Itertools;
 
 use crate::{
     composition::CompositionId,
+    graph::{BuiltGraph, GraphStats},
     parameters::{MethodId, Parameters},
-    prove_length::{prove_lengths, RefinedRanges},
-    utils::IdGenerator,
+    prove_length::RefinedRanges,
+    utils::{IdGenerator, Rng},
     Composition,
 };
 
-use self::atw::AtwTable;
+use self::{atw::AtwTable, stats::Counters};
+
+pub use self::stats::{Prune, SearchStats};
 
 /// Handle to a search being run by Monument.
 ///
@@ -35,12 +44,26 @@ use self::atw::AtwTable;
 pub struct Search {
     /* Data */
     config: Config,
+    /// The seed used to break ties in this search (either from [`Config::seed`] or chosen at
+    /// random)
+    seed: u64,
     params: Arc<Parameters>,
     id_generator: Arc<IdGenerator<CompositionId>>,
 
     refined_ranges: RefinedRanges,
+    graph_stats: GraphStats,
+    /// The reason why the graph can't produce any compositions, if we can tell before searching
+    graph_no_comps_reason: Option<NoCompsReason>,
     graph: self::graph::Graph,
+    /// An estimate of the number of bytes used by `graph`
+    graph_bytes: usize,
     atw_table: Arc<AtwTable>,
+
+    /* Controls which can be changed while the search is running */
+    /// How many compositions to generate (starts as [`Parameters::num_comps`])
+    num_comps: AtomicUsize,
+    /// The maximum number of prefixes in the queue, or `usize::MAX` for no limit
+    queue_limit: AtomicUsize,
 }
 
 impl Search {
@@ -51,12 +74,52 @@ impl Search {
     /// **The returned `Search` won't start until you explicitly call
     /// [`search.run(...)`](Self::run)**.
     pub fn new(params: Parameters, config: Config) -> crate::Result<Self> {
-        // Build and optimise the graph
-        let mut source_graph = crate::graph::Graph::unoptimised(&params, &config)?;
-        // Prove which lengths are impossible, and use that to refine the length and method count
-        // ranges
-        let refined_ranges = prove_lengths(&source_graph, &params)?;
-        source_graph.optimise(&params, &refined_ranges);
+        Self::with_build_progress(params, config, |_| {})
+    }
+
+    /// Same as [`Search::new`], but calls `progress_fn` with [`BuildProgress`] updates while the
+    /// graph is being built.  `progress_fn` is always called on the current thread.
+    pub fn with_build_progress(
+        params: Parameters,
+        config: Config,
+        progress_fn: impl FnMut(BuildProgress),
+    ) -> crate::Result<Self> {
+        let config = config.check()?;
+        let graph = BuiltGraph::with_build_progress(&params, &config, progress_fn)?;
+        Ok(Self::from_checked_graph(params, config, &graph))
+    }
+
+    /// Create a new `Search` which explores a [`BuiltGraph`], rather than building a new graph.
+    /// This is much faster than [`Search::new`], and the same graph can be searched any number of
+    /// times.  `params` can differ from the [`Parameters`] used to build `graph`, but only in ways
+    /// which don't change the graph (see [`BuiltGraph`]); otherwise,
+    /// [`Error::GraphParamsMismatch`](crate::Error::GraphParamsMismatch) is returned.
+    pub fn from_graph(
+        params: Parameters,
+        config: Config,
+        graph: &BuiltGraph,
+    ) -> crate::Result<Self> {
+        let config = config.check()?;
+        if !graph.is_compatible_with(&params) {
+            return Err(crate::Error::GraphParamsMismatch);
+        }
+        Ok(Self::from_checked_graph(params, config, graph))
+    }
+
+    /// Create a `Search` over a `graph` which is known to have been built for `params`
+    fn from_checked_graph(params: Parameters, config: Config, graph: &BuiltGraph) -> Self {
+        let source_graph = &graph.graph;
+        let refined_ranges = graph.refined_ranges.clone();
+        let graph_stats = graph.stats();
+        // Check for anything which would make the optimised graph obviously unable to produce any
+        // compositions, so that we can explain an empty search
+        let graph_no_comps_reason = if graph_stats.num_starts == 0 {
+            Some(NoCompsReason::NoStarts)
+        } else if graph_stats.num_ends == 0 {
+            Some(NoCompsReason::NoEnds)
+        } else {
+            None
+        };
         // Create a lookup table for fast atw calculation
         let chunk_lengths = source_graph
             .chunks
@@ -65,18 +128,27 @@ impl Search {
             .collect_vec();
         let atw_table = AtwTable::new(&params, &chunk_lengths);
         // Create a fast-to-traverse copy of the graph
-        let graph = self::graph::Graph::new(&source_graph, &params, &atw_table);
-        drop(source_graph);
+        let seed = config.seed.unwrap_or_else(Rng::random_seed);
+        log::debug!("Using random seed {seed}");
+        let graph = self::graph::Graph::new(source_graph, &params, &atw_table, seed);
+        let graph_bytes = graph.estimate_heap_size();
+
+        Search {
+            num_comps: AtomicUsize::new(params.num_comps),
+            queue_limit: AtomicUsize::new(config.queue_limit.unwrap_or(usize::MAX)),
 
-        Ok(Search {
             config,
+            seed,
             params: Arc::new(params),
             id_generator: Arc::new(IdGenerator::starting_at_zero()),
 
             refined_ranges,
+            graph_stats,
+            graph_no_comps_reason,
             graph,
+            graph_bytes,
             atw_table: Arc::new(atw_table),
-        })
+        }
     }
 
     pub fn id_generator(mut self, gen: Arc<IdGenerator<CompositionId>>) -> Self {
@@ -94,6 +166,32 @@ impl Search {
         log::debug!("Starting search");
         best_first::search(self, update_fn, abort_flag);
     }
+
+    /// Runs a Monte-Carlo tree search, **blocking the current thread** until `num_comps`
+    /// compositions have been generated, every prefix has been explored, or the search is aborted.
+    ///
+    /// Instead of always expanding the best prefix, this estimates how good each prefix is by
+    /// finishing it at random many times (favouring better chunks), and then explores the prefixes
+    /// which led to the best compositions.  In huge graphs (e.g. with lots of tenors-split
+    /// courses), this can find good compositions in regions which a best-first search would never
+    /// leave.  Compositions are generated in no particular order, and `shortlist` is ignored.
+    pub fn run_monte_carlo(&self, update_fn: impl FnMut(Update), abort_flag: &AtomicBool) {
+        abort_flag.store(false, Ordering::SeqCst);
+        log::debug!("Starting Monte-Carlo search");
+        monte_carlo::search(self, update_fn, abort_flag);
+    }
+
+    /// Runs an exhaustive search, **blocking the current thread** until every [`Composition`]
+    /// satisfying the [`Parameters`] has been generated (or the search is aborted).
+    ///
+    /// Compositions aren't scored or pruned by score, so `num_comps` and `shortlist` are ignored
+    /// and compositions are generated in no particular order.  This is only practical for small
+    /// searches, such as 120s of Doubles or 720s of Minor.
+    pub fn run_exhaustive(&self, update_fn: impl FnMut(Update), abort_flag: &AtomicBool) {
+        abort_flag.store(false, Ordering::SeqCst);
+        log::debug!("Starting exhaustive search");
+        exhaustive::search(self, update_fn, abort_flag);
+    }
 }
 
 impl Search {
@@ -108,6 +206,108 @@ impl Search {
     pub fn parameters(&self) -> &Parameters {
         &self.params
     }
+
+    /// Statistics about the (optimised) graph that this `Search` will explore.
+    pub fn graph_stats(&self) -> GraphStats {
+        self.graph_stats
+    }
+
+    /// The seed used to break ties between equally good compositions.  Passing this as
+    /// [`Config::seed`] will reproduce this exact `Search`.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Work out why a search finished without generating any compositions, given the
+    /// compositions it rejected and whether or not it had to truncate its queue.
+    fn no_comps_reason(&self, counters: &Counters, truncated_queue: bool) -> NoCompsReason {
+        if let Some(reason) = &self.graph_no_comps_reason {
+            return reason.clone();
+        }
+        // Report the check which rejected the most compositions
+        let rejections = counters.rejections();
+        let most_common_rejection = rejections
+            .iter()
+            .max_by_key(|(rejection, count)| (**count, std::cmp::Reverse(**rejection)));
+        if let Some((&reason, _)) = most_common_rejection {
+            return NoCompsReason::AllRejected {
+                num_rejected: rejections.values().sum(),
+                reason,
+            };
+        }
+        if truncated_queue {
+            return NoCompsReason::QueueTruncated;
+        }
+        let length = &self.refined_ranges.length;
+        NoCompsReason::NoneInLengthRange {
+            length: length.start().as_usize()..=length.end().as_usize(),
+        }
+    }
+
+    /// Send the [`Update`]s which end a search: why no compositions were found (unless the search
+    /// generated some or was aborted), the [`SearchStats`] (if [`Config::collect_stats`] is set)
+    /// and finally [`Update::Complete`].
+    fn send_final_updates(
+        &self,
+        update_fn: &mut impl FnMut(Update),
+        counters: &Counters,
+        num_comps: usize,
+        truncated_queue: bool,
+        aborted: bool,
+    ) {
+        if num_comps == 0 && !aborted {
+            update_fn(Update::NoComps(
+                self.no_comps_reason(counters, truncated_queue),
+            ));
+        }
+        if self.config.collect_stats {
+            update_fn(Update::Stats(counters.stats(self)));
+        }
+        update_fn(Update::Complete);
+    }
+
+    /// The maximum number of bytes which the search routine can use, taking
+    /// [`Config::mem_limit`] and [`Config::auto_tune`] into account
+    fn mem_limit(&self) -> usize {
+        self.config
+            .mem_limit
+            .unwrap_or_else(|| match self.config.auto_tune {
+                true => auto_mem_limit(),
+                false => default_mem_limit(),
+            })
+    }
+}
+
+/// Controls which can be changed from another thread while the `Search` is running.  Changes are
+/// picked up every few thousand iterations.
+impl Search {
+    /// How many [`Composition`]s this `Search` will generate before stopping
+    pub fn num_comps(&self) -> usize {
+        self.num_comps.load(Ordering::Relaxed)
+    }
+
+    /// Change how many [`Composition`]s this `Search` will generate.  If it has already generated
+    /// at least `num_comps`, it will stop.
+    pub fn set_num_comps(&self, num_comps: usize) {
+        self.num_comps.store(num_comps, Ordering::Relaxed);
+    }
+
+    /// The maximum number of prefixes which can be in the queue, if any.  If the [`Config`] doesn't
+    /// set a limit but `auto_tune` is on, this is set once the search starts.  If
+    /// [`Config::max_memory_bytes`] is set, this is also re-computed whenever the queue is truncated.
+    pub fn queue_limit(&self) -> Option<usize> {
+        match self.queue_limit.load(Ordering::Relaxed) {
+            usize::MAX => None,
+            limit => Some(limit),
+        }
+    }
+
+    /// Change the maximum number of prefixes which can be in the queue.  If the queue is longer
+    /// than the new limit, it will be truncated.
+    pub fn set_queue_limit(&self, limit: Option<usize>) {
+        self.queue_limit
+            .store(limit.unwrap_or(usize::MAX), Ordering::Relaxed);
+    }
 }
 
 /// Update message from an in-progress [`Search`].
@@ -117,11 +317,116 @@ pub enum Update {
     Comp(Composition),
     /// A thread is sending a status update
     Progress(Progress),
+    /// The search finished without generating any compositions.  This is sent just before
+    /// [`Update::Complete`], but not if the search was aborted.
+    NoComps(NoCompsReason),
+    /// A report of where the search spent its effort.  This is only sent (just before
+    /// [`Update::Complete`]) if [`Config::collect_stats`] is set.
+    Stats(SearchStats),
     /// The search has completed
     Complete,
 }
 
+/// The most likely reason why a [`Search`] didn't generate any [`Composition`]s.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NoCompsReason {
+    /// Once the graph had been optimised, there were no ways to start a composition (e.g. every
+    /// start was false or too far from rounds)
+    NoStarts,
+    /// Once the graph had been optimised, there were no ways to finish a composition
+    NoEnds,
+    /// Some compositions came round, but every one was rejected.  `reason` is the check which
+    /// rejected the most compositions.
+    AllRejected {
+        num_rejected: usize,
+        reason: Rejection,
+    },
+    /// No compositions came round before the search ran out of memory (or hit its queue limit),
+    /// and some partial compositions had to be discarded
+    QueueTruncated,
+    /// Every partial composition was explored, but none came round within the length range (after
+    /// it was refined to the lengths which the graph can actually produce)
+    NoneInLengthRange { length: RangeInclusive<usize> },
+}
+
+impl Display for NoCompsReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NoCompsReason::NoStarts => {
+                write!(f, "no valid ways to start a composition remain in the graph")
+            }
+            NoCompsReason::NoEnds => {
+                write!(f, "no valid ways to end a composition remain in the graph")
+            }
+            NoCompsReason::AllRejected {
+                num_rejected,
+                reason,
+            } => write!(
+                f,
+                "{num_rejected} compositions came round, but were rejected (mostly because {reason})"
+            ),
+            NoCompsReason::QueueTruncated => write!(
+                f,
+                "the search ran out of memory before any compositions came round"
+            ),
+            NoCompsReason::NoneInLengthRange { length } => write!(
+                f,
+                "no compositions came round with lengths in {}..={}",
+                length.start(),
+                length.end()
+            ),
+        }
+    }
+}
+
+/// The check which caused a [`Search`] to reject a composition which came round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Rejection {
+    /// The composition was too long or too short
+    Length,
+    /// The composition didn't satisfy the method counts
+    MethodCounts,
+    /// The composition didn't contain every one of the required rows
+    RequiredRows,
+    /// The composition contained too many consecutive rows of duffers
+    DufferRows,
+    /// The composition failed the final validity checks (e.g. music count ranges, start/end
+    /// indices or splice style)
+    Invalid,
+    /// The composition's calling was rejected by the calling filters
+    Calling,
+    /// The composition's total score was below the minimum
+    TotalScore,
+    /// The composition's music score was below the minimum
+    MusicScore,
+    /// The composition had too many calls
+    NumCalls,
+    /// The composition was too hard to call
+    Conductability,
+}
+
+impl Display for Rejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Rejection::Length => "of their length",
+            Rejection::MethodCounts => "of their method counts",
+            Rejection::RequiredRows => "they didn't contain every required row",
+            Rejection::DufferRows => "they had too many consecutive duffer rows",
+            Rejection::Invalid => "of music counts, start/end indices or splice style",
+            Rejection::Calling => "of their calling",
+            Rejection::TotalScore => "their score was too low",
+            Rejection::MusicScore => "their music score was too low",
+            Rejection::NumCalls => "they had too many calls",
+            Rejection::Conductability => "they were too hard to call",
+        })
+    }
+}
+
 /// How much of a [`Search`] has been completed so far.
+///
+/// The memory estimates aren't broken down by thread, because every [`Search`] is run by a single
+/// thread ([`Config::thread_limit`] only applies to building the graph).  Searches which run at the
+/// same time (e.g. on several threads) each send their own `Progress`.
 #[derive(Debug, Clone, Copy)]
 pub struct Progress {
     /// How many times the core composing loop has been run so far.
@@ -136,6 +441,18 @@ pub struct Progress {
     /// The length of the longest [`Composition`] prefix in the queue.
     pub max_length: usize,
 
+    /// An estimate of the number of bytes of memory used by the queue of [`Composition`] prefixes
+    /// (including the paths which they share).  This grows as the search runs, until it reaches
+    /// `mem_limit` and the queue is truncated.
+    pub queue_bytes: usize,
+    /// An estimate of the number of bytes of memory used by the graph being searched.  This
+    /// doesn't change while the search is running.
+    pub graph_bytes: usize,
+    /// The number of bytes that the queue can use before it gets truncated (see
+    /// [`Config::mem_limit`]), or `None` if the queue is never truncated (e.g. in
+    /// [exhaustive searches](Search::run_exhaustive)).
+    pub mem_limit: Option<usize>,
+
     /// `true` if the search routine is currently shortening the prefix queue to save memory.
     pub truncating_queue: bool,
     /// `true` if the search routine is in the process of aborting
@@ -152,9 +469,33 @@ impl Progress {
         avg_length: 0.0,
         max_length: 0,
 
+        queue_bytes: 0,
+        graph_bytes: 0,
+        mem_limit: None,
+
         truncating_queue: false,
         aborting: false,
     };
+
+    /// An estimate of the total number of bytes of memory used by the [`Search`] (i.e. by its queue
+    /// and graph).  Every `Search` runs on the thread which called it (e.g.
+    /// [`Search::run`]), so this is also the memory used by that thread.
+    pub fn total_bytes(&self) -> usize {
+        self.queue_bytes + self.graph_bytes
+    }
+}
+
+/// How much of a [`Search`]'s graph has been built so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildProgress {
+    /// Monument is building the table used to compute falseness between chunks.  This is quadratic
+    /// in the number of distinct chunk ranges, so is usually the slowest part of the graph build.
+    Falseness {
+        /// How many chunk ranges have been compared against every other chunk range
+        done: usize,
+        /// The total number of chunk ranges which need comparing
+        total: usize,
+    },
 }
 
 /// Configuration options for a [`Search`].
@@ -165,7 +506,8 @@ impl Progress {
 pub struct Config {
     /* General */
     /// Number of threads used to generate compositions.  If `None`, this uses the number of
-    /// **physical** CPU cores (i.e. ignoring hyper-threading).
+    /// **physical** CPU cores (i.e. ignoring hyper-threading).  Ignored if the `threads` feature
+    /// is disabled.
     pub thread_limit: Option<usize>,
 
     /* Graph Generation */
@@ -174,15 +516,67 @@ pub struct Config {
     /// out-of-memory crash by requesting a hugely open search such as split-tenors Maximus.
     pub graph_size_limit: usize,
 
+    /// If set, falseness tables will be saved to (and loaded from) files in this directory.  The
+    /// falseness table only depends on the methods and the chunks in the graph, so this saves a
+    /// lot of time when re-running a search with different music or weights.  If `None`, the
+    /// falseness table is always computed from scratch.
+    pub falseness_cache_dir: Option<PathBuf>,
+
     /* Search */
     /// The maximum number of bytes of heap memory which the search routine is allowed to use.
     /// Defaults to 80% of available memory.
     pub mem_limit: Option<usize>,
+    /// The maximum number of partial compositions which the search can store.  If this is
+    /// exceeded, the search's queue(s) are halved in size (exactly as though
+    /// [`mem_limit`](Self::mem_limit) had been reached).  If `None`, the queue size is only
+    /// limited by memory.
+    pub queue_limit: Option<usize>,
+    /// If set (and [`queue_limit`](Self::queue_limit) isn't), the search measures how many bytes
+    /// each partial composition really uses (including its share of the paths), and keeps
+    /// adjusting the queue limit so that the queue fits into this many bytes.  When the queue
+    /// reaches this limit, it is only truncated to 3/4 of the limit rather than being halved.
+    /// This is capped by [`mem_limit`](Self::mem_limit).
+    pub max_memory_bytes: Option<usize>,
+    /// Which partial compositions are kept when the search's queue(s) get truncated.
+    pub truncation_policy: TruncationPolicy,
+    /// If `true`, any of [`thread_limit`](Self::thread_limit), [`mem_limit`](Self::mem_limit) or
+    /// [`queue_limit`](Self::queue_limit) which aren't set will be chosen automatically from the
+    /// machine's CPUs and memory and the size of the graph.  The chosen values are logged at
+    /// the `info` level.
+    pub auto_tune: bool,
+    /// If set, each start chunk gets its own frontier, and the search expands these frontiers in
+    /// turn, spending at most this long on each one before moving on to the next.  This stops a
+    /// pathological start chunk from consuming the whole search.  If `None`, all start chunks
+    /// share a single frontier.  Ignored on WebAssembly, which has no clock.
+    pub start_chunk_time_budget: Option<Duration>,
+    /// If `true`, partial compositions are ordered by an upper bound on the
+    /// [`ranking`](Parameters::ranking) of any composition they could become, rather than by
+    /// their ranking so far.  This makes the search A*: it is much less likely to miss the best
+    /// compositions, but it can explore far more partial compositions before finding any complete
+    /// ones.  This only applies to [`Ranking::AvgScore`](crate::parameters::Ranking::AvgScore) and
+    /// [`Ranking::TotalScore`](crate::parameters::Ranking::TotalScore).
+    pub a_star: bool,
+    /// Seed for the random number generator which breaks ties between equally good partial
+    /// compositions.  Running the same search with the same seed will generate the same
+    /// compositions (unless a time-based option like `start_chunk_time_budget` is set).  If
+    /// `None`, a new seed is chosen at random for every [`Search`].
+    pub seed: Option<u64>,
+    /// If set, the search doesn't always expand the best partial composition.  Instead, partial
+    /// compositions are chosen at random, with probability proportional to
+    /// `exp(priority / temperature)` (where `priority` is the average score per row or whatever
+    /// else [`Parameters::ranking`] uses).  Higher temperatures make the search more random, so
+    /// different seeds explore very different parts of huge searches, at the cost of
+    /// (usually) finding worse compositions.  Must be positive.
+    pub temperature: Option<f32>,
+    /// If `true`, the search records how many times each chunk is expanded, why successors are
+    /// discarded and how often the queue is truncated, and sends a [`SearchStats`] report (as
+    /// [`Update::Stats`]) once it finishes.  This makes the search slightly slower.
+    pub collect_stats: bool,
     /// If `true`, the data structures used by searches will be leaked using [`std::mem::forget`].
     /// This massively improves the termination speed (because the search creates tons of small
     /// allocations which we now don't need to explicitly free) but only makes sense for the CLI,
     /// where the process will do exactly one search run before terminating (thus returning the memory
-    /// to the OS anyway).
+    /// to the OS anyway).  Ignored on WebAssembly.
     pub leak_search_memory: bool,
 }
 
@@ -192,13 +586,81 @@ impl Default for Config {
             thread_limit: None,
 
             graph_size_limit: 100_000,
+            falseness_cache_dir: None,
 
             mem_limit: None,
+            queue_limit: None,
+            max_memory_bytes: None,
+            truncation_policy: TruncationPolicy::default(),
+            auto_tune: false,
+            start_chunk_time_budget: None,
+            a_star: false,
+            seed: None,
+            temperature: None,
+            collect_stats: false,
             leak_search_memory: false,
         }
     }
 }
 
+/// How a [`Search`] decides which partial compositions to keep when its queue gets truncated
+/// (see [`Config::mem_limit`] and [`Config::queue_limit`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TruncationPolicy {
+    /// Keep only the best partial compositions.  This is fast, but the survivors are often very
+    /// similar to each other (e.g. all the same length, or all in the same part), which can make
+    /// the rest of the search very myopic.
+    #[default]
+    KeepBest,
+    /// Group the partial compositions by their length and part head, and keep the same proportion
+    /// of every group (always keeping each group's best prefixes first).  The queue keeps roughly
+    /// the same shape after truncation, so the search carries on exploring every length and part
+    /// head rather than only extending the prefixes which happen to be in the lead.
+    Stratified,
+}
+
+impl Config {
+    /// The number of threads which Monument should use, taking [`Config::thread_limit`] and
+    /// [`Config::auto_tune`] into account.  This is always 1 if the `threads` feature is disabled.
+    pub(crate) fn num_threads(&self) -> usize {
+        if !cfg!(feature = "threads") {
+            return 1;
+        }
+        self.thread_limit
+            .unwrap_or_else(|| match self.auto_tune {
+                true => auto_thread_limit(),
+                false => num_cpus::get_physical(),
+            })
+            .max(1)
+    }
+
+    /// Check that this `Config` makes sense, and fill in the thread limit if it's chosen by
+    /// [`Config::auto_tune`].  [`Search::new`] and [`Search::from_graph`] already do this, so
+    /// this is only needed when a `Config` is used without creating a [`Search`].
+    pub fn check(mut self) -> crate::Result<Self> {
+        if let Some(temperature) = self.temperature.filter(|&t| t <= 0.0 || t.is_nan()) {
+            return Err(crate::Error::NonPositiveTemperature(temperature));
+        }
+        if self.auto_tune && self.thread_limit.is_none() {
+            let thread_limit = auto_thread_limit();
+            log::info!("Auto-tune: using {thread_limit} threads");
+            self.thread_limit = Some(thread_limit);
+        }
+        Ok(self)
+    }
+}
+
+/// Return the number of threads to use if chosen by [`Config::auto_tune`].  This uses every
+/// physical core, except that one core is left free (for the OS and UI) on machines with more than
+/// two of them.
+fn auto_thread_limit() -> usize {
+    let physical_cores = num_cpus::get_physical();
+    match physical_cores {
+        0..=2 => physical_cores.max(1),
+        _ => physical_cores - 1,
+    }
+}
+
 /// Return the memory limit for this search, if not specified by the user's [`Config`].  On most
 /// systems, this will return 80% of available memory.
 fn default_mem_limit() -> usize {
@@ -206,12 +668,37 @@ fn default_mem_limit() -> usize {
 
     // Use as a memory limit either 80% of available memory or 5GB if we can't access
     // availability
-    let ideal_mem_limit = if sysinfo::IS_SUPPORTED_SYSTEM {
-        (sysinfo::System::new_all().available_memory() as f32 * 0.8) as u64
-    } else {
-        5_000_000_000u64
+    let ideal_mem_limit = match available_memory() {
+        Some(available) => (available as f32 * 0.8) as u64,
+        None => 5_000_000_000u64,
     };
     log::debug!("Got memory usage");
+    clamp_mem_limit(ideal_mem_limit)
+}
+
+/// Return the memory limit to use if chosen by [`Config::auto_tune`].  This is like
+/// [`default_mem_limit`], but always leaves at least 1GB of memory free so that smaller machines
+/// don't start swapping.
+fn auto_mem_limit() -> usize {
+    const MEM_TO_LEAVE_FREE: u64 = 1_000_000_000;
+    const MIN_MEM_LIMIT: u64 = 100_000_000;
+
+    let ideal_mem_limit = match available_memory() {
+        Some(available) => ((available as f32 * 0.8) as u64)
+            .min(available.saturating_sub(MEM_TO_LEAVE_FREE))
+            .max(MIN_MEM_LIMIT),
+        None => 5_000_000_000u64,
+    };
+    clamp_mem_limit(ideal_mem_limit)
+}
+
+/// The number of bytes of memory available on this system, if it can be determined
+fn available_memory() -> Option<u64> {
+    sysinfo::IS_SUPPORTED_SYSTEM.then(|| sysinfo::System::new_all().available_memory())
+}
+
+/// Make sure that a memory limit can actually be reached on this system
+fn clamp_mem_limit(ideal_mem_limit: u64) -> usize {
     // However, always use 500MB less than the memory that's accessible by the system (i.e. if
     // we're running in 32-bit environments like WASM, we can't fill available memory so we
     // just default to `2*32 - 500MB ~= 3.5GB`)
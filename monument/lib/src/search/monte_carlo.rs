@@ -0,0 +1,339 @@
+use std::{
+    collections::HashSet,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use datasize::DataSize;
+use index_vec::IndexVec;
+
+use crate::{
+    composition::{ParamsData, PathElem},
+    utils::Rng,
+    Composition,
+};
+
+use super::{
+    best_first::{
+        send_progress_update, ITERS_BETWEEN_ABORT_CHECKS, ITERS_BETWEEN_PROGRESS_UPDATES,
+    },
+    path::Paths,
+    prefix::CompPrefix,
+    stats::Counters,
+    Search, Update,
+};
+
+/// Every rollout adds a path node for every prefix it creates, so we garbage-collect the paths
+/// much more often than best-first search
+const ITERS_BETWEEN_PATH_GCS: usize = 10_000;
+/// How strongly the search favours rarely visited prefixes over prefixes which have led to good
+/// compositions (the constant `c` in the UCB1 formula)
+const EXPLORATION_WEIGHT: f32 = std::f32::consts::SQRT_2;
+/// If [`Config::temperature`](crate::Config::temperature) isn't set, rollouts choose between
+/// successors with this temperature.  This suits the default ranking by average score.
+const DEFAULT_ROLLOUT_TEMPERATURE: f32 = 0.05;
+
+index_vec::define_index_type! { struct NodeIdx = u32; }
+const ROOT: NodeIdx = NodeIdx::from_raw_unchecked(0);
+
+/// Searches a [`Graph`](m_gr::Graph) using Monte-Carlo tree search.  Each iteration walks down
+/// the tree of prefixes (balancing prefixes which have led to good compositions against prefixes
+/// which haven't been tried much), expands the prefix it reaches, and then estimates how good
+/// that prefix is by finishing it at random (a 'rollout').  Every composition found by a rollout
+/// is generated, so the search stops after `num_comps` rollouts succeed.
+pub(crate) fn search(search: &Search, mut update_fn: impl FnMut(Update), abort_flag: &AtomicBool) {
+    let param_data = ParamsData::new(&search.params);
+    let mem_limit = search.mem_limit();
+    let mut rng = Rng::new(search.seed);
+    // If the search has a temperature, then every prefix's priority already contains noise of
+    // that temperature, so rollouts can just pick the successor with the highest priority
+    let rollout_temperature = match search.config.temperature {
+        Some(_) => 0.0,
+        None => DEFAULT_ROLLOUT_TEMPERATURE,
+    };
+
+    let mut paths = Paths::new();
+    let starts = CompPrefix::starts(search, &mut paths);
+    if starts.is_empty() {
+        // Don't run a search if no compositions are possible
+        search.send_final_updates(&mut update_fn, &Counters::new(search), 0, false, false);
+        return;
+    }
+    let prefix_size = starts[0].size();
+    let mut tree = Tree::new(starts);
+
+    let mut iter_count = 0;
+    let mut num_comps = 0;
+    let mut counters = Counters::new(search);
+    // Rollouts often find the same composition many times, but each one should only be generated
+    // once
+    let mut generated_comps = HashSet::<Vec<PathElem>>::new();
+
+    macro_rules! send_progress_update {
+        () => {
+            send_progress_update(
+                search,
+                tree.leaf_prefixes(),
+                &paths,
+                Some(mem_limit),
+                &mut update_fn,
+                iter_count,
+                num_comps,
+                false,
+                abort_flag.load(Ordering::SeqCst),
+            );
+        };
+    }
+
+    // Send 'empty' update before search starts
+    send_progress_update!();
+
+    while !tree.nodes[ROOT].exhausted {
+        // Walk down the tree to a leaf, and expand it if there's enough memory to grow the tree.
+        // Then do a rollout from the leaf (or its best child)
+        let mut node_path = tree.select();
+        let leaf = *node_path.last().unwrap();
+        let mem_usage = tree.nodes.len() * (std::mem::size_of::<Node>() + prefix_size)
+            + paths.estimate_heap_size();
+        let rollout_start = if mem_usage < mem_limit {
+            let prefix = tree.nodes[leaf].prefix.take().unwrap();
+            let mut children = Vec::new();
+            match prefix.expand(
+                search,
+                &mut paths,
+                &mut children,
+                &param_data,
+                &mut counters,
+            ) {
+                Some(comp) => Err(Some(comp)), // The leaf is a finished composition
+                None => match tree.add_children(leaf, children) {
+                    Some(child) => {
+                        node_path.push(child);
+                        Ok(tree.nodes[child].prefix.clone().unwrap())
+                    }
+                    None => Err(None), // The leaf can't be extended
+                },
+            }
+        } else {
+            Ok(tree.nodes[leaf].prefix.clone().unwrap())
+        };
+        let maybe_comp = match rollout_start {
+            Ok(prefix) => rollout(
+                prefix,
+                search,
+                &mut paths,
+                &param_data,
+                &mut counters,
+                &mut rng,
+                rollout_temperature,
+            ),
+            Err(maybe_comp) => maybe_comp,
+        };
+
+        // Generate the composition (if it's new), and propagate its ranking back up the tree
+        let ranking = maybe_comp.map(|comp| {
+            let values = comp.values(&param_data).unwrap();
+            let ranking = search.params.ranking.value(&values);
+            if generated_comps.insert(comp.path.clone()) {
+                update_fn(Update::Comp(comp));
+                num_comps += 1;
+            }
+            ranking
+        });
+        tree.backpropagate(&node_path, ranking);
+        if num_comps >= search.num_comps() {
+            break; // Stop the search once we've got enough comps
+        }
+
+        iter_count += 1;
+
+        // Check for abort every so often
+        if iter_count % ITERS_BETWEEN_ABORT_CHECKS == 0 && abort_flag.load(Ordering::Relaxed) {
+            break;
+        }
+        // Send stats every so often
+        if iter_count % ITERS_BETWEEN_PROGRESS_UPDATES == 0 {
+            send_progress_update!();
+        }
+        // Garbage-collect the paths every so often, because otherwise they'll keep every prefix
+        // created by every rollout
+        if iter_count % ITERS_BETWEEN_PATH_GCS == 0 {
+            paths.gc(tree.leaf_prefixes().map(CompPrefix::path_head));
+        }
+    }
+
+    // Always send a final update before finishing
+    send_progress_update!();
+    search.send_final_updates(
+        &mut update_fn,
+        &counters,
+        num_comps,
+        false,
+        abort_flag.load(Ordering::SeqCst),
+    );
+}
+
+/// Finish a `prefix` at random, returning the resulting [`Composition`] (or `None` if the prefix
+/// reached a dead end).  At every step, the next prefix is chosen with probability proportional to
+/// `exp(priority / temperature)`.
+fn rollout(
+    mut prefix: CompPrefix,
+    search: &Search,
+    paths: &mut Paths,
+    param_data: &ParamsData,
+    counters: &mut Counters,
+    rng: &mut Rng,
+    temperature: f32,
+) -> Option<Composition> {
+    let mut successors = Vec::new();
+    loop {
+        if let Some(comp) = prefix.expand(search, paths, &mut successors, param_data, counters) {
+            return Some(comp);
+        }
+        // Choose a successor with the Gumbel-max trick
+        prefix = successors
+            .drain(..)
+            .map(|p| (p.ordering_key().0 + temperature * rng.next_gumbel(), p))
+            .max_by(|(key1, _), (key2, _)| key1.total_cmp(key2))?
+            .1;
+    }
+}
+
+/// The tree of prefixes explored by the Monte-Carlo search
+#[derive(Debug)]
+struct Tree {
+    nodes: IndexVec<NodeIdx, Node>,
+    /// The lowest and highest rankings of any composition found by a rollout.  Rankings are
+    /// normalised to `0..=1` using these, so that [`EXPLORATION_WEIGHT`] doesn't depend on how
+    /// compositions are ranked.
+    min_ranking: f32,
+    max_ranking: f32,
+}
+
+/// A node in the search [`Tree`], representing one prefix
+#[derive(Debug)]
+struct Node {
+    /// The prefix represented by this node, or `None` if it's been expanded (or is the root)
+    prefix: Option<CompPrefix>,
+    /// The child nodes, sorted by decreasing priority.  Empty unless this node has been expanded.
+    children: Vec<NodeIdx>,
+    /// The number of rollouts which have passed through this node
+    visits: u32,
+    /// The number of those rollouts which found a composition
+    successes: u32,
+    /// The total ranking of the compositions found by those rollouts
+    ranking_sum: f32,
+    /// `true` if every composition below this node has been found, so it doesn't need to be
+    /// explored any further
+    exhausted: bool,
+}
+
+impl Tree {
+    fn new(starts: Vec<CompPrefix>) -> Self {
+        let mut tree = Self {
+            nodes: IndexVec::new(),
+            min_ranking: f32::INFINITY,
+            max_ranking: f32::NEG_INFINITY,
+        };
+        tree.nodes.push(Node::new(None));
+        tree.add_children(ROOT, starts);
+        tree
+    }
+
+    /// Walk from the root to a leaf, returning the nodes visited.  At each step, the child with
+    /// the highest UCB1 score is chosen.
+    fn select(&self) -> Vec<NodeIdx> {
+        let mut node_path = vec![ROOT];
+        let mut node = &self.nodes[ROOT];
+        while !node.children.is_empty() {
+            let mut best_child = None;
+            for &child_idx in &node.children {
+                let child = &self.nodes[child_idx];
+                if child.exhausted {
+                    continue;
+                }
+                let score = self.ucb_score(child, node.visits);
+                if best_child.is_none_or(|(_, best_score)| score > best_score) {
+                    best_child = Some((child_idx, score));
+                }
+            }
+            // Nodes are exhausted once all their children are, so a non-exhausted child exists
+            let (child_idx, _) = best_child.unwrap();
+            node_path.push(child_idx);
+            node = &self.nodes[child_idx];
+        }
+        node_path
+    }
+
+    fn ucb_score(&self, node: &Node, parent_visits: u32) -> f32 {
+        if node.visits == 0 {
+            return f32::INFINITY; // Always try every child once
+        }
+        let visits = node.visits as f32;
+        let successes = node.successes as f32;
+        let ranking_range = self.max_ranking - self.min_ranking;
+        // Failed rollouts count as `0`, i.e. as bad as the worst composition
+        let normalised_ranking_sum = if ranking_range > 0.0 {
+            (node.ranking_sum - successes * self.min_ranking) / ranking_range
+        } else {
+            successes
+        };
+        let exploration = ((parent_visits as f32).ln() / visits).sqrt();
+        normalised_ranking_sum / visits + EXPLORATION_WEIGHT * exploration
+    }
+
+    /// Adds `prefixes` as the children of `parent`, returning the child with the highest priority
+    /// (or `None` if `prefixes` is empty, in which case `parent` is exhausted).
+    fn add_children(&mut self, parent: NodeIdx, mut prefixes: Vec<CompPrefix>) -> Option<NodeIdx> {
+        prefixes.sort_by_key(|p| std::cmp::Reverse(p.ordering_key()));
+        let children = prefixes
+            .into_iter()
+            .map(|prefix| self.nodes.push(Node::new(Some(prefix))))
+            .collect::<Vec<_>>();
+        let best_child = children.first().copied();
+        let parent = &mut self.nodes[parent];
+        parent.exhausted = children.is_empty();
+        parent.children = children;
+        best_child
+    }
+
+    /// Record the result of a rollout which went through every node in `node_path`
+    fn backpropagate(&mut self, node_path: &[NodeIdx], ranking: Option<f32>) {
+        if let Some(ranking) = ranking {
+            self.min_ranking = self.min_ranking.min(ranking);
+            self.max_ranking = self.max_ranking.max(ranking);
+        }
+        for &idx in node_path.iter().rev() {
+            let node = &self.nodes[idx];
+            // Leaves are exhausted if they can't produce any more compositions (i.e. they've been
+            // expanded into nothing or they were finished compositions).  Other nodes are
+            // exhausted once all their children are.
+            let exhausted =
+                node.prefix.is_none() && node.children.iter().all(|&c| self.nodes[c].exhausted);
+            let node = &mut self.nodes[idx];
+            node.exhausted = exhausted;
+            node.visits += 1;
+            if let Some(ranking) = ranking {
+                node.successes += 1;
+                node.ranking_sum += ranking;
+            }
+        }
+    }
+
+    /// The prefixes of every leaf which hasn't been expanded yet
+    fn leaf_prefixes(&self) -> impl Iterator<Item = &CompPrefix> {
+        self.nodes.iter().filter_map(|node| node.prefix.as_ref())
+    }
+}
+
+impl Node {
+    fn new(prefix: Option<CompPrefix>) -> Self {
+        Self {
+            prefix,
+            children: Vec::new(),
+            visits: 0,
+            successes: 0,
+            ranking_sum: 0.0,
+            exhausted: false,
+        }
+    }
+}
@@ -0,0 +1,222 @@
+//! Instrumentation which records where a [`Search`] spends its effort (see
+//! [`Config::collect_stats`](super::Config::collect_stats)).
+
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+};
+
+use itertools::Itertools;
+use ringing_utils::BigNumInt;
+
+use super::{graph::ChunkIdx, Rejection, Search};
+
+/// The number of chunks listed in [`SearchStats::most_expanded_chunks`]
+const NUM_CHUNKS_TO_REPORT: usize = 10;
+
+/// The check which caused a [`Search`] to discard a successor of a partial composition, before it
+/// was ever added to the queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Prune {
+    /// The successor couldn't come round before the maximum length
+    Length,
+    /// The successor was false against (or repeated too many rows of) the partial composition
+    Falseness,
+    /// The method counts couldn't be satisfied before the maximum length
+    MethodCounts,
+    /// Some music type's minimum count couldn't be reached before the maximum length
+    MusicCounts,
+    /// The successor would make the run of consecutive duffer rows too long
+    DufferRows,
+    /// The successor would repeat a course which had already been left
+    RepeatedCourse,
+    /// The successor skipped over part of the required call sequence
+    CallSequence,
+    /// Some row can no longer be rung, so the partial composition can't become an extent
+    ExtentRow,
+}
+
+impl Prune {
+    const ALL: [Self; 8] = [
+        Self::Length,
+        Self::Falseness,
+        Self::MethodCounts,
+        Self::MusicCounts,
+        Self::DufferRows,
+        Self::RepeatedCourse,
+        Self::CallSequence,
+        Self::ExtentRow,
+    ];
+}
+
+impl Display for Prune {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Prune::Length => "they couldn't come round in time",
+            Prune::Falseness => "they were false",
+            Prune::MethodCounts => "the method counts couldn't be reached",
+            Prune::MusicCounts => "the music counts couldn't be reached",
+            Prune::DufferRows => "they had too many consecutive duffer rows",
+            Prune::RepeatedCourse => "they repeated a course",
+            Prune::CallSequence => "they skipped part of the call sequence",
+            Prune::ExtentRow => "some row could no longer be rung in the extent",
+        })
+    }
+}
+
+/// A report of where a [`Search`] spent its effort, sent as [`Update::Stats`](super::Update::Stats)
+/// at the end of the search if [`Config::collect_stats`](super::Config::collect_stats) is set.
+/// This shows which of the search's constraints are limiting it.
+#[derive(Debug, Clone, Default)]
+pub struct SearchStats {
+    /// How many partial compositions were expanded (not counting those which came round)
+    pub num_expansions: usize,
+    /// The chunks which were expanded the most, most expanded first.  Each chunk is described by
+    /// its method, lead head and index within the lead.
+    pub most_expanded_chunks: Vec<(String, usize)>,
+    /// How many successors were discarded by each check during expansion, most common first.
+    /// Checks which never discarded anything are omitted.
+    pub prunes: Vec<(Prune, usize)>,
+    /// How many compositions came round but were rejected by each check, most common first
+    pub rejections: Vec<(Rejection, usize)>,
+    /// How many times the queue had to be truncated to stay within the memory or queue limits
+    pub num_truncations: usize,
+    /// How many partial compositions were discarded by truncating the queue
+    pub num_truncated_prefixes: usize,
+}
+
+impl Display for SearchStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Search statistics:")?;
+        writeln!(
+            f,
+            "  {} partial compositions expanded",
+            BigNumInt(self.num_expansions)
+        )?;
+        if !self.most_expanded_chunks.is_empty() {
+            writeln!(f, "  Most expanded chunks:")?;
+            for (chunk, count) in &self.most_expanded_chunks {
+                writeln!(f, "    {:>12}  {chunk}", BigNumInt(*count))?;
+            }
+        }
+        if !self.prunes.is_empty() {
+            writeln!(f, "  Successors discarded because:")?;
+            for (prune, count) in &self.prunes {
+                writeln!(f, "    {:>12}  {prune}", BigNumInt(*count))?;
+            }
+        }
+        if !self.rejections.is_empty() {
+            writeln!(f, "  Compositions rejected because:")?;
+            for (rejection, count) in &self.rejections {
+                writeln!(f, "    {:>12}  {rejection}", BigNumInt(*count))?;
+            }
+        }
+        write!(
+            f,
+            "  Queue truncated {} times, discarding {} partial compositions",
+            self.num_truncations,
+            BigNumInt(self.num_truncated_prefixes)
+        )
+    }
+}
+
+/// Running counts of what a search driver has done.  Rejections are always counted (they're used
+/// to explain searches which don't generate any compositions), as are prunes (which are just
+/// array increments).  Per-chunk expansion counts are only kept if
+/// [`Config::collect_stats`](super::Config::collect_stats) is set.
+#[derive(Debug)]
+pub(super) struct Counters {
+    rejections: HashMap<Rejection, usize>,
+    prunes: [usize; Prune::ALL.len()],
+    /// Number of expansions of each chunk, indexed by [`ChunkIdx`].  Empty if we're not collecting
+    /// stats
+    expansions: Vec<usize>,
+    num_expansions: usize,
+    num_truncations: usize,
+    num_truncated_prefixes: usize,
+}
+
+impl Counters {
+    pub(super) fn new(search: &Search) -> Self {
+        let num_chunks = match search.config.collect_stats {
+            true => search.graph.chunks.len(),
+            false => 0,
+        };
+        Self {
+            rejections: HashMap::new(),
+            prunes: [0; Prune::ALL.len()],
+            expansions: vec![0; num_chunks],
+            num_expansions: 0,
+            num_truncations: 0,
+            num_truncated_prefixes: 0,
+        }
+    }
+
+    pub(super) fn add_rejection(&mut self, rejection: Rejection) {
+        *self.rejections.entry(rejection).or_default() += 1;
+    }
+
+    pub(super) fn add_prune(&mut self, prune: Prune) {
+        self.prunes[prune as usize] += 1;
+    }
+
+    pub(super) fn add_expansion(&mut self, chunk_idx: ChunkIdx) {
+        self.num_expansions += 1;
+        if let Some(count) = self.expansions.get_mut(chunk_idx.index()) {
+            *count += 1;
+        }
+    }
+
+    pub(super) fn add_truncation(&mut self, num_prefixes_removed: usize) {
+        self.num_truncations += 1;
+        self.num_truncated_prefixes += num_prefixes_removed;
+    }
+
+    /// How many compositions were rejected by each check
+    pub(super) fn rejections(&self) -> &HashMap<Rejection, usize> {
+        &self.rejections
+    }
+
+    /// Summarise these counts into a [`SearchStats`] report
+    pub(super) fn stats(&self, search: &Search) -> SearchStats {
+        let most_expanded_chunks = self
+            .expansions
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .sorted_by_key(|(idx, count)| (std::cmp::Reverse(**count), *idx))
+            .take(NUM_CHUNKS_TO_REPORT)
+            .map(|(idx, count)| {
+                let id = &search.graph.chunks[ChunkIdx::new(idx)].id;
+                let method = &search.params.methods[id.method];
+                let description = format!(
+                    "{} lead {}, row {}",
+                    method.shorthand(),
+                    id.lead_head,
+                    id.sub_lead_idx
+                );
+                (description, *count)
+            })
+            .collect_vec();
+        let prunes = Prune::ALL
+            .iter()
+            .map(|&prune| (prune, self.prunes[prune as usize]))
+            .filter(|(_, count)| *count > 0)
+            .sorted_by_key(|(prune, count)| (std::cmp::Reverse(*count), *prune))
+            .collect_vec();
+        let rejections = self
+            .rejections
+            .iter()
+            .map(|(&rejection, &count)| (rejection, count))
+            .sorted_by_key(|(rejection, count)| (std::cmp::Reverse(*count), *rejection))
+            .collect_vec();
+        SearchStats {
+            num_expansions: self.num_expansions,
+            most_expanded_chunks,
+            prunes,
+            rejections,
+            num_truncations: self.num_truncations,
+            num_truncated_prefixes: self.num_truncated_prefixes,
+        }
+    }
+}
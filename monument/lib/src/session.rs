@@ -0,0 +1,182 @@
+//! Code for running many related searches, sharing as much work as possible between them.
+
+use std::{
+    collections::HashMap,
+    sync::{atomic::AtomicBool, Arc, Mutex},
+};
+
+#[cfg(feature = "cc_lib")]
+use std::sync::OnceLock;
+
+#[cfg(feature = "cc_lib")]
+use bellframe::MethodLib;
+
+use crate::{
+    composition::CompositionId,
+    graph::{params_hash, BuiltGraph, FalsenessCache},
+    search::{BuildProgress, Config, NoCompsReason, Search, Update},
+    utils::IdGenerator,
+    Composition, Parameters,
+};
+
+/// A long-lived set of resources which are shared between many [`Search`]es, making it much
+/// cheaper to run several related [`Query`]s than to create each [`Search`] separately.
+///
+/// A `Session` reuses work between queries in the following ways:
+/// - Queries whose [`Parameters`] would build the same graph (i.e. they only differ in things like
+///   `num_comps`, score thresholds or `ranking`; see [`BuiltGraph`]) share a single graph, so
+///   only the first of them has to build it.
+/// - Otherwise, graphs which contain the same chunks of the same methods (e.g. queries which only
+///   change the music) share their falseness tables.
+/// - Every query uses the same [`Config`] (and therefore the same thread and memory limits), and
+///   the [`Composition`]s generated by every query have distinct [`CompositionId`]s.
+/// - The Central Council method library is loaded at most once (see `Session::method_lib`, which
+///   needs the `cc_lib` feature).
+///
+/// Graphs are kept for as long as the `Session` exists, so a `Session` with many different
+/// graphs can use a lot of memory.  `Session` is [`Sync`], so queries can also be run from
+/// several threads at once.
+#[derive(Debug)]
+pub struct Session {
+    config: Config,
+    id_generator: Arc<IdGenerator<CompositionId>>,
+    #[cfg(feature = "cc_lib")]
+    method_lib: OnceLock<Option<MethodLib>>,
+    falseness_cache: FalsenessCache,
+    /// Every graph built by this `Session`, indexed by the [`params_hash`] of the [`Parameters`]
+    /// which built it
+    graphs: Mutex<HashMap<u64, Arc<BuiltGraph>>>,
+}
+
+/// One search to be run by a [`Session`].
+#[derive(Debug, Clone)]
+pub struct Query {
+    /// A name for this query, which is used to label its [`Update`]s and [`QueryResult`]
+    pub name: String,
+    pub params: Parameters,
+    /// If set, overrides the [`Session`]'s [`Config`] for this query
+    pub config: Option<Config>,
+}
+
+/// The outcome of running one [`Query`] with [`Session::run`].
+#[derive(Debug, Clone)]
+pub struct QueryResult {
+    /// The [`Query::name`] of the query which generated these results
+    pub name: String,
+    /// The compositions generated, in the order they were found
+    pub comps: Vec<Composition>,
+    /// Why the search didn't generate any compositions, if it finished without generating any
+    pub no_comps_reason: Option<NoCompsReason>,
+}
+
+impl Session {
+    /// Create a new `Session`, where every [`Query`] will use the given [`Config`] (unless it sets
+    /// its own).
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            id_generator: Arc::new(IdGenerator::starting_at_zero()),
+            #[cfg(feature = "cc_lib")]
+            method_lib: OnceLock::new(),
+            falseness_cache: FalsenessCache::default(),
+            graphs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The [`Config`] used by every [`Query`] which doesn't set its own
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// The Central Council method library, which is loaded the first time this is called.
+    /// Returns `None` if the library couldn't be loaded.
+    #[cfg(feature = "cc_lib")]
+    pub fn method_lib(&self) -> Option<&MethodLib> {
+        self.method_lib.get_or_init(MethodLib::cc_lib).as_ref()
+    }
+
+    /// Get the optimised graph for the given [`Parameters`], building it (and calling
+    /// `progress_fn` with [`BuildProgress`] updates) only if no compatible graph has already been
+    /// built by this `Session`.
+    pub fn graph(
+        &self,
+        params: &Parameters,
+        config: &Config,
+        mut progress_fn: impl FnMut(BuildProgress),
+    ) -> crate::Result<Arc<BuiltGraph>> {
+        let hash = params_hash(params);
+        if let Some(graph) = self.graphs.lock().unwrap().get(&hash) {
+            log::debug!("Reusing graph from an earlier query");
+            return Ok(graph.clone());
+        }
+        // Don't hold the lock while building, so other threads can use other graphs.  If two
+        // threads build the same graph at once, one of them will just be wasted work
+        let graph = Arc::new(BuiltGraph::build(
+            params,
+            config,
+            &self.falseness_cache,
+            &mut progress_fn,
+        )?);
+        self.graphs.lock().unwrap().insert(hash, graph.clone());
+        Ok(graph)
+    }
+
+    /// Create the [`Search`] for a [`Query`], reusing as much work from earlier queries as
+    /// possible.  `progress_fn` is called with [`BuildProgress`] updates if a new graph has to be
+    /// built.
+    pub fn search(
+        &self,
+        query: Query,
+        progress_fn: impl FnMut(BuildProgress),
+    ) -> crate::Result<Search> {
+        let config = query.config.unwrap_or_else(|| self.config.clone());
+        let graph = self.graph(&query.params, &config, progress_fn)?;
+        let search = Search::from_graph(query.params, config, &graph)?;
+        Ok(search.id_generator(self.id_generator.clone()))
+    }
+
+    /// Run every [`Query`] in turn, **blocking the current thread** until they've all finished.
+    /// `update_fn` is called with every [`Update`] from every search, along with the name of the
+    /// query which sent it.  If the search is aborted using `abort_flag`, no more queries are
+    /// started and the results of the queries so far are returned.
+    ///
+    /// If creating any query's [`Search`] fails, the first such [`Error`](crate::Error) is
+    /// returned.
+    pub fn run(
+        &self,
+        queries: impl IntoIterator<Item = Query>,
+        mut update_fn: impl FnMut(&str, Update),
+        abort_flag: &AtomicBool,
+    ) -> crate::Result<Vec<QueryResult>> {
+        let mut results = Vec::new();
+        for query in queries {
+            let name = query.name.clone();
+            log::debug!("Running query {name:?}");
+            let search = self.search(query, |_| {})?;
+            let mut comps = Vec::new();
+            let mut no_comps_reason = None;
+            search.run(
+                |update| {
+                    match &update {
+                        Update::Comp(comp) => comps.push(comp.clone()),
+                        Update::NoComps(reason) => no_comps_reason = Some(reason.clone()),
+                        _ => {}
+                    }
+                    update_fn(&name, update);
+                },
+                abort_flag,
+            );
+            results.push(QueryResult {
+                name,
+                comps,
+                no_comps_reason,
+            });
+            // `Search::run` clears the abort flag when it starts, so it's only still set if this
+            // search was aborted
+            if abort_flag.load(std::sync::atomic::Ordering::SeqCst) {
+                break;
+            }
+        }
+        Ok(results)
+    }
+}
@@ -0,0 +1,139 @@
+//! Helpers for building [`Parameters`] and [`Composition`]s in unit tests.
+
+use std::{collections::HashMap, sync::atomic::AtomicBool};
+
+use bellframe::{music::AtRowPositions, Mask, RowBuf, Stage, Stroke};
+use itertools::Itertools;
+
+use crate::{
+    parameters::{
+        base_calls, BaseCallType, CallDisplayStyle, Method, MethodId, MultipartCallDisplay,
+        MusicType, NonDufferMusic, OptionalRangeInclusive, Ranking, SpliceStyle,
+    },
+    utils::{IdGenerator, TotalLength},
+    Composition, Config, Parameters, PartHeadGroup, Search, Update,
+};
+
+/// [`Parameters`] for Plain Bob at the given [`Stage`], with near bobs and singles and 4-bell runs
+/// as the only music.  The length is left open, so any composition of Plain Bob is valid.
+pub(crate) fn plain_bob_params(stage: Stage) -> Parameters {
+    let place_notation = format!("x1{0}x1{0}x1{0},12", stage.num_bells());
+    let mut inner =
+        bellframe::Method::from_place_not_string(String::new(), stage, &place_notation).unwrap();
+    inner.set_lead_end_label();
+    let method = Method {
+        id: MethodId(0),
+        inner,
+        custom_shorthand: "P".to_owned(),
+        count_range: OptionalRangeInclusive::OPEN,
+        count_range_per_part: OptionalRangeInclusive::OPEN,
+        start_indices: vec![0],
+        end_indices: (0..stage.num_bells() as isize * 2).collect_vec(),
+        can_start: true,
+        can_end: true,
+        link_position: None,
+        allowed_courses: vec![Mask::parse_with_stage("1*", stage).unwrap().into()],
+    };
+    let runs = MusicType {
+        show_total: true,
+        show_positions: AtRowPositions::FRONT_AND_BACK,
+        name: "4-bell runs".to_owned(),
+        inner: bellframe::MusicType::runs(4, stage),
+        weights: AtRowPositions::front_and_back(1.0),
+        count_range: OptionalRangeInclusive::OPEN,
+        non_duffer: false,
+    };
+
+    Parameters {
+        length: TotalLength::ZERO..=TotalLength::new(5000),
+        stage,
+        num_comps: 100,
+        require_truth: true,
+        max_row_repeats: 1,
+        extent: false,
+        prune_dominated_chunks: false,
+        min_total_score: None,
+        min_music_score: None,
+        max_calls: None,
+        min_conductability: None,
+        shortlist: None,
+        ranking: Ranking::AvgScore,
+        row_weight: 0.0,
+        target_length: None,
+
+        methods: index_vec::index_vec![method],
+        splice_style: SpliceStyle::LeadLabels,
+        splice_weight: 0.0,
+        splice_weights: HashMap::new(),
+        method_counts_in_leads: false,
+        calls: base_calls(
+            &mut IdGenerator::starting_at_zero(),
+            BaseCallType::Near,
+            Some(-1.8),
+            Some(-2.3),
+            stage,
+        ),
+        call_display_style: CallDisplayStyle::CallingPositions,
+        calling_bell: stage.tenor(),
+        calling_position_names: HashMap::new(),
+        allowed_calling_positions: None,
+        multipart_call_display: MultipartCallDisplay::FirstPart,
+        strict_labels: false,
+        atw_weight: None,
+        require_atw: false,
+
+        start_row: RowBuf::rounds(stage),
+        end_row: RowBuf::rounds(stage),
+        part_head_group: PartHeadGroup::one_part(stage),
+        course_weights: vec![],
+        no_repeated_courses: false,
+        calling: None,
+        omit_round_blocks: false,
+        calling_filters: vec![],
+
+        music_types: index_vec::index_vec![runs],
+        no_duffers: false,
+        max_duffer_rows: None,
+        non_duffer_music: NonDufferMusic::Marked,
+        required_rows: vec![],
+        start_stroke: Stroke::Back,
+    }
+}
+
+/// Same as [`plain_bob_params`], but for a multi-part composition where the part heads are
+/// generated by `part_head` (e.g. `"124653"`).
+pub(crate) fn multipart_plain_bob_params(stage: Stage, part_head: &str) -> Parameters {
+    let part_head = RowBuf::parse_with_stage(part_head, stage).unwrap();
+    Parameters {
+        part_head_group: PartHeadGroup::new(&part_head),
+        ..plain_bob_params(stage)
+    }
+}
+
+/// Find the [`Composition`] which has the given `calling` (e.g. `"sWsH"`) under `params`.  Panics
+/// unless exactly one such composition exists.
+pub(crate) fn comp_with_calling(params: &Parameters, calling: &str) -> Composition {
+    let mut params = params.clone();
+    params.calling = Some(calling.to_owned());
+    let config = Config {
+        thread_limit: Some(1),
+        seed: Some(0),
+        ..Default::default()
+    };
+    let search = Search::new(params, config).unwrap();
+    let mut comps = Vec::new();
+    search.run_exhaustive(
+        |update| {
+            if let Update::Comp(comp) = update {
+                comps.push(comp);
+            }
+        },
+        &AtomicBool::new(false),
+    );
+    assert_eq!(
+        comps.len(),
+        1,
+        "Expected exactly one comp with calling {calling:?}"
+    );
+    comps.pop().unwrap()
+}
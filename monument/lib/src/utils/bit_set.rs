@@ -0,0 +1,144 @@
+use std::sync::Arc;
+
+use datasize::DataSize;
+
+/// Number of bits stored in each word of a [`BitSet`]
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// A fixed-size set of bits, stored as a slice of `u64`s so that unions can be vectorised.  The
+/// words are reference-counted, so cloning a `BitSet` only costs a reference-count increment.  The
+/// words are only copied (copy-on-write) when a shared set is modified, so prefixes which haven't
+/// diverged from their parent don't use any extra memory.
+#[derive(Debug, Clone)]
+pub(crate) struct BitSet {
+    words: Arc<[u64]>,
+    len: usize,
+}
+
+impl BitSet {
+    /// Creates a `BitSet` which can hold `len` bits, all of which are `false`.
+    pub fn empty(len: usize) -> Self {
+        Self {
+            words: vec![0; len.div_ceil(WORD_BITS)].into(),
+            len,
+        }
+    }
+
+    /// Returns the bit at `idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of range.
+    pub fn get(&self, idx: usize) -> bool {
+        assert!(
+            idx < self.len,
+            "Index {idx} out of range for `BitSet` of {}",
+            self.len
+        );
+        self.words[idx / WORD_BITS] & (1 << (idx % WORD_BITS)) != 0
+    }
+
+    /// Sets the bit at `idx` to `true`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of range.
+    pub fn insert(&mut self, idx: usize) {
+        assert!(
+            idx < self.len,
+            "Index {idx} out of range for `BitSet` of {}",
+            self.len
+        );
+        Arc::make_mut(&mut self.words)[idx / WORD_BITS] |= 1 << (idx % WORD_BITS);
+    }
+
+    /// Sets every bit which is set in `other`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` and `other` have different lengths.
+    pub fn union_with(&mut self, other: &Self) {
+        assert_eq!(self.len, other.len);
+        let words = Arc::make_mut(&mut self.words);
+        #[cfg(target_arch = "x86_64")]
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: we've just checked that the CPU supports AVX2
+            unsafe { union_words_avx2(words, &other.words) };
+            return;
+        }
+        union_words(words, &other.words);
+    }
+
+    /// Iterates over the indices of every bit which is set, in increasing order.
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(word_idx, &word)| {
+            let mut word = word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit_idx = word.trailing_zeros() as usize;
+                word &= word - 1; // Clear the lowest set bit
+                Some(word_idx * WORD_BITS + bit_idx)
+            })
+        })
+    }
+
+    /// The number of bytes occupied by the words of this set.  Shared words are counted in full,
+    /// so this is an upper bound on the memory used by each copy of the set.
+    pub fn num_bytes(&self) -> usize {
+        std::mem::size_of_val(&*self.words)
+    }
+}
+
+impl DataSize for BitSet {
+    const IS_DYNAMIC: bool = true;
+
+    const STATIC_HEAP_SIZE: usize = 0;
+
+    fn estimate_heap_size(&self) -> usize {
+        self.num_bytes()
+    }
+}
+
+/// Sets every bit in `words` which is set in `other`.  This is a plain loop, which LLVM vectorises
+/// with whatever SIMD instructions the target is compiled for.
+#[inline(always)]
+fn union_words(words: &mut [u64], other: &[u64]) {
+    for (word, other_word) in words.iter_mut().zip(other) {
+        *word |= *other_word;
+    }
+}
+
+/// [`union_words`], compiled to use 256-bit AVX2 instructions.
+///
+/// # Safety
+///
+/// The CPU must support AVX2.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn union_words_avx2(words: &mut [u64], other: &[u64]) {
+    union_words(words, other);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BitSet;
+
+    #[test]
+    fn insert_and_union() {
+        let mut a = BitSet::empty(130);
+        a.insert(0);
+        a.insert(64);
+        let mut b = BitSet::empty(130);
+        b.insert(64);
+        b.insert(129);
+        let a_before = a.clone();
+
+        a.union_with(&b);
+        assert_eq!(a.iter_ones().collect::<Vec<_>>(), vec![0, 64, 129]);
+        assert!(a.get(129) && !a.get(128));
+        // `a_before` shared its words with `a`, so it mustn't see the union
+        assert_eq!(a_before.iter_ones().collect::<Vec<_>>(), vec![0, 64]);
+    }
+}
@@ -3,8 +3,10 @@ use std::{
     sync::atomic::{AtomicU32, Ordering},
 };
 
+pub(crate) mod bit_set;
 pub(crate) mod counts;
 pub(crate) mod lengths;
+pub(crate) mod time;
 
 pub use lengths::{PerPartLength, TotalLength};
 
@@ -63,7 +65,7 @@ impl<Id: From<u32> + Into<u32>> IdGenerator<Id> {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Boundary {
     Start,
     End,
@@ -73,3 +75,77 @@ pub enum Boundary {
 pub(crate) fn div_rounding_up(lhs: usize, rhs: usize) -> usize {
     (lhs + rhs - 1) / rhs
 }
+
+/// Hash some bytes with [FNV-1a](https://en.wikipedia.org/wiki/Fowler%E2%80%93Noll%E2%80%93Vo_hash_function).
+/// Unlike [`std::hash::DefaultHasher`], this is guaranteed to give the same hash in every build of
+/// Monument, so can be used for hashes which are saved to disk.
+pub(crate) fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// Format a number of rows as a (possibly fractional) number of leads.  For example, 224 rows of a
+/// 32-row lead is `"7"` and 240 rows is `"7.5"`.
+pub(crate) fn leads_string(rows: usize, lead_len: usize) -> String {
+    if rows.is_multiple_of(lead_len) {
+        (rows / lead_len).to_string()
+    } else {
+        let leads = format!("{:.2}", rows as f64 / lead_len as f64);
+        leads.trim_end_matches('0').to_owned()
+    }
+}
+
+/// A small, fast and deterministic pseudo-random number generator (using the
+/// [SplitMix64](https://prng.di.unimi.it/splitmix64.c) algorithm).  Two `Rng`s created with the
+/// same seed will always produce the same sequence of numbers, on any platform.
+#[derive(Debug, Clone)]
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Create a new seed from the OS's source of randomness
+    pub fn random_seed() -> u64 {
+        use std::hash::{BuildHasher, Hasher};
+        std::collections::hash_map::RandomState::new()
+            .build_hasher()
+            .finish()
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A uniformly random number in the open range `(0, 1)`
+    pub fn next_f32(&mut self) -> f32 {
+        // Use the top 24 bits, which exactly fill an `f32`'s mantissa
+        ((self.next_u64() >> 40) as f32 + 0.5) / (1u32 << 24) as f32
+    }
+
+    /// A random number from the standard Gumbel distribution.  If `x + gumbel` is computed for
+    /// many values of `x`, the largest is the one which a softmax over the values would choose.
+    pub fn next_gumbel(&mut self) -> f32 {
+        -(-self.next_f32().ln()).ln()
+    }
+
+    /// Shuffle a slice into a uniformly random order (using a Fisher-Yates shuffle)
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            // Modulo bias is negligible, since `slice.len()` is tiny compared to `u64::MAX`
+            let j = (self.next_u64() % (i as u64 + 1)) as usize;
+            slice.swap(i, j);
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//! A replacement for [`std::time::Instant`] which also works on `wasm32-unknown-unknown` (where
+//! the standard library has no clock, so [`std::time::Instant::now`] panics).
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::Instant;
+
+/// Whether [`Instant`] actually measures time.  If not, every [`Instant`] is the same and
+/// [`Instant::elapsed`] always returns zero, so [`Instant`]s can only be used to time log
+/// messages.
+pub(crate) const HAS_CLOCK: bool = cfg!(not(target_arch = "wasm32"));
+
+#[cfg(target_arch = "wasm32")]
+pub use self::no_clock::Instant;
+
+#[cfg(target_arch = "wasm32")]
+mod no_clock {
+    use std::{ops::Add, time::Duration};
+
+    /// A point in time on a platform without a clock.  No time ever passes.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    pub struct Instant;
+
+    impl Instant {
+        pub fn now() -> Self {
+            Instant
+        }
+
+        pub fn elapsed(&self) -> Duration {
+            Duration::ZERO
+        }
+    }
+
+    impl Add<Duration> for Instant {
+        type Output = Self;
+
+        fn add(self, _: Duration) -> Self {
+            Instant
+        }
+    }
+}
@@ -0,0 +1,356 @@
+//! Python bindings to Monument, for scripting searches (e.g. sweeping music weights) and analysing
+//! their results from Python.  Build with `maturin develop` or `pip install .` in this directory.
+//!
+//! Queries start from the same TOML format as the CLI, and can then be tweaked before each search:
+//!
+//! ```python
+//! import monument
+//!
+//! builder = monument.QueryBuilder.from_file("spec.toml")
+//! for weight in [0.5, 1.0, 2.0]:
+//!     comps = builder.music_weight("5678s", weight).num_comps(20).build().run()
+//!     print(weight, max(c.score_per_row for c in comps))
+//! ```
+//!
+//! Every [`Search`] built by the same `QueryBuilder` (or its copies) shares work through a
+//! [`Session`], so sweeping over weights only builds each graph once.
+
+#![deny(rustdoc::broken_intra_doc_links, rustdoc::private_intra_doc_links)]
+
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use bellframe::music::AtRowPositions;
+use monument::{
+    composition::ParamsData, utils::TotalLength, Config, Parameters, Query, Session, Update,
+};
+use monument_cli::{args::Options, toml_file::TomlFile};
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+#[pymodule]
+#[pyo3(name = "monument")]
+fn monument_python(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<QueryBuilder>()?;
+    m.add_class::<Search>()?;
+    m.add_class::<Composition>()?;
+    Ok(())
+}
+
+/// A query loaded from a TOML specification, which can be modified before building a [`Search`].
+/// Every setter returns the same `QueryBuilder`, so they can be chained.
+#[pyclass(module = "monument")]
+#[derive(Clone)]
+struct QueryBuilder {
+    params: Parameters,
+    config: Config,
+    exhaustive: bool,
+    monte_carlo: bool,
+    session: Arc<Session>,
+}
+
+#[pymethods]
+impl QueryBuilder {
+    /// Load a query from the contents of a TOML specification.  `path` is where the TOML would
+    /// have been loaded from, and is used to find any other files it refers to.
+    #[staticmethod]
+    #[pyo3(signature = (toml, path = None))]
+    fn from_toml(toml: &str, path: Option<PathBuf>) -> PyResult<Self> {
+        let toml_file = monument_cli::utils::parse_toml::<TomlFile>(toml).map_err(to_py_err)?;
+        let params = toml_file
+            .to_params(&path.unwrap_or_default())
+            .map_err(to_py_err)?;
+        let config = toml_file.config(&Options::default(), false);
+        Ok(Self {
+            params,
+            exhaustive: toml_file.exhaustive(),
+            monte_carlo: toml_file.monte_carlo(),
+            session: Arc::new(Session::new(config.clone())),
+            config,
+        })
+    }
+
+    /// Load a query from a TOML specification file.
+    #[staticmethod]
+    fn from_file(path: PathBuf) -> PyResult<Self> {
+        let toml = monument_cli::utils::read_file_to_string(&path).map_err(to_py_err)?;
+        Self::from_toml(&toml, Some(path))
+    }
+
+    /// Make a copy of this query, which can be modified separately but still shares work with
+    /// this one.
+    fn copy(&self) -> Self {
+        self.clone()
+    }
+
+    /// Only generate compositions with between `min` and `max` rows (inclusive).  If `max` isn't
+    /// given, only compositions of exactly `min` rows are generated.
+    #[pyo3(signature = (min, max = None))]
+    fn length(mut slf: PyRefMut<'_, Self>, min: usize, max: Option<usize>) -> PyRefMut<'_, Self> {
+        let max = max.unwrap_or(min);
+        slf.params.length = TotalLength::new(min)..=TotalLength::new(max);
+        slf
+    }
+
+    /// Stop the search once it has generated this many compositions.
+    fn num_comps(mut slf: PyRefMut<'_, Self>, num_comps: usize) -> PyRefMut<'_, Self> {
+        slf.params.num_comps = num_comps;
+        slf
+    }
+
+    /// Set the score given to every change of method (usually negative, to discourage splices).
+    fn splice_weight(mut slf: PyRefMut<'_, Self>, weight: f32) -> PyRefMut<'_, Self> {
+        slf.params.splice_weight = weight;
+        slf
+    }
+
+    /// Set the weight of every call with the given symbol (e.g. `"-"` for bobs).
+    fn call_weight(
+        mut slf: PyRefMut<'_, Self>,
+        symbol: char,
+        weight: f32,
+    ) -> PyResult<PyRefMut<'_, Self>> {
+        let mut calls = slf.params.calls.iter_mut().filter(|c| c.symbol == symbol);
+        let Some(first_call) = calls.next() else {
+            return Err(PyValueError::new_err(format!(
+                "No call has symbol {symbol:?}"
+            )));
+        };
+        first_call.weight = weight;
+        calls.for_each(|c| c.weight = weight);
+        Ok(slf)
+    }
+
+    /// Set the weight of every row matching the music type called `name` (see
+    /// [`music_names`](Self::music_names)).
+    fn music_weight(
+        mut slf: PyRefMut<'_, Self>,
+        name: &str,
+        weight: f32,
+    ) -> PyResult<PyRefMut<'_, Self>> {
+        let Some(music_type) = slf.params.music_types.iter_mut().find(|m| m.name == name) else {
+            return Err(PyValueError::new_err(format!(
+                "No music type called {name:?}"
+            )));
+        };
+        music_type.weights = AtRowPositions::splat(weight);
+        Ok(slf)
+    }
+
+    /// The names of every music type in this query, in the order they're displayed.
+    fn music_names(&self) -> Vec<String> {
+        let music_types = self.params.music_types.iter();
+        music_types.map(|m| m.name.clone()).collect()
+    }
+
+    /// Set the seed used to break ties between equally good compositions, so that a search can
+    /// be repeated exactly.  If `seed` is `None` (the default), every search picks a random seed.
+    #[pyo3(signature = (seed = None))]
+    fn seed(mut slf: PyRefMut<'_, Self>, seed: Option<u64>) -> PyRefMut<'_, Self> {
+        slf.config.seed = seed;
+        slf
+    }
+
+    /// Create a [`Search`] for this query, building its graph if no earlier search could share
+    /// one.
+    fn build(&self, py: Python<'_>) -> PyResult<Search> {
+        let query = Query {
+            name: String::new(),
+            params: self.params.clone(),
+            config: Some(self.config.clone()),
+        };
+        // Building the graph can take a while, so let other Python threads run
+        let inner = py
+            .allow_threads(|| self.session.search(query, |_| {}))
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(Search {
+            inner,
+            exhaustive: self.exhaustive,
+            monte_carlo: self.monte_carlo,
+            abort_flag: AtomicBool::new(false),
+        })
+    }
+}
+
+/// A search which is ready to run
+#[pyclass(module = "monument")]
+struct Search {
+    inner: monument::Search,
+    exhaustive: bool,
+    monte_carlo: bool,
+    abort_flag: AtomicBool,
+}
+
+#[pymethods]
+impl Search {
+    /// Run the search, **blocking the current thread** until it either finishes or is aborted
+    /// with [`abort`](Self::abort).  Returns every composition generated, in the order they were
+    /// found.
+    fn run(&self, py: Python<'_>) -> Vec<Composition> {
+        let comps = py.allow_threads(|| {
+            let mut comps = Vec::new();
+            let update_fn = |update: Update| {
+                if let Update::Comp(comp) = update {
+                    comps.push(comp);
+                }
+            };
+            if self.exhaustive {
+                self.inner.run_exhaustive(update_fn, &self.abort_flag);
+            } else if self.monte_carlo {
+                self.inner.run_monte_carlo(update_fn, &self.abort_flag);
+            } else {
+                self.inner.run(update_fn, &self.abort_flag);
+            }
+            comps
+        });
+        let params_data = ParamsData::new(self.inner.parameters());
+        comps
+            .iter()
+            .filter_map(|comp| Composition::new(comp, &params_data))
+            .collect()
+    }
+
+    /// Abort a search which is being run on another thread.  The search stops shortly afterwards,
+    /// and `run` returns the compositions generated so far.
+    fn abort(&self) {
+        self.abort_flag.store(true, Ordering::SeqCst);
+    }
+}
+
+/// A composition generated by a [`Search`]
+#[pyclass(module = "monument", frozen, get_all)]
+struct Composition {
+    length: usize,
+    call_string: String,
+    part_head: String,
+    music_score: f32,
+    total_score: f32,
+    score_per_row: f32,
+    repeated_rows: usize,
+    /// The number of rows of each method, indexed by method title
+    method_counts: HashMap<String, usize>,
+    /// The number of occurrences of every displayed music type, indexed by name
+    music_counts: HashMap<String, usize>,
+}
+
+impl Composition {
+    /// Returns `None` if the composition can't be described with these parameters
+    fn new(comp: &monument::Composition, params_data: &ParamsData) -> Option<Self> {
+        let params: &Parameters = params_data;
+        let v = comp.values(params_data)?;
+        let method_counts = params.methods.iter().zip(&v.method_counts);
+        Some(Self {
+            length: v.length(),
+            part_head: v.part_head().to_string(),
+            music_score: v.music_score,
+            total_score: v.total_score,
+            score_per_row: v.score_per_row(),
+            repeated_rows: v.num_repeated_rows(),
+            method_counts: method_counts
+                .map(|(method, count)| (method.title(), count.as_usize()))
+                .collect(),
+            music_counts: v
+                .music_counts_by_name(params)
+                .into_iter()
+                .map(|(name, count)| (name.to_owned(), count))
+                .collect(),
+            call_string: v.call_string,
+        })
+    }
+}
+
+#[pymethods]
+impl Composition {
+    fn __repr__(&self) -> String {
+        format!(
+            "Composition(length={}, call_string={:?}, score_per_row={})",
+            self.length, self.call_string, self.score_per_row
+        )
+    }
+}
+
+fn to_py_err(error: anyhow::Error) -> PyErr {
+    PyValueError::new_err(format!("{error:#}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML: &str = r#"
+        length = { min = 0, max = 240 }
+        method = { name = "Plain Bob", place_notation = "x16x16x16,12", stage = 6 }
+        num_comps = 10
+        music = [{ name = "4-bell runs", run_lengths = [4] }]
+        base_music = "none"
+    "#;
+
+    /// Run `f` with a `QueryBuilder` loaded from [`TOML`]
+    fn with_builder(f: impl FnOnce(Python<'_>, &Bound<'_, QueryBuilder>)) {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let builder = Bound::new(py, QueryBuilder::from_toml(TOML, None).unwrap()).unwrap();
+            f(py, &builder);
+        });
+    }
+
+    #[test]
+    fn from_toml() {
+        with_builder(|_py, builder| {
+            let builder = builder.borrow();
+            assert_eq!(builder.params.stage, bellframe::Stage::MINOR);
+            assert_eq!(builder.params.num_comps, 10);
+            assert_eq!(builder.music_names(), ["4-bell runs"]);
+            assert!(!builder.exhaustive && !builder.monte_carlo);
+        });
+        // Invalid specifications become Python exceptions
+        assert!(QueryBuilder::from_toml("length = ", None).is_err());
+        assert!(QueryBuilder::from_toml("length = 100", None).is_err()); // No methods
+    }
+
+    #[test]
+    fn setter_errors() {
+        with_builder(|py, builder| {
+            let error = QueryBuilder::call_weight(builder.borrow_mut(), 'x', -1.0)
+                .err()
+                .unwrap();
+            assert_eq!(error.value_bound(py).to_string(), "No call has symbol 'x'");
+            let error = QueryBuilder::music_weight(builder.borrow_mut(), "5678s", 1.0)
+                .err()
+                .unwrap();
+            assert_eq!(
+                error.value_bound(py).to_string(),
+                r#"No music type called "5678s""#
+            );
+
+            // Setters which succeed change the parameters
+            assert!(QueryBuilder::call_weight(builder.borrow_mut(), '-', -1.0).is_ok());
+            let builder = builder.borrow();
+            let bobs = builder.params.calls.iter().filter(|c| c.symbol == '-');
+            assert!(bobs.map(|c| c.weight).all(|w| w == -1.0));
+        });
+    }
+
+    #[test]
+    fn compositions() {
+        with_builder(|py, builder| {
+            QueryBuilder::seed(builder.borrow_mut(), Some(0));
+            let comps = builder.borrow().build(py).unwrap().run(py);
+            assert_eq!(comps.len(), 10);
+            for comp in &comps {
+                assert!(comp.length <= 240);
+                assert_eq!(comp.method_counts["Plain Bob Minor"], comp.length);
+                assert!(comp.music_counts.contains_key("4-bell runs"));
+                assert_eq!(comp.repeated_rows, 0);
+                let call_weights = comp.total_score - comp.music_score;
+                assert!(call_weights <= 0.0);
+                assert_eq!(comp.score_per_row, comp.total_score / comp.length as f32);
+            }
+        });
+    }
+}
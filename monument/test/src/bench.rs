@@ -55,7 +55,11 @@ fn run_bench(unrun_case: common::UnrunTestCase<CaseData>) -> common::RunTestCase
     println!();
     println!("Running {}", unrun_case.name().white().bold());
 
-    let run_case = unrun_case.run(&["--only-update-line"], /* display_stderr = */ true);
+    let args = common::Args {
+        before_path: vec![],
+        after_path: vec!["--only-update-line", "--no-falseness-cache"],
+    };
+    let run_case = unrun_case.run(&args, /* display_stderr = */ true);
 
     // Print summary
     println!();
@@ -64,6 +64,14 @@ pub fn get_filter_regex(filter: Option<&str>) -> Result<Regex, anyhow::Error> {
     Regex::new(&format!("^{filter}$")).context("Error parsing filter")
 }
 
+/// Command-line arguments for `monument_cli`, which go either side of the path of the case
+#[derive(Debug, Clone)]
+pub struct Args<'a> {
+    /// Global options and subcommands (e.g. `["-q", "check"]`)
+    pub before_path: Vec<&'a str>,
+    pub after_path: Vec<&'a str>,
+}
+
 #[derive(Debug)]
 pub struct UnrunTestCase<D> {
     pub path: PathFromMonument,
@@ -102,7 +110,7 @@ impl<D> std::ops::Deref for UnrunTestCase<D> {
 }
 
 impl<D> UnrunTestCase<D> {
-    pub fn run(self, args: &[&str], display_stderr: bool) -> RunTestCase<D> {
+    pub fn run(self, args: &Args, display_stderr: bool) -> RunTestCase<D> {
         if self.ignored {
             return RunTestCase {
                 base: self,
@@ -112,6 +120,19 @@ impl<D> UnrunTestCase<D> {
             };
         }
 
+        let start = Instant::now();
+        let (panicked, output) = self.run_monument(args, display_stderr);
+        RunTestCase {
+            duration: start.elapsed(),
+            base: self,
+            panicked,
+            output: Some(output),
+        }
+    }
+
+    /// Run `monument_cli` on this case with the given `args`, returning whether or not it
+    /// panicked along with its (colour-free) output.
+    pub fn run_monument(&self, args: &Args, display_stderr: bool) -> (bool, String) {
         // Determine where the 'monument_cli' executible is.  This is harder than it seems because
         // Cargo allows users (like myself) to override the location of the build directory from the
         // default of `target/`.  The most reliable way to find the executible path is to ask
@@ -136,10 +157,10 @@ impl<D> UnrunTestCase<D> {
 
         // Spawn a command to run Monument, and fetch its stdout output as the test result
         let toml_path = self.path.relative_to_cargo_toml();
-        let start = Instant::now();
         let cmd = std::process::Command::new(monument_cli_path)
+            .args(&args.before_path)
             .arg(&*toml_path.as_os_str().to_string_lossy())
-            .args(args)
+            .args(&args.after_path)
             .stdout(Stdio::piped())
             .stderr(if display_stderr {
                 Stdio::inherit()
@@ -149,7 +170,6 @@ impl<D> UnrunTestCase<D> {
             .spawn()
             .unwrap();
         let output = cmd.wait_with_output().unwrap();
-        let duration = start.elapsed();
 
         let panicked = match output.status.code().unwrap() {
             0 => false,   // Success
@@ -170,13 +190,7 @@ impl<D> UnrunTestCase<D> {
         let output = String::from_utf8_lossy(output_text).into_owned();
         let ansi_escape_regex = Regex::new("\x1b\\[.*?m").unwrap();
         let color_free_output = ansi_escape_regex.replace_all(&output, "").into_owned();
-
-        RunTestCase {
-            base: self,
-            duration,
-            panicked,
-            output: Some(color_free_output),
-        }
+        (panicked, color_free_output)
     }
 }
 
@@ -24,6 +24,8 @@ const TEST_DIRS: &[&str] = &[
     "test/cases/", // Test cases which we expect to succeed
     "examples/",   // Examples to show how Monument's TOML format works
 ];
+/// Test cases in this directory are run with the `check` subcommand rather than searched
+const CHECK_DIR: &str = "test/cases/check/";
 
 ///////////////////
 // MAIN FUNCTION //
@@ -44,7 +46,7 @@ fn main() -> anyhow::Result<()> {
         }
         None => {
             // If no args were given, just run the tests
-            match run(args.filter.as_deref())? {
+            match run(args.filter.as_deref(), args.check_threads)? {
                 Outcome::Fail => Err(anyhow::Error::msg("Tests failed")),
                 Outcome::Pass => Ok(()),
             }
@@ -61,6 +63,10 @@ struct Args {
     /// Only tests who's paths match this regex will be run
     #[structopt(long, short = "F")]
     filter: Option<String>,
+    /// Also run every test single-threaded (i.e. with `-T 1`), failing any test who's output
+    /// differs from the multi-threaded run.  This catches races in Monument's parallel code.
+    #[structopt(long)]
+    check_threads: bool,
     #[structopt(subcommand)]
     sub_command: Option<SubCommand>,
 }
@@ -80,8 +86,9 @@ enum SubCommand {
 // TOP-LEVEL RUNNER CODE //
 ///////////////////////////
 
-/// Run the full test suite.
-pub fn run(filter: Option<&str>) -> anyhow::Result<Outcome> {
+/// Run the full test suite.  If `check_threads` is set, every test is also run single-threaded
+/// and its output compared to the multi-threaded run.
+pub fn run(filter: Option<&str>, check_threads: bool) -> anyhow::Result<Outcome> {
     monument_cli::init_logging(log::LevelFilter::Warn); // Equivalent to '-q'
 
     let start = Instant::now();
@@ -90,7 +97,10 @@ pub fn run(filter: Option<&str>) -> anyhow::Result<Outcome> {
     let cases = get_cases(filter)?;
     // Run the tests.
     println!("running {} tests", cases.len());
-    let completed_tests: Vec<RunTestCase<CaseData>> = cases.into_par_iter().map(run_test).collect();
+    let completed_tests: Vec<RunTestCase<CaseData>> = cases
+        .into_par_iter()
+        .map(|case| run_test(case, check_threads))
+        .collect();
     // Collate failures and unspecified tests from all `Suite`s
     report_failures(&completed_tests);
     print_fails_summary(&completed_tests);
@@ -110,15 +120,20 @@ fn get_cases(filter: Option<&str>) -> anyhow::Result<Vec<UnrunTestCase<CaseData>
         filter,
         |path: &PathFromMonument, is_ignored: bool| {
             let is_example = path.to_string().contains("example");
+            let is_check = path.to_string().starts_with(CHECK_DIR);
             CaseData {
                 behaviour: if is_ignored {
                     CaseBehaviour::Ignored
                 } else if is_example {
                     CaseBehaviour::Example
+                } else if is_check {
+                    CaseBehaviour::Check
                 } else {
                     CaseBehaviour::Test
                 },
                 expected_output: results.remove(path),
+                single_threaded_output: None,
+                single_threaded_panicked: false,
             }
         },
     )
@@ -126,21 +141,47 @@ fn get_cases(filter: Option<&str>) -> anyhow::Result<Vec<UnrunTestCase<CaseData>
 
 /// Run a test case (skipping ignored cases), determining its [`TestResult`].  Prints a status line
 /// once finished.
-fn run_test(case: UnrunTestCase<CaseData>) -> RunTestCase<CaseData> {
+fn run_test(case: UnrunTestCase<CaseData>, check_threads: bool) -> RunTestCase<CaseData> {
     let no_search = match case.behaviour {
         CaseBehaviour::Test => false,
         CaseBehaviour::Example => true,
+        CaseBehaviour::Check => false,   // `check` never searches
         CaseBehaviour::Ignored => false, // Exact value doesn't matter
     };
 
-    let mut args = vec![
-        "-q",                // Info messages might change often
-        "--no-comp-numbers", // We only want to test *which* comps are outputted, not their order
-    ];
+    let mut args = common::Args {
+        // Info messages might change often.  `-q` must come before any subcommand
+        before_path: vec!["-q"],
+        after_path: vec![
+            // We only want to test *which* comps are outputted, not their order
+            "--no-comp-numbers",
+            // Stale cache files shouldn't be able to hide falseness bugs
+            "--no-falseness-cache",
+            // Version numbers and dates shouldn't change the results
+            "--no-provenance",
+        ],
+    };
+    if case.behaviour == CaseBehaviour::Check {
+        args.before_path.push("check");
+    }
     if no_search {
-        args.extend(["-D", "no-search"]);
+        args.after_path.extend(["-D", "no-search"]);
+    }
+    // When comparing thread counts, both runs must use the same seed.  We use the case's own seed
+    // if it has one, so that the output can still be compared to the expected results
+    let check_threads = check_threads && case.behaviour == CaseBehaviour::Test;
+    let seed = check_threads.then(|| case_seed(&case).unwrap_or(0).to_string());
+    if let Some(seed) = &seed {
+        args.after_path.extend(["--seed", seed]);
+    }
+    let mut run_case = case.run(&args, /* display_stderr = */ false);
+    if check_threads {
+        // Run the same search again on only one thread, which should give identical results
+        args.after_path.extend(["-T", "1"]);
+        let (panicked, output) = run_case.run_monument(&args, /* display_stderr = */ false);
+        run_case.base.data.single_threaded_output = Some(output);
+        run_case.base.data.single_threaded_panicked = panicked;
     }
-    let run_case = case.run(&args, /* display_stderr = */ false);
 
     println!(
         "{} ... {}",
@@ -150,6 +191,14 @@ fn run_test(case: UnrunTestCase<CaseData>) -> RunTestCase<CaseData> {
     run_case
 }
 
+/// The `seed` set in a case's TOML file, if any
+fn case_seed(case: &UnrunTestCase<CaseData>) -> Option<u64> {
+    let toml_string = std::fs::read_to_string(case.path.relative_to_cargo_toml()).ok()?;
+    let table = toml::from_str::<toml::Table>(&toml_string).ok()?;
+    let seed = table.get("seed")?.as_integer()?;
+    u64::try_from(seed).ok()
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[must_use]
 pub enum Outcome {
@@ -184,6 +233,8 @@ fn write_actual_results(cases: Vec<RunTestCase<CaseData>>) -> anyhow::Result<()>
 pub enum CaseBehaviour {
     Test,
     Example,
+    /// Run with the `check` subcommand (see [`CHECK_DIR`])
+    Check,
     Ignored,
 }
 
@@ -302,6 +353,18 @@ fn report_failures(run_cases: &[RunTestCase<CaseData>]) {
             }
             _ => {} // Everything else isn't a failure
         }
+        if case.single_threaded_panicked {
+            println!();
+            println!("{path_string} panicked when run single-threaded, with message:");
+            println!("{}", case.single_threaded_output.as_ref().unwrap());
+        } else if case.threads_disagree() {
+            println!();
+            println!("{path_string} produced different output when run single-threaded:");
+            print_diff(
+                case.output.as_ref().unwrap(),
+                case.single_threaded_output.as_ref().unwrap(),
+            );
+        }
     }
 }
 
@@ -463,6 +526,10 @@ fn print_summary_line(completed_tests: &[RunTestCase<CaseData>], duration: Durat
 struct CaseData {
     behaviour: CaseBehaviour,
     expected_output: Option<String>,
+    /// The output of running this case with `-T 1`, if `--check-threads` was set
+    single_threaded_output: Option<String>,
+    /// Whether running this case with `-T 1` panicked
+    single_threaded_panicked: bool,
 }
 
 impl RunTestCase<CaseData> {
@@ -470,13 +537,24 @@ impl RunTestCase<CaseData> {
         self.outcome().colored_string()
     }
 
+    /// Returns `true` if this case was run single-threaded and gave different output
+    fn threads_disagree(&self) -> bool {
+        match (&self.single_threaded_output, &self.output) {
+            (Some(single_threaded), Some(multi_threaded)) => single_threaded != multi_threaded,
+            _ => false,
+        }
+    }
+
     fn outcome(&self) -> CaseOutcome {
         if self.behaviour == CaseBehaviour::Ignored {
             return CaseOutcome::Ignored;
         }
-        if self.panicked {
+        if self.panicked || self.single_threaded_panicked {
             return CaseOutcome::Panicked;
         }
+        if self.threads_disagree() {
+            return CaseOutcome::Fail;
+        }
         let Some(expected_output) = &self.expected_output else {
             return CaseOutcome::Unspecified;
         };
@@ -0,0 +1,87 @@
+//! A WebAssembly wrapper around Monument, so that it can run in web browsers.  Build it with
+//! `wasm-pack build --target web`.
+//!
+//! Searches are specified as JSON (see [`query`] for the format), and every composition is passed
+//! to JavaScript as a JSON string.  Searches block the thread they're running on, so should be run
+//! in a Web Worker:
+//!
+//! ```js
+//! import init, { Search } from "./pkg/monument_wasm.js";
+//!
+//! await init();
+//! const search = new Search(JSON.stringify(query)); // Throws if `query` is invalid
+//! search.run((json) => postMessage(JSON.parse(json)));
+//! ```
+//!
+//! WebAssembly has no threads or clock, so searches run on a single thread and every
+//! time-dependent option is ignored.
+
+#![deny(rustdoc::broken_intra_doc_links, rustdoc::private_intra_doc_links)]
+
+mod query;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use monument::{composition::ParamsData, Update};
+use wasm_bindgen::prelude::*;
+
+/// A search which has been created from a JSON query
+#[wasm_bindgen]
+pub struct Search {
+    inner: monument::Search,
+    abort_flag: AtomicBool,
+}
+
+#[wasm_bindgen]
+impl Search {
+    /// Create a search from a JSON query, throwing an error if the query is invalid.
+    #[wasm_bindgen(constructor)]
+    pub fn new(query_json: &str) -> Result<Search, JsError> {
+        let query = serde_json::from_str::<query::Query>(query_json)
+            .map_err(|e| JsError::new(&format!("Invalid query: {e}")))?;
+        let (params, config) = query.to_params().map_err(|e| JsError::new(&e))?;
+        let inner =
+            monument::Search::new(params, config).map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(Search {
+            inner,
+            abort_flag: AtomicBool::new(false),
+        })
+    }
+
+    /// Run the search, **blocking the current thread** until it either finishes or is aborted.
+    /// `on_comp` is called with every composition as a JSON string.  If `on_comp` throws, the
+    /// search is aborted and the error is rethrown.
+    pub fn run(&self, on_comp: &js_sys::Function) -> Result<(), JsValue> {
+        let params_data = ParamsData::new(self.inner.parameters());
+        let mut num_comps = 0;
+        let mut error = None;
+        self.inner.run(
+            |update| {
+                let Update::Comp(comp) = update else {
+                    return;
+                };
+                if error.is_some() {
+                    return; // Already aborting
+                }
+                let json = comp.to_json(num_comps, &params_data);
+                num_comps += 1;
+                if let Err(e) = on_comp.call1(&JsValue::NULL, &JsValue::from_str(&json)) {
+                    error = Some(e);
+                    self.abort();
+                }
+            },
+            &self.abort_flag,
+        );
+        match error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Abort the search.  JavaScript is single-threaded, so this can only be called from inside
+    /// `on_comp`; the search stops shortly afterwards.
+    pub fn abort(&self) {
+        self.abort_flag.store(true, Ordering::SeqCst);
+    }
+}
+
@@ -0,0 +1,272 @@
+//! The JSON format used to specify searches from JavaScript.  This is a small subset of the CLI's
+//! TOML format, covering the things a web front-end needs.  For example:
+//!
+//! ```json
+//! {
+//!     "length": { "min": 1250, "max": 1300 },
+//!     "methods": [
+//!         { "name": "Plain Bob", "place_notation": "x18x18x18x18,12", "stage": 8 }
+//!     ],
+//!     "music": [{ "run_length": 4 }, { "pattern": "5678", "weight": 2 }],
+//!     "num_comps": 20
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+use bellframe::{music::AtRowPositions, Mask, RowBuf, Stage, Stroke};
+use itertools::Itertools;
+use monument::{
+    parameters::{
+        base_calls, BaseCallType, CallDisplayStyle, CourseSet, Method, MethodId, MethodVec,
+        MultipartCallDisplay, MusicType, MusicTypeVec, NonDufferMusic, OptionalRangeInclusive,
+        Ranking, SpliceStyle, DEFAULT_BOB_WEIGHT, DEFAULT_SINGLE_WEIGHT,
+    },
+    utils::{IdGenerator, TotalLength},
+    Config, Parameters, PartHeadGroup,
+};
+use serde::Deserialize;
+
+/// The default memory limit.  WebAssembly can only address 4GB, and browsers often allow less.
+const DEFAULT_MEM_LIMIT: usize = 1_000_000_000;
+
+/// A search, as specified by JavaScript
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Query {
+    length: Length,
+    methods: Vec<QueryMethod>,
+    #[serde(default)]
+    music: Vec<QueryMusic>,
+
+    #[serde(default = "get_num_comps")]
+    num_comps: usize,
+    #[serde(default = "get_true")]
+    require_truth: bool,
+    /// Course masks which every method can use.  Defaults to keeping the tenors together.
+    courses: Option<Vec<String>>,
+    #[serde(default)]
+    splice_weight: f32,
+
+    #[serde(default)]
+    base_calls: BaseCalls,
+    #[serde(default = "get_bob_weight")]
+    bob_weight: f32,
+    #[serde(default = "get_single_weight")]
+    single_weight: f32,
+
+    /// Seed for breaking ties between equally good partial compositions
+    seed: Option<u64>,
+    /// The maximum number of bytes which the search can use
+    mem_limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged, deny_unknown_fields)]
+enum Length {
+    Exact(usize),
+    Range { min: usize, max: usize },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct QueryMethod {
+    name: String,
+    place_notation: String,
+    stage: u8,
+    #[serde(default)]
+    shorthand: String,
+}
+
+/// A music type, which matches either a [`pattern`](Self::pattern) or runs of a given
+/// [`run_length`](Self::run_length)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct QueryMusic {
+    pattern: Option<String>,
+    run_length: Option<u8>,
+    #[serde(default = "get_one")]
+    weight: f32,
+    name: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum BaseCalls {
+    #[default]
+    Near,
+    Far,
+    None,
+}
+
+impl Query {
+    /// Convert this `Query` into the [`Parameters`] and [`Config`] for a [`Search`].  The error
+    /// is a human-readable description of what's wrong with the query.
+    ///
+    /// [`Search`]: monument::Search
+    pub(crate) fn to_params(&self) -> Result<(Parameters, Config), String> {
+        // Methods
+        let Some(first_method) = self.methods.first() else {
+            return Err("No methods specified".to_owned());
+        };
+        let stage = Stage::new(first_method.stage);
+        if self.methods.iter().any(|m| m.stage != first_method.stage) {
+            return Err("Every method must have the same stage".to_owned());
+        }
+        let allowed_courses = match &self.courses {
+            Some(masks) => masks
+                .iter()
+                .map(|s| {
+                    Mask::parse_with_stage(s, stage)
+                        .map_err(|e| format!("Can't parse course mask {s:?}: {e}"))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            // Keep the tenors together
+            None => vec![Mask::with_fixed_bells(stage, stage.bells().skip(6))],
+        };
+        let mut methods = MethodVec::new();
+        for (idx, m) in self.methods.iter().enumerate() {
+            let mut method =
+                bellframe::Method::from_place_not_string(m.name.clone(), stage, &m.place_notation)
+                    .map_err(|e| format!("Can't parse place notation for {:?}: {e}", m.name))?;
+            method.set_lead_end_label();
+            let lead_len = method.lead_len();
+            methods.push(Method {
+                id: MethodId(idx as u32),
+                inner: method,
+
+                custom_shorthand: m.shorthand.clone(),
+                count_range: OptionalRangeInclusive::OPEN,
+                count_range_per_part: OptionalRangeInclusive::OPEN,
+                start_indices: vec![0],
+                end_indices: (0..lead_len as isize).collect_vec(),
+                can_start: true,
+                can_end: true,
+                link_position: None,
+                allowed_courses: vec![CourseSet::from(allowed_courses.clone())],
+            });
+        }
+
+        // Calls
+        let base_call_type = match self.base_calls {
+            BaseCalls::Near => Some(BaseCallType::Near),
+            BaseCalls::Far => Some(BaseCallType::Far),
+            BaseCalls::None => None,
+        };
+        let calls = match base_call_type {
+            Some(ty) => base_calls(
+                &mut IdGenerator::starting_at_zero(),
+                ty,
+                Some(self.bob_weight),
+                Some(self.single_weight),
+                stage,
+            ),
+            None => index_vec::index_vec![],
+        };
+
+        // Music
+        let mut music_types = MusicTypeVec::new();
+        for music in &self.music {
+            let (inner, default_name) = match (&music.pattern, music.run_length) {
+                (Some(pattern), None) => (
+                    bellframe::MusicType::parse(pattern)
+                        .map_err(|e| format!("Can't parse music pattern {pattern:?}: {e}"))?,
+                    format!("{pattern}s"),
+                ),
+                (None, Some(len)) => (
+                    bellframe::MusicType::runs(len, stage),
+                    format!("{len}-bell runs"),
+                ),
+                _ => return Err("Music needs exactly one of `pattern` or `run_length`".to_owned()),
+            };
+            music_types.push(MusicType {
+                show_total: true,
+                show_positions: AtRowPositions::FALSE,
+                name: music.name.clone().unwrap_or(default_name),
+                inner,
+                weights: AtRowPositions::splat(music.weight),
+                count_range: OptionalRangeInclusive::OPEN,
+                non_duffer: false,
+            });
+        }
+
+        let (min_length, max_length) = match self.length {
+            Length::Exact(len) => (len, len),
+            Length::Range { min, max } => (min, max),
+        };
+        let params = Parameters {
+            length: TotalLength::new(min_length)..=TotalLength::new(max_length),
+            stage,
+            num_comps: self.num_comps,
+            require_truth: self.require_truth,
+            max_row_repeats: 1,
+            extent: false,
+            prune_dominated_chunks: false,
+            min_total_score: None,
+            min_music_score: None,
+            max_calls: None,
+            min_conductability: None,
+            shortlist: None,
+            ranking: Ranking::AvgScore,
+            row_weight: 0.0,
+            target_length: None,
+
+            methods,
+            splice_style: SpliceStyle::LeadLabels,
+            splice_weight: self.splice_weight,
+            splice_weights: HashMap::new(),
+            method_counts_in_leads: false,
+            calls,
+            call_display_style: CallDisplayStyle::CallingPositions,
+            calling_bell: stage.tenor(),
+            calling_position_names: HashMap::new(),
+            allowed_calling_positions: None,
+            strict_labels: false,
+            multipart_call_display: MultipartCallDisplay::FirstPart,
+            atw_weight: None,
+            require_atw: false,
+
+            start_row: RowBuf::rounds(stage),
+            end_row: RowBuf::rounds(stage),
+            part_head_group: PartHeadGroup::one_part(stage),
+            course_weights: vec![],
+            no_repeated_courses: false,
+            calling: None,
+            omit_round_blocks: false,
+            calling_filters: vec![],
+
+            music_types,
+            no_duffers: false,
+            max_duffer_rows: None,
+            non_duffer_music: NonDufferMusic::Marked,
+            required_rows: vec![],
+            start_stroke: Stroke::Hand,
+        };
+        let config = Config {
+            mem_limit: Some(self.mem_limit.unwrap_or(DEFAULT_MEM_LIMIT)),
+            seed: self.seed,
+            ..Config::default()
+        };
+        Ok((params, config))
+    }
+}
+
+fn get_num_comps() -> usize {
+    100
+}
+
+fn get_true() -> bool {
+    true
+}
+
+fn get_one() -> f32 {
+    1.0
+}
+
+fn get_bob_weight() -> f32 {
+    DEFAULT_BOB_WEIGHT
+}
+
+fn get_single_weight() -> f32 {
+    DEFAULT_SINGLE_WEIGHT
+}
@@ -99,6 +99,35 @@ impl Display for BigNumFloat {
     }
 }
 
+/// Find the string in `candidates` which is most similar to `name` (ignoring case), so that it
+/// can be suggested as a correction for a typo.  Returns `None` if no candidate is close enough to
+/// `name` to be a likely typo.  Ties are broken alphabetically, so the result doesn't depend on the
+/// order of `candidates`.
+///
+/// # Example
+/// ```
+/// use kneasle_ringing_utils::closest_match;
+///
+/// let fields = ["length", "num_comps", "method"];
+/// assert_eq!(closest_match("nun_comps", fields), Some("num_comps"));
+/// assert_eq!(closest_match("LENGTH", fields), Some("length"));
+/// assert_eq!(closest_match("calling", fields), None);
+/// ```
+pub fn closest_match<'c>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'c str>,
+) -> Option<&'c str> {
+    let name = name.to_lowercase();
+    // Allow roughly one typo for every three characters
+    let max_distance = name.chars().count() / 3;
+    candidates
+        .into_iter()
+        .map(|c| (edit_distance::edit_distance(&name, &c.to_lowercase()), c))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, c)| c)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -159,4 +188,30 @@ mod tests {
         check(56_312_357_123.0, "56.31G");
         check(1_456_312_357_123.0, "1.46T");
     }
+
+    #[test]
+    fn closest_match() {
+        #[track_caller]
+        fn check(name: &str, candidates: &[&str], expected: Option<&str>) {
+            let candidates = candidates.iter().copied();
+            assert_eq!(super::closest_match(name, candidates), expected);
+        }
+
+        check("LE", &["LE", "HL"], Some("LE"));
+        check("le", &["LE", "HL"], Some("LE"));
+        check("poo", &["LE", "HL"], None);
+        check("h", &["B", "M", "W", "H"], Some("H"));
+        check("x", &["B", "M", "W", "H"], None);
+        check("crus", &["crus", "runs"], Some("crus"));
+        check("5678 combs", &["5678 combinations", "crus"], None);
+        check(
+            "5678 combinaions",
+            &["5678 combinations", "crus"],
+            Some("5678 combinations"),
+        );
+        check("nun_comps", &["num_comps", "length"], Some("num_comps"));
+        check("ab", &["b", "a"], None);
+        check("abc", &["abd", "abb"], Some("abb")); // Ties are broken alphabetically
+        check("anything", &[], None);
+    }
 }